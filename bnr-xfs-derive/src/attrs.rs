@@ -0,0 +1,143 @@
+//! Parsing for the `#[xfs(...)]` attributes.
+
+use syn::{Attribute, LitStr, Result};
+
+/// Attributes set on the deriving type.
+pub(crate) struct ContainerAttrs {
+    pub name: LitStr,
+    pub no_display: bool,
+}
+
+impl ContainerAttrs {
+    /// Parses the container attributes, `name` is required.
+    pub fn parse(ident: &syn::Ident, attrs: &[Attribute]) -> Result<Self> {
+        let mut name = None;
+        let mut no_display = false;
+
+        for attr in attrs.iter().filter(|a| a.path().is_ident("xfs")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("name") {
+                    name = Some(meta.value()?.parse::<LitStr>()?);
+                    Ok(())
+                } else if meta.path.is_ident("no_display") {
+                    no_display = true;
+                    Ok(())
+                } else {
+                    Err(meta.error("unknown xfs container attribute"))
+                }
+            })?;
+        }
+
+        let name = name.ok_or_else(|| {
+            syn::Error::new_spanned(ident, r#"missing `#[xfs(name = "...")]` attribute"#)
+        })?;
+
+        Ok(Self { name, no_display })
+    }
+}
+
+/// XFS value types used to encode a struct field.
+#[derive(Clone, Copy, PartialEq)]
+pub(crate) enum FieldKind {
+    /// Field type implements the XFS conversions, e.g. created with `create_xfs_i4!`.
+    Xfs,
+    /// Primitive integer encoded as an `i4`.
+    I4,
+    /// Primitive integer encoded as an `int`.
+    Int,
+    /// `bool` encoded as a `boolean`.
+    Boolean,
+    /// `String` encoded as a `string`.
+    String,
+}
+
+/// Attributes set on a struct field.
+pub(crate) struct FieldAttrs {
+    pub name: Option<LitStr>,
+    pub kind: FieldKind,
+    pub required: bool,
+    pub skip: bool,
+}
+
+impl FieldAttrs {
+    /// Parses the field attributes.
+    pub fn parse(field: &syn::Field) -> Result<Self> {
+        let mut name = None;
+        let mut kind = FieldKind::Xfs;
+        let mut required = false;
+        let mut skip = false;
+
+        for attr in field.attrs.iter().filter(|a| a.path().is_ident("xfs")) {
+            attr.parse_nested_meta(|meta| {
+                let set_kind = |kind: &mut FieldKind, new_kind| {
+                    if *kind == FieldKind::Xfs {
+                        *kind = new_kind;
+                        Ok(())
+                    } else {
+                        Err(meta.error("only one XFS type can be set for a field"))
+                    }
+                };
+
+                if meta.path.is_ident("name") {
+                    name = Some(meta.value()?.parse::<LitStr>()?);
+                    Ok(())
+                } else if meta.path.is_ident("i4") {
+                    set_kind(&mut kind, FieldKind::I4)
+                } else if meta.path.is_ident("int") {
+                    set_kind(&mut kind, FieldKind::Int)
+                } else if meta.path.is_ident("boolean") {
+                    set_kind(&mut kind, FieldKind::Boolean)
+                } else if meta.path.is_ident("string") {
+                    set_kind(&mut kind, FieldKind::String)
+                } else if meta.path.is_ident("required") {
+                    required = true;
+                    Ok(())
+                } else if meta.path.is_ident("skip") {
+                    skip = true;
+                    Ok(())
+                } else {
+                    Err(meta.error("unknown xfs field attribute"))
+                }
+            })?;
+        }
+
+        if kind != FieldKind::Xfs && name.is_none() && !skip {
+            return Err(syn::Error::new_spanned(
+                field,
+                r#"primitive XFS fields need a `#[xfs(name = "...")]` attribute"#,
+            ));
+        }
+
+        Ok(Self {
+            name,
+            kind,
+            required,
+            skip,
+        })
+    }
+}
+
+/// Attributes set on an enum variant.
+pub(crate) struct VariantAttrs {
+    pub display: Option<LitStr>,
+}
+
+impl VariantAttrs {
+    /// Parses the variant attributes.
+    pub fn parse(variant: &syn::Variant) -> Result<Self> {
+        let mut display = None;
+
+        for attr in variant.attrs.iter().filter(|a| a.path().is_ident("xfs")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("display") {
+                    display = Some(meta.value()?.parse::<LitStr>()?);
+                    Ok(())
+                } else {
+                    Err(meta.error("unknown xfs variant attribute"))
+                }
+            })?;
+        }
+
+        Ok(Self { display })
+    }
+}
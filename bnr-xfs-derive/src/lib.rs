@@ -0,0 +1,66 @@
+//! Derive macros for [bnr-xfs](https://docs.rs/bnr-xfs) protocol types.
+//!
+//! The macros are re-exported from `bnr-xfs`, where the `create_xfs_struct!` and
+//! `create_xfs_array!` macros expand to them. See the `README` for the attribute reference.
+
+use proc_macro::TokenStream;
+use syn::{parse_macro_input, DeriveInput};
+
+mod attrs;
+mod xfs_array;
+mod xfs_enum;
+mod xfs_struct;
+
+/// Implements the XFS `struct` conversions for a struct with named fields.
+///
+/// Generates:
+///
+/// - `xfs_name()`
+/// - conversions to/from `XfsStruct`, `XfsValue` and `XfsMember`
+/// - `XfsDecode` for the streaming codec
+/// - `Display`, unless `#[xfs(no_display)]` is set
+///
+/// Conversion errors name the type, field, and XFS member that failed.
+#[proc_macro_derive(XfsStruct, attributes(xfs))]
+pub fn derive_xfs_struct(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    xfs_struct::expand(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Implements the XFS `i4` conversions for a `#[repr(u32)]` enum with unit variants.
+///
+/// Generates:
+///
+/// - `new()`, returning the `#[default]` (or first) variant
+/// - `create(u32)`, returning the default variant for unknown values
+/// - `xfs_name()`, `inner()`, `into_inner()`, and the conversions from `impl_xfs_enum!`
+/// - `Display` and `&'static str` conversions, unless `#[xfs(no_display)]` is set
+#[proc_macro_derive(XfsEnum, attributes(xfs))]
+pub fn derive_xfs_enum(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    xfs_enum::expand(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Implements the XFS `array` conversions for a struct with a single
+/// `items: BoundedList<Item, LEN>` field.
+///
+/// Generates:
+///
+/// - `xfs_name()`, and the list functions from `impl_xfs_list!`
+/// - conversions to/from `XfsArray`, `XfsValue` and `XfsMember`
+/// - `XfsDecode` for the streaming codec
+/// - `Display`, unless `#[xfs(no_display)]` is set
+///
+/// Items that fail to convert are set to their default value, and logged with the type and item
+/// index. Items past `LEN` are ignored.
+#[proc_macro_derive(XfsArray, attributes(xfs))]
+pub fn derive_xfs_array(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    xfs_array::expand(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
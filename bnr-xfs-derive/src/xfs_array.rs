@@ -0,0 +1,261 @@
+//! Implementation of `#[derive(XfsArray)]`.
+
+use proc_macro2::TokenStream;
+use quote::{quote, ToTokens};
+use syn::{Data, DeriveInput, Fields, GenericArgument, PathArguments, Result, Type};
+
+use crate::attrs::ContainerAttrs;
+
+/// Gets the item type and maximum length from an `items: BoundedList<Item, LEN>` field type.
+fn bounded_list_args(ty: &Type) -> Option<(syn::Ident, TokenStream)> {
+    let segment = match ty {
+        Type::Path(path) if path.qself.is_none() => path.path.segments.last()?,
+        _ => return None,
+    };
+
+    if segment.ident != "BoundedList" {
+        return None;
+    }
+
+    let args = match &segment.arguments {
+        PathArguments::AngleBracketed(args) if args.args.len() == 2 => &args.args,
+        _ => return None,
+    };
+
+    // a plain identifier is required by `impl_xfs_list!`
+    let item = match &args[0] {
+        GenericArgument::Type(Type::Path(path)) if path.qself.is_none() => {
+            path.path.get_ident()?.clone()
+        }
+        _ => return None,
+    };
+
+    // a constant name parses as a type, so accept both
+    let len = match &args[1] {
+        GenericArgument::Const(expr) => expr.to_token_stream(),
+        GenericArgument::Type(ty) => ty.to_token_stream(),
+        _ => return None,
+    };
+
+    Some((item, len))
+}
+
+pub(crate) fn expand(input: &DeriveInput) -> Result<TokenStream> {
+    let ident = &input.ident;
+    let ty_name = ident.to_string();
+    let container = ContainerAttrs::parse(ident, &input.attrs)?;
+    let xfs_name = &container.name;
+
+    if !input.generics.params.is_empty() {
+        return Err(syn::Error::new_spanned(
+            &input.generics,
+            "XfsArray cannot be derived for generic types",
+        ));
+    }
+
+    let list_err = || {
+        syn::Error::new_spanned(
+            ident,
+            "XfsArray can only be derived for structs with a single `items: BoundedList<Item, LEN>` field",
+        )
+    };
+
+    let field = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) if fields.named.len() == 1 => &fields.named[0],
+            _ => return Err(list_err()),
+        },
+        _ => return Err(list_err()),
+    };
+
+    if field.ident.as_ref().map(|i| i != "items").unwrap_or(true) {
+        return Err(list_err());
+    }
+
+    if let Some(attr) = field.attrs.iter().find(|a| a.path().is_ident("xfs")) {
+        return Err(syn::Error::new_spanned(
+            attr,
+            "XfsArray fields do not take xfs attributes",
+        ));
+    }
+
+    let (item, len) = bounded_list_args(&field.ty).ok_or_else(list_err)?;
+
+    let display = if container.no_display {
+        quote!()
+    } else {
+        quote! {
+            impl ::std::fmt::Display for #ident {
+                fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+                    write!(f, r#"{{"items":["#)?;
+                    for (i, item) in self.iter().enumerate() {
+                        if i != 0 {
+                            write!(f, ",")?;
+                        }
+                        write!(f, "{item}")?;
+                    }
+                    write!(f, "]}}")
+                }
+            }
+        }
+    };
+
+    Ok(quote! {
+        ::bnr_xfs::impl_xfs_list!(#ident, #item, #len);
+
+        impl #ident {
+            /// Gets the [XfsMember](::bnr_xfs::xfs::xfs_struct::XfsMember) name.
+            pub const fn xfs_name() -> &'static str {
+                #xfs_name
+            }
+        }
+
+        impl From<&#ident> for ::bnr_xfs::xfs::array::XfsArray {
+            fn from(val: &#ident) -> Self {
+                Self::create(val.items().iter().map(::bnr_xfs::xfs::value::XfsValue::from))
+            }
+        }
+
+        impl From<#ident> for ::bnr_xfs::xfs::array::XfsArray {
+            fn from(val: #ident) -> Self {
+                (&val).into()
+            }
+        }
+
+        impl TryFrom<&::bnr_xfs::xfs::array::XfsArray> for #ident {
+            type Error = ::bnr_xfs::Error;
+
+            fn try_from(val: &::bnr_xfs::xfs::array::XfsArray) -> ::bnr_xfs::Result<Self> {
+                let data = val.data();
+                let max = #ident::max_size();
+                let mut res = #ident::new();
+
+                ::bnr_xfs::xfs::array::truncated_items(
+                    #ty_name,
+                    #xfs_name,
+                    data.len().saturating_sub(max),
+                    max,
+                );
+
+                for (i, src) in data.iter().take(max).map(|m| m.inner()).enumerate() {
+                    let item = match <#item>::try_from(src) {
+                        Ok(d) => d,
+                        Err(err) => {
+                            ::bnr_xfs::xfs::array::item_error(#ty_name, #xfs_name, i, err);
+                            Default::default()
+                        }
+                    };
+                    res.push_item(item)?;
+                }
+
+                Ok(res)
+            }
+        }
+
+        impl TryFrom<::bnr_xfs::xfs::array::XfsArray> for #ident {
+            type Error = ::bnr_xfs::Error;
+
+            fn try_from(val: ::bnr_xfs::xfs::array::XfsArray) -> ::bnr_xfs::Result<Self> {
+                (&val).try_into()
+            }
+        }
+
+        impl From<&#ident> for ::bnr_xfs::xfs::value::XfsValue {
+            fn from(val: &#ident) -> Self {
+                Self::new().with_array(val.into())
+            }
+        }
+
+        impl From<#ident> for ::bnr_xfs::xfs::value::XfsValue {
+            fn from(val: #ident) -> Self {
+                (&val).into()
+            }
+        }
+
+        impl TryFrom<&::bnr_xfs::xfs::value::XfsValue> for #ident {
+            type Error = ::bnr_xfs::Error;
+
+            fn try_from(val: &::bnr_xfs::xfs::value::XfsValue) -> ::bnr_xfs::Result<Self> {
+                let name = #ident::xfs_name();
+                val
+                    .array()
+                    .ok_or(::bnr_xfs::Error::Xfs(format!(
+                        "Expected {name} XfsValue, have: {val}"
+                    )))?
+                    .try_into()
+            }
+        }
+
+        impl TryFrom<::bnr_xfs::xfs::value::XfsValue> for #ident {
+            type Error = ::bnr_xfs::Error;
+
+            fn try_from(val: ::bnr_xfs::xfs::value::XfsValue) -> ::bnr_xfs::Result<Self> {
+                (&val).try_into()
+            }
+        }
+
+        impl From<&#ident> for ::bnr_xfs::xfs::xfs_struct::XfsMember {
+            fn from(val: &#ident) -> Self {
+                Self::create(#ident::xfs_name(), val.into())
+            }
+        }
+
+        impl From<#ident> for ::bnr_xfs::xfs::xfs_struct::XfsMember {
+            fn from(val: #ident) -> Self {
+                (&val).into()
+            }
+        }
+
+        impl TryFrom<&::bnr_xfs::xfs::xfs_struct::XfsMember> for #ident {
+            type Error = ::bnr_xfs::Error;
+
+            fn try_from(val: &::bnr_xfs::xfs::xfs_struct::XfsMember) -> ::bnr_xfs::Result<Self> {
+                let name = #ident::xfs_name();
+                match (val.name(), val.value().array()) {
+                    (n, Some(v)) if n == name => v.try_into(),
+                    _ => Err(::bnr_xfs::Error::Xfs(format!(
+                        "Expected {name} XfsMember, have: {val}"
+                    ))),
+                }
+            }
+        }
+
+        impl TryFrom<::bnr_xfs::xfs::xfs_struct::XfsMember> for #ident {
+            type Error = ::bnr_xfs::Error;
+
+            fn try_from(val: ::bnr_xfs::xfs::xfs_struct::XfsMember) -> ::bnr_xfs::Result<Self> {
+                (&val).try_into()
+            }
+        }
+
+        impl ::bnr_xfs::xfs::codec::XfsDecode for #ident {
+            fn decode_value(reader: &mut ::bnr_xfs::xfs::codec::XfsReader<'_>) -> ::bnr_xfs::Result<Self> {
+                let max = #ident::max_size();
+                let mut res = #ident::new();
+                let mut skipped = 0usize;
+
+                reader.decode_array(|reader, i| {
+                    if i < max {
+                        let item = match reader.try_decode::<#item>()? {
+                            Ok(d) => d,
+                            Err(err) => {
+                                ::bnr_xfs::xfs::array::item_error(#ty_name, #xfs_name, i, err);
+                                Default::default()
+                            }
+                        };
+                        res.push_item(item)
+                    } else {
+                        skipped += 1;
+                        reader.skip_value()
+                    }
+                })?;
+
+                ::bnr_xfs::xfs::array::truncated_items(#ty_name, #xfs_name, skipped, max);
+
+                Ok(res)
+            }
+        }
+
+        #display
+    })
+}
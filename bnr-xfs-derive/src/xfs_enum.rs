@@ -0,0 +1,148 @@
+//! Implementation of `#[derive(XfsEnum)]`.
+
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{Data, DeriveInput, Fields, Result};
+
+use crate::attrs::{ContainerAttrs, VariantAttrs};
+
+pub(crate) fn expand(input: &DeriveInput) -> Result<TokenStream> {
+    let ident = &input.ident;
+    let container = ContainerAttrs::parse(ident, &input.attrs)?;
+    let xfs_name = &container.name;
+
+    let has_repr_u32 = input.attrs.iter().any(|attr| {
+        attr.path().is_ident("repr")
+            && attr
+                .parse_args::<syn::Ident>()
+                .map(|r| r == "u32")
+                .unwrap_or(false)
+    });
+    if !has_repr_u32 {
+        return Err(syn::Error::new_spanned(
+            ident,
+            "XfsEnum requires a `#[repr(u32)]` attribute",
+        ));
+    }
+
+    let data = match &input.data {
+        Data::Enum(data) => data,
+        _ => {
+            return Err(syn::Error::new_spanned(
+                ident,
+                "XfsEnum can only be derived for enums",
+            ))
+        }
+    };
+
+    if data.variants.is_empty() {
+        return Err(syn::Error::new_spanned(
+            ident,
+            "XfsEnum requires at least one variant",
+        ));
+    }
+
+    let mut variants = Vec::with_capacity(data.variants.len());
+    let mut displays = Vec::with_capacity(data.variants.len());
+    for variant in data.variants.iter() {
+        if !matches!(variant.fields, Fields::Unit) {
+            return Err(syn::Error::new_spanned(
+                variant,
+                "XfsEnum variants cannot have fields",
+            ));
+        }
+
+        let attrs = VariantAttrs::parse(variant)?;
+        displays.push(
+            attrs
+                .display
+                .map(|d| d.value())
+                .unwrap_or_else(|| display_name(&variant.ident.to_string())),
+        );
+        variants.push(&variant.ident);
+    }
+
+    // the `#[default]` variant is used for unknown values, otherwise the first variant
+    let default = data
+        .variants
+        .iter()
+        .find(|v| v.attrs.iter().any(|a| a.path().is_ident("default")))
+        .map(|v| &v.ident)
+        .unwrap_or(variants[0]);
+
+    let new_doc = format!("Creates a new [{ident}].");
+    let create_doc = format!("Creates a new [{ident}] from the provided parameter.");
+
+    let display = if container.no_display {
+        quote!()
+    } else {
+        quote! {
+            impl From<&#ident> for &'static str {
+                fn from(val: &#ident) -> Self {
+                    match val {
+                        #(#ident::#variants => #displays,)*
+                    }
+                }
+            }
+
+            impl From<#ident> for &'static str {
+                fn from(val: #ident) -> Self {
+                    (&val).into()
+                }
+            }
+
+            impl ::std::fmt::Display for #ident {
+                fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+                    write!(f, r#""{}""#, <&str>::from(self))
+                }
+            }
+        }
+    };
+
+    Ok(quote! {
+        impl #ident {
+            #[doc = #new_doc]
+            pub const fn new() -> Self {
+                Self::#default
+            }
+
+            #[doc = #create_doc]
+            pub const fn create(val: u32) -> Self {
+                #(
+                if val == Self::#variants as u32 {
+                    return Self::#variants;
+                }
+                )*
+                Self::#default
+            }
+        }
+
+        ::bnr_xfs::impl_xfs_enum!(#ident, #xfs_name);
+
+        #display
+    })
+}
+
+/// Converts a `CamelCase` variant name into a lowercase, space separated display name.
+fn display_name(ident: &str) -> String {
+    let mut name = String::with_capacity(ident.len() + 4);
+    for (i, c) in ident.chars().enumerate() {
+        if c.is_uppercase() && i != 0 {
+            name.push(' ');
+        }
+        name.extend(c.to_lowercase());
+    }
+    name
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display_name() {
+        assert_eq!(display_name("Atm").as_str(), "atm");
+        assert_eq!(display_name("NotSupported").as_str(), "not supported");
+        assert_eq!(display_name("SafeDoorOpen").as_str(), "safe door open");
+    }
+}
@@ -0,0 +1,368 @@
+//! Implementation of `#[derive(XfsStruct)]`.
+
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::{Data, DeriveInput, Fields, Result};
+
+use crate::attrs::{ContainerAttrs, FieldAttrs, FieldKind};
+
+/// A parsed struct field.
+struct Field {
+    ident: syn::Ident,
+    ty: syn::Type,
+    attrs: FieldAttrs,
+}
+
+impl Field {
+    /// Expression for the XFS member name.
+    fn member_name(&self) -> TokenStream {
+        let ty = &self.ty;
+        match &self.attrs.name {
+            Some(name) => quote!(#name),
+            None => quote!(<#ty>::xfs_name()),
+        }
+    }
+
+    /// Expression converting `val.<field>` into an `XfsValue`.
+    fn encode_value(&self) -> TokenStream {
+        let ident = &self.ident;
+        match self.attrs.kind {
+            FieldKind::Xfs => quote!(::bnr_xfs::xfs::value::XfsValue::from(&val.#ident)),
+            FieldKind::I4 => {
+                quote!(::bnr_xfs::xfs::value::XfsValue::new().with_i4(val.#ident as i32))
+            }
+            FieldKind::Int => {
+                quote!(::bnr_xfs::xfs::value::XfsValue::new().with_int(val.#ident as i64))
+            }
+            FieldKind::Boolean => {
+                quote!(::bnr_xfs::xfs::value::XfsValue::new().with_boolean(u8::from(val.#ident)))
+            }
+            FieldKind::String => {
+                quote!(::bnr_xfs::xfs::value::XfsValue::new().with_string(val.#ident.as_str()))
+            }
+        }
+    }
+
+    /// Expression converting the `&XfsValue` named `value` into the field type.
+    fn convert_value(&self) -> TokenStream {
+        let ty = &self.ty;
+        let expected = |xfs_ty: &str| {
+            let msg = format!("Expected {xfs_ty} XfsValue, have: {{value}}");
+            quote!(::bnr_xfs::Error::Xfs(format!(#msg)))
+        };
+
+        match self.attrs.kind {
+            FieldKind::Xfs => quote!(<#ty>::try_from(value)),
+            FieldKind::I4 => {
+                let err = expected("i4");
+                quote!(value.i4().map(|v| *v as #ty).ok_or_else(|| #err))
+            }
+            FieldKind::Int => {
+                let err = expected("int");
+                quote!(value.int().map(|v| *v as #ty).ok_or_else(|| #err))
+            }
+            FieldKind::Boolean => {
+                let err = expected("boolean");
+                quote!(value.boolean().map(|v| *v != 0).ok_or_else(|| #err))
+            }
+            FieldKind::String => {
+                let err = expected("string");
+                quote!(value.string().map(<#ty>::from).ok_or_else(|| #err))
+            }
+        }
+    }
+
+    /// Expression decoding the field type from the `XfsReader` named `reader`.
+    fn decode(&self) -> TokenStream {
+        let ty = &self.ty;
+        match self.attrs.kind {
+            FieldKind::Xfs => {
+                quote!(<#ty as ::bnr_xfs::xfs::codec::XfsDecode>::decode_value(reader))
+            }
+            _ => {
+                let convert_value = self.convert_value();
+                quote! {
+                    <::bnr_xfs::xfs::value::XfsValue as ::bnr_xfs::xfs::codec::XfsDecode>::decode_value(reader)
+                        .and_then(|value| {
+                            let value = &value;
+                            #convert_value
+                        })
+                }
+            }
+        }
+    }
+
+    /// Expression wrapping the `err` from converting the field with the member details.
+    fn member_error(&self, ty_name: &str) -> TokenStream {
+        let field = self.ident.to_string();
+        let name = self.member_name();
+        quote!(|err| ::bnr_xfs::xfs::xfs_struct::member_error(#ty_name, #field, #name, err))
+    }
+
+    /// Expression used when the member is missing.
+    fn missing(&self, ty_name: &str) -> TokenStream {
+        let field = self.ident.to_string();
+        let name = self.member_name();
+        let required = self.attrs.required;
+        quote! {
+            {
+                ::bnr_xfs::xfs::xfs_struct::missing_member(#ty_name, #field, #name, #required)?;
+                Default::default()
+            }
+        }
+    }
+}
+
+pub(crate) fn expand(input: &DeriveInput) -> Result<TokenStream> {
+    let ident = &input.ident;
+    let ty_name = ident.to_string();
+    let container = ContainerAttrs::parse(ident, &input.attrs)?;
+    let xfs_name = &container.name;
+
+    if !input.generics.params.is_empty() {
+        return Err(syn::Error::new_spanned(
+            &input.generics,
+            "XfsStruct cannot be derived for generic types",
+        ));
+    }
+
+    let named = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    ident,
+                    "XfsStruct can only be derived for structs with named fields",
+                ))
+            }
+        },
+        _ => {
+            return Err(syn::Error::new_spanned(
+                ident,
+                "XfsStruct can only be derived for structs",
+            ))
+        }
+    };
+
+    let mut fields = Vec::with_capacity(named.len());
+    let mut skipped = false;
+    for field in named.iter() {
+        let attrs = FieldAttrs::parse(field)?;
+        if attrs.skip {
+            skipped = true;
+        } else {
+            fields.push(Field {
+                ident: field.ident.clone().expect("named field"),
+                ty: field.ty.clone(),
+                attrs,
+            });
+        }
+    }
+
+    let idents: Vec<_> = fields.iter().map(|f| &f.ident).collect();
+    // decoded values are prefixed to not shadow the generated locals, e.g. a `name` field
+    let locals: Vec<_> = idents.iter().map(|i| format_ident!("xfs_{i}")).collect();
+    let types: Vec<_> = fields.iter().map(|f| &f.ty).collect();
+    let names: Vec<_> = fields.iter().map(Field::member_name).collect();
+    let encodes: Vec<_> = fields.iter().map(Field::encode_value).collect();
+    let converts: Vec<_> = fields.iter().map(Field::convert_value).collect();
+    let decodes: Vec<_> = fields.iter().map(Field::decode).collect();
+    let member_errors: Vec<_> = fields.iter().map(|f| f.member_error(&ty_name)).collect();
+    let missing: Vec<_> = fields.iter().map(|f| f.missing(&ty_name)).collect();
+
+    // skipped fields take their value from the container `Default`, e.g. a list's maximum size
+    let rest = if skipped {
+        quote!(..Default::default())
+    } else {
+        quote!()
+    };
+
+    let decode_member = if fields.is_empty() {
+        quote!(reader.decode_struct(|_, _| Ok(false))?;)
+    } else {
+        quote! {
+            reader.decode_struct(|reader, name| {
+                // fields sharing an XFS name all take the first matching member
+                let start = *reader;
+                let mut decoded = false;
+                #(
+                if #locals.is_none() && name == #names {
+                    *reader = start;
+                    #locals = Some(#decodes.map_err(#member_errors)?);
+                    decoded = true;
+                }
+                )*
+                Ok(decoded)
+            })?;
+        }
+    };
+
+    let display = if container.no_display {
+        quote!()
+    } else {
+        let fmts: Vec<_> = fields
+            .iter()
+            .enumerate()
+            .map(|(i, f)| {
+                let sep = if i == 0 { "" } else { ", " };
+                let value = if f.attrs.kind == FieldKind::String {
+                    r#""{}""#
+                } else {
+                    "{}"
+                };
+                format!(r#"{sep}"{}": {value}"#, f.ident)
+            })
+            .collect();
+
+        quote! {
+            impl ::std::fmt::Display for #ident {
+                fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+                    write!(f, "{{")?;
+                    #(write!(f, #fmts, self.#idents)?;)*
+                    write!(f, "}}")
+                }
+            }
+        }
+    };
+
+    Ok(quote! {
+        impl #ident {
+            /// Gets the [XfsMember](::bnr_xfs::xfs::xfs_struct::XfsMember) name.
+            pub const fn xfs_name() -> &'static str {
+                #xfs_name
+            }
+        }
+
+        impl From<&#ident> for ::bnr_xfs::xfs::xfs_struct::XfsStruct {
+            #[allow(clippy::unnecessary_cast)]
+            fn from(val: &#ident) -> Self {
+                Self::create([
+                    #(::bnr_xfs::xfs::xfs_struct::XfsMember::create(#names, #encodes),)*
+                ])
+            }
+        }
+
+        impl From<#ident> for ::bnr_xfs::xfs::xfs_struct::XfsStruct {
+            fn from(val: #ident) -> Self {
+                (&val).into()
+            }
+        }
+
+        impl TryFrom<&::bnr_xfs::xfs::xfs_struct::XfsStruct> for #ident {
+            type Error = ::bnr_xfs::Error;
+
+            #[allow(clippy::unnecessary_cast)]
+            fn try_from(val: &::bnr_xfs::xfs::xfs_struct::XfsStruct) -> ::bnr_xfs::Result<Self> {
+                Ok(Self {
+                    #(
+                        #idents: match val.find_member(#names) {
+                            Ok(m) => {
+                                let value = m.value();
+                                #converts.map_err(#member_errors)?
+                            }
+                            Err(_err) => #missing,
+                        },
+                    )*
+                    #rest
+                })
+            }
+        }
+
+        impl TryFrom<::bnr_xfs::xfs::xfs_struct::XfsStruct> for #ident {
+            type Error = ::bnr_xfs::Error;
+
+            fn try_from(val: ::bnr_xfs::xfs::xfs_struct::XfsStruct) -> ::bnr_xfs::Result<Self> {
+                (&val).try_into()
+            }
+        }
+
+        impl From<&#ident> for ::bnr_xfs::xfs::value::XfsValue {
+            fn from(val: &#ident) -> Self {
+                Self::new().with_xfs_struct(val.into())
+            }
+        }
+
+        impl From<#ident> for ::bnr_xfs::xfs::value::XfsValue {
+            fn from(val: #ident) -> Self {
+                (&val).into()
+            }
+        }
+
+        impl TryFrom<&::bnr_xfs::xfs::value::XfsValue> for #ident {
+            type Error = ::bnr_xfs::Error;
+
+            fn try_from(val: &::bnr_xfs::xfs::value::XfsValue) -> ::bnr_xfs::Result<Self> {
+                let name = #ident::xfs_name();
+                val
+                    .xfs_struct()
+                    .ok_or(::bnr_xfs::Error::Xfs(format!(
+                        "Expected {name} XfsValue, have: {val}"
+                    )))?
+                    .try_into()
+            }
+        }
+
+        impl TryFrom<::bnr_xfs::xfs::value::XfsValue> for #ident {
+            type Error = ::bnr_xfs::Error;
+
+            fn try_from(val: ::bnr_xfs::xfs::value::XfsValue) -> ::bnr_xfs::Result<Self> {
+                (&val).try_into()
+            }
+        }
+
+        impl From<&#ident> for ::bnr_xfs::xfs::xfs_struct::XfsMember {
+            fn from(val: &#ident) -> Self {
+                Self::create(#ident::xfs_name(), val.into())
+            }
+        }
+
+        impl From<#ident> for ::bnr_xfs::xfs::xfs_struct::XfsMember {
+            fn from(val: #ident) -> Self {
+                (&val).into()
+            }
+        }
+
+        impl TryFrom<&::bnr_xfs::xfs::xfs_struct::XfsMember> for #ident {
+            type Error = ::bnr_xfs::Error;
+
+            fn try_from(val: &::bnr_xfs::xfs::xfs_struct::XfsMember) -> ::bnr_xfs::Result<Self> {
+                let name = #ident::xfs_name();
+                match (val.name(), val.value().xfs_struct()) {
+                    (n, Some(v)) if n == name => v.try_into(),
+                    _ => Err(::bnr_xfs::Error::Xfs(format!(
+                        "Expected {name} XfsMember, have: {val}"
+                    ))),
+                }
+            }
+        }
+
+        impl TryFrom<::bnr_xfs::xfs::xfs_struct::XfsMember> for #ident {
+            type Error = ::bnr_xfs::Error;
+
+            fn try_from(val: ::bnr_xfs::xfs::xfs_struct::XfsMember) -> ::bnr_xfs::Result<Self> {
+                (&val).try_into()
+            }
+        }
+
+        impl ::bnr_xfs::xfs::codec::XfsDecode for #ident {
+            #[allow(clippy::unnecessary_cast)]
+            fn decode_value(reader: &mut ::bnr_xfs::xfs::codec::XfsReader<'_>) -> ::bnr_xfs::Result<Self> {
+                #(let mut #locals: Option<#types> = None;)*
+
+                #decode_member
+
+                Ok(Self {
+                    #(
+                        #idents: match #locals {
+                            Some(v) => v,
+                            None => #missing,
+                        },
+                    )*
+                    #rest
+                })
+            }
+        }
+
+        #display
+    })
+}
@@ -0,0 +1,118 @@
+//! Benchmarks the streaming [codec](bnr_xfs::xfs::codec) against the `serde-xml-rust` path.
+//!
+//! Run with:
+//!
+//! ```bash
+//! cargo bench --bench codec
+//! ```
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use bnr_xfs::xfs::{
+    self, codec,
+    method_call::{XfsMethodCall, XfsMethodName},
+    method_response::{XfsMethodResponse, XfsMethodResponseStruct},
+    params::XfsParam,
+    value::XfsValue,
+};
+use bnr_xfs::{Capabilities, CashUnit, CdrStatus, Result};
+
+const WARMUP: Duration = Duration::from_millis(200);
+const MEASURE: Duration = Duration::from_secs(1);
+
+fn bench<T, F: FnMut() -> T>(name: &str, mut f: F) -> f64 {
+    let start = Instant::now();
+    while start.elapsed() < WARMUP {
+        black_box(f());
+    }
+
+    let mut iters = 0u64;
+    let start = Instant::now();
+    while start.elapsed() < MEASURE {
+        black_box(f());
+        iters += 1;
+    }
+
+    let ns = start.elapsed().as_nanos() as f64 / iters as f64;
+    println!("{name:<40} {ns:>12.0} ns/iter ({iters} iterations)");
+
+    ns
+}
+
+fn compare<T, S, C>(name: &str, serde_fn: S, codec_fn: C)
+where
+    S: FnMut() -> T,
+    C: FnMut() -> T,
+{
+    let serde_ns = bench(format!("{name}/serde").as_str(), serde_fn);
+    let codec_ns = bench(format!("{name}/codec").as_str(), codec_fn);
+
+    println!("{name:<40} {:>12.1}x speedup\n", serde_ns / codec_ns);
+}
+
+// wraps a bare `struct` fixture into a method response
+fn response_xml(struct_xml: &str) -> String {
+    let struct_xml = struct_xml.trim_start_matches(r#"<?xml version="1.0"?>"#);
+    format!(
+        r#"<?xml version="1.0"?><methodResponse><params><param><value>{struct_xml}</value></param></params></methodResponse>"#
+    )
+}
+
+fn serde_response(xml: &str) -> XfsMethodResponse {
+    xfs::from_str::<XfsMethodResponseStruct>(xml)
+        .unwrap()
+        .into_inner()
+}
+
+fn main() -> Result<()> {
+    let cash_unit_xml = include_str!("../tests/xml/query-cash-unit.xml");
+    let status_xml = response_xml(include_str!("../tests/xml/status.xml"));
+    let caps_xml = response_xml(include_str!("../tests/xml/capabilities.xml"));
+
+    compare(
+        "decode tree: queryCashUnit",
+        || serde_response(cash_unit_xml),
+        || codec::decode_method_response(cash_unit_xml).unwrap(),
+    );
+
+    compare(
+        "decode typed: CashUnit",
+        || CashUnit::try_from(serde_response(cash_unit_xml)).unwrap(),
+        || codec::decode_response::<CashUnit>(cash_unit_xml).unwrap(),
+    );
+
+    compare(
+        "decode typed: CdrStatus",
+        || CdrStatus::try_from(serde_response(&status_xml)).unwrap(),
+        || codec::decode_response::<CdrStatus>(&status_xml).unwrap(),
+    );
+
+    compare(
+        "decode typed: Capabilities",
+        || Capabilities::try_from(serde_response(&caps_xml)).unwrap(),
+        || codec::decode_response::<Capabilities>(&caps_xml).unwrap(),
+    );
+
+    let call = XfsMethodCall::create(
+        XfsMethodName::QueryCashUnit,
+        [XfsParam::create(XfsValue::new().with_base64("AAE="))],
+    );
+
+    compare(
+        "encode: methodCall",
+        || xfs::to_string(&call).unwrap(),
+        || codec::encode_method_call(&call, "UTF-8"),
+    );
+
+    let res = codec::decode_method_response(cash_unit_xml)?;
+    let res_struct = XfsMethodResponseStruct::from(&res);
+
+    compare(
+        "encode: queryCashUnit response",
+        || xfs::to_iso_string(&res_struct).unwrap(),
+        || codec::encode_method_response(&res, "ISO-8859-1"),
+    );
+
+    Ok(())
+}
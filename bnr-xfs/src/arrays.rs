@@ -1,60 +0,0 @@
-//! Work-around for const-generic arrays in serde from MikailBag:
-//!
-//! <https://github.com/serde-rs/serde/issues/1937#issuecomment-812137971>
-
-use std::{convert::TryInto, marker::PhantomData};
-
-use serde::{
-    de::{SeqAccess, Visitor},
-    ser::SerializeTuple,
-    Deserialize, Deserializer, Serialize, Serializer,
-};
-pub fn serialize<S: Serializer, T: Serialize, const N: usize>(
-    data: &[T; N],
-    ser: S,
-) -> Result<S::Ok, S::Error> {
-    let mut s = ser.serialize_tuple(N)?;
-    for item in data {
-        s.serialize_element(item)?;
-    }
-    s.end()
-}
-
-struct ArrayVisitor<T, const N: usize>(PhantomData<T>);
-
-impl<'de, T, const N: usize> Visitor<'de> for ArrayVisitor<T, N>
-where
-    T: Deserialize<'de>,
-{
-    type Value = [T; N];
-
-    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
-        formatter.write_str(&format!("an array of length {}", N))
-    }
-
-    #[inline]
-    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
-    where
-        A: SeqAccess<'de>,
-    {
-        // can be optimized using MaybeUninit
-        let mut data = Vec::with_capacity(N);
-        for _ in 0..N {
-            match (seq.next_element())? {
-                Some(val) => data.push(val),
-                None => return Err(serde::de::Error::invalid_length(N, &self)),
-            }
-        }
-        match data.try_into() {
-            Ok(arr) => Ok(arr),
-            Err(_) => Err(serde::de::Error::invalid_length(N, &self)),
-        }
-    }
-}
-pub fn deserialize<'de, D, T, const N: usize>(deserializer: D) -> Result<[T; N], D::Error>
-where
-    D: Deserializer<'de>,
-    T: Deserialize<'de>,
-{
-    deserializer.deserialize_tuple(N, ArrayVisitor::<T, N>(PhantomData))
-}
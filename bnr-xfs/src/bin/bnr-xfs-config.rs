@@ -0,0 +1,73 @@
+//! Backs up and restores the persistent configuration of a BNR device.
+//!
+//! Clones a golden configuration onto other devices with a versioned JSON document.
+
+use std::{env, fs, process};
+
+use bnr_xfs::{DeviceConfig, DeviceHandle, Error, Result};
+
+const USAGE: &str = "\
+Usage: bnr-xfs-config <COMMAND> [FILE]
+
+Backs up and restores the persistent BNR device configuration.
+
+Commands:
+  backup [FILE]   writes the device configuration to FILE, or stdout
+  diff <FILE>     prints the changes restoring FILE would make
+  restore <FILE>  restores the configuration in FILE onto the device
+
+Options:
+  -h, --help      print this help";
+
+fn open() -> Result<DeviceHandle> {
+    DeviceHandle::open(None, None, None)
+}
+
+fn restore(file: &str, dry_run: bool) -> Result<()> {
+    let config = DeviceConfig::from_json(fs::read_to_string(file)?.as_str())?;
+    let changes = open()?.restore_config(&config, dry_run)?;
+
+    if changes.is_empty() {
+        println!("Device configuration is up-to-date");
+    } else {
+        for change in changes.iter() {
+            println!("{change}");
+        }
+
+        let verb = if dry_run { "would change" } else { "changed" };
+        println!("{verb} {} setting(s)", changes.len());
+    }
+
+    Ok(())
+}
+
+fn run() -> Result<()> {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let args: Vec<&str> = args.iter().map(|a| a.as_str()).collect();
+
+    match args.as_slice() {
+        ["-h" | "--help", ..] => {
+            println!("{USAGE}");
+            Ok(())
+        }
+        ["backup"] => {
+            println!("{}", open()?.backup_config()?.to_json()?);
+            Ok(())
+        }
+        ["backup", file] => {
+            fs::write(file, open()?.backup_config()?.to_json()?)?;
+            Ok(())
+        }
+        ["diff", file] => restore(file, true),
+        ["restore", file] => restore(file, false),
+        _ => Err(Error::Parsing(format!("invalid arguments: {args:?}"))),
+    }
+}
+
+fn main() {
+    if let Err(err) = run() {
+        eprintln!("bnr-xfs-config: {err}");
+        eprintln!("{USAGE}");
+        process::exit(1);
+    }
+}
@@ -0,0 +1,198 @@
+//! Decodes captured BNR XFS messages into their typed structures.
+//!
+//! Reads raw XFS XML messages, or log files with `Raw ... response:` lines, and prints each
+//! message as JSON or a table.
+
+use std::io::{self, Read};
+use std::{env, fs, process};
+
+use bnr_xfs::xfs::method_call::XfsMethodName;
+use bnr_xfs::{parse_capture_bytes, CapturedMessage, Error, Result};
+
+const USAGE: &str = "\
+Usage: bnr-xfs-decode [OPTIONS] [FILE]...
+
+Decodes BNR XFS messages from raw XML, log files, or captures.
+Reads from stdin if no FILE is given.
+
+Options:
+  -f, --format <json|table>  output format [default: json]
+  -m, --method <NAME>        method for responses without one, e.g. bnr.getstatus
+  -h, --help                 print this help";
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+enum Format {
+    #[default]
+    Json,
+    Table,
+}
+
+#[derive(Debug, Default)]
+struct Args {
+    format: Format,
+    method: Option<XfsMethodName>,
+    files: Vec<String>,
+}
+
+fn parse_args() -> Result<Args> {
+    let mut args = Args::default();
+    let mut iter = env::args().skip(1);
+
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "-h" | "--help" => {
+                println!("{USAGE}");
+                process::exit(0);
+            }
+            "-f" | "--format" => {
+                args.format = match iter.next().as_deref() {
+                    Some("json") => Format::Json,
+                    Some("table") => Format::Table,
+                    f => return Err(Error::Parsing(format!("invalid format: {f:?}"))),
+                };
+            }
+            "-m" | "--method" => {
+                let method = iter
+                    .next()
+                    .ok_or(Error::Parsing("missing method name".into()))?;
+                args.method = Some(XfsMethodName::try_from(method.as_str())?);
+            }
+            _ if arg.starts_with('-') && arg.len() > 1 => {
+                return Err(Error::Parsing(format!("unknown option: {arg}")));
+            }
+            _ => args.files.push(arg),
+        }
+    }
+
+    Ok(args)
+}
+
+// Reads the raw bytes, each message is decoded with its declared encoding.
+fn read_input(files: &[String]) -> Result<Vec<u8>> {
+    let mut input = Vec::new();
+    if files.is_empty() {
+        io::stdin().read_to_end(&mut input)?;
+    } else {
+        for file in files {
+            input.extend_from_slice(&fs::read(file)?);
+            input.push(b'\n');
+        }
+    }
+    Ok(input)
+}
+
+fn message_json(index: usize, msg: &CapturedMessage) -> serde_json::Value {
+    let method = msg
+        .method()
+        .map(|m| serde_json::Value::from(<&str>::from(m)))
+        .unwrap_or_default();
+
+    match msg.decode() {
+        Ok(decoded) => serde_json::json!({
+            "index": index,
+            "method": method,
+            "type": decoded.type_name(),
+            "value": decoded.to_json(),
+        }),
+        Err(err) => serde_json::json!({
+            "index": index,
+            "method": method,
+            "error": format!("{err}"),
+        }),
+    }
+}
+
+// Flattens nested JSON into `path = value` rows.
+fn flatten(path: String, value: &serde_json::Value, rows: &mut Vec<(String, String)>) {
+    match value {
+        serde_json::Value::Object(map) if !map.is_empty() => {
+            for (key, val) in map {
+                let key = if path.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{path}.{key}")
+                };
+                flatten(key, val, rows);
+            }
+        }
+        serde_json::Value::Array(list) if !list.is_empty() => {
+            for (i, val) in list.iter().enumerate() {
+                flatten(format!("{path}[{i}]"), val, rows);
+            }
+        }
+        serde_json::Value::String(val) => rows.push((path, val.clone())),
+        val => rows.push((path, format!("{val}"))),
+    }
+}
+
+fn print_table(msg: &serde_json::Value) {
+    let title = ["index", "method", "type"]
+        .iter()
+        .filter_map(|k| msg.get(k))
+        .filter(|v| !v.is_null())
+        .map(|v| {
+            v.as_str()
+                .map(String::from)
+                .unwrap_or_else(|| format!("{v}"))
+        })
+        .collect::<Vec<String>>()
+        .join(" ");
+    println!("# {title}");
+
+    let mut rows = Vec::new();
+    match msg.get("error") {
+        Some(err) => flatten("error".into(), err, &mut rows),
+        None => flatten(String::new(), &msg["value"], &mut rows),
+    }
+
+    let width = rows.iter().map(|(k, _)| k.len()).max().unwrap_or(0);
+    for (key, val) in rows {
+        println!("{key:<width$}  {val}");
+    }
+    println!();
+}
+
+fn run() -> Result<bool> {
+    let args = parse_args()?;
+    let input = read_input(&args.files)?;
+
+    let mut messages = parse_capture_bytes(input.as_ref());
+    if let Some(method) = args.method {
+        for msg in messages
+            .iter_mut()
+            .filter(|m| m.is_response() && m.method().is_none())
+        {
+            msg.set_method(method);
+        }
+    }
+
+    if messages.is_empty() {
+        return Err(Error::Parsing("no XFS messages found in the input".into()));
+    }
+
+    let decoded: Vec<serde_json::Value> = messages
+        .iter()
+        .enumerate()
+        .map(|(i, msg)| message_json(i, msg))
+        .collect();
+    let ok = decoded.iter().all(|m| m.get("error").is_none());
+
+    match args.format {
+        Format::Json => println!("{}", serde_json::to_string_pretty(&decoded)?),
+        Format::Table => decoded.iter().for_each(print_table),
+    }
+
+    Ok(ok)
+}
+
+fn main() {
+    match run() {
+        Ok(true) => (),
+        Ok(false) => process::exit(1),
+        Err(err) => {
+            eprintln!("bnr-xfs-decode: {err}");
+            eprintln!("{USAGE}");
+            process::exit(2);
+        }
+    }
+}
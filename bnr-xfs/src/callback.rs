@@ -0,0 +1,76 @@
+//! Callback types used by the XFS API for asynchronous device events.
+
+use crate::currency::CashOrder;
+use crate::{Error, Result};
+
+/// Trait for arguments to state change callbacks used by the XFS API.
+pub trait CallbackArg {
+    fn value(&self) -> i32;
+    fn is_null(&self) -> bool;
+    fn is_cash_order(&self) -> bool;
+    fn as_cash_order(&self) -> Result<&CashOrder>;
+    fn as_cash_order_mut(&mut self) -> Result<&mut CashOrder>;
+}
+
+impl CallbackArg for () {
+    fn value(&self) -> i32 {
+        0
+    }
+
+    fn is_null(&self) -> bool {
+        true
+    }
+
+    fn is_cash_order(&self) -> bool {
+        false
+    }
+
+    fn as_cash_order(&self) -> Result<&CashOrder> {
+        Err(Error::Xfs(
+            "Expected CashOrder CallbackArg, have: null".into(),
+        ))
+    }
+
+    fn as_cash_order_mut(&mut self) -> Result<&mut CashOrder> {
+        Err(Error::Xfs(
+            "Expected CashOrder CallbackArg, have: null".into(),
+        ))
+    }
+}
+
+/// Function signature for the `Operation Completed` callback used by the XFS API.
+///
+/// Handles device-sent messages indicating an asynchronous operation has completed.
+///
+/// # Parameters
+///
+/// - `call_id`: callback ID returned by the initial async call
+/// - `operation_id`: async operation ID to uniquely identify the type of call
+/// - `result`: the result status of the call
+/// - `extended_result`: the extended result of the call
+/// - `callback_arg`: callback call argument (may be the `unit` type if not supplied)
+pub type OperationCompletedFn = fn(i32, i32, i32, i32, &mut dyn CallbackArg);
+
+/// Function signature for the `Intermediate Occurred` callback used by the XFS API.
+///
+/// Handles an intermediate state transition occurred during an ongoing transaction.
+///
+/// # Parameters
+///
+/// - `call_id`: callback ID returned by the initial async call
+/// - `operation_id`: async operation ID to uniquely identify the type of call
+/// - `reason`: specifies the reason for the intermediate event
+/// - `callback_arg`: callback call argument (may be the `unit` type if not supplied)
+pub type IntermediateOccurredFn = fn(i32, i32, i32, &mut dyn CallbackArg);
+
+/// Function signature for the `Status Occured` callback used by the XFS API.
+///
+/// Handles a status event that occurred on the device.
+///
+/// # Parameters
+///
+/// - `status`: the status that occurred on the device (e.g. bill inserted)
+/// - `result`: the result of the status event
+/// - `extended_result`: the extended result of the status event
+/// - `callback_arg`: callback call argument (may be the `unit` type if not supplied)
+pub type StatusOccurredFn = fn(i32, i32, i32, &mut dyn CallbackArg);
@@ -1,6 +1,6 @@
 use std::fmt;
 
-use crate::{create_xfs_i4, impl_xfs_struct, xfs::OperationId};
+use crate::{create_xfs_i4, xfs::OperationId, XfsStruct};
 
 create_xfs_i4!(
     IntermediateIdentificationId,
@@ -10,7 +10,10 @@ create_xfs_i4!(
 
 /// Represents a response to a callback call made by the device.
 #[repr(C)]
-#[derive(Clone, Copy, Debug, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, serde::Deserialize, serde::Serialize, XfsStruct,
+)]
+#[xfs(name = "callbackResponse", no_display)]
 pub struct CallbackIntermediateResponse {
     operation_id: OperationId,
     identification_id: IntermediateIdentificationId,
@@ -75,8 +78,6 @@ impl fmt::Display for CallbackIntermediateResponse {
     }
 }
 
-impl_xfs_struct!(CallbackIntermediateResponse, "callbackResponse", [operation_id: OperationId, identification_id: IntermediateIdentificationId]);
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1,6 +1,4 @@
-use std::fmt;
-
-use crate::{create_xfs_i4, impl_xfs_struct, xfs::OperationId};
+use crate::{create_xfs_i4, xfs::OperationId, XfsStruct};
 
 create_xfs_i4!(
     OperationIdentificationId,
@@ -10,7 +8,10 @@ create_xfs_i4!(
 
 /// Represents a response to a callback call made by the device.
 #[repr(C)]
-#[derive(Clone, Copy, Debug, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, serde::Deserialize, serde::Serialize, XfsStruct,
+)]
+#[xfs(name = "callbackResponse")]
 pub struct CallbackOperationResponse {
     operation_id: OperationId,
     identification_id: OperationIdentificationId,
@@ -66,17 +67,6 @@ impl CallbackOperationResponse {
     }
 }
 
-impl fmt::Display for CallbackOperationResponse {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{{")?;
-        write!(f, r#""operation_id": {}, "#, self.operation_id())?;
-        write!(f, r#""identification_id": {}"#, self.identification_id())?;
-        write!(f, "}}")
-    }
-}
-
-impl_xfs_struct!(CallbackOperationResponse, "callbackResponse", [operation_id: OperationId, identification_id: OperationIdentificationId]);
-
 #[cfg(test)]
 mod tests {
     use super::*;
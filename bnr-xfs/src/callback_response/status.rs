@@ -1,6 +1,6 @@
 use std::fmt;
 
-use crate::{create_xfs_i4, impl_xfs_struct};
+use crate::{create_xfs_i4, XfsStruct};
 
 create_xfs_i4!(
     CallbackStatus,
@@ -18,7 +18,10 @@ create_xfs_i4!(
 
 /// Represents a response to a callback call made by the device.
 #[repr(C)]
-#[derive(Clone, Copy, Debug, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, serde::Deserialize, serde::Serialize, XfsStruct,
+)]
+#[xfs(name = "callbackResponse", no_display)]
 pub struct CallbackStatusResponse {
     status: CallbackStatus,
     result: CallbackResult,
@@ -83,8 +86,6 @@ impl fmt::Display for CallbackStatusResponse {
     }
 }
 
-impl_xfs_struct!(CallbackStatusResponse, "callbackResponse", [status: CallbackStatus, result: CallbackResult]);
-
 #[cfg(test)]
 mod tests {
     use super::*;
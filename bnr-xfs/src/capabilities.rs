@@ -5,7 +5,7 @@ use crate::xfs::{
     method_response::XfsMethodResponse,
     params::{XfsParam, XfsParams},
 };
-use crate::{create_xfs_bool, create_xfs_i4, impl_xfs_struct, Error, Result};
+use crate::{create_xfs_bool, create_xfs_i4, Error, Result, XfsStruct};
 
 mod anti_fishing_level;
 mod cdr_type;
@@ -108,7 +108,8 @@ create_xfs_bool!(RejectViaOutlet, "rejectViaOutlet", "Specifies whether measured
 /// - `auto_retract`
 /// - `reject_via_outlet`
 #[repr(C)]
-#[derive(Clone, Copy, Debug, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+#[derive(Clone, Debug, Default, PartialEq, serde::Deserialize, serde::Serialize, XfsStruct)]
+#[xfs(name = "capabilities", no_display)]
 pub struct Capabilities {
     pub auto_present: AutoPresent,
     ///  Type of device. Always [CdrType::ATM](CdrType) for the BNR.
@@ -319,39 +320,3 @@ impl fmt::Display for Capabilities {
         write!(f, "}}")
     }
 }
-
-impl_xfs_struct!(
-    Capabilities,
-    "capabilities",
-    [
-        auto_present: AutoPresent,
-        cd_type: CdrType,
-        euro_art6_capability: EuroArt6Capability,
-        trusted_user: TrustedUser,
-        max_in_bills: MaxInBills,
-        max_out_bills: MaxOutBills,
-        shutter_cmd: ShutterCmd,
-        retract: Retract,
-        safe_door_cmd: SafeDoorCmd,
-        cash_box: CashBox,
-        refill: Refill,
-        dispense: Dispense,
-        deposit: Deposit,
-        intermediate_stacker: IntermediateStacker,
-        bills_taken_sensor: BillsTakenSensor,
-        escrow: Escrow,
-        escrow_size: EscrowSize,
-        detector: Detector,
-        default_rollback_position: DefaultRollbackPosition,
-        position_capabilities_list: CdrPositionCapabilitiesList,
-        self_test_mode: SelfTestMode,
-        recognition_sensor_type: RecognitionSensorType,
-        anti_fishing_level: AntiFishingLevel,
-        allow_usb_front_switch: AllowUsbFrontSwitch,
-        reporting_mode: ReportingMode,
-        report_usb_consumption: ReportUsbConsumption,
-        auto_retract_at_inlet: AutoRetractAtInlet,
-        reject_via_outlet: RejectViaOutlet,
-        secured_comm_level: SecuredCommLevel
-    ]
-);
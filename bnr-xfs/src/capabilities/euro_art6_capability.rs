@@ -1,6 +1,4 @@
-use std::fmt;
-
-use crate::{create_xfs_bool, impl_xfs_struct};
+use crate::{create_xfs_bool, XfsStruct};
 
 create_xfs_bool!(Category2, "category2", "Euro Article 6, Category 2 support");
 create_xfs_bool!(Category3, "category3", "Euro Article 6, Category 3 support");
@@ -10,7 +8,10 @@ create_xfs_bool!(Unfit, "unfit", "Euro Article 6, Unfit classification");
 ///
 /// See [Capabilities].
 #[repr(C)]
-#[derive(Clone, Copy, Debug, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, serde::Deserialize, serde::Serialize, XfsStruct,
+)]
+#[xfs(name = "eurArt6Capabilities")]
 pub struct EuroArt6Capability {
     pub category2: Category2,
     pub category3: Category3,
@@ -27,23 +28,3 @@ impl EuroArt6Capability {
         }
     }
 }
-
-impl_xfs_struct!(
-    EuroArt6Capability,
-    "eurArt6Capabilities",
-    [
-        category2: Category2,
-        category3: Category3,
-        unfit: Unfit
-    ]
-);
-
-impl fmt::Display for EuroArt6Capability {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{{")?;
-        write!(f, r#""category2": {}, "#, self.category2)?;
-        write!(f, r#""category3": {}, "#, self.category3)?;
-        write!(f, r#""unfit": {}"#, self.unfit)?;
-        write!(f, "}}")
-    }
-}
@@ -1,7 +1,7 @@
 use std::fmt;
 
 use crate::xfs::{value::XfsValue, xfs_struct::XfsMember};
-use crate::{Error, Result};
+use crate::{impl_xfs_decode, Error, Result};
 
 /// Recognition sensor type identification. Always ‘B’ in the BNR.
 #[repr(C)]
@@ -100,6 +100,8 @@ impl TryFrom<&XfsValue> for RecognitionSensorType {
     }
 }
 
+impl_xfs_decode!(RecognitionSensorType);
+
 impl From<&RecognitionSensorType> for XfsMember {
     fn from(val: &RecognitionSensorType) -> Self {
         Self::create(RecognitionSensorType::xfs_name(), val.into())
@@ -0,0 +1,489 @@
+//! Types for decoding captured XFS messages, e.g. from device logs.
+
+use std::fmt;
+use std::ops::Range;
+
+use crate::capabilities::Capabilities;
+use crate::cash_unit::CashUnit;
+use crate::currency::{CashOrder, Currency, Denomination};
+use crate::denominations::{BillsetIdList, DenominationList};
+use crate::history::{
+    BillAcceptanceHistory, BillDispenseHistory, SystemFailureHistory, SystemRestartHistory,
+    SystemUseHistory,
+};
+use crate::json::JsonStr;
+use crate::status::CdrStatus;
+use crate::xfs::{
+    self, codec,
+    method_call::{XfsMethodCall, XfsMethodName},
+    method_response::XfsMethodResponse,
+    OperationId,
+};
+use crate::{Error, Result};
+
+const METHOD_CALL: &str = "methodCall";
+const METHOD_RESPONSE: &str = "methodResponse";
+
+/// Represents a single XFS message found in a capture.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CapturedMessage {
+    method: Option<XfsMethodName>,
+    xml: String,
+}
+
+impl CapturedMessage {
+    /// Creates a new [CapturedMessage].
+    pub const fn new() -> Self {
+        Self {
+            method: None,
+            xml: String::new(),
+        }
+    }
+
+    /// Creates a new [CapturedMessage] from the provided parameters.
+    pub fn create<S: Into<String>>(method: Option<XfsMethodName>, xml: S) -> Self {
+        Self {
+            method,
+            xml: xml.into(),
+        }
+    }
+
+    /// Gets the [XfsMethodName] the message belongs to, if known.
+    ///
+    /// For responses, this is the method of the call that the device responded to.
+    pub const fn method(&self) -> Option<XfsMethodName> {
+        self.method
+    }
+
+    /// Sets the [XfsMethodName] the message belongs to.
+    pub fn set_method(&mut self, method: XfsMethodName) {
+        self.method = Some(method);
+    }
+
+    /// Builder function that sets the [XfsMethodName] the message belongs to.
+    pub fn with_method(mut self, method: XfsMethodName) -> Self {
+        self.set_method(method);
+        self
+    }
+
+    /// Gets the raw XML message.
+    pub fn xml(&self) -> &str {
+        self.xml.as_str()
+    }
+
+    /// Gets whether the message is an [XfsMethodCall].
+    pub fn is_call(&self) -> bool {
+        root_element(self.xml()) == Some(METHOD_CALL)
+    }
+
+    /// Gets whether the message is an [XfsMethodResponse].
+    pub fn is_response(&self) -> bool {
+        root_element(self.xml()) == Some(METHOD_RESPONSE)
+    }
+
+    /// Decodes the message into its typed [DecodedMessage].
+    ///
+    /// Responses to methods without a typed result, or with an unknown method, decode to the
+    /// generic [XfsMethodResponse].
+    pub fn decode(&self) -> Result<DecodedMessage> {
+        match root_element(self.xml()) {
+            Some(METHOD_CALL) => {
+                let call = codec::decode_method_call(self.xml())?;
+                match call.name() {
+                    Ok(name) if is_callback(name) => {
+                        Ok(DecodedMessage::Callback(CallbackCall::try_from(&call)?))
+                    }
+                    _ => Ok(DecodedMessage::Call(call)),
+                }
+            }
+            Some(METHOD_RESPONSE) => {
+                let res = codec::decode_method_response(self.xml())?;
+                if res.is_fault() {
+                    return Ok(DecodedMessage::Response(res));
+                }
+
+                match self.method {
+                    Some(XfsMethodName::GetStatus) => Ok(DecodedMessage::Status(res.try_into()?)),
+                    Some(XfsMethodName::QueryCashUnit) => {
+                        Ok(DecodedMessage::CashUnit(res.try_into()?))
+                    }
+                    Some(XfsMethodName::GetCapabilities | XfsMethodName::SetCapabilities) => {
+                        Ok(DecodedMessage::Capabilities(res.try_into()?))
+                    }
+                    Some(XfsMethodName::QueryDenominations) => {
+                        Ok(DecodedMessage::Denominations(res.try_into()?))
+                    }
+                    Some(XfsMethodName::QueryBillsetIds) => {
+                        Ok(DecodedMessage::BillsetIds(res.try_into()?))
+                    }
+                    Some(XfsMethodName::GetBillAcceptanceHistory) => {
+                        Ok(DecodedMessage::BillAcceptanceHistory(res.try_into()?))
+                    }
+                    Some(XfsMethodName::GetBillDispenseHistory) => {
+                        Ok(DecodedMessage::BillDispenseHistory(res.try_into()?))
+                    }
+                    Some(XfsMethodName::GetFailureHistory) => {
+                        Ok(DecodedMessage::FailureHistory(res.try_into()?))
+                    }
+                    Some(XfsMethodName::GetRestartHistory) => {
+                        Ok(DecodedMessage::RestartHistory(res.try_into()?))
+                    }
+                    Some(XfsMethodName::GetUseHistory) => {
+                        Ok(DecodedMessage::UseHistory(res.try_into()?))
+                    }
+                    _ => Ok(DecodedMessage::Response(res)),
+                }
+            }
+            _ => Err(Error::Xfs(format!(
+                "expected <{METHOD_CALL}> or <{METHOD_RESPONSE}> message, have: {}",
+                self.xml().chars().take(64).collect::<String>()
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for CapturedMessage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{{")?;
+        match self.method.as_ref() {
+            Some(method) => write!(f, r#""method": {method}, "#)?,
+            None => write!(f, r#""method": null, "#)?,
+        }
+        write!(f, r#""xml": {}"#, JsonStr(&self.xml))?;
+        write!(f, "}}")
+    }
+}
+
+/// Represents an asynchronous callback call sent by the device.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CallbackCall {
+    name: XfsMethodName,
+    call_id: i32,
+    operation_id: OperationId,
+    result: i32,
+    ext_result: i32,
+    cash_order: Option<CashOrder>,
+}
+
+impl CallbackCall {
+    /// Creates a new [CallbackCall].
+    pub const fn new() -> Self {
+        Self {
+            name: XfsMethodName::StatusOccurred,
+            call_id: 0,
+            operation_id: OperationId::new(),
+            result: 0,
+            ext_result: 0,
+            cash_order: None,
+        }
+    }
+
+    /// Gets the callback [XfsMethodName].
+    pub const fn name(&self) -> XfsMethodName {
+        self.name
+    }
+
+    /// Gets the callback ID returned by the call that started the operation.
+    ///
+    /// For `StatusOccurred` callbacks, this is the status code.
+    pub const fn call_id(&self) -> i32 {
+        self.call_id
+    }
+
+    /// Gets the [OperationId].
+    pub const fn operation_id(&self) -> OperationId {
+        self.operation_id
+    }
+
+    /// Gets the operation result.
+    ///
+    /// For `IntermediateOccurred` callbacks, this is the intermediate event reason.
+    pub const fn result(&self) -> i32 {
+        self.result
+    }
+
+    /// Gets the operation extended result.
+    pub const fn ext_result(&self) -> i32 {
+        self.ext_result
+    }
+
+    /// Gets the [CashOrder] argument, if present.
+    pub const fn cash_order(&self) -> Option<&CashOrder> {
+        self.cash_order.as_ref()
+    }
+}
+
+impl TryFrom<&XfsMethodCall> for CallbackCall {
+    type Error = Error;
+
+    fn try_from(val: &XfsMethodCall) -> Result<Self> {
+        let cash_order = match val.xfs_struct() {
+            Ok(xfs)
+                if xfs.find_member(Currency::xfs_name()).is_ok()
+                    && xfs.find_member(Denomination::xfs_name()).is_ok() =>
+            {
+                Some(CashOrder::try_from(&xfs)?)
+            }
+            _ => None,
+        };
+
+        Ok(Self {
+            name: val.name()?,
+            call_id: val.call_id()?,
+            operation_id: val.operation_id()?,
+            result: val.result().unwrap_or(0),
+            ext_result: val.ext_result().unwrap_or(0),
+            cash_order,
+        })
+    }
+}
+
+impl TryFrom<XfsMethodCall> for CallbackCall {
+    type Error = Error;
+
+    fn try_from(val: XfsMethodCall) -> Result<Self> {
+        (&val).try_into()
+    }
+}
+
+impl fmt::Display for CallbackCall {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{{")?;
+        write!(f, r#""name": {}, "#, self.name)?;
+        write!(f, r#""call_id": {}, "#, self.call_id)?;
+        write!(f, r#""operation_id": {}, "#, self.operation_id)?;
+        write!(f, r#""result": {}, "#, self.result)?;
+        write!(f, r#""ext_result": {}, "#, self.ext_result)?;
+        match self.cash_order.as_ref() {
+            Some(cash_order) => write!(f, r#""cash_order": {cash_order}"#)?,
+            None => write!(f, r#""cash_order": null"#)?,
+        }
+        write!(f, "}}")
+    }
+}
+
+/// Represents a [CapturedMessage] decoded into its typed structure.
+#[derive(Clone, Debug, PartialEq)]
+pub enum DecodedMessage {
+    Status(CdrStatus),
+    CashUnit(CashUnit),
+    Capabilities(Capabilities),
+    Denominations(DenominationList),
+    BillsetIds(BillsetIdList),
+    BillAcceptanceHistory(BillAcceptanceHistory),
+    BillDispenseHistory(BillDispenseHistory),
+    FailureHistory(SystemFailureHistory),
+    RestartHistory(SystemRestartHistory),
+    UseHistory(SystemUseHistory),
+    Callback(CallbackCall),
+    Call(XfsMethodCall),
+    Response(XfsMethodResponse),
+}
+
+impl DecodedMessage {
+    /// Gets the name of the decoded type.
+    pub const fn type_name(&self) -> &'static str {
+        match self {
+            Self::Status(_) => "CdrStatus",
+            Self::CashUnit(_) => "CashUnit",
+            Self::Capabilities(_) => "Capabilities",
+            Self::Denominations(_) => "DenominationList",
+            Self::BillsetIds(_) => "BillsetIdList",
+            Self::BillAcceptanceHistory(_) => "BillAcceptanceHistory",
+            Self::BillDispenseHistory(_) => "BillDispenseHistory",
+            Self::FailureHistory(_) => "SystemFailureHistory",
+            Self::RestartHistory(_) => "SystemRestartHistory",
+            Self::UseHistory(_) => "SystemUseHistory",
+            Self::Callback(_) => "CallbackCall",
+            Self::Call(_) => "XfsMethodCall",
+            Self::Response(_) => "XfsMethodResponse",
+        }
+    }
+
+    /// Converts the decoded message into a JSON value.
+    ///
+    /// Falls back to a JSON string of the [Display](fmt::Display) output if it is not valid JSON.
+    pub fn to_json(&self) -> serde_json::Value {
+        let display = format!("{self}");
+        serde_json::from_str(display.as_str()).unwrap_or(serde_json::Value::String(display))
+    }
+}
+
+impl fmt::Display for DecodedMessage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Status(msg) => write!(f, "{msg}"),
+            Self::CashUnit(msg) => write!(f, "{msg}"),
+            Self::Capabilities(msg) => write!(f, "{msg}"),
+            Self::Denominations(msg) => write!(f, "{msg}"),
+            Self::BillsetIds(msg) => write!(f, "{msg}"),
+            Self::BillAcceptanceHistory(msg) => write!(f, "{msg}"),
+            Self::BillDispenseHistory(msg) => write!(f, "{msg}"),
+            Self::FailureHistory(msg) => write!(f, "{msg}"),
+            Self::RestartHistory(msg) => write!(f, "{msg}"),
+            Self::UseHistory(msg) => write!(f, "{msg}"),
+            Self::Callback(msg) => write!(f, "{msg}"),
+            Self::Call(msg) => write!(f, "{msg}"),
+            Self::Response(msg) => write!(f, "{msg}"),
+        }
+    }
+}
+
+/// Finds all XFS messages in a capture.
+///
+/// The capture can be raw XML messages, or log output containing `Raw <method> response: <xml>`
+/// and `Raw callback call: <xml>` lines. Any other text between messages is ignored.
+///
+/// Responses take their [XfsMethodName] from the log line, or otherwise from the closest
+/// preceding non-callback [XfsMethodCall] in the capture.
+pub fn parse_capture(capture: &str) -> Vec<CapturedMessage> {
+    parse_messages(capture.as_bytes(), |xml| Ok(capture[xml].into()))
+}
+
+/// Finds all XFS messages in a raw capture, e.g. read from a file.
+///
+/// Raw messages are decoded with the encoding from their XML declaration, see
+/// [decode](crate::xfs::decode). Messages from `Raw ...:` log lines were already decoded before
+/// logging, and are read as `UTF-8`. Messages that fail to decode are skipped.
+///
+/// See [parse_capture] for the capture formats.
+pub fn parse_capture_bytes(capture: &[u8]) -> Vec<CapturedMessage> {
+    parse_messages(capture, |xml| {
+        let bytes = &capture[xml.clone()];
+        if log_line(&capture[..xml.start]).is_some() {
+            Ok(std::str::from_utf8(bytes)?.into())
+        } else {
+            xfs::decode(bytes)
+        }
+    })
+}
+
+fn parse_messages<F>(capture: &[u8], decode: F) -> Vec<CapturedMessage>
+where
+    F: Fn(Range<usize>) -> Result<String>,
+{
+    let mut messages = Vec::new();
+    let mut last_call: Option<XfsMethodName> = None;
+    let mut pos = 0;
+
+    while let Some((start, root)) = next_message(capture, pos) {
+        let close = format!("</{root}>");
+        let end = match find(&capture[start..], close.as_bytes()) {
+            Some(end) => start + end + close.len(),
+            None => {
+                log::warn!("Ignoring truncated <{root}> message at offset {start}");
+                break;
+            }
+        };
+        pos = end;
+
+        let xml = match decode(start..end) {
+            Ok(xml) => xml,
+            Err(err) => {
+                log::warn!("Ignoring <{root}> message at offset {start}: {err}");
+                continue;
+            }
+        };
+        let mut msg = CapturedMessage::create(None, xml);
+
+        if root == METHOD_CALL {
+            if let Ok(call) = codec::decode_method_call(msg.xml()) {
+                if let Ok(name) = call.name() {
+                    msg.set_method(name);
+                    if !is_callback(name) {
+                        last_call = Some(name);
+                    }
+                }
+            }
+        } else if let Some(method) = logged_method(&capture[..start]).or(last_call.take()) {
+            msg.set_method(method);
+        }
+
+        messages.push(msg);
+    }
+
+    messages
+}
+
+fn is_callback(name: XfsMethodName) -> bool {
+    matches!(
+        name,
+        XfsMethodName::OperationCompleteOccurred
+            | XfsMethodName::IntermediateOccurred
+            | XfsMethodName::StatusOccurred
+    )
+}
+
+// Finds the start of the next message, including any XML declaration, and its root element.
+//
+// The markers are all ASCII, so the search works on the raw bytes of any supported encoding.
+fn next_message(capture: &[u8], pos: usize) -> Option<(usize, &'static str)> {
+    let mut search = pos;
+    loop {
+        let tag = search + capture[search..].iter().position(|&b| b == b'<')?;
+        let rest = &capture[tag..];
+        let root = if rest.starts_with(b"<methodCall>") {
+            METHOD_CALL
+        } else if rest.starts_with(b"<methodResponse>") {
+            METHOD_RESPONSE
+        } else {
+            search = tag + 1;
+            continue;
+        };
+
+        // include an XML declaration directly preceding the root element
+        let start = rfind(&capture[pos..tag], b"<?xml")
+            .map(|decl| pos + decl)
+            .filter(|&decl| {
+                find(&capture[decl..tag], b"?>")
+                    .map(|end| capture[decl + end + 2..tag].trim_ascii().is_empty())
+                    .unwrap_or(false)
+            })
+            .unwrap_or(tag);
+
+        return Some((start, root));
+    }
+}
+
+// Gets the `<method> response:` or `callback call:` text of a `Raw ...` log line preceding a
+// message.
+fn log_line(prefix: &[u8]) -> Option<&str> {
+    let line = &prefix[prefix
+        .iter()
+        .rposition(|&b| b == b'\n')
+        .map(|n| n + 1)
+        .unwrap_or(0)..];
+    let line = std::str::from_utf8(&line[rfind(line, b"Raw ")? + 4..]).ok()?;
+
+    Some(line.trim_end()).filter(|l| l.ends_with(':'))
+}
+
+// Gets the method name from a `Raw <method> response:` log line preceding a message.
+fn logged_method(prefix: &[u8]) -> Option<XfsMethodName> {
+    let method = log_line(prefix)?.strip_suffix(" response:")?;
+
+    XfsMethodName::try_from(method.trim().trim_matches('"')).ok()
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|w| w == needle)
+}
+
+fn rfind(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).rposition(|w| w == needle)
+}
+
+fn root_element(xml: &str) -> Option<&'static str> {
+    let mut xml = xml.trim_start();
+    if xml.starts_with("<?xml") {
+        xml = xml[xml.find("?>")? + 2..].trim_start();
+    }
+
+    if xml.starts_with("<methodCall>") {
+        Some(METHOD_CALL)
+    } else if xml.starts_with("<methodResponse>") {
+        Some(METHOD_RESPONSE)
+    } else {
+        None
+    }
+}
@@ -0,0 +1,331 @@
+//! Types for tracking `CashIn` transactions.
+
+use std::fmt;
+
+use crate::currency::{CashOrder, CuType, Currency, CurrencyCode, Money};
+use crate::{CashLocation, CashUnit, Error, InventoryItem, Result};
+
+#[cfg(feature = "usb")]
+mod session;
+
+#[cfg(feature = "usb")]
+pub use session::*;
+
+/// Tracks the events of an ongoing `CashIn` transaction.
+///
+/// Started by [cash_in_start](crate::DeviceHandle::cash_in_start) with a [CashUnit] snapshot, and
+/// updated from the device callbacks:
+///
+/// - the [CashOrder] payload of every successful `CashIn` operation complete event
+/// - every [InputRefused](crate::IntermediateEvent::InputRefused) intermediate event
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CashInTransaction {
+    baseline: CashUnit,
+    orders: Vec<CashOrder>,
+    refused: u32,
+}
+
+impl CashInTransaction {
+    /// Creates a new [CashInTransaction].
+    pub fn new() -> Self {
+        Self {
+            baseline: CashUnit::new(),
+            orders: Vec::new(),
+            refused: 0,
+        }
+    }
+
+    /// Creates a new [CashInTransaction] from the [CashUnit] snapshot at the start of the
+    /// transaction.
+    pub fn create(baseline: CashUnit) -> Self {
+        Self {
+            baseline,
+            orders: Vec::new(),
+            refused: 0,
+        }
+    }
+
+    /// Gets the [CashUnit] snapshot at the start of the transaction.
+    pub const fn baseline(&self) -> &CashUnit {
+        &self.baseline
+    }
+
+    /// Gets the [CashOrder]s of the completed `CashIn` operations.
+    pub fn orders(&self) -> &[CashOrder] {
+        self.orders.as_ref()
+    }
+
+    /// Adds the [CashOrder] of a completed `CashIn` operation.
+    pub fn push_order(&mut self, order: CashOrder) {
+        self.orders.push(order);
+    }
+
+    /// Gets the number of refused notes.
+    pub const fn refused(&self) -> u32 {
+        self.refused
+    }
+
+    /// Counts a refused note.
+    pub fn add_refused(&mut self) {
+        self.refused = self.refused.saturating_add(1);
+    }
+
+    /// Discards the [CashOrder]s after a `CashInRollback` returned the notes to the customer.
+    pub fn rollback(&mut self) {
+        self.orders.clear();
+    }
+
+    /// Creates the [CashInResult] from the [CashUnit] counts at the end of the transaction.
+    pub fn result(&self, end: &CashUnit) -> CashInResult {
+        let before = self.baseline.logical_cash_unit_list();
+        let pcus = end.physical_cash_unit_list().items();
+
+        let mut accepted = Vec::new();
+        let mut rejected = 0u32;
+
+        for lcu in end.logical_cash_unit_list().iter() {
+            let location = CashLocation::create(lcu.cu_kind());
+            if location == CashLocation::Escrow {
+                continue;
+            }
+
+            let prev = before
+                .iter()
+                .find(|l| l.number() == lcu.number())
+                .map(|l| l.count())
+                .unwrap_or(0);
+            let delta = lcu.count().saturating_sub(prev);
+            if delta == 0 {
+                continue;
+            }
+
+            if lcu.cu_type() == CuType::RejectCassette || lcu.cash_type().value() == 0 {
+                rejected = rejected.saturating_add(delta);
+            } else {
+                accepted.push(InventoryItem::create(
+                    lcu.number(),
+                    pcus.get(lcu.physical_cu_index() as usize)
+                        .map(|p| *p.name())
+                        .unwrap_or_default(),
+                    location,
+                    lcu.cash_type(),
+                    delta,
+                ));
+            }
+        }
+
+        CashInResult {
+            accepted,
+            orders: self.orders.clone(),
+            refused: self.refused,
+            rejected,
+        }
+    }
+}
+
+/// Represents the result of a `CashIn` transaction.
+///
+/// Accepted notes and their routing come from the [CashUnit] count delta over the transaction.
+/// The amounts reported by the device come from the `CashIn` operation complete events.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CashInResult {
+    accepted: Vec<InventoryItem>,
+    orders: Vec<CashOrder>,
+    refused: u32,
+    rejected: u32,
+}
+
+impl CashInResult {
+    /// Creates a new [CashInResult].
+    pub const fn new() -> Self {
+        Self {
+            accepted: Vec::new(),
+            orders: Vec::new(),
+            refused: 0,
+            rejected: 0,
+        }
+    }
+
+    /// Gets the accepted notes, per logical cash unit they were routed to.
+    pub fn accepted(&self) -> &[InventoryItem] {
+        self.accepted.as_ref()
+    }
+
+    /// Gets the number of accepted notes.
+    pub fn accepted_count(&self) -> u32 {
+        self.accepted.iter().map(|i| i.count()).sum()
+    }
+
+    /// Gets the number of accepted notes routed to a [CashLocation].
+    pub fn location_count(&self, location: CashLocation) -> u32 {
+        self.accepted
+            .iter()
+            .filter(|i| i.location() == location)
+            .map(|i| i.count())
+            .sum()
+    }
+
+    /// Gets the [CashOrder]s reported by the `CashIn` operation complete events.
+    pub fn orders(&self) -> &[CashOrder] {
+        self.orders.as_ref()
+    }
+
+    /// Gets the number of notes refused and returned to the customer.
+    pub const fn refused(&self) -> u32 {
+        self.refused
+    }
+
+    /// Gets the number of notes routed to the reject cassette.
+    pub const fn rejected(&self) -> u32 {
+        self.rejected
+    }
+
+    /// Gets the total accepted amount as [Money].
+    ///
+    /// Uses the amounts reported by the device when present, otherwise the value of the accepted
+    /// notes. Returns an error if the transaction mixed currencies.
+    pub fn total(&self) -> Result<Money> {
+        let amounts = if self.orders.is_empty() {
+            self.accepted
+                .iter()
+                .map(|i| i.total().money())
+                .collect::<Result<Vec<Money>>>()?
+        } else {
+            self.orders
+                .iter()
+                .map(|o| o.amount())
+                .collect::<Result<Vec<Money>>>()?
+        };
+
+        let mut iter = amounts.into_iter();
+        let first = iter
+            .next()
+            .ok_or(Error::Money("no notes accepted".into()))?;
+
+        iter.try_fold(first, |acc, m| acc.checked_add(&m))
+    }
+
+    /// Gets the total accepted amount, or a zero amount in the provided currency if no notes were
+    /// accepted.
+    pub fn total_or_zero(&self, currency: CurrencyCode) -> Result<Money> {
+        if self.orders.is_empty() && self.accepted.is_empty() {
+            Currency::from(currency).to_money(0)
+        } else {
+            self.total()
+        }
+    }
+}
+
+impl fmt::Display for CashInResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{{")?;
+        write!(f, r#""accepted": ["#)?;
+        for (i, item) in self.accepted.iter().enumerate() {
+            if i != 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{item}")?;
+        }
+        write!(f, "], ")?;
+        write!(f, r#""orders": ["#)?;
+        for (i, order) in self.orders.iter().enumerate() {
+            if i != 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{order}")?;
+        }
+        write!(f, "], ")?;
+        write!(f, r#""refused": {}, "#, self.refused)?;
+        write!(f, r#""rejected": {}"#, self.rejected)?;
+        write!(f, "}}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cash_unit::tests::cash_unit;
+    use crate::currency::Denomination;
+    use crate::PcuName;
+
+    fn add_count(cash_unit: &mut CashUnit, number: u32, count: u32) {
+        if let Some(lcu) = cash_unit
+            .logical_cash_unit_list_mut()
+            .iter_mut()
+            .find(|l| l.number() == number)
+        {
+            lcu.set_count(lcu.count() + count);
+        }
+    }
+
+    #[test]
+    fn test_cash_in_result() -> Result<()> {
+        let start = cash_unit()?;
+        let mut txn = CashInTransaction::create(start.clone());
+
+        // $100 to RE3, $20 to the cashbox, one note to the reject cassette, escrow is ignored
+        let mut end = start.clone();
+        add_count(&mut end, 7, 2);
+        add_count(&mut end, 13, 1);
+        add_count(&mut end, 12, 1);
+        add_count(&mut end, 6, 3);
+
+        let res = txn.result(&end);
+        assert_eq!(res.accepted_count(), 3);
+        assert_eq!(res.location_count(CashLocation::Recycler), 2);
+        assert_eq!(res.location_count(CashLocation::Cashbox), 1);
+        assert_eq!(res.rejected(), 1);
+        assert_eq!(res.refused(), 0);
+        assert_eq!(res.accepted()[0].pcu_name(), &PcuName::from("RE3"));
+
+        let usd = CurrencyCode::from("USD");
+        let value: u32 = res
+            .accepted()
+            .iter()
+            .map(|i| i.count() * i.cash_type().value())
+            .sum();
+        assert_eq!(res.total()?, Money::create(usd, value as i64));
+
+        // device-reported amounts take precedence
+        txn.push_order(CashOrder::create(
+            Currency::from(usd),
+            Denomination::new().with_amount(10_000),
+        ));
+        txn.add_refused();
+        let res = txn.result(&end);
+        assert_eq!(res.total()?, Money::create(usd, 10_000));
+        assert_eq!(res.refused(), 1);
+
+        let empty = CashInTransaction::create(start.clone()).result(&start);
+        assert!(empty.accepted().is_empty());
+        assert!(empty.total().is_err());
+        assert_eq!(empty.total_or_zero(usd)?, Money::create(usd, 0));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_cash_in_rollback() -> Result<()> {
+        let start = cash_unit()?;
+        let usd = CurrencyCode::from("USD");
+
+        let mut txn = CashInTransaction::create(start.clone());
+        txn.push_order(CashOrder::create(
+            Currency::from(usd),
+            Denomination::new().with_amount(10_000),
+        ));
+        txn.add_refused();
+
+        // the escrowed notes are returned, the refused count and baseline are kept
+        txn.rollback();
+        assert!(txn.orders().is_empty());
+        assert_eq!(txn.refused(), 1);
+        assert_eq!(txn.baseline(), &start);
+
+        let res = txn.result(&start);
+        assert!(res.accepted().is_empty());
+        assert_eq!(res.refused(), 1);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,467 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+use crate::currency::{Currency, Money};
+use crate::xfs::method_call::XfsMethodCall;
+use crate::{DeviceHandle, Error, Result};
+
+use super::CashInResult;
+
+/// Default time to wait for the device to complete a cancelled `CashIn` operation.
+pub const DEPOSIT_CANCEL_TIMEOUT: Duration = Duration::from_millis(4250);
+
+/// Interval between checks of the cancellation flag, see [DepositSession::wait_or_cancel].
+pub const DEPOSIT_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// State of a [DepositSession].
+pub trait DepositState {
+    /// Name of the state.
+    const NAME: &'static str;
+    /// Whether a `CashIn` transaction is open on the device in this state.
+    const ACTIVE: bool;
+}
+
+/// No `CashIn` transaction started.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Idle;
+
+/// `CashIn` transaction started, no notes requested yet.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Started;
+
+/// Waiting for the customer to insert notes.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Accepting {
+    call_id: i32,
+}
+
+/// Notes held in escrow, waiting to be committed or rolled back.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Escrowed;
+
+/// Notes stored in the device, the transaction is closed.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Committed {
+    result: CashInResult,
+}
+
+/// Notes returned to the customer, the transaction is closed.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct RolledBack;
+
+impl DepositState for Idle {
+    const NAME: &'static str = "idle";
+    const ACTIVE: bool = false;
+}
+
+impl DepositState for Started {
+    const NAME: &'static str = "started";
+    const ACTIVE: bool = true;
+}
+
+impl DepositState for Accepting {
+    const NAME: &'static str = "accepting";
+    const ACTIVE: bool = true;
+}
+
+impl DepositState for Escrowed {
+    const NAME: &'static str = "escrowed";
+    const ACTIVE: bool = true;
+}
+
+impl DepositState for Committed {
+    const NAME: &'static str = "committed";
+    const ACTIVE: bool = false;
+}
+
+impl DepositState for RolledBack {
+    const NAME: &'static str = "rolled back";
+    const ACTIVE: bool = false;
+}
+
+// Device calls that close an open `CashIn` transaction.
+trait DepositRecovery {
+    fn cancel(&self) -> Result<()>;
+    fn cash_in_rollback(&self) -> Result<()>;
+    fn cash_in_end(&self) -> Result<()>;
+}
+
+impl DepositRecovery for DeviceHandle {
+    fn cancel(&self) -> Result<()> {
+        DeviceHandle::cancel(self)
+    }
+
+    fn cash_in_rollback(&self) -> Result<()> {
+        DeviceHandle::cash_in_rollback(self)
+    }
+
+    fn cash_in_end(&self) -> Result<()> {
+        DeviceHandle::cash_in_end(self).map(|_| ())
+    }
+}
+
+// Cancels any running operation, rolls back and ends the `CashIn` transaction.
+//
+// Every call is sent even if an earlier one fails: the device rejects the calls that are invalid
+// in its current state, e.g. a cancel with no operation running.
+fn recover_transaction<D: DepositRecovery>(device: &D, state: &str) {
+    log::warn!("Recovering deposit session in the {state} state");
+
+    if let Err(err) = device.cancel() {
+        log::warn!("Deposit recovery: cancel failed: {err}");
+    }
+    if let Err(err) = device.cash_in_rollback() {
+        log::warn!("Deposit recovery: rollback failed: {err}");
+    }
+    if let Err(err) = device.cash_in_end() {
+        log::warn!("Deposit recovery: end failed: {err}");
+    }
+}
+
+/// Drives a deposit (`CashIn`) transaction, enforcing the order of the device calls.
+///
+/// The session moves through the states:
+///
+/// ```text
+/// Idle -> Started -> Accepting -> Escrowed -> Committed
+///                        ^            |
+///                        +------------+-----> RolledBack
+/// ```
+///
+/// Each transition consumes the session, so methods are only available in the states where the
+/// device accepts the call. When a call fails, or an open session is dropped, the session
+/// recovers by cancelling, rolling back and ending the `CashIn` transaction.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::time::Duration;
+///
+/// use bnr_xfs::{CurrencyCode, DepositSession, DeviceHandle, Money};
+///
+/// let handle = DeviceHandle::open(None, None, None).unwrap();
+///
+/// let amount = Money::from_major(CurrencyCode::from("USD"), 20).unwrap();
+///
+/// let result = DepositSession::new(&handle)
+///     .start()
+///     .and_then(|s| s.accept(Some(amount)))
+///     .and_then(|s| s.wait(Duration::from_secs(30)))
+///     .and_then(|s| s.commit())
+///     .unwrap()
+///     .into_result();
+///
+/// println!("accepted: {}", result.total().unwrap());
+/// ```
+pub struct DepositSession<'a, S: DepositState> {
+    handle: &'a DeviceHandle,
+    state: S,
+    done: bool,
+}
+
+impl<'a, S: DepositState> DepositSession<'a, S> {
+    /// Gets the [DeviceHandle] driven by the session.
+    pub const fn handle(&self) -> &'a DeviceHandle {
+        self.handle
+    }
+
+    /// Gets the name of the session state.
+    pub const fn state_name(&self) -> &'static str {
+        S::NAME
+    }
+
+    fn into_state<T: DepositState>(mut self, state: T) -> DepositSession<'a, T> {
+        self.done = true;
+
+        DepositSession {
+            handle: self.handle,
+            state,
+            done: false,
+        }
+    }
+
+    // Closes the `CashIn` transaction on the device, on a best-effort basis.
+    fn recover(&mut self) {
+        self.done = true;
+
+        if S::ACTIVE {
+            recover_transaction(self.handle, S::NAME);
+        }
+    }
+
+    fn fail<T>(mut self, err: Error) -> Result<T> {
+        log::error!("Deposit session failed in the {} state: {err}", S::NAME);
+        self.recover();
+        Err(err)
+    }
+}
+
+impl<S: DepositState> Drop for DepositSession<'_, S> {
+    fn drop(&mut self) {
+        if !self.done && S::ACTIVE {
+            self.recover();
+        }
+    }
+}
+
+impl<'a> DepositSession<'a, Idle> {
+    /// Creates a new [DepositSession] for the [DeviceHandle].
+    pub const fn new(handle: &'a DeviceHandle) -> Self {
+        Self {
+            handle,
+            state: Idle,
+            done: false,
+        }
+    }
+
+    /// Starts the `CashIn` transaction.
+    pub fn start(self) -> Result<DepositSession<'a, Started>> {
+        match self.handle.cash_in_start() {
+            Ok(()) => Ok(self.into_state(Started)),
+            Err(err) => {
+                // the transaction may be open on the device, even if the call failed
+                let session = self.into_state(Started);
+                session.fail(err)
+            }
+        }
+    }
+}
+
+impl<'a> DepositSession<'a, Started> {
+    /// Requests the device to accept notes, see [DeviceHandle::cash_in_amount].
+    pub fn accept(self, amount: Option<Money>) -> Result<DepositSession<'a, Accepting>> {
+        accept(self, amount)
+    }
+
+    /// Ends the transaction without accepting notes.
+    pub fn rollback(self) -> Result<DepositSession<'a, RolledBack>> {
+        match self.handle.cash_in_end() {
+            Ok(_) => Ok(self.into_state(RolledBack)),
+            Err(err) => self.fail(err),
+        }
+    }
+}
+
+impl<'a> DepositSession<'a, Accepting> {
+    /// Waits for the `CashIn` operation to complete, with the notes in escrow.
+    ///
+    /// If the operation does not complete before the `timeout`, it is cancelled: the notes
+    /// inserted so far stay in escrow.
+    pub fn wait(self, timeout: Duration) -> Result<DepositSession<'a, Escrowed>> {
+        let call_id = self.state.call_id;
+
+        match self.handle.wait_async_call(call_id, timeout) {
+            Ok(Some(msg)) => self.complete(&msg),
+            Ok(None) => {
+                log::debug!("Deposit session: CashIn timed out, cancelling");
+                self.cancel()
+            }
+            Err(err) => self.fail(err),
+        }
+    }
+
+    /// Waits for the `CashIn` operation to complete, like [wait](Self::wait), cancelling it
+    /// early when the `cancel` flag is set.
+    pub fn wait_or_cancel(
+        self,
+        timeout: Duration,
+        cancel: &AtomicBool,
+    ) -> Result<DepositSession<'a, Escrowed>> {
+        let call_id = self.state.call_id;
+        let start = Instant::now();
+
+        while start.elapsed() < timeout {
+            if cancel.load(Ordering::SeqCst) {
+                log::debug!("Deposit session: CashIn cancelled by the caller");
+                return self.cancel();
+            }
+
+            let slice = DEPOSIT_POLL_INTERVAL.min(timeout.saturating_sub(start.elapsed()));
+            match self.handle.wait_async_call(call_id, slice) {
+                Ok(Some(msg)) => return self.complete(&msg),
+                Ok(None) => (),
+                Err(err) => return self.fail(err),
+            }
+        }
+
+        log::debug!("Deposit session: CashIn timed out, cancelling");
+        self.cancel()
+    }
+
+    fn complete(self, msg: &XfsMethodCall) -> Result<DepositSession<'a, Escrowed>> {
+        match msg.result().unwrap_or(-1) {
+            0 => Ok(self.into_state(Escrowed)),
+            res => self.fail(Error::Xfs(format!(
+                "deposit session: CashIn operation failed, result: {res}"
+            ))),
+        }
+    }
+
+    /// Cancels the `CashIn` operation, keeping the notes inserted so far in escrow.
+    pub fn cancel(self) -> Result<DepositSession<'a, Escrowed>> {
+        if let Err(err) = self.handle.cancel() {
+            return self.fail(err);
+        }
+
+        // the cancelled operation completes with a non-zero result
+        match self
+            .handle
+            .wait_async_call(self.state.call_id, DEPOSIT_CANCEL_TIMEOUT)
+        {
+            Ok(Some(_)) => Ok(self.into_state(Escrowed)),
+            Ok(None) => self.fail(Error::Xfs(
+                "deposit session: no CashIn operation complete after cancel".into(),
+            )),
+            Err(err) => self.fail(err),
+        }
+    }
+}
+
+impl<'a> DepositSession<'a, Escrowed> {
+    /// Requests the device to accept more notes, see [DeviceHandle::cash_in_amount].
+    pub fn accept(self, amount: Option<Money>) -> Result<DepositSession<'a, Accepting>> {
+        accept(self, amount)
+    }
+
+    /// Stores the notes in escrow, and ends the transaction.
+    pub fn commit(self) -> Result<DepositSession<'a, Committed>> {
+        match self.handle.cash_in_end() {
+            Ok(result) => Ok(self.into_state(Committed { result })),
+            Err(err) => self.fail(err),
+        }
+    }
+
+    /// Returns the notes in escrow to the customer, and ends the transaction.
+    pub fn rollback(self) -> Result<DepositSession<'a, RolledBack>> {
+        if let Err(err) = self.handle.cash_in_rollback() {
+            return self.fail(err);
+        }
+
+        match self.handle.cash_in_end() {
+            Ok(_) => Ok(self.into_state(RolledBack)),
+            Err(err) => self.fail(err),
+        }
+    }
+}
+
+impl DepositSession<'_, Committed> {
+    /// Gets the [CashInResult] of the transaction.
+    pub const fn result(&self) -> &CashInResult {
+        &self.state.result
+    }
+
+    /// Converts the session into the [CashInResult] of the transaction.
+    pub fn into_result(mut self) -> CashInResult {
+        std::mem::take(&mut self.state.result)
+    }
+}
+
+fn accept<'a, S: DepositState>(
+    session: DepositSession<'a, S>,
+    amount: Option<Money>,
+) -> Result<DepositSession<'a, Accepting>> {
+    let (limit, currency) = match amount {
+        Some(amount) => {
+            let currency = Currency::from(amount.currency());
+            match amount.to_currency_mdu(&currency) {
+                Ok(mdu) => (Some(mdu), Some(amount.currency())),
+                Err(err) => return session.fail(err),
+            }
+        }
+        None => (None, None),
+    };
+
+    match session.handle.cash_in_inner(limit, currency) {
+        Ok(call_id) => Ok(session.into_state(Accepting { call_id })),
+        Err(err) => session.fail(err),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+
+    use super::*;
+
+    // Records the recovery calls, failing the calls in `fail`.
+    #[derive(Default)]
+    struct MockDevice {
+        calls: RefCell<Vec<&'static str>>,
+        fail: Vec<&'static str>,
+    }
+
+    impl MockDevice {
+        fn call(&self, name: &'static str) -> Result<()> {
+            self.calls.borrow_mut().push(name);
+            if self.fail.contains(&name) {
+                Err(Error::Xfs(format!("{name}: invalid in the current state")))
+            } else {
+                Ok(())
+            }
+        }
+    }
+
+    impl DepositRecovery for MockDevice {
+        fn cancel(&self) -> Result<()> {
+            self.call("cancel")
+        }
+
+        fn cash_in_rollback(&self) -> Result<()> {
+            self.call("rollback")
+        }
+
+        fn cash_in_end(&self) -> Result<()> {
+            self.call("end")
+        }
+    }
+
+    #[test]
+    fn test_recover_transaction() {
+        let device = MockDevice::default();
+        recover_transaction(&device, Accepting::NAME);
+        assert_eq!(*device.calls.borrow(), ["cancel", "rollback", "end"]);
+    }
+
+    #[test]
+    fn test_recover_transaction_invalid_state() {
+        // no operation running, and nothing in escrow: the transaction is still ended
+        let device = MockDevice {
+            fail: vec!["cancel", "rollback"],
+            ..Default::default()
+        };
+        recover_transaction(&device, Started::NAME);
+        assert_eq!(*device.calls.borrow(), ["cancel", "rollback", "end"]);
+
+        // every call fails: recovery does not stop, nor panic in drop
+        let device = MockDevice {
+            fail: vec!["cancel", "rollback", "end"],
+            ..Default::default()
+        };
+        recover_transaction(&device, Escrowed::NAME);
+        assert_eq!(device.calls.borrow().len(), 3);
+    }
+
+    #[test]
+    fn test_deposit_state_recovery() {
+        // dropping a session recovers only the states with an open transaction
+        let active = [
+            (Idle::NAME, Idle::ACTIVE),
+            (Started::NAME, Started::ACTIVE),
+            (Accepting::NAME, Accepting::ACTIVE),
+            (Escrowed::NAME, Escrowed::ACTIVE),
+            (Committed::NAME, Committed::ACTIVE),
+            (RolledBack::NAME, RolledBack::ACTIVE),
+        ];
+        assert_eq!(
+            active,
+            [
+                ("idle", false),
+                ("started", true),
+                ("accepting", true),
+                ("escrowed", true),
+                ("committed", false),
+                ("rolled back", false),
+            ]
+        );
+    }
+}
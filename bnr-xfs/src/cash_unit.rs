@@ -1,33 +1,47 @@
 use std::fmt;
+use std::time::{SystemTime, UNIX_EPOCH};
 
+use crate::currency::CuKind;
 use crate::xfs::method_response::XfsMethodResponse;
-use crate::{impl_xfs_struct, Error, Result, TransportCount};
+use crate::{Error, Result, TransportCount, XfsStruct};
 
 mod counters;
+mod diff;
+mod inventory;
+mod layout;
 mod lock;
 mod logical_cash_unit;
 mod number;
 mod pcu_name;
 mod physical_cash_unit;
+mod reconcile;
 mod status;
 mod threshold;
 mod unit_id;
 
 pub use counters::*;
+pub use diff::*;
+pub use inventory::*;
+pub use layout::*;
 pub use lock::*;
 pub use logical_cash_unit::*;
 pub use number::*;
 pub use pcu_name::*;
 pub use physical_cash_unit::*;
+pub use reconcile::*;
 pub use status::*;
 pub use threshold::*;
 pub use unit_id::*;
 
+/// Name of the bundler [PhysicalCashUnit], the intermediate stacker area.
+pub const BUNDLER_PCU_NAME: &str = "BU";
+
 /// Represents a cash unit in a BNR device.
 ///
 /// Describes the entire set of [LogicalCashUnit]s and [PhysicalCashUnit]s present on a device.
 #[repr(C)]
-#[derive(Clone, Debug, Default, PartialEq)]
+#[derive(Clone, Debug, Default, PartialEq, serde::Deserialize, serde::Serialize, XfsStruct)]
+#[xfs(name = "cashUnit", no_display)]
 pub struct CashUnit {
     transport_count: TransportCount,
     logical_cash_unit_list: LogicalCashUnitList,
@@ -97,6 +111,18 @@ impl CashUnit {
         self
     }
 
+    /// Gets the bundler [LogicalCashUnit], accounting the bills held in the intermediate stacker
+    /// area, e.g. after a [retract](crate::DeviceHandle::retract).
+    pub fn bundler(&self) -> Option<&LogicalCashUnit> {
+        let pcus = self.physical_cash_unit_list.items();
+        let name = PcuName::from(BUNDLER_PCU_NAME);
+
+        self.logical_cash_unit_list.iter().find(|lcu| {
+            pcus.get(lcu.physical_cu_index() as usize)
+                .is_some_and(|pcu| pcu.name() == &name)
+        })
+    }
+
     /// Deconstructs the [CashUnit] into a [LogicalCashUnitList] and [PhysicalCashUnitList].
     pub fn into_lists(self) -> (LogicalCashUnitList, PhysicalCashUnitList) {
         (self.logical_cash_unit_list, self.physical_cash_unit_list)
@@ -121,16 +147,6 @@ impl fmt::Display for CashUnit {
     }
 }
 
-impl_xfs_struct!(
-    CashUnit,
-    "cashUnit",
-    [
-        transport_count: TransportCount,
-        logical_cash_unit_list: LogicalCashUnitList,
-        physical_cash_unit_list: PhysicalCashUnitList
-    ]
-);
-
 impl TryFrom<&XfsMethodResponse> for CashUnit {
     type Error = Error;
 
@@ -156,18 +172,68 @@ impl TryFrom<XfsMethodResponse> for CashUnit {
     }
 }
 
+// Gets the index and name of the physical cash units holding a logical cash unit of `kind`.
+pub(crate) fn pcus_of_kind(
+    cash_unit: &CashUnit,
+    kind: CuKind,
+) -> impl Iterator<Item = (usize, PcuName)> + '_ {
+    let lcus = cash_unit.logical_cash_unit_list();
+
+    cash_unit
+        .physical_cash_unit_list()
+        .iter()
+        .enumerate()
+        .filter(move |(idx, _)| {
+            lcus.iter()
+                .any(|l| l.physical_cu_index() as usize == *idx && l.cu_kind() == kind)
+        })
+        .map(|(idx, pcu)| (idx, *pcu.name()))
+}
+
+// Gets the current time, in seconds since the Unix epoch.
+pub(crate) fn unix_time() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
 #[cfg(test)]
-mod tests {
+pub(crate) mod tests {
     use super::*;
     use crate::xfs;
 
-    #[test]
-    fn test_parse_cash_unit() -> Result<()> {
+    /// Parses the [CashUnit] from the `query-cash-unit.xml` test fixture.
+    pub(crate) fn cash_unit() -> Result<CashUnit> {
         let unit_xml = include_bytes!("../tests/xml/query-cash-unit.xml");
         let unit_xml_str = std::str::from_utf8(unit_xml.as_ref())?;
 
         let res = xfs::from_str::<xfs::method_response::XfsMethodResponseStruct>(unit_xml_str)?;
-        let _cash_unit = CashUnit::try_from(res.inner())?;
+        CashUnit::try_from(res.inner())
+    }
+
+    #[test]
+    fn test_parse_cash_unit() -> Result<()> {
+        let cash_unit = cash_unit()?;
+
+        let pcu_index: Vec<u32> = cash_unit
+            .logical_cash_unit_list()
+            .iter()
+            .take(6)
+            .map(|lcu| lcu.physical_cu_index())
+            .collect();
+        assert_eq!(pcu_index, [6, 3, 4, 5, 2, 0]);
+        assert_eq!(cash_unit.bundler().map(|lcu| lcu.number()), Some(6));
+
+        // cashbox LCUs have an empty unit ID
+        let last = cash_unit.logical_cash_unit_list().items()[29].clone();
+        assert_eq!(last.number(), 30);
+        assert_eq!(last.unit_id(), UnitId::new());
+        assert!(link_issues(
+            cash_unit.logical_cash_unit_list(),
+            cash_unit.physical_cash_unit_list()
+        )
+        .is_empty());
 
         Ok(())
     }
@@ -0,0 +1,689 @@
+use std::fmt;
+
+use crate::currency::{CashType, Currency, CurrencyCode, Exponent, Money};
+use crate::{CashLocation, CashUnit, ExtendedCounters, LogicalCashUnit, PcuName, Result};
+
+/// Represents the change of the [ExtendedCounters] of a logical cash unit between two [CashUnit]
+/// snapshots.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct CounterDelta {
+    deposit: i64,
+    retracted: i64,
+    emptied: i64,
+    forgery: i64,
+    disappeared: i64,
+    dispense: i64,
+    reject: i64,
+}
+
+impl CounterDelta {
+    /// Creates a new [CounterDelta].
+    pub const fn new() -> Self {
+        Self {
+            deposit: 0,
+            retracted: 0,
+            emptied: 0,
+            forgery: 0,
+            disappeared: 0,
+            dispense: 0,
+            reject: 0,
+        }
+    }
+
+    /// Creates a new [CounterDelta] from the [ExtendedCounters] of two snapshots.
+    pub fn create(before: &ExtendedCounters, after: &ExtendedCounters) -> Self {
+        let (bdep, adep) = (before.deposit(), after.deposit());
+        let (bdis, adis) = (before.dispense(), after.dispense());
+
+        Self {
+            deposit: delta(bdep.deposit_count().inner(), adep.deposit_count().inner()),
+            retracted: delta(
+                bdep.retracted_count().inner(),
+                adep.retracted_count().inner(),
+            ),
+            emptied: delta(bdep.emptied_count().inner(), adep.emptied_count().inner()),
+            forgery: delta(bdep.forgery_count().inner(), adep.forgery_count().inner()),
+            disappeared: delta(
+                bdep.disappeared_count().inner(),
+                adep.disappeared_count().inner(),
+            ),
+            dispense: delta(bdis.dispense_count().inner(), adis.dispense_count().inner()),
+            reject: delta(bdis.reject_count().inner(), adis.reject_count().inner()),
+        }
+    }
+
+    /// Gets the change of the deposit count.
+    pub const fn deposit(&self) -> i64 {
+        self.deposit
+    }
+
+    /// Gets the change of the retracted count.
+    pub const fn retracted(&self) -> i64 {
+        self.retracted
+    }
+
+    /// Gets the change of the emptied count.
+    pub const fn emptied(&self) -> i64 {
+        self.emptied
+    }
+
+    /// Gets the change of the forgery count.
+    pub const fn forgery(&self) -> i64 {
+        self.forgery
+    }
+
+    /// Gets the change of the disappeared count.
+    pub const fn disappeared(&self) -> i64 {
+        self.disappeared
+    }
+
+    /// Gets the change of the dispense count.
+    pub const fn dispense(&self) -> i64 {
+        self.dispense
+    }
+
+    /// Gets the change of the reject count.
+    pub const fn reject(&self) -> i64 {
+        self.reject
+    }
+
+    /// Gets whether none of the counters changed.
+    pub const fn is_zero(&self) -> bool {
+        self.deposit == 0
+            && self.retracted == 0
+            && self.emptied == 0
+            && self.forgery == 0
+            && self.disappeared == 0
+            && self.dispense == 0
+            && self.reject == 0
+    }
+}
+
+impl fmt::Display for CounterDelta {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{{")?;
+        write!(f, r#""deposit": {}, "#, self.deposit)?;
+        write!(f, r#""retracted": {}, "#, self.retracted)?;
+        write!(f, r#""emptied": {}, "#, self.emptied)?;
+        write!(f, r#""forgery": {}, "#, self.forgery)?;
+        write!(f, r#""disappeared": {}, "#, self.disappeared)?;
+        write!(f, r#""dispense": {}, "#, self.dispense)?;
+        write!(f, r#""reject": {}"#, self.reject)?;
+        write!(f, "}}")
+    }
+}
+
+/// Represents the change of a [LogicalCashUnit] between two [CashUnit] snapshots.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct LcuDiff {
+    number: u32,
+    pcu_name: PcuName,
+    location: CashLocation,
+    cash_type: CashType,
+    before: u32,
+    after: u32,
+    counters: CounterDelta,
+}
+
+impl LcuDiff {
+    /// Gets the number of the [LogicalCashUnit].
+    pub const fn number(&self) -> u32 {
+        self.number
+    }
+
+    /// Gets the name of the [PhysicalCashUnit](crate::PhysicalCashUnit) storing the bills.
+    pub const fn pcu_name(&self) -> &PcuName {
+        &self.pcu_name
+    }
+
+    /// Gets the [CashLocation] of the bills.
+    pub const fn location(&self) -> CashLocation {
+        self.location
+    }
+
+    /// Gets the [CashType] of the bills.
+    pub const fn cash_type(&self) -> CashType {
+        self.cash_type
+    }
+
+    /// Gets the count in the first snapshot.
+    pub const fn before(&self) -> u32 {
+        self.before
+    }
+
+    /// Gets the count in the second snapshot.
+    pub const fn after(&self) -> u32 {
+        self.after
+    }
+
+    /// Gets the count change.
+    pub const fn delta(&self) -> i64 {
+        self.after as i64 - self.before as i64
+    }
+
+    /// Gets the value change in minimum dispense units (MDU).
+    pub const fn mdu_delta(&self) -> i64 {
+        self.delta() * self.cash_type.value() as i64
+    }
+
+    /// Gets the [CounterDelta].
+    pub const fn counters(&self) -> &CounterDelta {
+        &self.counters
+    }
+
+    /// Gets whether the count changed, without any change of the [ExtendedCounters].
+    ///
+    /// Bills moved by the device are accounted in the counters, so this usually means the counts
+    /// were updated manually, or bills went missing.
+    pub const fn is_unexplained(&self) -> bool {
+        self.delta() != 0 && self.counters.is_zero()
+    }
+}
+
+impl fmt::Display for LcuDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{{")?;
+        write!(f, r#""number": {}, "#, self.number)?;
+        write!(f, r#""pcu_name": {}, "#, self.pcu_name)?;
+        write!(f, r#""location": {}, "#, self.location)?;
+        write!(f, r#""cash_type": {}, "#, self.cash_type)?;
+        write!(f, r#""before": {}, "#, self.before)?;
+        write!(f, r#""after": {}, "#, self.after)?;
+        write!(f, r#""counters": {}"#, self.counters)?;
+        write!(f, "}}")
+    }
+}
+
+/// Represents the count change of a [PhysicalCashUnit](crate::PhysicalCashUnit) between two
+/// [CashUnit] snapshots.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct PcuDiff {
+    name: PcuName,
+    before: u32,
+    after: u32,
+}
+
+impl PcuDiff {
+    /// Gets the name of the [PhysicalCashUnit](crate::PhysicalCashUnit).
+    pub const fn name(&self) -> &PcuName {
+        &self.name
+    }
+
+    /// Gets the count in the first snapshot.
+    pub const fn before(&self) -> u32 {
+        self.before
+    }
+
+    /// Gets the count in the second snapshot.
+    pub const fn after(&self) -> u32 {
+        self.after
+    }
+
+    /// Gets the count change.
+    pub const fn delta(&self) -> i64 {
+        self.after as i64 - self.before as i64
+    }
+}
+
+impl fmt::Display for PcuDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{{")?;
+        write!(f, r#""name": {}, "#, self.name)?;
+        write!(f, r#""before": {}, "#, self.before)?;
+        write!(f, r#""after": {}"#, self.after)?;
+        write!(f, "}}")
+    }
+}
+
+/// Represents the change of the number of bills, and their value, in a single currency.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct ValueChange {
+    currency: Currency,
+    count: i64,
+    mdu_amount: i64,
+}
+
+impl ValueChange {
+    /// Creates a new [ValueChange].
+    pub const fn create(currency: Currency, count: i64, mdu_amount: i64) -> Self {
+        Self {
+            currency,
+            count,
+            mdu_amount,
+        }
+    }
+
+    /// Gets the [Currency].
+    pub const fn currency(&self) -> Currency {
+        self.currency
+    }
+
+    /// Gets the change of the number of bills.
+    pub const fn count(&self) -> i64 {
+        self.count
+    }
+
+    /// Gets the value change in minimum dispense units (MDU).
+    pub const fn mdu_amount(&self) -> i64 {
+        self.mdu_amount
+    }
+
+    /// Gets the value change as [Money].
+    pub fn money(&self) -> Result<Money> {
+        let code = self.currency.currency_code();
+        let abs = Money::from_mdu(
+            code,
+            self.mdu_amount.unsigned_abs(),
+            Exponent::create(self.currency.exponent()),
+        )?;
+
+        if self.mdu_amount < 0 {
+            Money::create(code, 0).checked_sub(&abs)
+        } else {
+            Ok(abs)
+        }
+    }
+}
+
+impl fmt::Display for ValueChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{{")?;
+        write!(
+            f,
+            r#""currency_code": "{}", "#,
+            <&str>::from(self.currency.currency_code())
+        )?;
+        write!(f, r#""count": {}, "#, self.count)?;
+        write!(f, r#""mdu_amount": {}"#, self.mdu_amount)?;
+        write!(f, "}}")
+    }
+}
+
+/// Represents the changes between two [CashUnit] snapshots, e.g. from the start and the end of
+/// the day.
+///
+/// Logical cash units are matched by number, and physical cash units by name. Only changed units
+/// are listed.
+#[derive(Clone, Debug, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct CashUnitDiff {
+    lcus: Vec<LcuDiff>,
+    pcus: Vec<PcuDiff>,
+    transport: i64,
+}
+
+impl CashUnitDiff {
+    /// Creates a new [CashUnitDiff].
+    pub const fn new() -> Self {
+        Self {
+            lcus: Vec::new(),
+            pcus: Vec::new(),
+            transport: 0,
+        }
+    }
+
+    /// Creates a new [CashUnitDiff] from the `before` and `after` snapshots.
+    ///
+    /// A unit missing from one of the snapshots, e.g. after a configuration change, is counted as
+    /// empty in that snapshot.
+    pub fn create(before: &CashUnit, after: &CashUnit) -> Self {
+        let pcus = after.physical_cash_unit_list().items();
+        let before_lcus = before.logical_cash_unit_list();
+        let after_lcus = after.logical_cash_unit_list();
+        let empty = LogicalCashUnit::new();
+
+        let mut lcus: Vec<LcuDiff> = after_lcus
+            .iter()
+            .map(|lcu| {
+                let prev = before_lcus
+                    .iter()
+                    .find(|l| l.number() == lcu.number())
+                    .unwrap_or(&empty);
+
+                LcuDiff {
+                    number: lcu.number(),
+                    pcu_name: pcus
+                        .get(lcu.physical_cu_index() as usize)
+                        .map(|p| *p.name())
+                        .unwrap_or_default(),
+                    location: CashLocation::create(lcu.cu_kind()),
+                    cash_type: lcu.cash_type(),
+                    before: prev.count(),
+                    after: lcu.count(),
+                    counters: CounterDelta::create(
+                        prev.extended_counters(),
+                        lcu.extended_counters(),
+                    ),
+                }
+            })
+            .collect();
+
+        let before_pcus = before.physical_cash_unit_list().items();
+        lcus.extend(
+            before_lcus
+                .iter()
+                .filter(|l| !after_lcus.iter().any(|a| a.number() == l.number()))
+                .map(|lcu| LcuDiff {
+                    number: lcu.number(),
+                    pcu_name: before_pcus
+                        .get(lcu.physical_cu_index() as usize)
+                        .map(|p| *p.name())
+                        .unwrap_or_default(),
+                    location: CashLocation::create(lcu.cu_kind()),
+                    cash_type: lcu.cash_type(),
+                    before: lcu.count(),
+                    after: 0,
+                    counters: CounterDelta::create(
+                        lcu.extended_counters(),
+                        &ExtendedCounters::new(),
+                    ),
+                }),
+        );
+        lcus.retain(|l| l.delta() != 0 || !l.counters.is_zero());
+
+        let mut pcu_diffs: Vec<PcuDiff> = pcus
+            .iter()
+            .map(|pcu| PcuDiff {
+                name: *pcu.name(),
+                before: before_pcus
+                    .iter()
+                    .find(|p| p.name() == pcu.name())
+                    .map(|p| p.count())
+                    .unwrap_or(0),
+                after: pcu.count(),
+            })
+            .collect();
+        pcu_diffs.extend(
+            before_pcus
+                .iter()
+                .filter(|p| !pcus.iter().any(|a| a.name() == p.name()))
+                .map(|pcu| PcuDiff {
+                    name: *pcu.name(),
+                    before: pcu.count(),
+                    after: 0,
+                }),
+        );
+        pcu_diffs.retain(|p| p.delta() != 0);
+
+        Self {
+            lcus,
+            pcus: pcu_diffs,
+            transport: delta(before.transport_count(), after.transport_count()),
+        }
+    }
+
+    /// Gets the changed [LogicalCashUnit]s.
+    pub fn lcus(&self) -> &[LcuDiff] {
+        self.lcus.as_ref()
+    }
+
+    /// Gets the changed [PhysicalCashUnit](crate::PhysicalCashUnit)s.
+    pub fn pcus(&self) -> &[PcuDiff] {
+        self.pcus.as_ref()
+    }
+
+    /// Gets the change of the number of bills in the transport system.
+    pub const fn transport_delta(&self) -> i64 {
+        self.transport
+    }
+
+    /// Gets whether nothing changed between the snapshots.
+    pub fn is_empty(&self) -> bool {
+        self.lcus.is_empty() && self.pcus.is_empty() && self.transport == 0
+    }
+
+    /// Gets the [ValueChange] for each currency, across all locations.
+    ///
+    /// Logical cash units without a denomination, e.g. the reject cassette, are skipped.
+    pub fn value_changes(&self) -> Vec<ValueChange> {
+        let mut changes: Vec<ValueChange> = Vec::new();
+
+        for lcu in self.lcus.iter().filter(|l| l.cash_type.value() != 0) {
+            let code = lcu.cash_type.currency_code();
+            match changes
+                .iter_mut()
+                .find(|c| c.currency.currency_code() == code)
+            {
+                Some(change) => {
+                    change.count += lcu.delta();
+                    change.mdu_amount += lcu.mdu_delta();
+                }
+                None => changes.push(ValueChange::create(
+                    Currency::from(code),
+                    lcu.delta(),
+                    lcu.mdu_delta(),
+                )),
+            }
+        }
+
+        changes.sort_by(|a, b| currency_str(&a.currency).cmp(currency_str(&b.currency)));
+
+        changes
+    }
+
+    /// Gets the [ValueChange] for the provided currency.
+    pub fn value_change(&self, currency_code: CurrencyCode) -> ValueChange {
+        self.value_changes()
+            .into_iter()
+            .find(|c| c.currency.currency_code() == currency_code)
+            .unwrap_or(ValueChange::create(Currency::from(currency_code), 0, 0))
+    }
+
+    /// Gets the total number of bills reported disappeared by the device.
+    pub fn disappeared(&self) -> u32 {
+        self.lcus
+            .iter()
+            .map(|l| l.counters.disappeared.max(0) as u32)
+            .sum()
+    }
+}
+
+impl fmt::Display for CashUnitDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{{")?;
+        write!(f, r#""lcus": ["#)?;
+        for (i, lcu) in self.lcus.iter().enumerate() {
+            if i != 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{lcu}")?;
+        }
+        write!(f, "], ")?;
+        write!(f, r#""pcus": ["#)?;
+        for (i, pcu) in self.pcus.iter().enumerate() {
+            if i != 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{pcu}")?;
+        }
+        write!(f, "], ")?;
+        write!(f, r#""transport_delta": {}"#, self.transport)?;
+        write!(f, "}}")
+    }
+}
+
+fn delta(before: u32, after: u32) -> i64 {
+    after as i64 - before as i64
+}
+
+fn currency_str(currency: &Currency) -> &'static str {
+    currency.currency_code().into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cash_unit::tests::cash_unit;
+    use crate::{
+        DepositCounters, DisappearedCount, DispenseCount, DispenseCounters, LogicalCashUnitList,
+    };
+
+    fn with_lcus(cash_unit: &CashUnit, mut f: impl FnMut(&mut LogicalCashUnit)) -> CashUnit {
+        let mut res = cash_unit.clone();
+        res.logical_cash_unit_list_mut().iter_mut().for_each(&mut f);
+        res
+    }
+
+    fn numbers(diff: &CashUnitDiff) -> Vec<u32> {
+        diff.lcus().iter().map(|l| l.number()).collect()
+    }
+
+    #[test]
+    fn test_cash_unit_diff_unchanged() -> Result<()> {
+        let before = cash_unit()?;
+        let diff = CashUnitDiff::create(&before, &before);
+
+        assert!(diff.is_empty());
+        assert!(diff.value_changes().is_empty());
+        assert_eq!(diff.disappeared(), 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_cash_unit_diff_dispense() -> Result<()> {
+        let before = cash_unit()?;
+        // one $100 bill dispensed from RE3
+        let after = with_lcus(&before, |lcu| {
+            if lcu.number() == 7 {
+                let counters = *lcu.extended_counters();
+                lcu.set_count(lcu.count() - 1);
+                lcu.set_extended_counters(counters.with_dispense(
+                    DispenseCounters::new().with_dispense_count(DispenseCount::create(
+                        counters.dispense().dispense_count().inner() + 1,
+                    )),
+                ));
+            }
+        });
+
+        let diff = CashUnitDiff::create(&before, &after);
+        assert_eq!(numbers(&diff), [7]);
+
+        let re3 = &diff.lcus()[0];
+        assert_eq!(re3.delta(), -1);
+        assert_eq!(re3.mdu_delta(), -10_000);
+        assert_eq!(re3.counters().dispense(), 1);
+        assert_eq!(re3.pcu_name(), &PcuName::from("RE3"));
+        assert_eq!(re3.location(), CashLocation::Recycler);
+        assert!(!re3.is_unexplained());
+
+        // the PCU count is only listed when it changes
+        assert!(diff.pcus().is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_cash_unit_diff_unexplained() -> Result<()> {
+        let before = cash_unit()?;
+        let after = with_lcus(&before, |lcu| match lcu.number() {
+            // one bill disappeared from the bundler
+            6 => lcu.set_extended_counters(lcu.extended_counters().with_deposit(
+                DepositCounters::new().with_disappeared_count(DisappearedCount::create(1)),
+            )),
+            // manual count update of the cashbox
+            13 => lcu.set_count(lcu.count() + 2),
+            _ => (),
+        });
+
+        let diff = CashUnitDiff::create(&before, &after);
+        assert_eq!(numbers(&diff), [6, 13]);
+
+        // the counters explain the disappeared bill, nothing explains the count update
+        assert_eq!(diff.lcus()[0].delta(), 0);
+        assert!(!diff.lcus()[0].is_unexplained());
+        assert!(diff.lcus()[1].is_unexplained());
+        assert_eq!(diff.disappeared(), 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_cash_unit_diff_value_changes() -> Result<()> {
+        let usd = CurrencyCode::from("USD");
+        let eur = CurrencyCode::from("EUR");
+
+        // RE6 holds €20 bills
+        let before = with_lcus(&cash_unit()?, |lcu| {
+            if lcu.number() == 8 {
+                lcu.set_cash_type(CashType::create(eur, 2000, 0));
+            }
+        });
+        let after = with_lcus(&before, |lcu| match lcu.number() {
+            // three €20 bills recycled, one $100 and one $50 bill out
+            8 => lcu.set_count(lcu.count() + 3),
+            7 => lcu.set_count(lcu.count() - 1),
+            30 => lcu.set_count(lcu.count() - 1),
+            // bills without a denomination have no value
+            12 => lcu.set_count(lcu.count() + 4),
+            _ => (),
+        });
+
+        let diff = CashUnitDiff::create(&before, &after);
+        assert_eq!(numbers(&diff), [7, 8, 12, 30]);
+
+        let changes = diff.value_changes();
+        let totals: Vec<(CurrencyCode, i64, i64)> = changes
+            .iter()
+            .map(|c| (c.currency().currency_code(), c.count(), c.mdu_amount()))
+            .collect();
+        assert_eq!(totals, [(eur, 3, 6000), (usd, -2, -15_000)]);
+
+        assert_eq!(changes[0].money()?, Money::create(eur, 6000));
+        assert_eq!(changes[1].money()?, Money::create(usd, -15_000));
+        assert_eq!(diff.value_change(usd), changes[1]);
+
+        // currencies without changes have a zero value change
+        let gbp = diff.value_change(CurrencyCode::from("GBP"));
+        assert_eq!((gbp.count(), gbp.mdu_amount()), (0, 0));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_cash_unit_diff_missing_units() -> Result<()> {
+        let before = cash_unit()?;
+        let mut after = before.clone();
+
+        // RE3 is removed from the configuration
+        let lcus: Vec<LogicalCashUnit> = after
+            .logical_cash_unit_list()
+            .iter()
+            .filter(|l| l.number() != 7)
+            .cloned()
+            .collect();
+        after.set_logical_cash_unit_list(LogicalCashUnitList::new().with_items(&lcus));
+
+        let diff = CashUnitDiff::create(&before, &after);
+        assert_eq!(numbers(&diff), [7]);
+        assert_eq!(diff.lcus()[0].after(), 0);
+        assert_eq!(diff.lcus()[0].delta(), -1);
+        assert_eq!(diff.lcus()[0].pcu_name(), &PcuName::from("RE3"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_cash_unit_diff_pcus() -> Result<()> {
+        let before = cash_unit()?;
+        let mut after = before.clone();
+
+        let mut pcus = after.physical_cash_unit_list().clone();
+        if let Some(pcu) = pcus.iter_mut().find(|p| p.name() == &PcuName::from("RE3")) {
+            pcu.set_count(pcu.count() + 2);
+        }
+        after.set_physical_cash_unit_list(pcus);
+
+        let diff = CashUnitDiff::create(&before, &after);
+        assert!(diff.lcus().is_empty());
+        assert_eq!(diff.pcus().len(), 1);
+        assert_eq!(diff.pcus()[0].name(), &PcuName::from("RE3"));
+        assert_eq!(diff.pcus()[0].delta(), 2);
+        assert!(!diff.is_empty());
+
+        Ok(())
+    }
+}
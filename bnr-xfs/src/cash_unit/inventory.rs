@@ -0,0 +1,653 @@
+use std::fmt;
+
+use crate::currency::{CashType, CuKind, Currency, CurrencyCode, Money};
+use crate::{CashUnit, PcuName, Result};
+
+/// Represents where bills are stored in a BNR device.
+#[repr(C)]
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    Default,
+    Eq,
+    Hash,
+    Ord,
+    PartialEq,
+    PartialOrd,
+    serde::Deserialize,
+    serde::Serialize,
+)]
+pub enum CashLocation {
+    /// Recycler modules, bills are available for dispense.
+    Recycler,
+    /// Loader module, bills are available for dispense.
+    Loader,
+    /// Cashbox, bills are deposit-only.
+    Cashbox,
+    /// Bundler and escrow units, bills are held until the end of the transaction.
+    #[default]
+    Escrow,
+}
+
+impl CashLocation {
+    /// Creates a new [CashLocation].
+    pub const fn new() -> Self {
+        Self::Escrow
+    }
+
+    /// Creates a new [CashLocation] from the [CuKind] of a [LogicalCashUnit](crate::LogicalCashUnit).
+    pub const fn create(kind: CuKind) -> Self {
+        match kind {
+            CuKind::Recycle => Self::Recycler,
+            CuKind::Dispense => Self::Loader,
+            CuKind::Deposit => Self::Cashbox,
+            CuKind::NotAvailable => Self::Escrow,
+        }
+    }
+
+    /// Gets whether bills stored in the location are available for dispense.
+    pub const fn is_dispensable(&self) -> bool {
+        matches!(self, Self::Recycler | Self::Loader)
+    }
+
+    /// Gets whether bills stored in the location are deposit-only.
+    pub const fn is_deposit_only(&self) -> bool {
+        matches!(self, Self::Cashbox)
+    }
+}
+
+impl From<&CashLocation> for &'static str {
+    fn from(val: &CashLocation) -> Self {
+        match val {
+            CashLocation::Recycler => "recycler",
+            CashLocation::Loader => "loader",
+            CashLocation::Cashbox => "cashbox",
+            CashLocation::Escrow => "escrow",
+        }
+    }
+}
+
+impl From<CashLocation> for &'static str {
+    fn from(val: CashLocation) -> Self {
+        (&val).into()
+    }
+}
+
+impl fmt::Display for CashLocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, r#""{}""#, <&str>::from(self))
+    }
+}
+
+/// Represents a count of bills, and their value, in a single currency.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct CashTotal {
+    currency: Currency,
+    count: u64,
+    mdu_amount: u64,
+}
+
+impl CashTotal {
+    /// Creates a new [CashTotal].
+    pub const fn new() -> Self {
+        Self {
+            currency: Currency::new(),
+            count: 0,
+            mdu_amount: 0,
+        }
+    }
+
+    /// Creates a new [CashTotal] from the provided parameters.
+    pub const fn create(currency: Currency, count: u64, mdu_amount: u64) -> Self {
+        Self {
+            currency,
+            count,
+            mdu_amount,
+        }
+    }
+
+    /// Gets the [Currency].
+    pub const fn currency(&self) -> Currency {
+        self.currency
+    }
+
+    /// Gets the number of bills.
+    pub const fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// Gets the total amount in minimum dispense units (MDU), e.g. cents for `USD`.
+    pub const fn mdu_amount(&self) -> u64 {
+        self.mdu_amount
+    }
+
+    /// Gets the total amount in major units, e.g. dollars for `USD`.
+    ///
+    /// Applies the [Currency] exponent, use [mdu_amount](Self::mdu_amount) for exact arithmetic.
+    pub fn amount(&self) -> f64 {
+        self.mdu_amount as f64 * 10f64.powi(self.currency.exponent())
+    }
+
+    /// Gets the total amount as [Money].
+    pub fn money(&self) -> Result<Money> {
+        Money::from_mdu(
+            self.currency.currency_code(),
+            self.mdu_amount,
+            self.currency.exponent().into(),
+        )
+    }
+
+    /// Adds a number of bills with the provided MDU value.
+    pub fn add(&mut self, count: u32, mdu_value: u32) {
+        self.count = self.count.saturating_add(count as u64);
+        self.mdu_amount = self
+            .mdu_amount
+            .saturating_add(count as u64 * mdu_value as u64);
+    }
+
+    /// Gets the total amount in major units formatted with the exact number of decimal places.
+    pub fn amount_str(&self) -> String {
+        let exp = self.currency.exponent();
+
+        if exp >= 0 {
+            format!("{}", self.mdu_amount as u128 * 10u128.pow(exp as u32))
+        } else {
+            let places = exp.unsigned_abs() as usize;
+            let div = 10u64.pow(places as u32);
+            format!(
+                "{}.{:0places$}",
+                self.mdu_amount / div,
+                self.mdu_amount % div
+            )
+        }
+    }
+}
+
+impl fmt::Display for CashTotal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{{")?;
+        write!(
+            f,
+            r#""currency_code": "{}", "#,
+            <&str>::from(self.currency.currency_code())
+        )?;
+        write!(f, r#""count": {}, "#, self.count)?;
+        write!(f, r#""mdu_amount": {}, "#, self.mdu_amount)?;
+        write!(f, r#""amount": "{}""#, self.amount_str())?;
+        write!(f, "}}")
+    }
+}
+
+/// Represents the bills of a single [CashType] in one [LogicalCashUnit](crate::LogicalCashUnit).
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct InventoryItem {
+    number: u32,
+    pcu_name: PcuName,
+    location: CashLocation,
+    cash_type: CashType,
+    count: u32,
+}
+
+impl InventoryItem {
+    /// Creates a new [InventoryItem].
+    pub const fn new() -> Self {
+        Self {
+            number: 0,
+            pcu_name: PcuName::new(),
+            location: CashLocation::new(),
+            cash_type: CashType::new(),
+            count: 0,
+        }
+    }
+
+    /// Creates a new [InventoryItem] from the provided parameters.
+    pub const fn create(
+        number: u32,
+        pcu_name: PcuName,
+        location: CashLocation,
+        cash_type: CashType,
+        count: u32,
+    ) -> Self {
+        Self {
+            number,
+            pcu_name,
+            location,
+            cash_type,
+            count,
+        }
+    }
+
+    /// Gets the number of the [LogicalCashUnit](crate::LogicalCashUnit).
+    pub const fn number(&self) -> u32 {
+        self.number
+    }
+
+    /// Gets the name of the [PhysicalCashUnit](crate::PhysicalCashUnit) storing the bills.
+    pub const fn pcu_name(&self) -> &PcuName {
+        &self.pcu_name
+    }
+
+    /// Gets the [CashLocation] of the bills.
+    pub const fn location(&self) -> CashLocation {
+        self.location
+    }
+
+    /// Gets the [CashType] of the bills.
+    pub const fn cash_type(&self) -> CashType {
+        self.cash_type
+    }
+
+    /// Gets the number of bills.
+    pub const fn count(&self) -> u32 {
+        self.count
+    }
+
+    /// Gets the [CashTotal] of the bills.
+    pub fn total(&self) -> CashTotal {
+        let mut total = CashTotal::create(currency(&self.cash_type), 0, 0);
+        total.add(self.count, self.cash_type.value());
+        total
+    }
+}
+
+impl fmt::Display for InventoryItem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{{")?;
+        write!(f, r#""number": {}, "#, self.number)?;
+        write!(f, r#""pcu_name": {}, "#, self.pcu_name)?;
+        write!(f, r#""location": {}, "#, self.location)?;
+        write!(f, r#""cash_type": {}, "#, self.cash_type)?;
+        write!(f, r#""count": {}"#, self.count)?;
+        write!(f, "}}")
+    }
+}
+
+/// Represents the bills of one denomination, split by dispensable and deposit-only stock.
+///
+/// Variants of the same currency and value are counted together.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct DenominationTotal {
+    currency: Currency,
+    value: u32,
+    dispensable: CashTotal,
+    deposit_only: CashTotal,
+    escrow: CashTotal,
+}
+
+impl DenominationTotal {
+    /// Creates a new [DenominationTotal].
+    pub const fn new() -> Self {
+        Self {
+            currency: Currency::new(),
+            value: 0,
+            dispensable: CashTotal::new(),
+            deposit_only: CashTotal::new(),
+            escrow: CashTotal::new(),
+        }
+    }
+
+    /// Creates a new [DenominationTotal] for the provided [CashType].
+    pub fn create(cash_type: &CashType) -> Self {
+        let currency = currency(cash_type);
+
+        Self {
+            currency,
+            value: cash_type.value(),
+            dispensable: CashTotal::create(currency, 0, 0),
+            deposit_only: CashTotal::create(currency, 0, 0),
+            escrow: CashTotal::create(currency, 0, 0),
+        }
+    }
+
+    /// Gets the [Currency].
+    pub const fn currency(&self) -> Currency {
+        self.currency
+    }
+
+    /// Gets the denomination value in MDU.
+    pub const fn value(&self) -> u32 {
+        self.value
+    }
+
+    /// Gets the [CashTotal] available for dispense from recyclers and the loader.
+    pub const fn dispensable(&self) -> CashTotal {
+        self.dispensable
+    }
+
+    /// Gets the deposit-only [CashTotal] stored in the cashbox.
+    pub const fn deposit_only(&self) -> CashTotal {
+        self.deposit_only
+    }
+
+    /// Gets the [CashTotal] held in the bundler and escrow units.
+    pub const fn escrow(&self) -> CashTotal {
+        self.escrow
+    }
+
+    /// Gets the [CashTotal] across all locations.
+    pub fn total(&self) -> CashTotal {
+        [self.deposit_only, self.escrow]
+            .iter()
+            .fold(self.dispensable, |acc, t| {
+                CashTotal::create(
+                    acc.currency,
+                    acc.count.saturating_add(t.count),
+                    acc.mdu_amount.saturating_add(t.mdu_amount),
+                )
+            })
+    }
+
+    fn matches(&self, cash_type: &CashType) -> bool {
+        self.currency.currency_code() == cash_type.currency_code()
+            && self.value == cash_type.value()
+    }
+
+    fn add(&mut self, item: &InventoryItem) {
+        let total = match item.location {
+            loc if loc.is_dispensable() => &mut self.dispensable,
+            loc if loc.is_deposit_only() => &mut self.deposit_only,
+            _ => &mut self.escrow,
+        };
+
+        total.add(item.count, item.cash_type.value());
+    }
+}
+
+impl fmt::Display for DenominationTotal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{{")?;
+        write!(
+            f,
+            r#""currency_code": "{}", "#,
+            <&str>::from(self.currency.currency_code())
+        )?;
+        write!(f, r#""value": {}, "#, self.value)?;
+        write!(f, r#""dispensable": {}, "#, self.dispensable)?;
+        write!(f, r#""deposit_only": {}, "#, self.deposit_only)?;
+        write!(f, r#""escrow": {}"#, self.escrow)?;
+        write!(f, "}}")
+    }
+}
+
+/// Represents the cash stored in one [CashLocation], by currency.
+#[derive(Clone, Debug, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct LocationTotal {
+    location: CashLocation,
+    totals: Vec<CashTotal>,
+}
+
+impl LocationTotal {
+    /// Creates a new [LocationTotal].
+    pub const fn new() -> Self {
+        Self {
+            location: CashLocation::new(),
+            totals: Vec::new(),
+        }
+    }
+
+    /// Gets the [CashLocation].
+    pub const fn location(&self) -> CashLocation {
+        self.location
+    }
+
+    /// Gets the [CashTotal]s for each currency stored in the location.
+    pub fn totals(&self) -> &[CashTotal] {
+        self.totals.as_ref()
+    }
+}
+
+impl fmt::Display for LocationTotal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{{")?;
+        write!(f, r#""location": {}, "#, self.location)?;
+        write!(f, r#""totals": ["#)?;
+        for (i, total) in self.totals.iter().enumerate() {
+            if i != 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{total}")?;
+        }
+        write!(f, "]}}")
+    }
+}
+
+/// Represents the cash inventory of a BNR device.
+///
+/// Aggregates the [LogicalCashUnit](crate::LogicalCashUnit) counts of a [CashUnit] by
+/// denomination, currency and [CashLocation].
+#[derive(Clone, Debug, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct CashInventory {
+    items: Vec<InventoryItem>,
+}
+
+impl CashInventory {
+    /// Creates a new [CashInventory].
+    pub const fn new() -> Self {
+        Self { items: Vec::new() }
+    }
+
+    /// Creates a new [CashInventory] from the provided [CashUnit].
+    ///
+    /// Logical cash units without bills, or without a denomination, are skipped.
+    pub fn create(cash_unit: &CashUnit) -> Self {
+        let pcus = cash_unit.physical_cash_unit_list().items();
+
+        let items = cash_unit
+            .logical_cash_unit_list()
+            .iter()
+            .filter(|lcu| lcu.count() != 0 && lcu.cash_type().value() != 0)
+            .map(|lcu| InventoryItem {
+                number: lcu.number(),
+                pcu_name: pcus
+                    .get(lcu.physical_cu_index() as usize)
+                    .map(|p| *p.name())
+                    .unwrap_or_default(),
+                location: CashLocation::create(lcu.cu_kind()),
+                cash_type: lcu.cash_type(),
+                count: lcu.count(),
+            })
+            .collect();
+
+        Self { items }
+    }
+
+    /// Gets the list of [InventoryItem]s.
+    pub fn items(&self) -> &[InventoryItem] {
+        self.items.as_ref()
+    }
+
+    /// Gets the [CashTotal] for each currency across all locations.
+    pub fn totals(&self) -> Vec<CashTotal> {
+        currency_totals(self.items.iter())
+    }
+
+    /// Gets the [CashTotal] for each currency available for dispense.
+    pub fn dispensable_totals(&self) -> Vec<CashTotal> {
+        currency_totals(self.items.iter().filter(|i| i.location.is_dispensable()))
+    }
+
+    /// Gets the deposit-only [CashTotal] for each currency.
+    pub fn deposit_only_totals(&self) -> Vec<CashTotal> {
+        currency_totals(self.items.iter().filter(|i| i.location.is_deposit_only()))
+    }
+
+    /// Gets the [CashTotal] for the provided currency across all locations.
+    pub fn total(&self, currency_code: CurrencyCode) -> CashTotal {
+        let mut total = CashTotal::create(Currency::from(currency_code), 0, 0);
+
+        self.items
+            .iter()
+            .filter(|i| i.cash_type.currency_code() == currency_code)
+            .for_each(|i| total.add(i.count, i.cash_type.value()));
+
+        total
+    }
+
+    /// Gets the [DenominationTotal]s, ordered by currency and descending value.
+    pub fn by_denomination(&self) -> Vec<DenominationTotal> {
+        let mut denoms: Vec<DenominationTotal> = Vec::new();
+
+        for item in self.items.iter() {
+            match denoms.iter_mut().find(|d| d.matches(&item.cash_type)) {
+                Some(denom) => denom.add(item),
+                None => {
+                    let mut denom = DenominationTotal::create(&item.cash_type);
+                    denom.add(item);
+                    denoms.push(denom);
+                }
+            }
+        }
+
+        denoms.sort_by(|a, b| {
+            currency_str(&a.currency)
+                .cmp(currency_str(&b.currency))
+                .then(b.value.cmp(&a.value))
+        });
+
+        denoms
+    }
+
+    /// Gets the [LocationTotal]s for every [CashLocation] storing bills.
+    pub fn by_location(&self) -> Vec<LocationTotal> {
+        [
+            CashLocation::Recycler,
+            CashLocation::Loader,
+            CashLocation::Cashbox,
+            CashLocation::Escrow,
+        ]
+        .into_iter()
+        .map(|location| LocationTotal {
+            location,
+            totals: currency_totals(self.items.iter().filter(|i| i.location == location)),
+        })
+        .filter(|l| !l.totals.is_empty())
+        .collect()
+    }
+}
+
+impl From<&CashUnit> for CashInventory {
+    fn from(val: &CashUnit) -> Self {
+        Self::create(val)
+    }
+}
+
+impl From<CashUnit> for CashInventory {
+    fn from(val: CashUnit) -> Self {
+        Self::create(&val)
+    }
+}
+
+impl fmt::Display for CashInventory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{{")?;
+        write!(f, r#""items": ["#)?;
+        for (i, item) in self.items.iter().enumerate() {
+            if i != 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{item}")?;
+        }
+        write!(f, "]}}")
+    }
+}
+
+fn currency(cash_type: &CashType) -> Currency {
+    Currency::from(cash_type.currency_code())
+}
+
+fn currency_str(currency: &Currency) -> &'static str {
+    currency.currency_code().into()
+}
+
+fn currency_totals<'a>(items: impl Iterator<Item = &'a InventoryItem>) -> Vec<CashTotal> {
+    let mut totals: Vec<CashTotal> = Vec::new();
+
+    for item in items {
+        let code = item.cash_type.currency_code();
+        match totals
+            .iter_mut()
+            .find(|t| t.currency.currency_code() == code)
+        {
+            Some(total) => total.add(item.count, item.cash_type.value()),
+            None => totals.push(item.total()),
+        }
+    }
+
+    totals.sort_by(|a, b| currency_str(&a.currency).cmp(currency_str(&b.currency)));
+
+    totals
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cash_unit::tests::cash_unit;
+    use crate::Result;
+
+    #[test]
+    fn test_cash_inventory() -> Result<()> {
+        let inventory = CashInventory::from(cash_unit()?);
+
+        // one $100 bill in recycler RE3, the rest in the cashbox
+        let dispensable = inventory.dispensable_totals();
+        assert_eq!(dispensable.len(), 1);
+        assert_eq!(dispensable[0].count(), 1);
+        assert_eq!(dispensable[0].mdu_amount(), 10_000);
+        assert_eq!(dispensable[0].amount_str(), "100.00");
+        assert_eq!(dispensable[0].amount(), 100.0);
+
+        let deposit_only = inventory.deposit_only_totals();
+        assert_eq!(deposit_only[0].count(), 120);
+        assert_eq!(
+            deposit_only[0].mdu_amount(),
+            8 * 2000 + 1000 + 500 + 105 * 100 + 5 * 5000
+        );
+        assert_eq!(deposit_only[0].amount_str(), "530.00");
+
+        let total = inventory.total(CurrencyCode::from("USD"));
+        assert_eq!(total.count(), 121);
+        assert_eq!(
+            total.money()?,
+            Money::create(CurrencyCode::from("USD"), 63_000)
+        );
+        assert_eq!(inventory.totals(), [total]);
+
+        let denoms = inventory.by_denomination();
+        let values: Vec<u32> = denoms.iter().map(|d| d.value()).collect();
+        assert_eq!(values, [10000, 5000, 2000, 1000, 500, 100]);
+        assert_eq!(denoms[0].dispensable().count(), 1);
+        assert_eq!(denoms[0].deposit_only().count(), 0);
+        assert_eq!(denoms[2].total().count(), 8);
+
+        let locations: Vec<CashLocation> = inventory
+            .by_location()
+            .iter()
+            .map(|l| l.location())
+            .collect();
+        assert_eq!(locations, [CashLocation::Recycler, CashLocation::Cashbox]);
+
+        assert_eq!(inventory.items()[0].pcu_name(), &PcuName::from("RE3"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_cash_total_exponent() {
+        let mut total = CashTotal::create(Currency::from(CurrencyCode::from("JPY")), 0, 0);
+        total.add(3, 10_000);
+        assert_eq!(total.amount_str(), "3000.0");
+
+        let mut total = CashTotal::create(Currency::from(CurrencyCode::from("AMD")), 0, 0);
+        total.add(2, 5_000);
+        assert_eq!(total.amount_str(), "10000");
+        assert_eq!(total.amount(), 10_000.0);
+
+        let mut total = CashTotal::create(Currency::from(CurrencyCode::from("USD")), 0, 0);
+        total.add(3, 5);
+        assert_eq!(total.amount_str(), "0.15");
+    }
+}
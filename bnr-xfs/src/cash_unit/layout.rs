@@ -0,0 +1,641 @@
+use std::fmt;
+
+use crate::currency::{CashType, CuKind, CuType};
+use crate::json::JsonStr;
+use crate::{
+    CashUnit, Error, LogicalCashUnit, LogicalCashUnitList, PcuName, PhysicalCashUnitList, Result,
+    Threshold, PCU_NAME_LEN,
+};
+
+/// Describes one [PhysicalCashUnit](crate::PhysicalCashUnit) in a [CashUnitLayout].
+///
+/// The PCU is referenced by its `name`, e.g. `RE3` for the third module slot recycler.
+#[derive(Clone, Debug, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct PcuLayout {
+    name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    cash_type: Option<CashType>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    threshold: Option<Threshold>,
+}
+
+impl PcuLayout {
+    /// Creates a new [PcuLayout].
+    pub const fn new() -> Self {
+        Self {
+            name: String::new(),
+            cash_type: None,
+            threshold: None,
+        }
+    }
+
+    /// Creates a new [PcuLayout] from the provided PCU name.
+    pub fn create(name: &str) -> Self {
+        Self {
+            name: name.into(),
+            cash_type: None,
+            threshold: None,
+        }
+    }
+
+    /// Gets the PCU name.
+    pub fn name(&self) -> &str {
+        self.name.as_str()
+    }
+
+    /// Sets the PCU name.
+    pub fn set_name(&mut self, name: &str) {
+        self.name = name.into();
+    }
+
+    /// Builder function that sets the PCU name.
+    pub fn with_name(mut self, name: &str) -> Self {
+        self.set_name(name);
+        self
+    }
+
+    /// Gets the [CashType] assigned to the PCU.
+    ///
+    /// Required for recyclers and the loader, cashboxes accept every denomination.
+    pub const fn cash_type(&self) -> Option<CashType> {
+        self.cash_type
+    }
+
+    /// Sets the [CashType] assigned to the PCU.
+    pub fn set_cash_type(&mut self, cash_type: CashType) {
+        self.cash_type = Some(cash_type);
+    }
+
+    /// Builder function that sets the [CashType] assigned to the PCU.
+    pub fn with_cash_type(mut self, cash_type: CashType) -> Self {
+        self.set_cash_type(cash_type);
+        self
+    }
+
+    /// Gets the [Threshold] levels of the PCU.
+    ///
+    /// If unset, the device thresholds are left unchanged.
+    pub const fn threshold(&self) -> Option<Threshold> {
+        self.threshold
+    }
+
+    /// Sets the [Threshold] levels of the PCU.
+    pub fn set_threshold(&mut self, threshold: Threshold) {
+        self.threshold = Some(threshold);
+    }
+
+    /// Builder function that sets the [Threshold] levels of the PCU.
+    pub fn with_threshold(mut self, threshold: Threshold) -> Self {
+        self.set_threshold(threshold);
+        self
+    }
+
+    /// Gets whether the PCU name matches the provided [PcuName].
+    pub fn matches(&self, name: &PcuName) -> bool {
+        PcuName::from(self.name()) == *name
+    }
+
+    fn issues(&self, role: &str, issues: &mut Vec<String>) {
+        let name = self.name();
+        let len = name.chars().count();
+
+        if len == 0 || len > PCU_NAME_LEN {
+            issues.push(format!(
+                "{role} PCU name {name:?} must be 1 to {PCU_NAME_LEN} characters"
+            ));
+        } else if name.chars().any(|c| !c.is_ascii_graphic()) {
+            issues.push(format!(
+                "{role} PCU name {name:?} must only contain printable ASCII characters"
+            ));
+        }
+
+        match (role, self.cash_type) {
+            ("cashbox", Some(_)) => issues.push(format!(
+                "cashbox {name} cannot be assigned a cash type, it accepts every denomination"
+            )),
+            ("recycler" | "loader", None) => issues.push(format!("{role} {name} has no cash type")),
+            ("recycler" | "loader", Some(cash_type)) if cash_type.value() == 0 => {
+                issues.push(format!("{role} {name} has a cash type without a value"))
+            }
+            _ => (),
+        }
+
+        if let Some(threshold) = self.threshold {
+            let (empty, low, high, full) = (
+                threshold.empty(),
+                threshold.low(),
+                threshold.high(),
+                threshold.full(),
+            );
+
+            if !(empty <= low && low < high && high < full) {
+                issues.push(format!(
+                    "{role} {name} thresholds must be ordered empty <= low < high < full, have: empty {empty}, low {low}, high {high}, full {full}"
+                ));
+            }
+        }
+    }
+}
+
+impl fmt::Display for PcuLayout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{{")?;
+        write!(f, r#""name": {}, "#, JsonStr(&self.name))?;
+        match self.cash_type.as_ref() {
+            Some(cash_type) => write!(f, r#""cash_type": {cash_type}, "#)?,
+            None => write!(f, r#""cash_type": null, "#)?,
+        }
+        match self.threshold.as_ref() {
+            Some(t) => write!(
+                f,
+                r#""threshold": {{"full": {}, "high": {}, "low": {}, "empty": {}}}"#,
+                t.full(),
+                t.high(),
+                t.low(),
+                t.empty()
+            )?,
+            None => write!(f, r#""threshold": null"#)?,
+        }
+        write!(f, "}}")
+    }
+}
+
+/// Declarative profile of the cash unit layout of a BNR device.
+///
+/// Describes the recyclers, loader and cashbox by denomination, and is validated before
+/// producing the lists sent with [configure_cash_unit](crate::DeviceHandle::configure_cash_unit).
+///
+/// Example JSON profile:
+///
+/// ```json
+/// {
+///   "cashbox": { "name": "CB", "threshold": { "full": 600, "high": 550, "low": 0, "empty": 0 } },
+///   "loader": { "name": "LO1", "cash_type": { "currency_code": "USD", "value": 2000, "variant": 0 } },
+///   "recyclers": [
+///     { "name": "RE3", "cash_type": { "currency_code": "USD", "value": 100, "variant": 0 } },
+///     { "name": "RE4", "cash_type": { "currency_code": "USD", "value": 500, "variant": 0 } }
+///   ]
+/// }
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct CashUnitLayout {
+    cashbox: PcuLayout,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    loader: Option<PcuLayout>,
+    #[serde(default)]
+    recyclers: Vec<PcuLayout>,
+}
+
+impl CashUnitLayout {
+    /// Creates a new [CashUnitLayout].
+    pub const fn new() -> Self {
+        Self {
+            cashbox: PcuLayout::new(),
+            loader: None,
+            recyclers: Vec::new(),
+        }
+    }
+
+    /// Gets the cashbox [PcuLayout].
+    pub const fn cashbox(&self) -> &PcuLayout {
+        &self.cashbox
+    }
+
+    /// Sets the cashbox [PcuLayout].
+    pub fn set_cashbox(&mut self, cashbox: PcuLayout) {
+        self.cashbox = cashbox;
+    }
+
+    /// Builder function that sets the cashbox [PcuLayout].
+    pub fn with_cashbox(mut self, cashbox: PcuLayout) -> Self {
+        self.set_cashbox(cashbox);
+        self
+    }
+
+    /// Gets the optional loader [PcuLayout].
+    pub const fn loader(&self) -> Option<&PcuLayout> {
+        self.loader.as_ref()
+    }
+
+    /// Sets the loader [PcuLayout].
+    pub fn set_loader(&mut self, loader: PcuLayout) {
+        self.loader = Some(loader);
+    }
+
+    /// Builder function that sets the loader [PcuLayout].
+    pub fn with_loader(mut self, loader: PcuLayout) -> Self {
+        self.set_loader(loader);
+        self
+    }
+
+    /// Gets the recycler [PcuLayout]s.
+    pub fn recyclers(&self) -> &[PcuLayout] {
+        self.recyclers.as_ref()
+    }
+
+    /// Sets the recycler [PcuLayout]s.
+    pub fn set_recyclers(&mut self, recyclers: &[PcuLayout]) {
+        self.recyclers = recyclers.into();
+    }
+
+    /// Builder function that sets the recycler [PcuLayout]s.
+    pub fn with_recyclers(mut self, recyclers: &[PcuLayout]) -> Self {
+        self.set_recyclers(recyclers);
+        self
+    }
+
+    /// Parses and validates a [CashUnitLayout] from a JSON profile.
+    pub fn from_json(json: &str) -> Result<Self> {
+        let layout: Self = serde_json::from_str(json)?;
+        layout.validate()?;
+        Ok(layout)
+    }
+
+    /// Writes the [CashUnitLayout] to a JSON profile.
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    fn pcus(&self) -> impl Iterator<Item = (&'static str, &PcuLayout)> + '_ {
+        [("cashbox", &self.cashbox)]
+            .into_iter()
+            .chain(self.loader.iter().map(|l| ("loader", l)))
+            .chain(self.recyclers.iter().map(|r| ("recycler", r)))
+    }
+
+    /// Gets the list of inconsistencies in the profile.
+    ///
+    /// Checks the PCU names, the cash types assigned to each PCU, duplicate recycler
+    /// denominations, and the [Threshold] ordering.
+    pub fn issues(&self) -> Vec<String> {
+        let mut issues = Vec::new();
+
+        let pcus: Vec<(&str, &PcuLayout)> = self.pcus().collect();
+        for (i, (role, pcu)) in pcus.iter().enumerate() {
+            pcu.issues(role, &mut issues);
+
+            if pcus[..i].iter().any(|(_, p)| p.name() == pcu.name()) {
+                issues.push(format!("duplicate PCU name {:?}", pcu.name()));
+            }
+        }
+
+        for (i, recycler) in self.recyclers.iter().enumerate() {
+            let dup = recycler.cash_type.and_then(|ct| {
+                self.recyclers[..i].iter().find(|r| {
+                    r.cash_type.is_some_and(|c| {
+                        c.currency_code() == ct.currency_code() && c.value() == ct.value()
+                    })
+                })
+            });
+
+            if let Some(dup) = dup {
+                issues.push(format!(
+                    "recyclers {} and {} have the same denomination",
+                    dup.name(),
+                    recycler.name()
+                ));
+            }
+        }
+
+        issues
+    }
+
+    /// Validates the profile.
+    ///
+    /// Returns an [Error::Config] listing every issue found, see [issues](Self::issues).
+    pub fn validate(&self) -> Result<()> {
+        into_result(self.issues())
+    }
+
+    /// Gets the list of inconsistencies between the profile and the device cash unit lists.
+    ///
+    /// Checks that every profile PCU exists on the device, and that it is linked to logical cash
+    /// units of the right kind: deposit for the cashbox, dispense for the loader, and recycle for
+    /// the recyclers. Also checks the links of the lists themselves, see [link_issues].
+    pub fn cash_unit_issues(
+        &self,
+        lcu_list: &LogicalCashUnitList,
+        pcu_list: &PhysicalCashUnitList,
+    ) -> Vec<String> {
+        let mut issues = link_issues(lcu_list, pcu_list);
+
+        for (role, layout) in self.pcus() {
+            let kind = match role {
+                "cashbox" => CuKind::Deposit,
+                "loader" => CuKind::Dispense,
+                _ => CuKind::Recycle,
+            };
+
+            match pcu_list.iter().position(|p| layout.matches(p.name())) {
+                Some(idx) => {
+                    if !lcus_for(lcu_list, idx).any(|l| l.cu_kind() == kind) {
+                        issues.push(format!(
+                            "{role} {} has no {} logical cash unit",
+                            layout.name(),
+                            <&str>::from(kind)
+                        ));
+                    }
+                }
+                None => issues.push(format!(
+                    "{role} {} is not a physical cash unit on the device",
+                    layout.name()
+                )),
+            }
+        }
+
+        for (idx, pcu) in pcu_list.iter().enumerate() {
+            let recycler = lcus_for(lcu_list, idx).any(|l| l.cu_kind() == CuKind::Recycle);
+            if recycler && !self.recyclers.iter().any(|r| r.matches(pcu.name())) {
+                issues.push(format!(
+                    "recycler {} is missing from the layout",
+                    pcu.name().decoded()
+                ));
+            }
+        }
+
+        issues
+    }
+
+    /// Applies the profile onto the `current` device [CashUnit].
+    ///
+    /// Assigns the recycler and loader cash types to every logical cash unit linked to the PCU,
+    /// and sets the PCU thresholds. Counts are left unchanged.
+    ///
+    /// The profile and the resulting lists are validated before returning, so nothing
+    /// inconsistent is sent to the device.
+    ///
+    /// Returns the lists for [configure_cash_unit](crate::DeviceHandle::configure_cash_unit).
+    pub fn apply(&self, current: &CashUnit) -> Result<(LogicalCashUnitList, PhysicalCashUnitList)> {
+        self.validate()?;
+
+        let mut lcu_list = current.logical_cash_unit_list().clone();
+        let mut pcu_list = current.physical_cash_unit_list().clone();
+
+        into_result(self.cash_unit_issues(&lcu_list, &pcu_list))?;
+
+        for (idx, pcu) in pcu_list.iter_mut().enumerate() {
+            let Some((_, layout)) = self.pcus().find(|(_, l)| l.matches(pcu.name())) else {
+                continue;
+            };
+
+            if let Some(threshold) = layout.threshold() {
+                pcu.set_threshold(threshold);
+            }
+
+            if let Some(cash_type) = layout.cash_type() {
+                for lcu in lcu_list
+                    .iter_mut()
+                    .filter(|l| l.physical_cu_index() as usize == idx)
+                {
+                    set_lcu_cash_type(lcu, cash_type);
+                }
+            }
+        }
+
+        into_result(self.cash_unit_issues(&lcu_list, &pcu_list))?;
+
+        Ok((lcu_list, pcu_list))
+    }
+}
+
+impl fmt::Display for CashUnitLayout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{{")?;
+        write!(f, r#""cashbox": {}, "#, self.cashbox)?;
+        match self.loader.as_ref() {
+            Some(loader) => write!(f, r#""loader": {loader}, "#)?,
+            None => write!(f, r#""loader": null, "#)?,
+        }
+        write!(f, r#""recyclers": ["#)?;
+        for (i, recycler) in self.recyclers.iter().enumerate() {
+            if i != 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{recycler}")?;
+        }
+        write!(f, "]}}")
+    }
+}
+
+/// Gets the list of inconsistent links between [LogicalCashUnit]s and
+/// [PhysicalCashUnit](crate::PhysicalCashUnit)s.
+///
+/// Checks that every `physical_cu_index` refers to a PCU in the list, that dispense and recycle
+/// LCUs are bill cassettes, that deposit LCUs are bill or reject cassettes, and that the PCU
+/// names are unique.
+pub fn link_issues(lcu_list: &LogicalCashUnitList, pcu_list: &PhysicalCashUnitList) -> Vec<String> {
+    let mut issues = Vec::new();
+    let pcus = pcu_list.items();
+
+    for lcu in lcu_list.iter() {
+        let number = lcu.number();
+        let idx = lcu.physical_cu_index() as usize;
+
+        if idx >= pcus.len() {
+            issues.push(format!(
+                "LCU {number} links to PCU index {idx}, the device has {} PCUs",
+                pcus.len()
+            ));
+        }
+
+        let type_ok = match lcu.cu_kind() {
+            CuKind::NotAvailable => true,
+            // the cashbox also stores rejected bills
+            CuKind::Deposit => {
+                matches!(lcu.cu_type(), CuType::BillCassette | CuType::RejectCassette)
+            }
+            CuKind::Dispense | CuKind::Recycle => lcu.cu_type() == CuType::BillCassette,
+        };
+
+        if !type_ok {
+            issues.push(format!(
+                "LCU {number} of kind {} has type {}",
+                <&str>::from(lcu.cu_kind()),
+                <&str>::from(lcu.cu_type())
+            ));
+        }
+    }
+
+    for (i, pcu) in pcus.iter().enumerate() {
+        if pcus[..i].iter().any(|p| p.name() == pcu.name()) {
+            issues.push(format!("duplicate PCU name {:?}", pcu.name().decoded()));
+        }
+    }
+
+    issues
+}
+
+fn lcus_for(
+    lcu_list: &LogicalCashUnitList,
+    pcu_index: usize,
+) -> impl Iterator<Item = &LogicalCashUnit> {
+    lcu_list
+        .iter()
+        .filter(move |l| l.physical_cu_index() as usize == pcu_index)
+}
+
+fn set_lcu_cash_type(lcu: &mut LogicalCashUnit, cash_type: CashType) {
+    let cur = lcu.cash_type();
+
+    // secondary cash types are variants of the main denomination, clear them on a change
+    if cur.currency_code() != cash_type.currency_code() || cur.value() != cash_type.value() {
+        lcu.set_secondary_cash_types(Default::default());
+    }
+
+    lcu.set_cash_type(cash_type);
+}
+
+fn into_result(issues: Vec<String>) -> Result<()> {
+    if issues.is_empty() {
+        Ok(())
+    } else {
+        Err(Error::Config(issues.join("; ")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::currency::CurrencyCode;
+    use crate::PhysicalCashUnit;
+
+    fn usd(value: u32) -> CashType {
+        CashType::create(CurrencyCode::from("USD"), value, 0)
+    }
+
+    fn layout() -> CashUnitLayout {
+        CashUnitLayout::new()
+            .with_cashbox(PcuLayout::create("CB"))
+            .with_loader(PcuLayout::create("LO1").with_cash_type(usd(2000)))
+            .with_recyclers(&[
+                PcuLayout::create("RE3").with_cash_type(usd(100)),
+                PcuLayout::create("RE4")
+                    .with_cash_type(usd(500))
+                    .with_threshold(Threshold::new().with_full(60).with_high(50).with_low(5)),
+            ])
+    }
+
+    fn cash_unit() -> CashUnit {
+        let lcu = |number, kind, idx| {
+            LogicalCashUnit::new()
+                .with_number(number)
+                .with_cu_kind(kind)
+                .with_cu_type(CuType::BillCassette)
+                .with_physical_cash_unit(idx)
+        };
+
+        let lcus = [
+            lcu(1, CuKind::Deposit, 0).with_cash_type(usd(1000)),
+            lcu(2, CuKind::Dispense, 1).with_cash_type(usd(1000)),
+            lcu(3, CuKind::Recycle, 2).with_cash_type(usd(1000)),
+            lcu(4, CuKind::Recycle, 3).with_cash_type(usd(2000)),
+        ];
+        let pcus = ["CB", "LO1", "RE3", "RE4"]
+            .map(|name| PhysicalCashUnit::new().with_name(PcuName::from(name)));
+
+        CashUnit::new()
+            .with_logical_cash_unit_list(LogicalCashUnitList::new().with_items(&lcus))
+            .with_physical_cash_unit_list(PhysicalCashUnitList::new().with_items(&pcus))
+    }
+
+    #[test]
+    fn test_layout_validate() -> Result<()> {
+        layout().validate()?;
+
+        let json = r#"{
+            "cashbox": { "name": "CB" },
+            "loader": { "name": "LO1", "cash_type": { "currency_code": "USD", "value": 2000, "variant": 0 } },
+            "recyclers": [
+                { "name": "RE3", "cash_type": { "currency_code": "USD", "value": 100, "variant": 0 } },
+                { "name": "RE4", "cash_type": { "currency_code": "USD", "value": 500, "variant": 0 },
+                  "threshold": { "full": 60, "high": 50, "low": 5, "empty": 0 } }
+            ]
+        }"#;
+        assert_eq!(CashUnitLayout::from_json(json)?, layout());
+
+        let json = layout().to_json()?;
+        assert_eq!(CashUnitLayout::from_json(json.as_str())?, layout());
+
+        let bad = layout()
+            .with_cashbox(PcuLayout::create("CASHBOX").with_cash_type(usd(100)))
+            .with_recyclers(&[
+                PcuLayout::create("RE3").with_cash_type(usd(100)),
+                PcuLayout::create("RE4")
+                    .with_cash_type(usd(100))
+                    .with_threshold(Threshold::new().with_full(50).with_high(50)),
+                PcuLayout::create("RE4"),
+            ]);
+
+        let issues = bad.issues();
+        assert_eq!(issues.len(), 6, "{issues:?}");
+        assert!(issues.iter().any(|i| i.contains("1 to 5 characters")));
+        assert!(issues.iter().any(|i| i.contains("cashbox CASHBOX cannot")));
+        assert!(issues
+            .iter()
+            .any(|i| i.contains("thresholds must be ordered")));
+        assert!(issues
+            .iter()
+            .any(|i| i.contains("recycler RE4 has no cash type")));
+        assert!(issues.iter().any(|i| i.contains("duplicate PCU name")));
+        assert!(issues.iter().any(|i| i.contains("same denomination")));
+
+        assert!(matches!(bad.validate(), Err(Error::Config(_))));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_link_issues_cu_type() {
+        let cash_unit = cash_unit();
+        let mut lcus = cash_unit.logical_cash_unit_list().clone();
+
+        // the cashbox also stores rejected bills
+        lcus.items_mut()[0].set_cu_type(CuType::RejectCassette);
+        assert!(link_issues(&lcus, cash_unit.physical_cash_unit_list()).is_empty());
+
+        // dispense and recycle LCUs only hold bill cassettes
+        lcus.items_mut()[1].set_cu_type(CuType::RejectCassette);
+        lcus.items_mut()[2].set_cu_type(CuType::NotAvailable);
+        let issues = link_issues(&lcus, cash_unit.physical_cash_unit_list());
+
+        assert_eq!(issues.len(), 2, "{issues:?}");
+        assert!(issues[0].starts_with("LCU 2 of kind dispense has type"));
+        assert!(issues[1].starts_with("LCU 3 of kind recycle has type"));
+    }
+
+    #[test]
+    fn test_layout_apply() -> Result<()> {
+        let (lcus, pcus) = layout().apply(&cash_unit())?;
+
+        let values: Vec<u32> = lcus.iter().map(|l| l.cash_type().value()).collect();
+        assert_eq!(values, [1000, 2000, 100, 500]);
+        assert_eq!(pcus.items()[3].threshold().full(), 60);
+        assert_eq!(pcus.items()[2].threshold(), Threshold::new());
+
+        // a recycler missing from the device, and one missing from the profile
+        let missing = layout().with_recyclers(&[PcuLayout::create("RE5").with_cash_type(usd(100))]);
+        let err = missing.apply(&cash_unit()).unwrap_err();
+        let Error::Config(msg) = err else {
+            panic!("unexpected error: {err}");
+        };
+        assert!(msg.contains("RE5 is not a physical cash unit"));
+        assert!(msg.contains("recycler RE3 is missing"));
+
+        // an LCU linked past the end of the PCU list
+        let mut cash_unit = cash_unit();
+        let mut lcus = cash_unit.logical_cash_unit_list().clone();
+        lcus.items_mut()[0].set_physical_cu_index(7);
+        cash_unit.set_logical_cash_unit_list(lcus);
+
+        let issues = layout().cash_unit_issues(
+            cash_unit.logical_cash_unit_list(),
+            cash_unit.physical_cash_unit_list(),
+        );
+        assert!(issues
+            .iter()
+            .any(|i| i.contains("LCU 1 links to PCU index 7")));
+        assert!(issues.iter().any(|i| i.contains("cashbox CB has no")));
+
+        Ok(())
+    }
+}
@@ -1,16 +1,23 @@
 use std::fmt;
 
 use crate::currency::{CashType, CashTypeList, CuKind, CuType};
-use crate::impl_xfs_struct;
+use crate::{create_xfs_i4, XfsStruct};
 use crate::{Count, ExtendedCounters, InitialCount, Number, Status, UnitId};
 
 mod list;
 
 pub use list::*;
 
+create_xfs_i4!(
+    PhysicalCuIndex,
+    "physicalCuIndex",
+    "Represents the index of the [PhysicalCashUnit](crate::PhysicalCashUnit) backing a [LogicalCashUnit]."
+);
+
 /// Represents a logical cash unit, and its parameters.
 #[repr(C)]
-#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[derive(Clone, Debug, Default, PartialEq, serde::Deserialize, serde::Serialize, XfsStruct)]
+#[xfs(name = "logicalCashUnit", no_display)]
 pub struct LogicalCashUnit {
     cash_type: CashType,
     secondary_cash_types: CashTypeList,
@@ -21,8 +28,9 @@ pub struct LogicalCashUnit {
     initial_count: InitialCount,
     count: Count,
     status: Status,
+    #[xfs(skip)]
     extended_counters: ExtendedCounters,
-    physical_cu_index: UnitId,
+    physical_cu_index: PhysicalCuIndex,
 }
 
 impl LogicalCashUnit {
@@ -39,7 +47,7 @@ impl LogicalCashUnit {
             count: Count::new(),
             status: Status::new(),
             extended_counters: ExtendedCounters::new(),
-            physical_cu_index: UnitId::new(),
+            physical_cu_index: PhysicalCuIndex::new(),
         }
     }
 
@@ -219,20 +227,21 @@ impl LogicalCashUnit {
         self
     }
 
-    /// Gets the [UnitId] of the [PhysicalCashUnit] backing this [LogicalCashUnit].
+    /// Gets the index of the [PhysicalCashUnit](crate::PhysicalCashUnit) backing this [LogicalCashUnit].
     ///
-    /// The C library uses a pointer here, but that is wildly unsafe. Use the ID instead.
-    pub const fn physical_cu_index(&self) -> UnitId {
-        self.physical_cu_index
+    /// The C library uses a pointer here, but that is wildly unsafe. Use the index into the
+    /// [PhysicalCashUnitList](crate::PhysicalCashUnitList) instead.
+    pub const fn physical_cu_index(&self) -> u32 {
+        self.physical_cu_index.inner()
     }
 
-    /// Sets the physical cash unit [UnitId].
-    pub fn set_physical_cu_index(&mut self, pcu_index: UnitId) {
-        self.physical_cu_index = pcu_index;
+    /// Sets the physical cash unit index.
+    pub fn set_physical_cu_index(&mut self, pcu_index: u32) {
+        self.physical_cu_index.set_inner(pcu_index);
     }
 
-    /// Builder function that sets the physical cash unit [UnitId].
-    pub fn with_physical_cash_unit(mut self, pcu_index: UnitId) -> Self {
+    /// Builder function that sets the physical cash unit index.
+    pub fn with_physical_cash_unit(mut self, pcu_index: u32) -> Self {
         self.set_physical_cu_index(pcu_index);
         self
     }
@@ -259,20 +268,3 @@ impl fmt::Display for LogicalCashUnit {
         write!(f, "}}")
     }
 }
-
-impl_xfs_struct!(
-    LogicalCashUnit,
-    "logicalCashUnit",
-    [
-        cash_type: CashType,
-        secondary_cash_types: CashTypeList,
-        number: Number,
-        cu_kind: CuKind,
-        cu_type: CuType,
-        unit_id: UnitId,
-        initial_count: InitialCount,
-        count: Count,
-        status: Status,
-        physical_cu_index: UnitId
-    ]
-);
@@ -1,51 +1,37 @@
-use std::{cmp, fmt};
+use std::fmt;
 
-use crate::{impl_xfs_array, impl_xfs_struct, MaxSize, Size};
+use crate::{BoundedList, MaxSize, Result, Size, XfsArray, XfsStruct};
 
 use super::*;
 
 /// Maximum number of [LogicalCashUnit]s that can be present on a device.
 pub const LCU_LIST_LEN: usize = 83;
 
-#[repr(C)]
-#[derive(Clone, Copy, Debug, PartialEq)]
+/// Represents the [LogicalCashUnit] items of a [LogicalCashUnitList].
+#[derive(Clone, Debug, Default, PartialEq, serde::Deserialize, serde::Serialize, XfsArray)]
+#[xfs(name = "items")]
 pub struct LogicalCashUnitItems {
-    size: Size,
-    items: [LogicalCashUnit; LCU_LIST_LEN],
+    items: BoundedList<LogicalCashUnit, LCU_LIST_LEN>,
 }
 
 impl LogicalCashUnitItems {
     /// Creates a new [LogicalCashUnitItems] list.
     pub const fn new() -> Self {
         Self {
-            size: Size::new(),
-            items: [LogicalCashUnit::new(); LCU_LIST_LEN],
+            items: BoundedList::new(),
         }
     }
 
-    /// Creates a new [LogicalCashUnitItems] list from the provided parameter.
-    pub fn create(items: [LogicalCashUnit; LCU_LIST_LEN]) -> Self {
-        Self {
-            size: Size::create(LCU_LIST_LEN as u32),
-            items,
-        }
-    }
-
-    /// Gets the max size.
-    pub const fn max_size() -> usize {
-        LCU_LIST_LEN
-    }
-
     /// Gets the size.
-    pub const fn size(&self) -> u32 {
-        self.size.inner()
+    pub fn size(&self) -> u32 {
+        self.items.len() as u32
     }
 
     /// Sets the size.
+    ///
+    /// New items are set to the default value, no-op if `size` is larger than the max size.
     pub fn set_size(&mut self, size: u32) {
-        if size as usize <= Self::max_size() {
-            self.size.set_inner(size);
-        }
+        self.items.resize(size as usize);
     }
 
     /// Builder function that sets the size.
@@ -53,56 +39,13 @@ impl LogicalCashUnitItems {
         self.set_size(size);
         self
     }
-
-    /// Gets a list of the [LogicalCashUnit]s.
-    pub fn items(&self) -> &[LogicalCashUnit] {
-        let size = self.size() as usize;
-
-        if (0..self.items.len()).contains(&size) {
-            self.items[..size].as_ref()
-        } else {
-            self.items.as_ref()
-        }
-    }
-
-    /// Gets a mutable list of the [LogicalCashUnit]s.
-    pub fn items_mut(&mut self) -> &mut [LogicalCashUnit] {
-        let size = self.size() as usize;
-
-        if (0..self.items.len()).contains(&size) {
-            self.items[..size].as_mut()
-        } else {
-            self.items.as_mut()
-        }
-    }
-
-    /// Sets a list of the [LogicalCashUnit]s.
-    pub fn set_items(&mut self, items: &[LogicalCashUnit]) {
-        let len = cmp::min(items.len(), LCU_LIST_LEN);
-
-        self.set_size(len as u32);
-        self.items[..len].copy_from_slice(items[..len].as_ref());
-    }
-
-    /// Builder function that sets a list of the [LogicalCashUnit]s.
-    pub fn with_items(mut self, items: &[LogicalCashUnit]) -> Self {
-        self.set_items(items);
-        self
-    }
 }
 
-impl Default for LogicalCashUnitItems {
-    fn default() -> Self {
-        Self::new()
-    }
-}
-
-impl_xfs_array!(LogicalCashUnitItems, "items");
-
 /// Represents a list of [LogicalCashUnit]s.
-#[repr(C)]
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize, XfsStruct)]
+#[xfs(name = "logicalCashUnits", no_display)]
 pub struct LogicalCashUnitList {
+    #[xfs(skip)]
     max_size: MaxSize,
     size: Size,
     items: LogicalCashUnitItems,
@@ -150,6 +93,25 @@ impl LogicalCashUnitList {
         self.items.items_mut()
     }
 
+    /// Gets an iterator over the [LogicalCashUnit]s.
+    pub fn iter(&self) -> std::slice::Iter<'_, LogicalCashUnit> {
+        self.items.iter()
+    }
+
+    /// Gets a mutable iterator over the [LogicalCashUnit]s.
+    pub fn iter_mut(&mut self) -> std::slice::IterMut<'_, LogicalCashUnit> {
+        self.items.iter_mut()
+    }
+
+    /// Pushes a [LogicalCashUnit] onto the end of the list.
+    ///
+    /// Returns an error if the list is at the max size.
+    pub fn push_item(&mut self, item: LogicalCashUnit) -> Result<()> {
+        self.items.push_item(item)?;
+        self.size.set_inner(self.items.size());
+        Ok(())
+    }
+
     /// Sets a list of [LogicalCashUnit]s.
     pub fn set_items(&mut self, items: &[LogicalCashUnit]) {
         self.items.set_items(items);
@@ -169,6 +131,15 @@ impl Default for LogicalCashUnitList {
     }
 }
 
+impl<'a> IntoIterator for &'a LogicalCashUnitList {
+    type Item = &'a LogicalCashUnit;
+    type IntoIter = std::slice::Iter<'a, LogicalCashUnit>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
 impl fmt::Display for LogicalCashUnitList {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{{")?;
@@ -186,5 +157,3 @@ impl fmt::Display for LogicalCashUnitList {
         write!(f, "]}}")
     }
 }
-
-impl_xfs_struct!(LogicalCashUnitList, "logicalCashUnits", [size: Size, items: LogicalCashUnitItems]);
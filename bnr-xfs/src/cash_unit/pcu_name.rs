@@ -1,11 +1,14 @@
-use std::{cmp, fmt};
+use std::{cmp, fmt, str::FromStr};
 
+use crate::json::JsonStr;
 use crate::xfs::{value::XfsValue, xfs_struct::XfsMember};
-use crate::{Error, Result};
+use crate::{impl_xfs_decode, Error, Result};
 
 pub const PCU_NAME_LEN: usize = 5;
 
 /// Represents the name of a PCU.
+///
+/// The name is stored as `ISO-8859-1` bytes, one byte per character, the same as on the device.
 #[repr(C)]
 #[derive(Clone, Copy, Debug, Default, PartialEq, serde::Deserialize, serde::Serialize)]
 pub struct PcuName([u8; 5]);
@@ -16,7 +19,7 @@ impl PcuName {
         Self([0u8; PCU_NAME_LEN])
     }
 
-    /// Creates a new [PcuName] from the provided parameter.
+    /// Creates a new [PcuName] from the provided `ISO-8859-1` bytes.
     pub fn create(s: &[u8]) -> Self {
         let len = cmp::min(s.len(), PCU_NAME_LEN);
         let mut inner = [0u8; PCU_NAME_LEN];
@@ -41,6 +44,20 @@ impl PcuName {
         std::str::from_utf8(self.0.as_ref()).unwrap_or("")
     }
 
+    /// Gets the decoded name of the [PcuName].
+    ///
+    /// Decodes the `ISO-8859-1` bytes, and strips trailing null bytes.
+    pub fn decoded(&self) -> String {
+        let len = self
+            .0
+            .iter()
+            .rposition(|&b| b != 0)
+            .map(|p| p + 1)
+            .unwrap_or(0);
+
+        crate::xfs::decode_latin1(&self.0[..len])
+    }
+
     /// Gets a mutable reference to the inner representation of the [PcuName].
     pub fn inner_mut(&mut self) -> &mut [u8] {
         &mut self.0
@@ -54,7 +71,7 @@ impl PcuName {
 
 impl fmt::Display for PcuName {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, r#""{}""#, self.inner_str())
+        write!(f, "{}", JsonStr(&self.decoded()))
     }
 }
 
@@ -65,14 +82,47 @@ impl From<&[u8]> for PcuName {
 }
 
 impl From<&str> for PcuName {
+    /// Converts the name, replacing characters outside the `ISO-8859-1` range with `?`.
+    ///
+    /// Use [parse](str::parse) to get an error instead.
     fn from(val: &str) -> Self {
-        Self::create(val.as_bytes())
+        // the name is truncated by character (not by UTF-8 byte)
+        let mut lossy = false;
+        let bytes: Vec<u8> = val
+            .chars()
+            .take(PCU_NAME_LEN)
+            .map(|c| {
+                u8::try_from(u32::from(c)).unwrap_or_else(|_| {
+                    lossy = true;
+                    b'?'
+                })
+            })
+            .collect();
+
+        if lossy {
+            log::warn!("Replaced characters outside ISO-8859-1 in PcuName: {val:?}");
+        }
+
+        Self::create(bytes.as_ref())
+    }
+}
+
+impl FromStr for PcuName {
+    type Err = Error;
+
+    /// Parses the name, returning an error for characters outside the `ISO-8859-1` range.
+    ///
+    /// The name is truncated to [PCU_NAME_LEN] characters.
+    fn from_str(val: &str) -> Result<Self> {
+        let name: String = val.chars().take(PCU_NAME_LEN).collect();
+
+        Ok(Self::create(crate::xfs::encode_latin1(&name)?.as_ref()))
     }
 }
 
 impl From<&PcuName> for XfsValue {
     fn from(val: &PcuName) -> Self {
-        Self::new().with_string(val.inner_str())
+        Self::new().with_string(val.decoded())
     }
 }
 
@@ -103,6 +153,8 @@ impl TryFrom<XfsValue> for PcuName {
     }
 }
 
+impl_xfs_decode!(PcuName);
+
 impl From<&PcuName> for XfsMember {
     fn from(val: &PcuName) -> Self {
         Self::create(PcuName::xfs_name(), val.into())
@@ -135,3 +187,44 @@ impl TryFrom<XfsMember> for PcuName {
         (&val).try_into()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pcu_name_latin1() {
+        let name = PcuName::from("RÉ3");
+
+        assert_eq!(name.inner(), [b'R', 0xc9, b'3', 0, 0].as_ref());
+        assert_eq!(name.decoded(), "RÉ3");
+
+        let val = XfsValue::from(name);
+        assert_eq!(val.string(), Some("RÉ3"));
+        assert_eq!(PcuName::try_from(&val).unwrap(), name);
+
+        // truncates by character, not by UTF-8 byte
+        assert_eq!(PcuName::from("ÀÉÎÕÜÿ").decoded(), "ÀÉÎÕÜ");
+        assert_eq!(PcuName::from("€1").decoded(), "?1");
+
+        let ascii = PcuName::from("RE3");
+        assert_eq!(ascii.inner_str(), "RE3\0\0");
+        assert_eq!(ascii.decoded(), "RE3");
+    }
+
+    #[test]
+    fn test_pcu_name_parse() -> Result<()> {
+        assert_eq!("RÉ3".parse::<PcuName>()?, PcuName::from("RÉ3"));
+        assert_eq!("ÀÉÎÕÜÿ".parse::<PcuName>()?.decoded(), "ÀÉÎÕÜ");
+
+        match "€1".parse::<PcuName>() {
+            Err(Error::Xfs(msg)) => assert!(msg.contains("U+20AC"), "{msg}"),
+            res => panic!("unexpected result: {res:?}"),
+        }
+
+        // characters past the maximum length are not checked
+        assert_eq!("RE3AB€".parse::<PcuName>()?.decoded(), "RE3AB");
+
+        Ok(())
+    }
+}
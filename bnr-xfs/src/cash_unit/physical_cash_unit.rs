@@ -1,7 +1,6 @@
 use std::fmt;
 
-use crate::impl_xfs_struct;
-use crate::Count;
+use crate::{Count, XfsStruct};
 
 use super::*;
 
@@ -11,7 +10,10 @@ pub use list::*;
 
 /// Represents a XFS physical cash unit and its parameters.
 #[repr(C)]
-#[derive(Clone, Copy, Debug, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, serde::Deserialize, serde::Serialize, XfsStruct,
+)]
+#[xfs(name = "physicalCashUnit", no_display)]
 pub struct PhysicalCashUnit {
     name: PcuName,
     unit_id: UnitId,
@@ -202,18 +204,3 @@ impl fmt::Display for PhysicalCashUnit {
         write!(f, "}}")
     }
 }
-
-impl_xfs_struct!(
-    PhysicalCashUnit,
-    "physicalCashUnit",
-    [
-        name: PcuName,
-        unit_id: UnitId,
-        count: Count,
-        threshold: Threshold,
-        status: Status,
-        threshold_status: ThresholdStatus,
-        threshold_mode: ThresholdMode,
-        lock: Lock
-    ]
-);
@@ -1,52 +1,37 @@
-use std::{cmp, fmt};
+use std::fmt;
 
-use crate::{arrays, impl_xfs_array, impl_xfs_struct, MaxSize, Size};
+use crate::{BoundedList, MaxSize, Result, Size, XfsArray, XfsStruct};
 
 use super::*;
 
 /// Maximum number of [PhysicalCashUnit]s that can be present on a device.
 pub const PCU_LIST_LEN: usize = 10;
 
-#[repr(C)]
-#[derive(Clone, Copy, Debug, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+/// Represents the [PhysicalCashUnit] items of a [PhysicalCashUnitList].
+#[derive(Clone, Debug, Default, PartialEq, serde::Deserialize, serde::Serialize, XfsArray)]
+#[xfs(name = "items")]
 pub struct PhysicalCashUnitItems {
-    size: Size,
-    #[serde(with = "arrays")]
-    items: [PhysicalCashUnit; PCU_LIST_LEN],
+    items: BoundedList<PhysicalCashUnit, PCU_LIST_LEN>,
 }
 
 impl PhysicalCashUnitItems {
     /// Creates a new [PhysicalCashUnitItems] list.
     pub const fn new() -> Self {
         Self {
-            size: Size::new(),
-            items: [PhysicalCashUnit::new(); PCU_LIST_LEN],
+            items: BoundedList::new(),
         }
     }
 
-    /// Creates a new [PhysicalCashUnitItems] list from the provided parameter.
-    pub const fn create(items: [PhysicalCashUnit; PCU_LIST_LEN]) -> Self {
-        Self {
-            size: Size::create(PCU_LIST_LEN as u32),
-            items,
-        }
-    }
-
-    /// Gets the max size.
-    pub const fn max_size() -> usize {
-        PCU_LIST_LEN
-    }
-
     /// Gets the size.
-    pub const fn size(&self) -> u32 {
-        self.size.inner()
+    pub fn size(&self) -> u32 {
+        self.items.len() as u32
     }
 
     /// Sets the size.
+    ///
+    /// New items are set to the default value, no-op if `size` is larger than the max size.
     pub fn set_size(&mut self, size: u32) {
-        if size as usize <= Self::max_size() {
-            self.size.set_inner(size);
-        }
+        self.items.resize(size as usize);
     }
 
     /// Builder function that sets the size.
@@ -54,50 +39,13 @@ impl PhysicalCashUnitItems {
         self.set_size(size);
         self
     }
-
-    /// Gets a list of the [PhysicalCashUnit]s.
-    pub fn items(&self) -> &[PhysicalCashUnit] {
-        let size = self.size() as usize;
-
-        if (0..self.items.len()).contains(&size) {
-            self.items[..size].as_ref()
-        } else {
-            self.items.as_ref()
-        }
-    }
-
-    /// Gets a mutable list of the [PhysicalCashUnit]s.
-    pub fn items_mut(&mut self) -> &mut [PhysicalCashUnit] {
-        let size = self.size() as usize;
-
-        if (0..self.items.len()).contains(&size) {
-            self.items[..size].as_mut()
-        } else {
-            self.items.as_mut()
-        }
-    }
-
-    /// Sets a list of the [PhysicalCashUnit]s.
-    pub fn set_items(&mut self, items: &[PhysicalCashUnit]) {
-        let len = cmp::min(items.len(), PCU_LIST_LEN);
-
-        self.set_size(len as u32);
-        self.items[..len].copy_from_slice(items[..len].as_ref());
-    }
-
-    /// Builder function that sets a list of the [PhysicalCashUnit]s.
-    pub fn with_items(mut self, items: &[PhysicalCashUnit]) -> Self {
-        self.set_items(items);
-        self
-    }
 }
 
-impl_xfs_array!(PhysicalCashUnitItems, "items");
-
 /// Represents a list of [PhysicalCashUnit]s.
-#[repr(C)]
-#[derive(Clone, Copy, Debug, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+#[derive(Clone, Debug, Default, PartialEq, serde::Deserialize, serde::Serialize, XfsStruct)]
+#[xfs(name = "physicalCashUnits", no_display)]
 pub struct PhysicalCashUnitList {
+    #[xfs(skip)]
     max_size: MaxSize,
     size: Size,
     items: PhysicalCashUnitItems,
@@ -113,15 +61,6 @@ impl PhysicalCashUnitList {
         }
     }
 
-    /// Creates a new [PhysicalCashUnitList] from the provided parameter.
-    pub const fn create(items: [PhysicalCashUnit; PCU_LIST_LEN]) -> Self {
-        Self {
-            max_size: MaxSize::create(PCU_LIST_LEN as u32),
-            size: Size::create(PCU_LIST_LEN as u32),
-            items: PhysicalCashUnitItems::create(items),
-        }
-    }
-
     /// Gets the max size.
     pub const fn max_size(&self) -> u32 {
         self.max_size.inner()
@@ -154,6 +93,25 @@ impl PhysicalCashUnitList {
         self.items.items_mut()
     }
 
+    /// Gets an iterator over the [PhysicalCashUnit]s.
+    pub fn iter(&self) -> std::slice::Iter<'_, PhysicalCashUnit> {
+        self.items.iter()
+    }
+
+    /// Gets a mutable iterator over the [PhysicalCashUnit]s.
+    pub fn iter_mut(&mut self) -> std::slice::IterMut<'_, PhysicalCashUnit> {
+        self.items.iter_mut()
+    }
+
+    /// Pushes a [PhysicalCashUnit] onto the end of the list.
+    ///
+    /// Returns an error if the list is at the max size.
+    pub fn push_item(&mut self, item: PhysicalCashUnit) -> Result<()> {
+        self.items.push_item(item)?;
+        self.set_size(self.items.size());
+        Ok(())
+    }
+
     /// Sets a list of the [PhysicalCashUnit]s.
     pub fn set_items(&mut self, items: &[PhysicalCashUnit]) {
         self.items.set_items(items);
@@ -167,6 +125,15 @@ impl PhysicalCashUnitList {
     }
 }
 
+impl<'a> IntoIterator for &'a PhysicalCashUnitList {
+    type Item = &'a PhysicalCashUnit;
+    type IntoIter = std::slice::Iter<'a, PhysicalCashUnit>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
 impl fmt::Display for PhysicalCashUnitList {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{{")?;
@@ -185,5 +152,3 @@ impl fmt::Display for PhysicalCashUnitList {
         write!(f, "]}}")
     }
 }
-
-impl_xfs_struct!(PhysicalCashUnitList, "physicalCashUnits", [size: Size, items: PhysicalCashUnitItems]);
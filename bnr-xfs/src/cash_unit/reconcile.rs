@@ -0,0 +1,299 @@
+use std::fmt;
+
+use crate::currency::{Currency, CurrencyCode, Money};
+use crate::{CashInResult, CashUnitDiff, Result};
+
+/// Represents the cash movements recorded by the application between two [CashUnit](crate::CashUnit)
+/// snapshots.
+#[derive(Clone, Debug, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct RecordedTransactions {
+    deposits: Vec<Money>,
+    dispenses: Vec<Money>,
+}
+
+impl RecordedTransactions {
+    /// Creates a new [RecordedTransactions].
+    pub const fn new() -> Self {
+        Self {
+            deposits: Vec::new(),
+            dispenses: Vec::new(),
+        }
+    }
+
+    /// Gets the recorded deposit amounts.
+    pub fn deposits(&self) -> &[Money] {
+        self.deposits.as_ref()
+    }
+
+    /// Adds a deposit amount.
+    pub fn add_deposit(&mut self, amount: Money) {
+        self.deposits.push(amount);
+    }
+
+    /// Builder function that adds a deposit amount.
+    pub fn with_deposit(mut self, amount: Money) -> Self {
+        self.add_deposit(amount);
+        self
+    }
+
+    /// Adds the accepted amount of a `CashIn` transaction.
+    ///
+    /// Transactions without accepted notes are skipped.
+    pub fn add_cash_in(&mut self, result: &CashInResult) -> Result<()> {
+        if !result.orders().is_empty() || !result.accepted().is_empty() {
+            self.add_deposit(result.total()?);
+        }
+        Ok(())
+    }
+
+    /// Gets the recorded dispense amounts, i.e. bills taken by the customer.
+    pub fn dispenses(&self) -> &[Money] {
+        self.dispenses.as_ref()
+    }
+
+    /// Adds a dispense amount.
+    pub fn add_dispense(&mut self, amount: Money) {
+        self.dispenses.push(amount);
+    }
+
+    /// Builder function that adds a dispense amount.
+    pub fn with_dispense(mut self, amount: Money) -> Self {
+        self.add_dispense(amount);
+        self
+    }
+
+    /// Gets the expected value change for the currency: deposits minus dispenses.
+    pub fn expected_change(&self, currency_code: CurrencyCode) -> Result<Money> {
+        let zero = Money::create(currency_code, 0);
+
+        let deposited = self
+            .deposits
+            .iter()
+            .filter(|m| m.currency() == currency_code)
+            .try_fold(zero, |acc, m| acc.checked_add(m))?;
+
+        self.dispenses
+            .iter()
+            .filter(|m| m.currency() == currency_code)
+            .try_fold(deposited, |acc, m| acc.checked_sub(m))
+    }
+
+    fn currencies(&self) -> impl Iterator<Item = CurrencyCode> + '_ {
+        self.deposits
+            .iter()
+            .chain(self.dispenses.iter())
+            .map(|m| m.currency())
+    }
+}
+
+/// Represents a discrepancy between the device counts and the recorded transactions.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub enum Discrepancy {
+    /// The device reported bills disappeared from a logical cash unit.
+    Disappeared { number: u32, count: u32 },
+    /// The count of a logical cash unit changed without any change of its extended counters,
+    /// e.g. a manual count update.
+    UnexplainedCount { number: u32, delta: i64 },
+    /// The value change of a currency does not match the recorded transactions.
+    ValueMismatch { expected: Money, actual: Money },
+}
+
+impl fmt::Display for Discrepancy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Disappeared { number, count } => write!(
+                f,
+                r#"{{"kind": "disappeared", "number": {number}, "count": {count}}}"#
+            ),
+            Self::UnexplainedCount { number, delta } => write!(
+                f,
+                r#"{{"kind": "unexplained_count", "number": {number}, "delta": {delta}}}"#
+            ),
+            Self::ValueMismatch { expected, actual } => write!(
+                f,
+                r#"{{"kind": "value_mismatch", "expected": "{expected}", "actual": "{actual}"}}"#
+            ),
+        }
+    }
+}
+
+/// Compares the [CashUnitDiff] between two snapshots with the [RecordedTransactions].
+///
+/// Flags:
+///
+/// - bills the device reported disappeared
+/// - count changes the device counters do not account for
+/// - currencies where the value change differs from the recorded deposits minus dispenses
+#[derive(Clone, Debug, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct ReconciliationReport {
+    diff: CashUnitDiff,
+    discrepancies: Vec<Discrepancy>,
+}
+
+impl ReconciliationReport {
+    /// Creates a new [ReconciliationReport].
+    pub const fn new() -> Self {
+        Self {
+            diff: CashUnitDiff::new(),
+            discrepancies: Vec::new(),
+        }
+    }
+
+    /// Reconciles the [CashUnitDiff] with the [RecordedTransactions].
+    pub fn create(diff: CashUnitDiff, recorded: &RecordedTransactions) -> Result<Self> {
+        let mut discrepancies = Vec::new();
+
+        for lcu in diff.lcus() {
+            if lcu.counters().disappeared() > 0 {
+                discrepancies.push(Discrepancy::Disappeared {
+                    number: lcu.number(),
+                    count: lcu.counters().disappeared() as u32,
+                });
+            }
+            if lcu.is_unexplained() {
+                discrepancies.push(Discrepancy::UnexplainedCount {
+                    number: lcu.number(),
+                    delta: lcu.delta(),
+                });
+            }
+        }
+
+        let mut currencies: Vec<CurrencyCode> = diff
+            .value_changes()
+            .iter()
+            .map(|c| c.currency().currency_code())
+            .chain(recorded.currencies())
+            .collect();
+        currencies.sort_by_key(|&c| <&str>::from(c));
+        currencies.dedup();
+
+        for code in currencies {
+            let expected = recorded.expected_change(code)?;
+            let actual = diff.value_change(code).money()?;
+
+            if expected != actual {
+                discrepancies.push(Discrepancy::ValueMismatch { expected, actual });
+            }
+        }
+
+        Ok(Self {
+            diff,
+            discrepancies,
+        })
+    }
+
+    /// Gets the [CashUnitDiff].
+    pub const fn diff(&self) -> &CashUnitDiff {
+        &self.diff
+    }
+
+    /// Gets the [Discrepancy] list.
+    pub fn discrepancies(&self) -> &[Discrepancy] {
+        self.discrepancies.as_ref()
+    }
+
+    /// Gets whether the device counts match the recorded transactions.
+    pub fn is_reconciled(&self) -> bool {
+        self.discrepancies.is_empty()
+    }
+
+    /// Gets the value change of the currency not accounted for by the recorded transactions.
+    pub fn unexplained_value(&self, currency_code: CurrencyCode) -> Result<Money> {
+        self.discrepancies
+            .iter()
+            .find_map(|d| match d {
+                Discrepancy::ValueMismatch { expected, actual }
+                    if actual.currency() == currency_code =>
+                {
+                    Some(actual.checked_sub(expected))
+                }
+                _ => None,
+            })
+            .unwrap_or(Currency::from(currency_code).to_money(0))
+    }
+}
+
+impl fmt::Display for ReconciliationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{{")?;
+        write!(f, r#""diff": {}, "#, self.diff)?;
+        write!(f, r#""discrepancies": ["#)?;
+        for (i, discrepancy) in self.discrepancies.iter().enumerate() {
+            if i != 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{discrepancy}")?;
+        }
+        write!(f, "]}}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cash_unit::tests::cash_unit;
+    use crate::{DepositCount, DepositCounters, DisappearedCount};
+
+    #[test]
+    fn test_reconciliation_report() -> Result<()> {
+        let usd = CurrencyCode::from("USD");
+        let before = cash_unit()?;
+
+        // two $100 bills deposited to RE3, one of them disappeared
+        let mut after = before.clone();
+        if let Some(lcu) = after
+            .logical_cash_unit_list_mut()
+            .iter_mut()
+            .find(|l| l.number() == 7)
+        {
+            let counters = *lcu.extended_counters();
+            lcu.set_count(lcu.count() + 1);
+            lcu.set_extended_counters(
+                counters.with_deposit(
+                    DepositCounters::new()
+                        .with_deposit_count(DepositCount::create(2))
+                        .with_disappeared_count(DisappearedCount::create(1)),
+                ),
+            );
+        }
+
+        let recorded = RecordedTransactions::new()
+            .with_deposit(Money::create(usd, 20_000))
+            .with_deposit(Money::create(usd, 5_000))
+            .with_dispense(Money::create(usd, 5_000));
+        assert_eq!(recorded.expected_change(usd)?, Money::create(usd, 20_000));
+
+        let report =
+            ReconciliationReport::create(CashUnitDiff::create(&before, &after), &recorded)?;
+        assert!(!report.is_reconciled());
+        assert_eq!(
+            report.discrepancies(),
+            [
+                Discrepancy::Disappeared {
+                    number: 7,
+                    count: 1
+                },
+                Discrepancy::ValueMismatch {
+                    expected: Money::create(usd, 20_000),
+                    actual: Money::create(usd, 10_000),
+                },
+            ]
+        );
+        assert_eq!(report.unexplained_value(usd)?, Money::create(usd, -10_000));
+
+        let recorded = RecordedTransactions::new().with_deposit(Money::create(usd, 10_000));
+        let report =
+            ReconciliationReport::create(CashUnitDiff::create(&before, &after), &recorded)?;
+        assert_eq!(report.discrepancies().len(), 1);
+
+        let report = ReconciliationReport::create(
+            CashUnitDiff::create(&before, &before),
+            &RecordedTransactions::new(),
+        )?;
+        assert!(report.is_reconciled());
+        assert_eq!(report.unexplained_value(usd)?, Money::create(usd, 0));
+
+        Ok(())
+    }
+}
@@ -1,10 +1,6 @@
 use std::fmt;
 
-use crate::xfs::{
-    value::XfsValue,
-    xfs_struct::{XfsMember, XfsStruct},
-};
-use crate::{impl_xfs_i4, Error, Result};
+use crate::{impl_xfs_i4, XfsStruct};
 
 mod mode;
 mod status;
@@ -154,11 +150,18 @@ impl_xfs_i4!(ThresholdEmpty, "empty");
 
 /// Structure that defines the levels determining a physical cash unit [ThresholdStatus].
 #[repr(C)]
-#[derive(Clone, Copy, Debug, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, serde::Deserialize, serde::Serialize, XfsStruct,
+)]
+#[xfs(name = "threshold", no_display)]
 pub struct Threshold {
+    #[xfs(required)]
     full: ThresholdFull,
+    #[xfs(required)]
     high: ThresholdHigh,
+    #[xfs(required)]
     low: ThresholdLow,
+    #[xfs(required)]
     empty: ThresholdEmpty,
 }
 
@@ -173,11 +176,6 @@ impl Threshold {
         }
     }
 
-    /// Gets the [XfsMember] name.
-    pub const fn xfs_name() -> &'static str {
-        "threshold"
-    }
-
     /// Gets the full threshold limit.
     pub const fn full(&self) -> u32 {
         self.full.inner()
@@ -243,114 +241,6 @@ impl Threshold {
     }
 }
 
-impl From<&Threshold> for XfsStruct {
-    fn from(val: &Threshold) -> Self {
-        Self::create([
-            val.full.into(),
-            val.high.into(),
-            val.low.into(),
-            val.empty.into(),
-        ])
-    }
-}
-
-impl From<Threshold> for XfsStruct {
-    fn from(val: Threshold) -> Self {
-        (&val).into()
-    }
-}
-
-impl From<&Threshold> for XfsValue {
-    fn from(val: &Threshold) -> Self {
-        Self::new().with_xfs_struct(val.into())
-    }
-}
-
-impl From<Threshold> for XfsValue {
-    fn from(val: Threshold) -> Self {
-        (&val).into()
-    }
-}
-
-impl From<&Threshold> for XfsMember {
-    fn from(val: &Threshold) -> Self {
-        Self::create(Threshold::xfs_name(), val.into())
-    }
-}
-
-impl From<Threshold> for XfsMember {
-    fn from(val: Threshold) -> Self {
-        (&val).into()
-    }
-}
-
-impl TryFrom<&XfsValue> for Threshold {
-    type Error = Error;
-
-    fn try_from(val: &XfsValue) -> Result<Self> {
-        val.xfs_struct()
-            .ok_or(Error::Xfs(format!(
-                "Expected Threshold XfsValue, have: {val}"
-            )))?
-            .try_into()
-    }
-}
-
-impl TryFrom<XfsValue> for Threshold {
-    type Error = Error;
-
-    fn try_from(val: XfsValue) -> Result<Self> {
-        (&val).try_into()
-    }
-}
-
-impl TryFrom<&XfsMember> for Threshold {
-    type Error = Error;
-
-    fn try_from(val: &XfsMember) -> Result<Self> {
-        match (val.name(), val.value().xfs_struct()) {
-            (n, Some(s)) if n == Self::xfs_name() => s.try_into(),
-            _ => Err(Error::Xfs(format!(
-                "Expected Threshold XfsMember, have: {val}"
-            ))),
-        }
-    }
-}
-
-impl TryFrom<XfsMember> for Threshold {
-    type Error = Error;
-
-    fn try_from(val: XfsMember) -> Result<Self> {
-        (&val).try_into()
-    }
-}
-
-impl TryFrom<&XfsStruct> for Threshold {
-    type Error = Error;
-
-    fn try_from(val: &XfsStruct) -> Result<Self> {
-        let full: ThresholdFull = val.find_member(ThresholdFull::xfs_name())?.try_into()?;
-        let high: ThresholdHigh = val.find_member(ThresholdHigh::xfs_name())?.try_into()?;
-        let low: ThresholdLow = val.find_member(ThresholdLow::xfs_name())?.try_into()?;
-        let empty: ThresholdEmpty = val.find_member(ThresholdEmpty::xfs_name())?.try_into()?;
-
-        Ok(Self {
-            full,
-            high,
-            low,
-            empty,
-        })
-    }
-}
-
-impl TryFrom<XfsStruct> for Threshold {
-    type Error = Error;
-
-    fn try_from(val: XfsStruct) -> Result<Self> {
-        (&val).try_into()
-    }
-}
-
 impl fmt::Display for Threshold {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{{")?;
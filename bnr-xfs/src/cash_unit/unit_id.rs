@@ -1,11 +1,13 @@
 use std::fmt;
 
 use crate::xfs::{value::XfsValue, xfs_struct::XfsMember};
-use crate::{Error, Result};
+use crate::{impl_xfs_decode, Error, Result};
 
 pub const UNIT_ID_LEN: usize = 20;
 
 /// Represents the ID of an LCU and/or PCU.
+///
+/// Units without an ID, e.g. the cashbox, have an ID of `0`, sent as an empty string.
 #[repr(C)]
 #[derive(Clone, Copy, Debug, Default, PartialEq, serde::Deserialize, serde::Serialize)]
 pub struct UnitId(u64);
@@ -56,7 +58,11 @@ impl From<u64> for UnitId {
 
 impl From<&UnitId> for XfsValue {
     fn from(val: &UnitId) -> Self {
-        Self::new().with_string(format!("{val}"))
+        // units without an ID, e.g. the cashbox, are sent as an empty string
+        match val.inner() {
+            0 => Self::new().with_string(""),
+            id => Self::new().with_string(format!("{id}")),
+        }
     }
 }
 
@@ -70,6 +76,10 @@ impl TryFrom<&str> for UnitId {
     type Error = Error;
 
     fn try_from(val: &str) -> Result<Self> {
+        if val.trim().is_empty() {
+            return Ok(Self::new());
+        }
+
         Ok(val
             .parse::<u64>()
             .map_err(|_| Error::Xfs(format!("Expected UnitId string, have: {val}")))?
@@ -95,6 +105,8 @@ impl TryFrom<XfsValue> for UnitId {
     }
 }
 
+impl_xfs_decode!(UnitId);
+
 impl From<&UnitId> for XfsMember {
     fn from(val: &UnitId) -> Self {
         Self::create(UnitId::xfs_name(), val.into())
@@ -127,3 +139,26 @@ impl TryFrom<XfsMember> for UnitId {
         (&val).try_into()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unit_id_empty() -> Result<()> {
+        let id = UnitId::create(18111200357);
+        let val = XfsValue::from(id);
+        assert_eq!(val.string(), Some("18111200357"));
+        assert_eq!(UnitId::try_from(&val)?, id);
+
+        // units without an ID are sent as an empty string
+        let val = XfsValue::from(UnitId::new());
+        assert_eq!(val.string(), Some(""));
+        assert_eq!(UnitId::try_from(&val)?, UnitId::new());
+        assert_eq!(UnitId::try_from(" ")?, UnitId::new());
+
+        assert!(UnitId::try_from("CB").is_err());
+
+        Ok(())
+    }
+}
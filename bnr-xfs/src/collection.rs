@@ -0,0 +1,585 @@
+//! Types for the end-of-day cashbox collection.
+
+use std::fmt;
+use std::time::Duration;
+
+use base64::Engine;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+use crate::cash_unit::pcus_of_kind;
+use crate::currency::CuKind;
+use crate::json::JsonStr;
+use crate::{
+    CashInventory, CashLocation, CashTotal, CashUnit, InventoryItem, LogicalCashUnitList, PcuName,
+    PhysicalCashUnitList, Result,
+};
+
+#[cfg(feature = "usb")]
+mod procedure;
+
+#[cfg(feature = "usb")]
+pub(crate) use procedure::*;
+
+/// Time to wait for the device to empty a recycler to its float.
+pub const COLLECTION_EMPTY_TIMEOUT: Duration = Duration::from_secs(120);
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Represents a recycler emptied to its float during a collection.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct EmptiedUnit {
+    pcu_name: PcuName,
+    before: u32,
+    after: u32,
+}
+
+impl EmptiedUnit {
+    /// Creates a new [EmptiedUnit].
+    pub const fn new() -> Self {
+        Self {
+            pcu_name: PcuName::new(),
+            before: 0,
+            after: 0,
+        }
+    }
+
+    /// Creates a new [EmptiedUnit] from the provided parameters.
+    pub const fn create(pcu_name: PcuName, before: u32, after: u32) -> Self {
+        Self {
+            pcu_name,
+            before,
+            after,
+        }
+    }
+
+    /// Gets the name of the recycler [PhysicalCashUnit](crate::PhysicalCashUnit).
+    pub const fn pcu_name(&self) -> &PcuName {
+        &self.pcu_name
+    }
+
+    /// Gets the number of bills in the recycler before emptying.
+    pub const fn before(&self) -> u32 {
+        self.before
+    }
+
+    /// Gets the number of bills left in the recycler, i.e. the float.
+    pub const fn after(&self) -> u32 {
+        self.after
+    }
+
+    /// Gets the number of bills moved to the cashbox.
+    pub const fn moved(&self) -> u32 {
+        self.before.saturating_sub(self.after)
+    }
+}
+
+impl fmt::Display for EmptiedUnit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{{")?;
+        write!(f, r#""pcu_name": {}, "#, self.pcu_name)?;
+        write!(f, r#""before": {}, "#, self.before)?;
+        write!(f, r#""after": {}"#, self.after)?;
+        write!(f, "}}")
+    }
+}
+
+/// Represents a cashbox collection waiting for the cashbox to be swapped.
+///
+/// Returned by [start_collection](crate::DeviceHandle::start_collection) once the recyclers are
+/// emptied to their float and the modules are parked, and consumed by
+/// [finish_collection](crate::DeviceHandle::finish_collection) after the emptied cashbox is
+/// reinserted.
+///
+/// If a recycler could not be emptied, the collection is partial, see [error](Self::error): the
+/// counts still record the bills already moved to the cashbox.
+#[derive(Clone, Debug, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct PendingCollection {
+    started: u64,
+    emptied: Vec<EmptiedUnit>,
+    removed: CashUnit,
+    #[serde(default)]
+    error: Option<String>,
+}
+
+impl PendingCollection {
+    /// Creates a new [PendingCollection].
+    pub fn new() -> Self {
+        Self {
+            started: 0,
+            emptied: Vec::new(),
+            removed: CashUnit::new(),
+            error: None,
+        }
+    }
+
+    /// Creates a new [PendingCollection] from the [CashUnit] counts before and after emptying
+    /// the recyclers.
+    ///
+    /// Params:
+    ///
+    /// - `started`: time the collection started, in seconds since the Unix epoch.
+    /// - `before`: the [CashUnit] counts before emptying the recyclers.
+    /// - `removed`: the [CashUnit] counts before the cashbox removal.
+    pub fn create(started: u64, before: &CashUnit, removed: CashUnit) -> Self {
+        let emptied = recycler_names(before)
+            .into_iter()
+            .map(|name| {
+                EmptiedUnit::create(name, pcu_count(before, &name), pcu_count(&removed, &name))
+            })
+            .collect();
+
+        Self {
+            started,
+            emptied,
+            removed,
+            error: None,
+        }
+    }
+
+    /// Gets the time the collection started, in seconds since the Unix epoch.
+    pub const fn started(&self) -> u64 {
+        self.started
+    }
+
+    /// Gets the recyclers emptied to their float.
+    pub fn emptied(&self) -> &[EmptiedUnit] {
+        self.emptied.as_ref()
+    }
+
+    /// Gets the [CashUnit] counts recorded before the cashbox removal.
+    pub const fn removed(&self) -> &CashUnit {
+        &self.removed
+    }
+
+    /// Gets the number of bills in the cashbox before removal.
+    pub fn cashbox_count(&self) -> u32 {
+        cashbox_count(&self.removed)
+    }
+
+    /// Gets the error that stopped the collection before every recycler was emptied, if any.
+    pub fn error(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
+
+    /// Gets whether every recycler was emptied to its float.
+    pub const fn is_complete(&self) -> bool {
+        self.error.is_none()
+    }
+
+    /// Builder function that sets the error that stopped the collection.
+    pub fn with_error(mut self, error: &str) -> Self {
+        self.error = Some(error.into());
+        self
+    }
+
+    /// Creates the unsigned [CollectionReport] from the [CashUnit] counts after the reset.
+    ///
+    /// - `finished`: time the collection finished, in seconds since the Unix epoch.
+    /// - `reset`: the [CashUnit] counts after the cashbox count reset.
+    pub fn into_report(self, finished: u64, reset: &CashUnit) -> CollectionReport {
+        CollectionReport::create(self.started, finished, self.emptied, &self.removed, reset)
+    }
+}
+
+impl fmt::Display for PendingCollection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{{")?;
+        write!(f, r#""started": {}, "#, self.started)?;
+        write!(f, r#""emptied": ["#)?;
+        for (i, unit) in self.emptied.iter().enumerate() {
+            if i != 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{unit}")?;
+        }
+        write!(f, "], ")?;
+        write!(f, r#""cashbox_count": {}, "#, self.cashbox_count())?;
+        match self.error.as_ref() {
+            Some(err) => write!(f, r#""error": {}"#, JsonStr(err))?,
+            None => write!(f, r#""error": null"#)?,
+        }
+        write!(f, "}}")
+    }
+}
+
+/// Represents the result of an end-of-day cashbox collection.
+///
+/// Records the recyclers emptied to their float, the cashbox contents at removal, and the
+/// cashbox count after the reset. The report is signed with HMAC-SHA256 over its canonical JSON
+/// [payload](Self::payload), so later changes to the recorded counts are detected by
+/// [verify](Self::verify).
+#[derive(Clone, Debug, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct CollectionReport {
+    started: u64,
+    finished: u64,
+    emptied: Vec<EmptiedUnit>,
+    cashbox_count: u32,
+    removed: Vec<InventoryItem>,
+    removed_totals: Vec<CashTotal>,
+    reset_count: u32,
+    signature: String,
+}
+
+impl CollectionReport {
+    /// Creates a new [CollectionReport].
+    pub const fn new() -> Self {
+        Self {
+            started: 0,
+            finished: 0,
+            emptied: Vec::new(),
+            cashbox_count: 0,
+            removed: Vec::new(),
+            removed_totals: Vec::new(),
+            reset_count: 0,
+            signature: String::new(),
+        }
+    }
+
+    /// Creates a new unsigned [CollectionReport].
+    ///
+    /// Params:
+    ///
+    /// - `started`: time the collection started, in seconds since the Unix epoch.
+    /// - `finished`: time the collection finished, in seconds since the Unix epoch.
+    /// - `emptied`: the recyclers emptied to their float.
+    /// - `removed`: the [CashUnit] counts when the cashbox was removed.
+    /// - `reset`: the [CashUnit] counts after the cashbox count reset.
+    pub fn create(
+        started: u64,
+        finished: u64,
+        emptied: Vec<EmptiedUnit>,
+        removed: &CashUnit,
+        reset: &CashUnit,
+    ) -> Self {
+        let inventory = CashInventory::create(removed);
+
+        Self {
+            started,
+            finished,
+            emptied,
+            cashbox_count: cashbox_count(removed),
+            removed: inventory
+                .items()
+                .iter()
+                .filter(|i| i.location() == CashLocation::Cashbox)
+                .copied()
+                .collect(),
+            removed_totals: inventory.deposit_only_totals(),
+            reset_count: cashbox_count(reset),
+            signature: String::new(),
+        }
+    }
+
+    /// Gets the time the collection started, in seconds since the Unix epoch.
+    pub const fn started(&self) -> u64 {
+        self.started
+    }
+
+    /// Gets the time the collection finished, in seconds since the Unix epoch.
+    pub const fn finished(&self) -> u64 {
+        self.finished
+    }
+
+    /// Gets the recyclers emptied to their float.
+    pub fn emptied(&self) -> &[EmptiedUnit] {
+        self.emptied.as_ref()
+    }
+
+    /// Gets the number of bills in the cashbox when it was removed.
+    pub const fn cashbox_count(&self) -> u32 {
+        self.cashbox_count
+    }
+
+    /// Gets the cashbox contents when it was removed.
+    pub fn removed(&self) -> &[InventoryItem] {
+        self.removed.as_ref()
+    }
+
+    /// Gets the [CashTotal] removed from the cashbox for each currency.
+    pub fn removed_totals(&self) -> &[CashTotal] {
+        self.removed_totals.as_ref()
+    }
+
+    /// Gets the cashbox count after the reset.
+    pub const fn reset_count(&self) -> u32 {
+        self.reset_count
+    }
+
+    /// Gets the Base64 encoded HMAC-SHA256 signature, empty if the report is unsigned.
+    pub fn signature(&self) -> &str {
+        self.signature.as_str()
+    }
+
+    /// Gets whether the report is signed.
+    pub fn is_signed(&self) -> bool {
+        !self.signature.is_empty()
+    }
+
+    /// Gets the signed payload: the report without the signature, serialized to JSON.
+    pub fn payload(&self) -> Result<Vec<u8>> {
+        Ok(serde_json::to_vec(&Payload::from(self))?)
+    }
+
+    /// Signs the report with the provided `key`.
+    pub fn sign(&mut self, key: &[u8]) -> Result<()> {
+        let mac = self.mac(key)?.finalize().into_bytes();
+        self.signature = base64::engine::general_purpose::STANDARD.encode(mac);
+        Ok(())
+    }
+
+    /// Builder function that signs the report with the provided `key`.
+    pub fn signed(mut self, key: &[u8]) -> Result<Self> {
+        self.sign(key)?;
+        Ok(self)
+    }
+
+    /// Gets whether the report is signed, and the signature matches the provided `key`.
+    pub fn verify(&self, key: &[u8]) -> bool {
+        match base64::engine::general_purpose::STANDARD.decode(self.signature.as_bytes()) {
+            Ok(signature) if !signature.is_empty() => self
+                .mac(key)
+                .is_ok_and(|mac| mac.verify_slice(signature.as_ref()).is_ok()),
+            _ => false,
+        }
+    }
+
+    fn mac(&self, key: &[u8]) -> Result<HmacSha256> {
+        // HMAC accepts keys of any length
+        let mut mac = HmacSha256::new_from_slice(key).expect("HMAC key of any length");
+        mac.update(self.payload()?.as_ref());
+        Ok(mac)
+    }
+}
+
+// The signed fields of a [CollectionReport].
+#[derive(serde::Serialize)]
+struct Payload<'a> {
+    started: u64,
+    finished: u64,
+    emptied: &'a [EmptiedUnit],
+    cashbox_count: u32,
+    removed: &'a [InventoryItem],
+    removed_totals: &'a [CashTotal],
+    reset_count: u32,
+}
+
+impl<'a> From<&'a CollectionReport> for Payload<'a> {
+    fn from(val: &'a CollectionReport) -> Self {
+        Self {
+            started: val.started,
+            finished: val.finished,
+            emptied: val.emptied.as_ref(),
+            cashbox_count: val.cashbox_count,
+            removed: val.removed.as_ref(),
+            removed_totals: val.removed_totals.as_ref(),
+            reset_count: val.reset_count,
+        }
+    }
+}
+
+impl fmt::Display for CollectionReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{{")?;
+        write!(f, r#""started": {}, "#, self.started)?;
+        write!(f, r#""finished": {}, "#, self.finished)?;
+        write!(f, r#""emptied": ["#)?;
+        for (i, unit) in self.emptied.iter().enumerate() {
+            if i != 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{unit}")?;
+        }
+        write!(f, "], ")?;
+        write!(f, r#""cashbox_count": {}, "#, self.cashbox_count)?;
+        write!(f, r#""removed": ["#)?;
+        for (i, item) in self.removed.iter().enumerate() {
+            if i != 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{item}")?;
+        }
+        write!(f, "], ")?;
+        write!(f, r#""removed_totals": ["#)?;
+        for (i, total) in self.removed_totals.iter().enumerate() {
+            if i != 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{total}")?;
+        }
+        write!(f, "], ")?;
+        write!(f, r#""reset_count": {}, "#, self.reset_count)?;
+        write!(f, r#""signature": "{}""#, self.signature)?;
+        write!(f, "}}")
+    }
+}
+
+/// Gets the names of the recycler [PhysicalCashUnit](crate::PhysicalCashUnit)s.
+pub fn recycler_names(cash_unit: &CashUnit) -> Vec<PcuName> {
+    pcus_of_kind(cash_unit, CuKind::Recycle)
+        .map(|(_, name)| name)
+        .collect()
+}
+
+/// Gets the lists for [update_cash_unit](crate::DeviceHandle::update_cash_unit) that reset the
+/// cashbox counts to zero.
+///
+/// Every [LogicalCashUnit](crate::LogicalCashUnit) stored in the cashbox is reset, including
+/// the reject unit. Other counts are left unchanged.
+pub fn cashbox_reset_lists(cash_unit: &CashUnit) -> (LogicalCashUnitList, PhysicalCashUnitList) {
+    let cashboxes: Vec<usize> = pcus_of_kind(cash_unit, CuKind::Deposit)
+        .map(|(idx, _)| idx)
+        .collect();
+
+    let mut lcu_list = cash_unit.logical_cash_unit_list().clone();
+    let mut pcu_list = cash_unit.physical_cash_unit_list().clone();
+
+    lcu_list
+        .iter_mut()
+        .filter(|l| cashboxes.contains(&(l.physical_cu_index() as usize)))
+        .for_each(|l| l.set_count(0));
+
+    pcu_list
+        .iter_mut()
+        .enumerate()
+        .filter(|(idx, _)| cashboxes.contains(idx))
+        .for_each(|(_, p)| p.set_count(0));
+
+    (lcu_list, pcu_list)
+}
+
+fn cashbox_count(cash_unit: &CashUnit) -> u32 {
+    let pcus = cash_unit.physical_cash_unit_list().items();
+
+    pcus_of_kind(cash_unit, CuKind::Deposit)
+        .filter_map(|(idx, _)| pcus.get(idx))
+        .map(|p| p.count())
+        .sum()
+}
+
+fn pcu_count(cash_unit: &CashUnit, name: &PcuName) -> u32 {
+    cash_unit
+        .physical_cash_unit_list()
+        .iter()
+        .find(|p| p.name() == name)
+        .map(|p| p.count())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cash_unit::tests::cash_unit;
+
+    #[test]
+    fn test_hmac_sha256() {
+        // RFC 4231, test case 2
+        let mut mac = HmacSha256::new_from_slice(b"Jefe").unwrap();
+        mac.update(b"what do ya want for nothing?");
+
+        let expected = [
+            0x5b, 0xdc, 0xc1, 0x46, 0xbf, 0x60, 0x75, 0x4e, 0x6a, 0x04, 0x24, 0x26, 0x08, 0x95,
+            0x75, 0xc7, 0x5a, 0x00, 0x3f, 0x08, 0x9d, 0x27, 0x39, 0x83, 0x9d, 0xec, 0x58, 0xb9,
+            0x64, 0xec, 0x38, 0x43,
+        ];
+        assert_eq!(mac.finalize().into_bytes().as_slice(), expected);
+    }
+
+    #[test]
+    fn test_collection_report() -> Result<()> {
+        let removed = cash_unit()?;
+
+        assert_eq!(
+            recycler_names(&removed),
+            ["RE3", "RE4", "RE5", "RE6"].map(PcuName::from)
+        );
+
+        let (lcu_list, pcu_list) = cashbox_reset_lists(&removed);
+        for (idx, pcu) in pcu_list.iter().enumerate() {
+            let orig = &removed.physical_cash_unit_list().items()[idx];
+            let expected = if orig.name() == &PcuName::from("CB") {
+                0
+            } else {
+                orig.count()
+            };
+            assert_eq!(pcu.count(), expected);
+        }
+        assert!(lcu_list
+            .iter()
+            .filter(|l| l.physical_cu_index() == 1)
+            .all(|l| l.count() == 0));
+        assert_eq!(
+            lcu_list.iter().find(|l| l.number() == 7).map(|l| l.count()),
+            Some(1)
+        );
+
+        let reset = removed
+            .clone()
+            .with_logical_cash_unit_list(lcu_list)
+            .with_physical_cash_unit_list(pcu_list);
+
+        // RE3 held 31 bills before it was emptied to its float
+        let mut pcu_list = removed.physical_cash_unit_list().clone();
+        pcu_list.items_mut()[3].set_count(31);
+        let before = removed.clone().with_physical_cash_unit_list(pcu_list);
+
+        let pending = PendingCollection::create(1_700_000_000, &before, removed);
+        assert_eq!(pending.cashbox_count(), 120);
+        assert_eq!(pending.emptied().len(), 4);
+        assert_eq!(
+            pending.emptied()[0],
+            EmptiedUnit::create(PcuName::from("RE3"), 31, 1)
+        );
+        assert_eq!(pending.emptied()[0].moved(), 30);
+        assert_eq!(pending.emptied()[1].moved(), 0);
+
+        assert!(pending.is_complete());
+        let partial = pending.clone().with_error("failed to empty RE4");
+        assert!(!partial.is_complete());
+        assert!(format!("{partial}").ends_with(r#""error": "failed to empty RE4"}"#));
+
+        // the error message is escaped as a JSON string
+        let err = "device error: \"E_RE4\"\u{1}";
+        let json: serde_json::Value =
+            serde_json::from_str(&pending.clone().with_error(err).to_string())?;
+        assert_eq!(json["error"], err);
+
+        let report = pending.into_report(1_700_000_600, &reset);
+        assert_eq!(report.started(), 1_700_000_000);
+        assert_eq!(report.finished(), 1_700_000_600);
+        assert_eq!(report.emptied()[0].moved(), 30);
+        assert_eq!(report.cashbox_count(), 120);
+        assert_eq!(report.reset_count(), 0);
+        assert!(report
+            .removed()
+            .iter()
+            .all(|i| i.location() == CashLocation::Cashbox));
+        assert_eq!(report.removed_totals().len(), 1);
+        assert_eq!(report.removed_totals()[0].count(), 120);
+        assert_eq!(report.removed_totals()[0].amount_str(), "530.00");
+
+        assert!(!report.is_signed());
+        assert!(!report.verify(b"key"));
+
+        let report = report.signed(b"key")?;
+        assert!(report.is_signed());
+        assert!(report.verify(b"key"));
+        assert!(!report.verify(b"other key"));
+
+        let mut tampered = report.clone();
+        tampered.cashbox_count = 100;
+        assert!(!tampered.verify(b"key"));
+
+        // the payload is canonical JSON, without the signature
+        let payload: serde_json::Value = serde_json::from_slice(&report.payload()?)?;
+        assert_eq!(payload["cashbox_count"], 120);
+        assert_eq!(payload["emptied"][0]["before"], 31);
+        assert!(payload.get("signature").is_none());
+
+        let json = format!("{report}");
+        assert!(json.ends_with(&format!(r#""signature": "{}"}}"#, report.signature())));
+
+        Ok(())
+    }
+}
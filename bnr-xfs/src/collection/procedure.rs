@@ -0,0 +1,69 @@
+use crate::cash_unit::unix_time;
+use crate::{DeviceHandle, Error, PcuName, Result};
+
+use super::{cashbox_reset_lists, recycler_names};
+use super::{CollectionReport, PendingCollection, COLLECTION_EMPTY_TIMEOUT};
+
+/// Empties the recyclers to their float, parks the modules, and records the cashbox count.
+///
+/// Stops at the first recycler that cannot be emptied, and returns the partial
+/// [PendingCollection] with the error, the modules are not parked in that case.
+pub(crate) fn start_collection(handle: &DeviceHandle) -> Result<PendingCollection> {
+    let started = unix_time();
+    let before = handle.query_cash_unit_inner()?;
+
+    let res = recycler_names(&before)
+        .iter()
+        .try_for_each(|name| empty_to_float(handle, name));
+
+    if let Err(err) = res {
+        let pending = PendingCollection::create(started, &before, handle.query_cash_unit_inner()?)
+            .with_error(&format!("{err}"));
+        log::error!("Collection: stopped before the recyclers were emptied: {pending}");
+
+        return Ok(pending);
+    }
+
+    handle.park_inner()?;
+
+    let pending = PendingCollection::create(started, &before, handle.query_cash_unit_inner()?);
+    log::info!("Collection: modules parked, remove the cashbox: {pending}");
+
+    Ok(pending)
+}
+
+/// Resets the cashbox count, and returns the [CollectionReport] signed with the `key`.
+pub(crate) fn finish_collection(
+    handle: &DeviceHandle,
+    pending: PendingCollection,
+    key: &[u8],
+) -> Result<CollectionReport> {
+    let current = handle.query_cash_unit_inner()?;
+    let (lcu_list, pcu_list) = cashbox_reset_lists(&current);
+
+    handle.update_cash_unit_inner(current.transport_count(), &lcu_list, &pcu_list)?;
+
+    let report = pending
+        .into_report(unix_time(), &handle.query_cash_unit_inner()?)
+        .signed(key)?;
+    log::info!("Collection: {report}");
+
+    Ok(report)
+}
+
+fn empty_to_float(handle: &DeviceHandle, name: &PcuName) -> Result<()> {
+    let name = name.decoded();
+    let call_id = handle.empty_inner(&name, true)?;
+
+    match handle.wait_async_call(call_id, COLLECTION_EMPTY_TIMEOUT)? {
+        Some(msg) => match msg.result().unwrap_or(-1) {
+            0 => Ok(()),
+            res => Err(Error::Xfs(format!(
+                "Collection: failed to empty {name}, result: {res}"
+            ))),
+        },
+        None => Err(Error::Xfs(format!(
+            "Collection: no Empty operation complete for {name} after {COLLECTION_EMPTY_TIMEOUT:?}"
+        ))),
+    }
+}
@@ -1,3 +1,5 @@
+mod backup;
 mod version;
 
+pub use backup::*;
 pub use version::*;
@@ -0,0 +1,486 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::capabilities::{
+    AllowUsbFrontSwitch, AntiFishingLevel, AutoPresent, AutoRetractAtInlet, Capabilities,
+    RejectViaOutlet, ReportUsbConsumption, ReportingMode, SelfTestMode,
+};
+use crate::cash_unit::{
+    CashUnit, ExtendedCounters, LogicalCashUnit, LogicalCashUnitList, PhysicalCashUnit,
+    PhysicalCashUnitList, ThresholdStatus,
+};
+use crate::denominations::{DenominationInfo, DenominationList};
+use crate::{Error, Result};
+
+/// Current version of the [DeviceConfig] document format.
+pub const DEVICE_CONFIG_VERSION: u32 = 1;
+
+/// Represents the writable [Capabilities] fields.
+#[derive(Clone, Copy, Debug, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct CapabilitySettings {
+    pub auto_present: AutoPresent,
+    pub self_test_mode: SelfTestMode,
+    pub anti_fishing_level: AntiFishingLevel,
+    pub allow_usb_front_switch: AllowUsbFrontSwitch,
+    pub reporting_mode: ReportingMode,
+    pub report_usb_consumption: ReportUsbConsumption,
+    pub auto_retract_at_inlet: AutoRetractAtInlet,
+    pub reject_via_outlet: RejectViaOutlet,
+}
+
+impl CapabilitySettings {
+    /// Creates a new [CapabilitySettings].
+    pub const fn new() -> Self {
+        Self {
+            auto_present: AutoPresent::new(),
+            self_test_mode: SelfTestMode::new(),
+            anti_fishing_level: AntiFishingLevel::new(),
+            allow_usb_front_switch: AllowUsbFrontSwitch::create(true),
+            reporting_mode: ReportingMode::new(),
+            report_usb_consumption: ReportUsbConsumption::new(),
+            auto_retract_at_inlet: AutoRetractAtInlet::new(),
+            reject_via_outlet: RejectViaOutlet::new(),
+        }
+    }
+
+    /// Applies the settings to a set of [Capabilities], leaving the read-only fields unchanged.
+    pub fn apply(&self, caps: &Capabilities) -> Capabilities {
+        Capabilities {
+            auto_present: self.auto_present,
+            self_test_mode: self.self_test_mode,
+            anti_fishing_level: self.anti_fishing_level,
+            allow_usb_front_switch: self.allow_usb_front_switch,
+            reporting_mode: self.reporting_mode,
+            report_usb_consumption: self.report_usb_consumption,
+            auto_retract_at_inlet: self.auto_retract_at_inlet,
+            reject_via_outlet: self.reject_via_outlet,
+            ..caps.clone()
+        }
+    }
+}
+
+impl From<&Capabilities> for CapabilitySettings {
+    fn from(val: &Capabilities) -> Self {
+        Self {
+            auto_present: val.auto_present,
+            self_test_mode: val.self_test_mode,
+            anti_fishing_level: val.anti_fishing_level,
+            allow_usb_front_switch: val.allow_usb_front_switch,
+            reporting_mode: val.reporting_mode,
+            report_usb_consumption: val.report_usb_consumption,
+            auto_retract_at_inlet: val.auto_retract_at_inlet,
+            reject_via_outlet: val.reject_via_outlet,
+        }
+    }
+}
+
+impl From<Capabilities> for CapabilitySettings {
+    fn from(val: Capabilities) -> Self {
+        (&val).into()
+    }
+}
+
+/// Represents a snapshot of the persistent device configuration.
+///
+/// Used to clone a configuration from one device onto another. Counts and runtime statuses are
+/// not part of the configuration, and are cleared in the snapshot.
+#[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct DeviceConfig {
+    version: u32,
+    capabilities: CapabilitySettings,
+    denominations: Vec<DenominationInfo>,
+    logical_cash_units: Vec<LogicalCashUnit>,
+    physical_cash_units: Vec<PhysicalCashUnit>,
+}
+
+impl DeviceConfig {
+    /// Creates a new [DeviceConfig].
+    pub const fn new() -> Self {
+        Self {
+            version: DEVICE_CONFIG_VERSION,
+            capabilities: CapabilitySettings::new(),
+            denominations: Vec::new(),
+            logical_cash_units: Vec::new(),
+            physical_cash_units: Vec::new(),
+        }
+    }
+
+    /// Creates a new [DeviceConfig] from the device configuration.
+    pub fn create(
+        caps: &Capabilities,
+        denominations: &DenominationList,
+        cash_unit: &CashUnit,
+    ) -> Self {
+        Self {
+            version: DEVICE_CONFIG_VERSION,
+            capabilities: caps.into(),
+            denominations: denominations.items().into(),
+            logical_cash_units: cash_unit
+                .logical_cash_unit_list()
+                .iter()
+                .map(|lcu| clear_lcu_counts(lcu.clone()))
+                .collect(),
+            physical_cash_units: cash_unit
+                .physical_cash_unit_list()
+                .iter()
+                .map(|&pcu| clear_pcu_counts(pcu))
+                .collect(),
+        }
+    }
+
+    /// Gets the document format version.
+    pub const fn version(&self) -> u32 {
+        self.version
+    }
+
+    /// Gets the [CapabilitySettings].
+    pub const fn capabilities(&self) -> &CapabilitySettings {
+        &self.capabilities
+    }
+
+    /// Sets the [CapabilitySettings].
+    pub fn set_capabilities(&mut self, val: CapabilitySettings) {
+        self.capabilities = val;
+    }
+
+    /// Builder function that sets the [CapabilitySettings].
+    pub fn with_capabilities(mut self, val: CapabilitySettings) -> Self {
+        self.set_capabilities(val);
+        self
+    }
+
+    /// Gets the [DenominationInfo] settings.
+    pub fn denominations(&self) -> &[DenominationInfo] {
+        self.denominations.as_ref()
+    }
+
+    /// Gets the [LogicalCashUnit] configuration.
+    pub fn logical_cash_units(&self) -> &[LogicalCashUnit] {
+        self.logical_cash_units.as_ref()
+    }
+
+    /// Gets the [PhysicalCashUnit] configuration.
+    pub fn physical_cash_units(&self) -> &[PhysicalCashUnit] {
+        self.physical_cash_units.as_ref()
+    }
+
+    /// Parses a [DeviceConfig] from a JSON document.
+    ///
+    /// Returns an error if the document version is not supported.
+    pub fn from_json(json: &str) -> Result<Self> {
+        let config: Self = serde_json::from_str(json)?;
+
+        match config.version {
+            1..=DEVICE_CONFIG_VERSION => Ok(config),
+            v => Err(Error::Json(format!(
+                "unsupported device config version: {v}, max supported: {DEVICE_CONFIG_VERSION}"
+            ))),
+        }
+    }
+
+    /// Writes the [DeviceConfig] as a pretty-printed JSON document.
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /// Gets the [Capabilities] to restore onto a device with the `current` capabilities.
+    pub fn restore_capabilities(&self, current: &Capabilities) -> Capabilities {
+        self.capabilities.apply(current)
+    }
+
+    /// Gets the [DenominationList] to restore onto a device with the `current` denominations.
+    ///
+    /// Settings are matched by [CashType](crate::CashType). Denominations unknown to the device
+    /// are ignored, and denominations missing from the config are left unchanged.
+    pub fn restore_denominations(&self, current: &DenominationList) -> DenominationList {
+        let mut res = current.clone();
+
+        for denom in self.denominations.iter() {
+            match res.iter_mut().find(|d| d.cash_type() == denom.cash_type()) {
+                Some(d) => {
+                    d.set_enable_denomination(denom.enable_denomination());
+                    d.set_security_level(denom.security_level());
+                }
+                None => log::warn!(
+                    "Ignoring denomination unknown to the device: {}",
+                    denom.cash_type()
+                ),
+            }
+        }
+
+        res
+    }
+
+    /// Gets the cash unit lists to restore onto a device with the `current` [CashUnit].
+    ///
+    /// Units keep the counts and statuses of the matching `current` unit, matched by number for
+    /// [LogicalCashUnit]s and by name for [PhysicalCashUnit]s. New units start empty.
+    pub fn restore_cash_units(
+        &self,
+        current: &CashUnit,
+    ) -> (LogicalCashUnitList, PhysicalCashUnitList) {
+        let lcus: Vec<LogicalCashUnit> = self
+            .logical_cash_units
+            .iter()
+            .map(|lcu| {
+                match current
+                    .logical_cash_unit_list()
+                    .iter()
+                    .find(|c| c.number() == lcu.number())
+                {
+                    Some(cur) => lcu
+                        .clone()
+                        .with_initial_count(cur.initial_count())
+                        .with_count(cur.count())
+                        .with_status(cur.status())
+                        .with_extended_counters(*cur.extended_counters()),
+                    None => lcu.clone(),
+                }
+            })
+            .collect();
+
+        let pcus: Vec<PhysicalCashUnit> = self
+            .physical_cash_units
+            .iter()
+            .map(|&pcu| {
+                match current
+                    .physical_cash_unit_list()
+                    .iter()
+                    .find(|c| c.name() == pcu.name())
+                {
+                    Some(cur) => pcu
+                        .with_count(cur.count())
+                        .with_status(cur.status())
+                        .with_threshold_status(cur.threshold_status()),
+                    None => pcu,
+                }
+            })
+            .collect();
+
+        (
+            LogicalCashUnitList::new().with_items(&lcus),
+            PhysicalCashUnitList::new().with_items(&pcus),
+        )
+    }
+
+    /// Gets the [DeviceConfig] that results from restoring onto a device with the `current`
+    /// configuration.
+    pub fn restore_onto(&self, current: &DeviceConfig) -> DeviceConfig {
+        let denominations = self
+            .restore_denominations(&DenominationList::new().with_items(current.denominations()));
+
+        Self {
+            version: DEVICE_CONFIG_VERSION,
+            capabilities: self.capabilities,
+            denominations: denominations.items().into(),
+            logical_cash_units: self.logical_cash_units.clone(),
+            physical_cash_units: self.physical_cash_units.clone(),
+        }
+    }
+
+    /// Gets the list of changes from the `current` configuration to this configuration.
+    pub fn diff(&self, current: &DeviceConfig) -> Result<Vec<ConfigChange>> {
+        let mut old = BTreeMap::new();
+        let mut new = BTreeMap::new();
+
+        flatten(String::new(), &serde_json::to_value(current)?, &mut old);
+        flatten(String::new(), &serde_json::to_value(self)?, &mut new);
+
+        let mut changes = Vec::new();
+        for (path, new_val) in new.iter() {
+            match old.get(path) {
+                Some(old_val) if old_val == new_val => (),
+                old_val => changes.push(ConfigChange::create(
+                    path.as_str(),
+                    old_val.cloned(),
+                    Some(new_val.clone()),
+                )),
+            }
+        }
+        for (path, old_val) in old.iter().filter(|(p, _)| !new.contains_key(*p)) {
+            changes.push(ConfigChange::create(
+                path.as_str(),
+                Some(old_val.clone()),
+                None,
+            ));
+        }
+
+        Ok(changes)
+    }
+}
+
+impl Default for DeviceConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Display for DeviceConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let json = serde_json::to_string(self).map_err(|_| fmt::Error)?;
+        write!(f, "{json}")
+    }
+}
+
+/// Represents a single setting change between two [DeviceConfig]s.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ConfigChange {
+    path: String,
+    current: Option<String>,
+    new: Option<String>,
+}
+
+impl ConfigChange {
+    /// Creates a new [ConfigChange] from the provided parameters.
+    pub fn create(path: &str, current: Option<String>, new: Option<String>) -> Self {
+        Self {
+            path: path.into(),
+            current,
+            new,
+        }
+    }
+
+    /// Gets the path of the setting, e.g. `capabilities.auto_present`.
+    pub fn path(&self) -> &str {
+        self.path.as_str()
+    }
+
+    /// Gets the current value, if the setting exists on the device.
+    pub fn current(&self) -> Option<&str> {
+        self.current.as_deref()
+    }
+
+    /// Gets the new value, if the setting exists in the config.
+    pub fn new_value(&self) -> Option<&str> {
+        self.new.as_deref()
+    }
+}
+
+impl fmt::Display for ConfigChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let current = self.current().unwrap_or("(none)");
+        let new = self.new_value().unwrap_or("(none)");
+        write!(f, "{}: {current} -> {new}", self.path)
+    }
+}
+
+fn clear_lcu_counts(lcu: LogicalCashUnit) -> LogicalCashUnit {
+    lcu.with_initial_count(0)
+        .with_count(0)
+        .with_status(0)
+        .with_extended_counters(ExtendedCounters::new())
+}
+
+fn clear_pcu_counts(pcu: PhysicalCashUnit) -> PhysicalCashUnit {
+    pcu.with_count(0)
+        .with_status(0)
+        .with_threshold_status(ThresholdStatus::new())
+}
+
+// Flattens nested JSON into `path -> value` leaves.
+fn flatten(path: String, value: &serde_json::Value, out: &mut BTreeMap<String, String>) {
+    let join = |key: &str| {
+        if path.is_empty() {
+            key.to_string()
+        } else {
+            format!("{path}.{key}")
+        }
+    };
+
+    match value {
+        serde_json::Value::Object(map) if !map.is_empty() => {
+            for (key, val) in map {
+                flatten(join(key), val, out);
+            }
+        }
+        serde_json::Value::Array(list) if !list.is_empty() => {
+            for (i, val) in list.iter().enumerate() {
+                flatten(format!("{path}[{i}]"), val, out);
+            }
+        }
+        val => {
+            out.insert(path, format!("{val}"));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{EnableDenomination, PcuName, Threshold};
+
+    fn cash_unit(full: u32) -> CashUnit {
+        let lcu = LogicalCashUnit::new().with_number(1).with_count(10);
+        let pcu = PhysicalCashUnit::new()
+            .with_name(PcuName::from("RE3"))
+            .with_threshold(Threshold::new().with_full(full))
+            .with_count(10);
+
+        CashUnit::new()
+            .with_logical_cash_unit_list(LogicalCashUnitList::new().with_items(&[lcu]))
+            .with_physical_cash_unit_list(PhysicalCashUnitList::new().with_items(&[pcu]))
+    }
+
+    #[test]
+    fn test_device_config_json() -> Result<()> {
+        let config = DeviceConfig::create(
+            &Capabilities::new(),
+            &DenominationList::new(),
+            &cash_unit(40),
+        );
+
+        // counts are not configuration
+        assert_eq!(config.logical_cash_units()[0].count(), 0);
+        assert_eq!(config.physical_cash_units()[0].count(), 0);
+
+        let json = config.to_json()?;
+        assert_eq!(DeviceConfig::from_json(json.as_str())?, config);
+
+        let future = json.replacen(r#""version": 1"#, r#""version": 99"#, 1);
+        assert!(DeviceConfig::from_json(future.as_str()).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_device_config_restore() -> Result<()> {
+        let current_cu = cash_unit(40);
+        let current = DeviceConfig::create(
+            &Capabilities::new(),
+            &DenominationList::new().with_items(&[DenominationInfo::new()]),
+            &current_cu,
+        );
+
+        let golden = DeviceConfig::create(
+            &Capabilities {
+                auto_present: AutoPresent::create(true),
+                ..Capabilities::new()
+            },
+            &DenominationList::new()
+                .with_items(&[DenominationInfo::new()
+                    .with_enable_denomination(EnableDenomination::create(true))]),
+            &cash_unit(50),
+        );
+
+        assert!(current.diff(&current)?.is_empty());
+
+        let changes = golden.restore_onto(&current).diff(&current)?;
+        let paths: Vec<&str> = changes.iter().map(|c| c.path()).collect();
+        assert_eq!(
+            paths,
+            [
+                "capabilities.auto_present",
+                "denominations[0].enable_denomination",
+                "physical_cash_units[0].threshold.full",
+            ]
+        );
+
+        // restored units keep the device counts
+        let (lcus, pcus) = golden.restore_cash_units(&current_cu);
+        assert_eq!(lcus.items()[0].count(), 10);
+        assert_eq!(pcus.items()[0].count(), 10);
+        assert_eq!(pcus.items()[0].threshold().full(), 50);
+
+        Ok(())
+    }
+}
@@ -1,6 +1,6 @@
 use std::fmt;
 
-use crate::{create_xfs_i4, impl_xfs_struct};
+use crate::{create_xfs_i4, XfsStruct};
 
 create_xfs_i4!(
     Major,
@@ -14,7 +14,8 @@ create_xfs_i4!(
 );
 
 /// Simple version structure, used in version requirements.
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, XfsStruct)]
+#[xfs(name = "version", no_display)]
 pub struct Version {
     major: Major,
     minor: Minor,
@@ -77,8 +78,6 @@ impl fmt::Display for Version {
     }
 }
 
-impl_xfs_struct!(Version, "version", [major: Major, minor: Minor]);
-
 create_xfs_i4!(
     ModuleType,
     "moduleType",
@@ -91,7 +90,8 @@ create_xfs_i4!(
 );
 
 /// Represents a version requirement.
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, XfsStruct)]
+#[xfs(name = "versionRequirement", no_display)]
 pub struct VersionRequirement {
     module_type: ModuleType,
     component_type: ComponentType,
@@ -172,13 +172,3 @@ impl fmt::Display for VersionRequirement {
         write!(f, "}}")
     }
 }
-
-impl_xfs_struct!(
-    VersionRequirement,
-    "versionRequirement",
-    [
-        module_type: ModuleType,
-        component_type: ComponentType,
-        version: Version
-    ]
-);
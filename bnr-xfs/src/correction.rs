@@ -0,0 +1,566 @@
+//! Types for correcting the cash unit counts by hand.
+
+use std::fmt;
+
+use crate::cash_unit::unix_time;
+use crate::json::JsonStr;
+use crate::{
+    CashLocation, CashUnit, Error, LogicalCashUnitList, PcuName, PhysicalCashUnitList, Result,
+};
+
+#[cfg(feature = "usb")]
+mod procedure;
+
+#[cfg(feature = "usb")]
+pub(crate) use procedure::*;
+
+/// Maximum count of a [LogicalCashUnit](crate::LogicalCashUnit).
+pub const MAX_CORRECTED_COUNT: u32 = 65535;
+
+/// Represents the number of bills physically counted in a [LogicalCashUnit](crate::LogicalCashUnit).
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct CountedUnit {
+    number: u32,
+    count: u32,
+}
+
+impl CountedUnit {
+    /// Creates a new [CountedUnit].
+    pub const fn new() -> Self {
+        Self {
+            number: 0,
+            count: 0,
+        }
+    }
+
+    /// Creates a new [CountedUnit] from the provided parameters.
+    pub const fn create(number: u32, count: u32) -> Self {
+        Self { number, count }
+    }
+
+    /// Gets the number of the [LogicalCashUnit](crate::LogicalCashUnit).
+    pub const fn number(&self) -> u32 {
+        self.number
+    }
+
+    /// Gets the number of bills counted.
+    pub const fn count(&self) -> u32 {
+        self.count
+    }
+}
+
+impl fmt::Display for CountedUnit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{{")?;
+        write!(f, r#""number": {}, "#, self.number)?;
+        write!(f, r#""count": {}"#, self.count)?;
+        write!(f, "}}")
+    }
+}
+
+/// Represents a manual count correction, e.g. after clearing a jam by hand.
+///
+/// Records who made the correction and why, with the physically counted values.
+#[derive(Clone, Debug, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct CountCorrection {
+    operator: String,
+    reason: String,
+    counted: Vec<CountedUnit>,
+}
+
+impl CountCorrection {
+    /// Creates a new [CountCorrection].
+    pub fn new(operator: &str, reason: &str) -> Self {
+        Self {
+            operator: operator.into(),
+            reason: reason.into(),
+            counted: Vec::new(),
+        }
+    }
+
+    /// Gets the operator making the correction.
+    pub fn operator(&self) -> &str {
+        self.operator.as_str()
+    }
+
+    /// Gets the reason for the correction.
+    pub fn reason(&self) -> &str {
+        self.reason.as_str()
+    }
+
+    /// Gets the physically counted values.
+    pub fn counted(&self) -> &[CountedUnit] {
+        self.counted.as_ref()
+    }
+
+    /// Sets the number of bills counted in the [LogicalCashUnit](crate::LogicalCashUnit).
+    ///
+    /// Replaces any previous count of the same unit.
+    pub fn set_count(&mut self, number: u32, count: u32) {
+        match self.counted.iter_mut().find(|c| c.number == number) {
+            Some(counted) => counted.count = count,
+            None => self.counted.push(CountedUnit::create(number, count)),
+        }
+    }
+
+    /// Builder function that sets the number of bills counted in the
+    /// [LogicalCashUnit](crate::LogicalCashUnit).
+    pub fn with_count(mut self, number: u32, count: u32) -> Self {
+        self.set_count(number, count);
+        self
+    }
+
+    /// Computes the [CountAdjustment]s against the `cash_unit` counts.
+    ///
+    /// Returns an error if the operator or the reason is missing, a unit does not exist, or a
+    /// count exceeds [MAX_CORRECTED_COUNT].
+    pub fn adjustments(&self, cash_unit: &CashUnit) -> Result<Vec<CountAdjustment>> {
+        if self.operator.trim().is_empty() {
+            return Err(Error::Config("count correction without an operator".into()));
+        }
+        if self.reason.trim().is_empty() {
+            return Err(Error::Config("count correction without a reason".into()));
+        }
+
+        let pcus = cash_unit.physical_cash_unit_list().items();
+
+        self.counted
+            .iter()
+            .map(|counted| {
+                if counted.count > MAX_CORRECTED_COUNT {
+                    return Err(Error::Config(format!(
+                        "counted value {} of logical cash unit {} exceeds the maximum: {MAX_CORRECTED_COUNT}",
+                        counted.count, counted.number
+                    )));
+                }
+
+                let lcu = cash_unit
+                    .logical_cash_unit_list()
+                    .iter()
+                    .find(|l| l.number() == counted.number)
+                    .ok_or(Error::Config(format!(
+                        "no logical cash unit with number {}",
+                        counted.number
+                    )))?;
+
+                Ok(CountAdjustment {
+                    number: lcu.number(),
+                    pcu_name: pcus
+                        .get(lcu.physical_cu_index() as usize)
+                        .map(|p| *p.name())
+                        .unwrap_or_default(),
+                    location: CashLocation::create(lcu.cu_kind()),
+                    before: lcu.count(),
+                    counted: counted.count,
+                })
+            })
+            .collect()
+    }
+}
+
+impl fmt::Display for CountCorrection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{{")?;
+        write!(f, r#""operator": {}, "#, JsonStr(&self.operator))?;
+        write!(f, r#""reason": {}, "#, JsonStr(&self.reason))?;
+        write!(f, r#""counted": ["#)?;
+        for (i, counted) in self.counted.iter().enumerate() {
+            if i != 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{counted}")?;
+        }
+        write!(f, "]}}")
+    }
+}
+
+/// Represents the adjustment of one [LogicalCashUnit](crate::LogicalCashUnit) count.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct CountAdjustment {
+    number: u32,
+    pcu_name: PcuName,
+    location: CashLocation,
+    before: u32,
+    counted: u32,
+}
+
+impl CountAdjustment {
+    /// Creates a new [CountAdjustment].
+    pub const fn new() -> Self {
+        Self {
+            number: 0,
+            pcu_name: PcuName::new(),
+            location: CashLocation::new(),
+            before: 0,
+            counted: 0,
+        }
+    }
+
+    /// Gets the number of the [LogicalCashUnit](crate::LogicalCashUnit).
+    pub const fn number(&self) -> u32 {
+        self.number
+    }
+
+    /// Gets the name of the [PhysicalCashUnit](crate::PhysicalCashUnit) storing the bills.
+    pub const fn pcu_name(&self) -> &PcuName {
+        &self.pcu_name
+    }
+
+    /// Gets the [CashLocation] of the unit.
+    pub const fn location(&self) -> CashLocation {
+        self.location
+    }
+
+    /// Gets the count reported by the device before the correction.
+    pub const fn before(&self) -> u32 {
+        self.before
+    }
+
+    /// Gets the physically counted value.
+    pub const fn counted(&self) -> u32 {
+        self.counted
+    }
+
+    /// Gets the count change.
+    pub const fn delta(&self) -> i64 {
+        self.counted as i64 - self.before as i64
+    }
+
+    /// Gets whether the count can be written to the device.
+    ///
+    /// Only cashbox and loader counts are writable, bundler and recycler counts are read-only.
+    pub const fn is_writable(&self) -> bool {
+        matches!(self.location, CashLocation::Cashbox | CashLocation::Loader)
+    }
+
+    /// Gets whether the correction changes the count on the device.
+    pub const fn is_applied(&self) -> bool {
+        self.is_writable() && self.delta() != 0
+    }
+}
+
+impl fmt::Display for CountAdjustment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{{")?;
+        write!(f, r#""number": {}, "#, self.number)?;
+        write!(f, r#""pcu_name": {}, "#, self.pcu_name)?;
+        write!(f, r#""location": {}, "#, self.location)?;
+        write!(f, r#""before": {}, "#, self.before)?;
+        write!(f, r#""counted": {}, "#, self.counted)?;
+        write!(f, r#""writable": {}"#, self.is_writable())?;
+        write!(f, "}}")
+    }
+}
+
+/// Gets the lists for [update_cash_unit](crate::DeviceHandle::update_cash_unit) that apply the
+/// writable [CountAdjustment]s.
+///
+/// The [PhysicalCashUnit](crate::PhysicalCashUnit) counts change by the sum of their logical
+/// cash unit adjustments. Read-only units are left unchanged.
+pub fn correction_lists(
+    cash_unit: &CashUnit,
+    adjustments: &[CountAdjustment],
+) -> (LogicalCashUnitList, PhysicalCashUnitList) {
+    let mut lcu_list = cash_unit.logical_cash_unit_list().clone();
+    let mut pcu_list = cash_unit.physical_cash_unit_list().clone();
+
+    for adjustment in adjustments.iter().filter(|a| a.is_applied()) {
+        let Some(lcu) = lcu_list
+            .iter_mut()
+            .find(|l| l.number() == adjustment.number)
+        else {
+            continue;
+        };
+        lcu.set_count(adjustment.counted);
+
+        if let Some(pcu) = pcu_list
+            .items_mut()
+            .get_mut(lcu.physical_cu_index() as usize)
+        {
+            let count = (pcu.count() as i64 + adjustment.delta()).max(0);
+            pcu.set_count(count as u32);
+        }
+    }
+
+    (lcu_list, pcu_list)
+}
+
+/// Records a manual count correction, see [correct_counts](crate::DeviceHandle::correct_counts).
+#[derive(Clone, Debug, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct CorrectionRecord {
+    time: u64,
+    operator: String,
+    reason: String,
+    adjustments: Vec<CountAdjustment>,
+}
+
+impl CorrectionRecord {
+    /// Creates a new [CorrectionRecord].
+    pub const fn new() -> Self {
+        Self {
+            time: 0,
+            operator: String::new(),
+            reason: String::new(),
+            adjustments: Vec::new(),
+        }
+    }
+
+    /// Creates a new [CorrectionRecord] from the [CountCorrection] and its [CountAdjustment]s.
+    pub fn create(correction: &CountCorrection, adjustments: Vec<CountAdjustment>) -> Self {
+        Self {
+            time: unix_time(),
+            operator: correction.operator.clone(),
+            reason: correction.reason.clone(),
+            adjustments,
+        }
+    }
+
+    /// Gets the time of the correction, in seconds since the Unix epoch.
+    pub const fn time(&self) -> u64 {
+        self.time
+    }
+
+    /// Gets the operator who made the correction.
+    pub fn operator(&self) -> &str {
+        self.operator.as_str()
+    }
+
+    /// Gets the reason for the correction.
+    pub fn reason(&self) -> &str {
+        self.reason.as_str()
+    }
+
+    /// Gets the list of [CountAdjustment]s.
+    pub fn adjustments(&self) -> &[CountAdjustment] {
+        self.adjustments.as_ref()
+    }
+
+    /// Gets the [CountAdjustment]s applied to the device.
+    pub fn applied(&self) -> impl Iterator<Item = &CountAdjustment> {
+        self.adjustments.iter().filter(|a| a.is_applied())
+    }
+
+    /// Gets the [CountAdjustment]s of read-only units with a count difference, not applied to
+    /// the device.
+    pub fn skipped(&self) -> impl Iterator<Item = &CountAdjustment> {
+        self.adjustments
+            .iter()
+            .filter(|a| !a.is_writable() && a.delta() != 0)
+    }
+}
+
+impl fmt::Display for CorrectionRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{{")?;
+        write!(f, r#""time": {}, "#, self.time)?;
+        write!(f, r#""operator": {}, "#, JsonStr(&self.operator))?;
+        write!(f, r#""reason": {}, "#, JsonStr(&self.reason))?;
+        write!(f, r#""adjustments": ["#)?;
+        for (i, adjustment) in self.adjustments.iter().enumerate() {
+            if i != 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{adjustment}")?;
+        }
+        write!(f, "]}}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cash_unit::tests::cash_unit;
+
+    fn lcu_count(cash_unit: &CashUnit, number: u32) -> u32 {
+        cash_unit
+            .logical_cash_unit_list()
+            .iter()
+            .find(|l| l.number() == number)
+            .map(|l| l.count())
+            .unwrap_or(0)
+    }
+
+    // one $50 bill found in the cashbox, one $100 bill missing from recycler RE3, and the loader
+    // count confirmed
+    fn correction(cash_unit: &CashUnit) -> CountCorrection {
+        CountCorrection::new("operator 1", "E_MM05 jam cleared")
+            .with_count(13, lcu_count(cash_unit, 13) + 1)
+            .with_count(7, 0)
+            .with_count(11, lcu_count(cash_unit, 11))
+    }
+
+    fn config_err(res: Result<Vec<CountAdjustment>>) -> String {
+        match res {
+            Err(Error::Config(msg)) => msg,
+            res => panic!("unexpected result: {res:?}"),
+        }
+    }
+
+    #[test]
+    fn test_count_adjustments() -> Result<()> {
+        let cash_unit = cash_unit()?;
+        let adjustments = correction(&cash_unit).adjustments(&cash_unit)?;
+        assert_eq!(adjustments.len(), 3);
+
+        assert_eq!(adjustments[0].pcu_name(), &PcuName::from("CB"));
+        assert_eq!(adjustments[0].delta(), 1);
+        assert!(adjustments[0].is_applied());
+
+        assert_eq!(adjustments[1].location(), CashLocation::Recycler);
+        assert_eq!(adjustments[1].delta(), -1);
+        assert!(!adjustments[1].is_writable());
+
+        assert!(adjustments[2].is_writable());
+        assert!(!adjustments[2].is_applied());
+
+        // a later count of the same unit replaces the earlier one
+        let recount = correction(&cash_unit).with_count(7, 1);
+        assert_eq!(recount.counted().len(), 3);
+        assert_eq!(recount.adjustments(&cash_unit)?[1].delta(), 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_correction_lists() -> Result<()> {
+        let cash_unit = cash_unit()?;
+        let cashbox = lcu_count(&cash_unit, 13);
+        let adjustments = correction(&cash_unit).adjustments(&cash_unit)?;
+
+        let (lcu_list, pcu_list) = correction_lists(&cash_unit, &adjustments);
+        let count = |number: u32| {
+            lcu_list
+                .iter()
+                .find(|l| l.number() == number)
+                .map(|l| l.count())
+        };
+
+        // the read-only recycler count is left unchanged
+        assert_eq!(count(13), Some(cashbox + 1));
+        assert_eq!(count(7), Some(1));
+        assert_eq!(pcu_list.items()[1].count(), 121);
+        assert_eq!(pcu_list.items()[3].count(), 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_correction_record() -> Result<()> {
+        let cash_unit = cash_unit()?;
+        let correction = correction(&cash_unit);
+        let record = CorrectionRecord::create(&correction, correction.adjustments(&cash_unit)?);
+
+        assert_eq!(record.operator(), "operator 1");
+        assert_eq!(record.reason(), "E_MM05 jam cleared");
+        assert_eq!(record.adjustments().len(), 3);
+        assert_eq!(
+            record.applied().map(|a| a.number()).collect::<Vec<_>>(),
+            [13]
+        );
+        assert_eq!(
+            record.skipped().map(|a| a.number()).collect::<Vec<_>>(),
+            [7]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_count_correction_read_only() -> Result<()> {
+        let cash_unit = cash_unit()?;
+
+        // bills counted in recyclers RE3 and RE6 only
+        let correction = CountCorrection::new("operator 1", "audit")
+            .with_count(7, 3)
+            .with_count(8, 2);
+        let adjustments = correction.adjustments(&cash_unit)?;
+        assert!(adjustments.iter().all(|a| !a.is_writable()));
+        assert!(!adjustments.iter().any(|a| a.is_applied()));
+
+        // nothing is written to the device
+        let (lcu_list, pcu_list) = correction_lists(&cash_unit, &adjustments);
+        assert_eq!(&lcu_list, cash_unit.logical_cash_unit_list());
+        assert_eq!(&pcu_list, cash_unit.physical_cash_unit_list());
+
+        // every difference is reported as skipped
+        let record = CorrectionRecord::create(&correction, adjustments);
+        assert_eq!(record.applied().count(), 0);
+        assert_eq!(
+            record
+                .skipped()
+                .map(|a| (a.number(), a.delta()))
+                .collect::<Vec<_>>(),
+            [(7, 2), (8, 2)]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_count_correction_unknown_lcu() -> Result<()> {
+        let cash_unit = cash_unit()?;
+
+        // the whole correction is rejected, even with valid counts
+        let msg = config_err(
+            correction(&cash_unit)
+                .with_count(99, 1)
+                .adjustments(&cash_unit),
+        );
+        assert_eq!(msg, "no logical cash unit with number 99");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_count_correction_invalid() -> Result<()> {
+        let cash_unit = cash_unit()?;
+
+        let msg = config_err(
+            CountCorrection::new("", "jam")
+                .with_count(13, 1)
+                .adjustments(&cash_unit),
+        );
+        assert_eq!(msg, "count correction without an operator");
+
+        let msg = config_err(
+            CountCorrection::new("operator 1", " ")
+                .with_count(13, 1)
+                .adjustments(&cash_unit),
+        );
+        assert_eq!(msg, "count correction without a reason");
+
+        let msg = config_err(
+            CountCorrection::new("operator 1", "jam")
+                .with_count(13, MAX_CORRECTED_COUNT + 1)
+                .adjustments(&cash_unit),
+        );
+        assert!(msg.contains("exceeds the maximum"), "{msg}");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_count_correction_json() -> Result<()> {
+        let cash_unit = cash_unit()?;
+
+        // quotes in the free text do not break the JSON
+        let correction =
+            CountCorrection::new(r#"J. "Jo" Doe"#, r#"jam at "RE3""#).with_count(13, 1);
+        let json: serde_json::Value = serde_json::from_str(&correction.to_string())?;
+        assert_eq!(json["operator"], r#"J. "Jo" Doe"#);
+
+        let record = CorrectionRecord::create(&correction, correction.adjustments(&cash_unit)?);
+        let json: serde_json::Value = serde_json::from_str(&record.to_string())?;
+        assert_eq!(json["reason"], r#"jam at "RE3""#);
+
+        // control characters are escaped as JSON, not Rust, escapes
+        let correction = CountCorrection::new("operator\u{1}", "jam\n\\").with_count(13, 1);
+        let json: serde_json::Value = serde_json::from_str(&correction.to_string())?;
+        assert_eq!(json["operator"], "operator\u{1}");
+        assert_eq!(json["reason"], "jam\n\\");
+
+        Ok(())
+    }
+}
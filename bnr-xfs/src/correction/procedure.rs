@@ -0,0 +1,39 @@
+use crate::{DeviceHandle, Result};
+
+use super::{correction_lists, CorrectionRecord, CountCorrection};
+
+/// Applies the writable count adjustments of the `correction`, and logs every adjustment.
+pub(crate) fn correct_counts(
+    handle: &DeviceHandle,
+    correction: &CountCorrection,
+) -> Result<CorrectionRecord> {
+    let cash_unit = handle.query_cash_unit_inner()?;
+    let adjustments = correction.adjustments(&cash_unit)?;
+
+    if adjustments.iter().any(|a| a.is_applied()) {
+        let (lcu_list, pcu_list) = correction_lists(&cash_unit, &adjustments);
+        handle.update_cash_unit_inner(cash_unit.transport_count(), &lcu_list, &pcu_list)?;
+    }
+
+    let record = CorrectionRecord::create(correction, adjustments);
+    let (operator, reason) = (record.operator(), record.reason());
+
+    for adjustment in record.applied() {
+        log::info!(
+            "Count correction by {operator:?} ({reason}): logical cash unit {} count {} -> {}",
+            adjustment.number(),
+            adjustment.before(),
+            adjustment.counted()
+        );
+    }
+    for adjustment in record.skipped() {
+        log::warn!(
+            "Count correction by {operator:?} ({reason}): logical cash unit {} is read-only, counted {} but the device reports {}",
+            adjustment.number(),
+            adjustment.counted(),
+            adjustment.before()
+        );
+    }
+
+    Ok(record)
+}
@@ -2,7 +2,7 @@
 
 use std::fmt;
 
-use crate::impl_xfs_struct;
+use crate::{Result, XfsStruct};
 
 mod cash_order;
 mod cash_type;
@@ -11,6 +11,7 @@ mod cu_enum;
 mod denomination;
 mod exponent;
 mod mix;
+mod money;
 
 pub use cash_order::*;
 pub use cash_type::*;
@@ -19,10 +20,14 @@ pub use cu_enum::*;
 pub use denomination::*;
 pub use exponent::*;
 pub use mix::*;
+pub use money::*;
 
 /// Represents a currency set used in the CDR.
 #[repr(C)]
-#[derive(Clone, Copy, Debug, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, serde::Deserialize, serde::Serialize, XfsStruct,
+)]
+#[xfs(name = "currency", no_display)]
 pub struct Currency {
     currency_code: CurrencyCode,
     exponent: Exponent,
@@ -60,8 +65,17 @@ impl Currency {
     /// let currency = Currency::from(CurrencyCode::from("USD"));
     /// assert_eq!(currency.to_mdu_value(value), 1000);
     /// ```
+    ///
+    /// Saturates at [u32::MAX], use [Money::to_currency_mdu] for checked conversions.
     pub fn to_mdu_value(&self, value: u32) -> u32 {
-        (value as f32 * 10f32.powf(self.exponent.inner().abs() as f32)) as u32
+        let exp = self.exponent.inner();
+        let scaled = if exp < 0 {
+            (value as u64).saturating_mul(10u64.saturating_pow(exp.unsigned_abs()))
+        } else {
+            value as u64 / 10u64.saturating_pow(exp as u32)
+        };
+
+        scaled.min(u32::MAX as u64) as u32
     }
 
     /// Converts a MDU value to a standard value.
@@ -74,8 +88,23 @@ impl Currency {
     /// let currency = Currency::from(CurrencyCode::from("USD"));
     /// assert_eq!(currency.from_mdu_value(mdu_value), 10);
     /// ```
+    ///
+    /// Fractions of the standard unit are truncated, use [to_money](Self::to_money) for exact
+    /// conversions.
     pub fn from_mdu_value(&self, value: u32) -> u32 {
-        (value as f32 * 10f32.powf(self.exponent.inner() as f32)) as u32
+        let exp = self.exponent.inner();
+        let scaled = if exp < 0 {
+            value as u64 / 10u64.saturating_pow(exp.unsigned_abs())
+        } else {
+            (value as u64).saturating_mul(10u64.saturating_pow(exp as u32))
+        };
+
+        scaled.min(u32::MAX as u64) as u32
+    }
+
+    /// Converts a MDU value to [Money].
+    pub fn to_money(&self, value: u32) -> Result<Money> {
+        Money::from_mdu(self.currency_code, value.into(), self.exponent)
     }
 }
 
@@ -99,5 +128,3 @@ impl fmt::Display for Currency {
         )
     }
 }
-
-impl_xfs_struct!(Currency, "currency", [currency_code: CurrencyCode, exponent: Exponent]);
@@ -1,13 +1,14 @@
 use std::fmt;
 
 use crate::Result;
-use crate::{device_handle::CallbackArg, impl_xfs_struct};
+use crate::{CallbackArg, XfsStruct};
 
-use super::{Currency, Denomination};
+use super::{Currency, Denomination, Money};
 
 /// Represents a cash order event initiated by the device.
 #[repr(C)]
-#[derive(Clone, Copy, Debug, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+#[derive(Clone, Debug, Default, PartialEq, serde::Deserialize, serde::Serialize, XfsStruct)]
+#[xfs(name = "cashOrder", no_display)]
 pub struct CashOrder {
     currency: Currency,
     denomination: Denomination,
@@ -71,6 +72,11 @@ impl CashOrder {
         self.set_denomination(denomination);
         self
     }
+
+    /// Gets the [Denomination] amount as [Money].
+    pub fn amount(&self) -> Result<Money> {
+        self.currency.to_money(self.denomination.amount())
+    }
 }
 
 impl fmt::Display for CashOrder {
@@ -85,9 +91,11 @@ impl fmt::Display for CashOrder {
 impl CallbackArg for CashOrder {
     fn value(&self) -> i32 {
         let cash_order = self as &CashOrder;
-        cash_order
+        let value = cash_order
             .currency()
-            .from_mdu_value(cash_order.denomination().amount()) as i32
+            .from_mdu_value(cash_order.denomination().amount());
+
+        i32::try_from(value).unwrap_or(i32::MAX)
     }
 
     fn is_null(&self) -> bool {
@@ -106,5 +114,3 @@ impl CallbackArg for CashOrder {
         Ok(self as &mut CashOrder)
     }
 }
-
-impl_xfs_struct!(CashOrder, "cashOrder", [currency: Currency, denomination: Denomination]);
@@ -1,6 +1,6 @@
 use std::fmt;
 
-use crate::{create_xfs_i4, impl_xfs_array, impl_xfs_struct, Size};
+use crate::{create_xfs_i4, BoundedList, XfsArray, XfsStruct};
 
 use super::CurrencyCode;
 
@@ -16,7 +16,10 @@ create_xfs_i4!(
 
 /// Represents a cash type ISO currency code, value, and variant.
 #[repr(C)]
-#[derive(Clone, Copy, Debug, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, serde::Deserialize, serde::Serialize, XfsStruct,
+)]
+#[xfs(name = "cashType", no_display)]
 pub struct CashType {
     currency_code: CurrencyCode,
     value: Value,
@@ -33,6 +36,15 @@ impl CashType {
         }
     }
 
+    /// Creates a new [CashType] from the provided parameters.
+    pub const fn create(currency_code: CurrencyCode, value: u32, variant: u32) -> Self {
+        Self {
+            currency_code,
+            value: Value::create(value),
+            variant: Variant::create(variant),
+        }
+    }
+
     /// Gets the [CurrencyCode].
     pub const fn currency_code(&self) -> CurrencyCode {
         self.currency_code
@@ -59,73 +71,41 @@ impl fmt::Display for CashType {
     }
 }
 
-impl_xfs_struct!(CashType, "cashType", [currency_code: CurrencyCode, value: Value, variant: Variant]);
-
 /// [CashType] list used for LCU's [secondary cash_type](LogicalCashUnit::secondary_cash_types).
-#[repr(C)]
-#[derive(Clone, Copy, Debug, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+#[derive(Clone, Debug, Default, PartialEq, serde::Deserialize, serde::Serialize, XfsArray)]
+#[xfs(name = "secondaryCashTypes", no_display)]
 pub struct CashTypeList {
-    size: Size,
-    items: [CashType; CASH_TYPE_LIST_LEN],
+    items: BoundedList<CashType, CASH_TYPE_LIST_LEN>,
 }
 
 impl CashTypeList {
     /// Creates a new [CashTypeList].
     pub const fn new() -> Self {
         Self {
-            size: Size::new(),
-            items: [CashType::new(); CASH_TYPE_LIST_LEN],
+            items: BoundedList::new(),
         }
     }
 
-    /// Gets the max size.
-    pub const fn max_size() -> usize {
-        CASH_TYPE_LIST_LEN
-    }
-
     /// Gets the size.
-    pub const fn size(&self) -> u32 {
-        self.size.inner()
+    pub fn size(&self) -> u32 {
+        self.items.len() as u32
     }
 
     /// Sets the size.
+    ///
+    /// New items are set to the default value, no-op if `val` is larger than the max size.
     pub fn set_size(&mut self, val: u32) {
-        self.size.set_inner(val);
-    }
-
-    /// Gets whether the size is within bounds.
-    pub const fn size_is_valid(&self) -> bool {
-        self.size.inner() as usize <= CASH_TYPE_LIST_LEN
-    }
-
-    /// Gets a reference to the list of [CashType]s.
-    pub fn items(&self) -> &[CashType] {
-        let size = self.size.inner() as usize;
-        if size <= CASH_TYPE_LIST_LEN {
-            self.items[..size].as_ref()
-        } else {
-            self.items.as_ref()
-        }
-    }
-
-    /// Gets a mutable reference to the list of [CashType]s.
-    pub fn items_mut(&mut self) -> &mut [CashType] {
-        let size = self.size.inner() as usize;
-        if size <= CASH_TYPE_LIST_LEN {
-            self.items[..size].as_mut()
-        } else {
-            self.items.as_mut()
-        }
+        self.items.resize(val as usize);
     }
 }
 
 impl fmt::Display for CashTypeList {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{{")?;
-        write!(f, r#""size":{},"#, self.size)?;
+        write!(f, r#""size":{},"#, self.size())?;
 
         write!(f, r#""items":["#)?;
-        for (i, item) in self.items().iter().enumerate() {
+        for (i, item) in self.iter().enumerate() {
             if i != 0 {
                 write!(f, ", ")?;
             }
@@ -134,5 +114,3 @@ impl fmt::Display for CashTypeList {
         write!(f, "]}}")
     }
 }
-
-impl_xfs_array!(CashTypeList, "secondaryCashTypes");
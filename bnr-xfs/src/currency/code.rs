@@ -3,7 +3,7 @@ use std::fmt;
 use currency_iso4217::Currency as IsoCurrency;
 
 use crate::xfs::{value::XfsValue, xfs_struct::XfsMember};
-use crate::{Error, Result};
+use crate::{impl_xfs_decode, Error, Result};
 
 use super::Exponent;
 
@@ -103,6 +103,8 @@ impl TryFrom<XfsValue> for CurrencyCode {
     }
 }
 
+impl_xfs_decode!(CurrencyCode);
+
 impl From<&CurrencyCode> for XfsMember {
     fn from(val: &CurrencyCode) -> Self {
         Self::create(CurrencyCode::xfs_name(), val.into())
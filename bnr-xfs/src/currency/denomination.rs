@@ -1,6 +1,6 @@
-use std::{cmp, fmt};
+use std::fmt;
 
-use crate::{impl_xfs_array, impl_xfs_i4, impl_xfs_struct, Count, Size};
+use crate::{impl_xfs_i4, BoundedList, Count, Size, XfsArray, XfsStruct};
 
 pub const DENOM_ITEM_LEN: usize = 20;
 
@@ -126,9 +126,11 @@ impl fmt::Display for Unit {
 
 /// This structure handles a list of [DenominationItem]s.
 #[repr(C)]
-#[derive(Clone, Copy, Debug, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+#[derive(Clone, Debug, Default, PartialEq, serde::Deserialize, serde::Serialize, XfsStruct)]
+#[xfs(name = "denomination", no_display)]
 pub struct Denomination {
     /// Size of items array.
+    #[xfs(skip)]
     size: Size,
     /// Amount in MDU
     amount: Amount,
@@ -235,19 +237,12 @@ impl fmt::Display for Denomination {
     }
 }
 
-impl_xfs_struct!(
-    Denomination,
-    "denomination",
-    [
-        amount: Amount,
-        cashbox: Cashbox,
-        items: DenominationItems
-    ]
-);
-
 /// This structure describes the number of bills stored to or dispensed from a Logical Cash Unit.
 #[repr(C)]
-#[derive(Clone, Copy, Debug, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, serde::Deserialize, serde::Serialize, XfsStruct,
+)]
+#[xfs(name = "denominationItem", no_display)]
 pub struct DenominationItem {
     /// Logical Cash Unit number
     unit: Unit,
@@ -303,87 +298,41 @@ impl fmt::Display for DenominationItem {
     }
 }
 
-impl_xfs_struct!(DenominationItem, "denominationItem", [unit: Unit, count: Count]);
-
-#[repr(C)]
-#[derive(Clone, Copy, Debug, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+/// Represents the [DenominationItem]s of a [Denomination].
+#[derive(Clone, Debug, Default, PartialEq, serde::Deserialize, serde::Serialize, XfsArray)]
+#[xfs(name = "items", no_display)]
 pub struct DenominationItems {
-    size: Size,
-    items: [DenominationItem; DENOM_ITEM_LEN],
+    items: BoundedList<DenominationItem, DENOM_ITEM_LEN>,
 }
 
 impl DenominationItems {
     /// Creates a new [DenominationItems].
     pub const fn new() -> Self {
         Self {
-            size: Size::new(),
-            items: [DenominationItem::new(); DENOM_ITEM_LEN],
+            items: BoundedList::new(),
         }
     }
 
-    /// Creates a new [DenominationItems] from the provided parameter.
-    pub const fn create(items: [DenominationItem; DENOM_ITEM_LEN]) -> Self {
-        Self {
-            size: Size::create(DENOM_ITEM_LEN as u32),
-            items,
-        }
-    }
-
-    /// Gets the max size.
-    pub const fn max_size() -> usize {
-        DENOM_ITEM_LEN
-    }
-
     /// Gets the size.
-    pub const fn size(&self) -> u32 {
-        self.size.inner()
+    pub fn size(&self) -> u32 {
+        self.items.len() as u32
     }
 
     /// Sets the size.
+    ///
+    /// New items are set to the default value, no-op if `size` is larger than the max size.
     pub fn set_size(&mut self, size: u32) {
-        self.size.set_inner(size);
-    }
-
-    /// Gets a reference to the [DenominationItem] list.
-    pub fn items(&self) -> &[DenominationItem] {
-        let size = self.size.inner() as usize;
-        if size <= DENOM_ITEM_LEN {
-            self.items[..size].as_ref()
-        } else {
-            self.items.as_ref()
-        }
-    }
-
-    /// Gets a mutable reference to the [DenominationItem] list.
-    pub fn items_mut(&mut self) -> &mut [DenominationItem] {
-        let size = self.size.inner() as usize;
-        if size <= DENOM_ITEM_LEN {
-            self.items[..size].as_mut()
-        } else {
-            self.items.as_mut()
-        }
-    }
-
-    /// Sets the [DenominationItem] list.
-    pub fn set_items(&mut self, items: &[DenominationItem]) {
-        let len = cmp::min(items.len(), DENOM_ITEM_LEN);
-        self.items[..len]
-            .iter_mut()
-            .zip(items[..len].iter())
-            .for_each(|(dst, &src)| {
-                *dst = src;
-            });
-        self.size.set_inner(len as u32);
+        self.items.resize(size as usize);
     }
 }
 
 impl fmt::Display for DenominationItems {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{{")?;
-        write!(f, r#""size":{}, "#, self.size)?;
+        write!(f, r#""size":{}, "#, self.size())?;
         write!(f, r#""items": ["#)?;
 
-        for (i, item) in self.items.iter().enumerate() {
+        for (i, item) in self.iter().enumerate() {
             if i != 0 {
                 write!(f, ", ")?;
             }
@@ -393,5 +342,3 @@ impl fmt::Display for DenominationItems {
         write!(f, "]}}")
     }
 }
-
-impl_xfs_array!(DenominationItems, "items");
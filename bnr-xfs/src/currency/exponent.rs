@@ -1,7 +1,7 @@
 use std::fmt;
 
 use crate::xfs::{value::XfsValue, xfs_struct::XfsMember};
-use crate::{Error, Result};
+use crate::{impl_xfs_decode, Error, Result};
 
 /// Represents the exponent field of a [Currency].
 #[repr(C)]
@@ -113,6 +113,8 @@ impl TryFrom<&XfsValue> for Exponent {
     }
 }
 
+impl_xfs_decode!(Exponent);
+
 impl From<&Exponent> for XfsMember {
     fn from(val: &Exponent) -> Self {
         Self::create(Exponent::xfs_name(), val.into())
@@ -0,0 +1,265 @@
+use std::{cmp, fmt};
+
+use crate::{Error, Result};
+
+use super::{Currency, CurrencyCode, Exponent};
+
+/// Represents an amount of money in a single currency.
+///
+/// The amount is stored in minor units of the currency, i.e. the smallest unit given by the
+/// currency [Exponent]: cents for `USD` with an exponent of `-2`.
+///
+/// Arithmetic is checked: mixing currencies, or overflowing the amount returns an error instead of
+/// silently producing a wrong value.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct Money {
+    currency: CurrencyCode,
+    minor_units: i64,
+}
+
+impl Money {
+    /// Creates a new [Money].
+    pub const fn new() -> Self {
+        Self {
+            currency: CurrencyCode::new(),
+            minor_units: 0,
+        }
+    }
+
+    /// Creates a new [Money] from the provided currency and amount in minor units.
+    pub const fn create(currency: CurrencyCode, minor_units: i64) -> Self {
+        Self {
+            currency,
+            minor_units,
+        }
+    }
+
+    /// Creates a new [Money] from an amount in major units, e.g. dollars for `USD`.
+    pub fn from_major(currency: CurrencyCode, major_units: i64) -> Result<Self> {
+        let exp = Exponent::from(currency).inner();
+        let minor_units = if exp < 0 {
+            pow10(exp.unsigned_abs())
+                .and_then(|p| major_units.checked_mul(p))
+                .ok_or(overflow("from_major", major_units))?
+        } else {
+            let p = pow10(exp as u32).ok_or(overflow("from_major", major_units))?;
+            if major_units % p != 0 {
+                return Err(Error::Money(format!(
+                    "{major_units} is not a multiple of the {} minor unit",
+                    <&str>::from(currency)
+                )));
+            }
+            major_units / p
+        };
+
+        Ok(Self::create(currency, minor_units))
+    }
+
+    /// Creates a new [Money] from an amount in minimum dispense units (MDU).
+    ///
+    /// The `exponent` is the device [Exponent] of the MDU amount, e.g. from a [Currency].
+    pub fn from_mdu(currency: CurrencyCode, mdu: u64, exponent: Exponent) -> Result<Self> {
+        let minor_units = rescale(
+            i64::try_from(mdu)
+                .map_err(|_| Error::Money(format!("{mdu} is out of the Money range")))?,
+            exponent.inner(),
+            Exponent::from(currency).inner(),
+        )?;
+
+        Ok(Self::create(currency, minor_units))
+    }
+
+    /// Gets the [CurrencyCode].
+    pub const fn currency(&self) -> CurrencyCode {
+        self.currency
+    }
+
+    /// Gets the [Exponent] of the minor units.
+    pub fn exponent(&self) -> Exponent {
+        self.currency.into()
+    }
+
+    /// Gets the amount in minor units.
+    pub const fn minor_units(&self) -> i64 {
+        self.minor_units
+    }
+
+    /// Gets whether the amount is zero.
+    pub const fn is_zero(&self) -> bool {
+        self.minor_units == 0
+    }
+
+    /// Gets whether the amount is negative.
+    pub const fn is_negative(&self) -> bool {
+        self.minor_units < 0
+    }
+
+    /// Converts the amount to minimum dispense units (MDU) with the device [Exponent].
+    ///
+    /// Returns an error for negative amounts, amounts that do not fit the MDU range, and amounts
+    /// that cannot be expressed exactly with the `exponent`.
+    pub fn to_mdu(&self, exponent: Exponent) -> Result<u32> {
+        let mdu = rescale(
+            self.minor_units,
+            Exponent::from(self.currency).inner(),
+            exponent.inner(),
+        )?;
+
+        u32::try_from(mdu).map_err(|_| Error::Money(format!("{self} is out of the MDU range")))
+    }
+
+    /// Converts the amount to minimum dispense units (MDU) of the provided [Currency].
+    pub fn to_currency_mdu(&self, currency: &Currency) -> Result<u32> {
+        self.check_currency(currency.currency_code())?;
+        self.to_mdu(Exponent::create(currency.exponent()))
+    }
+
+    /// Adds two amounts of the same currency.
+    pub fn checked_add(&self, rhs: &Self) -> Result<Self> {
+        self.check_currency(rhs.currency)?;
+        self.minor_units
+            .checked_add(rhs.minor_units)
+            .map(|m| Self::create(self.currency, m))
+            .ok_or(Error::Money(format!("overflow adding {rhs} to {self}")))
+    }
+
+    /// Subtracts an amount of the same currency.
+    pub fn checked_sub(&self, rhs: &Self) -> Result<Self> {
+        self.check_currency(rhs.currency)?;
+        self.minor_units
+            .checked_sub(rhs.minor_units)
+            .map(|m| Self::create(self.currency, m))
+            .ok_or(Error::Money(format!(
+                "overflow subtracting {rhs} from {self}"
+            )))
+    }
+
+    /// Multiplies the amount, e.g. a denomination value by a number of bills.
+    pub fn checked_mul(&self, rhs: u32) -> Result<Self> {
+        self.minor_units
+            .checked_mul(rhs as i64)
+            .map(|m| Self::create(self.currency, m))
+            .ok_or(Error::Money(format!(
+                "overflow multiplying {self} by {rhs}"
+            )))
+    }
+
+    /// Compares two amounts of the same currency.
+    pub fn checked_cmp(&self, rhs: &Self) -> Result<cmp::Ordering> {
+        self.check_currency(rhs.currency)?;
+        Ok(self.minor_units.cmp(&rhs.minor_units))
+    }
+
+    /// Gets the amount in major units formatted with the exact number of decimal places.
+    pub fn amount_str(&self) -> String {
+        let exp = Exponent::from(self.currency).inner();
+        let sign = if self.is_negative() { "-" } else { "" };
+        let abs = self.minor_units.unsigned_abs() as u128;
+
+        if exp >= 0 {
+            format!("{sign}{}", abs * 10u128.pow(exp as u32))
+        } else {
+            let places = exp.unsigned_abs() as usize;
+            let div = 10u128.pow(places as u32);
+            format!("{sign}{}.{:0places$}", abs / div, abs % div)
+        }
+    }
+
+    fn check_currency(&self, currency: CurrencyCode) -> Result<()> {
+        if self.currency == currency {
+            Ok(())
+        } else {
+            Err(Error::Money(format!(
+                "currency mismatch, have: {}, expected: {}",
+                <&str>::from(currency),
+                <&str>::from(self.currency)
+            )))
+        }
+    }
+}
+
+impl fmt::Display for Money {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.amount_str(), <&str>::from(self.currency))
+    }
+}
+
+fn pow10(exp: u32) -> Option<i64> {
+    10i64.checked_pow(exp)
+}
+
+fn overflow(op: &str, val: i64) -> Error {
+    Error::Money(format!("overflow in {op}: {val}"))
+}
+
+// Converts an amount from one exponent to another, failing on overflow or loss of precision.
+fn rescale(val: i64, from: i32, to: i32) -> Result<i64> {
+    match from.cmp(&to) {
+        cmp::Ordering::Equal => Ok(val),
+        cmp::Ordering::Greater => pow10(from.abs_diff(to))
+            .and_then(|p| val.checked_mul(p))
+            .ok_or(overflow("rescale", val)),
+        cmp::Ordering::Less => {
+            let p = pow10(from.abs_diff(to)).ok_or(overflow("rescale", val))?;
+            if val % p == 0 {
+                Ok(val / p)
+            } else {
+                Err(Error::Money(format!(
+                    "{val} cannot be expressed exactly with exponent {to}"
+                )))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn usd(minor_units: i64) -> Money {
+        Money::create(CurrencyCode::from("USD"), minor_units)
+    }
+
+    #[test]
+    fn test_money_arithmetic() -> Result<()> {
+        assert_eq!(usd(1050).checked_add(&usd(250))?, usd(1300));
+        assert_eq!(usd(1050).checked_sub(&usd(2000))?, usd(-950));
+        assert_eq!(usd(2000).checked_mul(3)?, usd(6000));
+        assert_eq!(usd(1).checked_cmp(&usd(2))?, cmp::Ordering::Less);
+
+        let eur = Money::create(CurrencyCode::from("EUR"), 100);
+        assert!(matches!(usd(100).checked_add(&eur), Err(Error::Money(_))));
+        assert!(usd(i64::MAX).checked_add(&usd(1)).is_err());
+        assert!(usd(i64::MIN).checked_sub(&usd(1)).is_err());
+
+        assert_eq!(Money::from_major(CurrencyCode::from("USD"), 20)?, usd(2000));
+        assert_eq!(format!("{}", usd(1050)), "10.50 USD");
+        assert_eq!(format!("{}", usd(-5)), "-0.05 USD");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_money_mdu() -> Result<()> {
+        let code = CurrencyCode::from("USD");
+
+        assert_eq!(usd(2000).to_mdu(Exponent::create(-2))?, 2000);
+        assert_eq!(usd(2000).to_mdu(Exponent::create(0))?, 20);
+        assert_eq!(usd(2000).to_currency_mdu(&Currency::from(code))?, 2000);
+        assert!(usd(2050).to_mdu(Exponent::create(0)).is_err());
+        assert!(usd(-1).to_mdu(Exponent::create(-2)).is_err());
+        assert!(usd(u32::MAX as i64 + 1)
+            .to_mdu(Exponent::create(-2))
+            .is_err());
+
+        assert_eq!(Money::from_mdu(code, 20, Exponent::create(0))?, usd(2000));
+        assert_eq!(
+            Money::from_mdu(code, 2000, Exponent::create(-2))?,
+            usd(2000)
+        );
+        assert!(Money::from_mdu(code, 5, Exponent::create(-3)).is_err());
+
+        Ok(())
+    }
+}
@@ -1,6 +1,6 @@
 use std::fmt;
 
-use crate::{impl_xfs_string, impl_xfs_struct, ComponentType, ModuleType, Version};
+use crate::{impl_xfs_string, ComponentType, ModuleType, Version, XfsStruct};
 
 mod list;
 
@@ -41,7 +41,8 @@ impl fmt::Display for BillsetId {
 impl_xfs_string!(BillsetId, "billsetId");
 
 /// Represents billset information.
-#[derive(Clone, Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Eq, PartialEq, XfsStruct)]
+#[xfs(name = "billsetInfo", no_display)]
 pub struct BillsetInfo {
     billset_id: BillsetId,
     module_type: ModuleType,
@@ -141,14 +142,3 @@ impl Default for BillsetInfo {
         Self::new()
     }
 }
-
-impl_xfs_struct!(
-    BillsetInfo,
-    "billsetInfo",
-    [
-        billset_id: BillsetId,
-        module_type: ModuleType,
-        component_type: ComponentType,
-        version: Version
-    ]
-);
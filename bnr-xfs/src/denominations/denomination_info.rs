@@ -1,7 +1,6 @@
 use std::fmt;
 
-use crate::impl_xfs_struct;
-use crate::CashType;
+use crate::{CashType, XfsStruct};
 
 mod enable_denomination;
 mod security_level;
@@ -12,7 +11,8 @@ pub use security_level::*;
 pub use study_version::*;
 
 /// Denomination information for enabling and configuring BNR denominations.
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize, XfsStruct)]
+#[xfs(name = "denominationInfo", no_display)]
 pub struct DenominationInfo {
     cash_type: CashType,
     enable_denomination: EnableDenomination,
@@ -127,14 +127,3 @@ impl fmt::Display for DenominationInfo {
         write!(f, "}}")
     }
 }
-
-impl_xfs_struct!(
-    DenominationInfo,
-    "denominationInfo",
-    [
-        cash_type: CashType,
-        enable_denomination: EnableDenomination,
-        security_level: SecurityLevel,
-        study_version: StudyVersion
-    ]
-);
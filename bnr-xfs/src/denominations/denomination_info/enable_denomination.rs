@@ -3,7 +3,7 @@ use std::fmt;
 use crate::impl_xfs_bool;
 
 /// Represents whether to enable a denomination specified by a [DenominationInfo](super::DenominationInfo).
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
 pub struct EnableDenomination(bool);
 
 impl EnableDenomination {
@@ -3,7 +3,7 @@ use std::fmt;
 use crate::impl_xfs_i4;
 
 /// Represents the security level for acceptance of a denomination specified by a [DenominationInfo].
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
 pub struct SecurityLevel(u32);
 
 impl SecurityLevel {
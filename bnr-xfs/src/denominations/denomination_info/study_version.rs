@@ -3,7 +3,7 @@ use std::fmt;
 use crate::impl_xfs_string;
 
 /// Represents the study version of a denomination specified by a [DenominationInfo](super::DenominationInfo).
-#[derive(Clone, Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
 pub struct StudyVersion(String);
 
 impl StudyVersion {
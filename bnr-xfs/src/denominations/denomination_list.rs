@@ -1,84 +1,55 @@
-use std::{cmp, fmt};
+use std::fmt;
 
-use crate::impl_xfs_array;
 use crate::xfs::method_response::XfsMethodResponse;
+use crate::{BoundedList, XfsArray};
 use crate::{Error, Result};
 
 use super::DenominationInfo;
 
 pub const DENOMINATION_LIST_LEN: usize = 61;
 
-const DENOM_INIT: DenominationInfo = DenominationInfo::new();
-
 /// Represents a list of [DenominationInfo].
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, Default, PartialEq, serde::Deserialize, serde::Serialize, XfsArray)]
+#[xfs(name = "denominationList", no_display)]
 pub struct DenominationList {
-    size: usize,
-    items: [DenominationInfo; DENOMINATION_LIST_LEN],
+    items: BoundedList<DenominationInfo, DENOMINATION_LIST_LEN>,
 }
 
 impl DenominationList {
     /// Creates a new [DenominationList].
     pub const fn new() -> Self {
         Self {
-            size: 0,
-            items: [DENOM_INIT; DENOMINATION_LIST_LEN],
+            items: BoundedList::new(),
         }
     }
 
-    /// Gets a reference to the populated [DenominationInfo] items.
-    pub fn items(&self) -> &[DenominationInfo] {
-        let len = cmp::min(self.size, DENOMINATION_LIST_LEN);
-        &self.items[..len]
-    }
-
-    /// Gets a mutable reference to the populated [DenominationInfo] items.
-    pub fn items_mut(&mut self) -> &mut [DenominationInfo] {
-        let len = cmp::min(self.size, DENOMINATION_LIST_LEN);
-        &mut self.items[..len]
-    }
-
     /// Adds a [DenominationInfo] item to the list.
     ///
-    /// **NOTE**: If the [DenominationList] is at capacity, no item will be pushed.
-    pub fn push(&mut self, item: DenominationInfo) {
-        if self.size < DENOMINATION_LIST_LEN {
-            self.items[self.size] = item;
-            self.size += 1;
-        }
+    /// Returns an error if the [DenominationList] is at capacity.
+    pub fn push(&mut self, item: DenominationInfo) -> Result<()> {
+        self.push_item(item)
     }
 
     /// Pops a [DenominationInfo] from the back of the list.
     ///
     /// **NOTE**: Returns `None` if no items remain in the list.
     pub fn pop(&mut self) -> Option<DenominationInfo> {
-        if self.size > 0 {
-            self.size -= 1;
-            Some(self.items[self.size].clone())
-        } else {
-            None
-        }
-    }
-
-    /// Gets the maximum number of items.
-    pub const fn max_size() -> usize {
-        DENOMINATION_LIST_LEN
+        self.pop_item()
     }
 
     /// Gets a the size of populated [DenominationInfo] items.
-    pub const fn size(&self) -> usize {
-        self.size
+    pub fn size(&self) -> usize {
+        self.items.len()
     }
 
     /// Sets a the size of populated [DenominationInfo] items.
+    ///
+    /// New items are set to the default value, no-op if `size` is larger than the max size.
     pub fn set_size(&mut self, size: u32) {
-        let len = cmp::min(size as usize, DENOMINATION_LIST_LEN);
-        self.size = len;
+        self.items.resize(size as usize);
     }
 }
 
-impl_xfs_array!(DenominationList, "denominationList");
-
 impl TryFrom<&XfsMethodResponse> for DenominationList {
     type Error = Error;
 
@@ -108,7 +79,7 @@ impl fmt::Display for DenominationList {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{{")?;
         write!(f, r#""items": ["#)?;
-        for (i, item) in self.items().iter().enumerate() {
+        for (i, item) in self.iter().enumerate() {
             if i != 0 {
                 write!(f, ",")?;
             }
@@ -1,23 +1,39 @@
+use std::path::Path;
 use std::sync::{
     atomic::{AtomicBool, Ordering},
-    mpsc, Arc,
+    mpsc, Arc, Mutex,
 };
+use std::time::Duration;
 
 use time as datetime;
 
 use crate::capabilities::Capabilities;
-use crate::cash_unit::{CashUnit, LogicalCashUnitList, PhysicalCashUnitList};
-use crate::currency::{CashOrder, CurrencyCode};
+use crate::cash_in::{CashInResult, CashInTransaction};
+use crate::cash_unit::{CashUnit, CashUnitLayout, LogicalCashUnitList, PhysicalCashUnitList};
+use crate::collection::{CollectionReport, PendingCollection};
+use crate::config::{ConfigChange, DeviceConfig};
+use crate::correction::{CorrectionRecord, CountCorrection};
+use crate::currency::{CashOrder, Currency, CurrencyCode, MixNumber, Money};
 use crate::denominations::BillsetIdList;
 use crate::denominations::DenominationList;
-use crate::dispense::DispenseRequest;
+use crate::dispense::{
+    DenominateResult, DispensePlan, DispensePlanner, DispenseRequest, MixTable, PresentOutcome,
+    PresentReport,
+};
+use crate::health::HealthReport;
 use crate::history::{
     BillAcceptanceHistory, BillDispenseHistory, SystemFailureHistory, SystemRestartHistory,
     SystemUseHistory,
 };
+use crate::journal::{Journal, RecoveryReport};
+use crate::payment::{PaymentOptions, PaymentReceipt};
+use crate::refill::{LoadedNotes, RefillReconciliation, RefillRecord};
+use crate::resync::ResyncReport;
 use crate::status::CdrStatus;
 use crate::xfs;
-use crate::{Error, Result};
+use crate::{
+    CallbackArg, Error, IntermediateOccurredFn, OperationCompletedFn, Result, StatusOccurredFn,
+};
 
 mod inner;
 pub mod usb;
@@ -44,78 +60,6 @@ pub const BNR_CALLBACK_CALL_EP: u8 = (1 << 7) | 3;
 /// BNR USB endpoint for host-to-device asynchronous callback responses.
 pub const BNR_CALLBACK_RESPONSE_EP: u8 = 4;
 
-/// Trait for arguments to state change callbacks used by the XFS API.
-pub trait CallbackArg {
-    fn value(&self) -> i32;
-    fn is_null(&self) -> bool;
-    fn is_cash_order(&self) -> bool;
-    fn as_cash_order(&self) -> Result<&CashOrder>;
-    fn as_cash_order_mut(&mut self) -> Result<&mut CashOrder>;
-}
-
-impl CallbackArg for () {
-    fn value(&self) -> i32 {
-        0
-    }
-
-    fn is_null(&self) -> bool {
-        true
-    }
-
-    fn is_cash_order(&self) -> bool {
-        false
-    }
-
-    fn as_cash_order(&self) -> Result<&CashOrder> {
-        Err(Error::Xfs(
-            "Expected CashOrder CallbackArg, have: null".into(),
-        ))
-    }
-
-    fn as_cash_order_mut(&mut self) -> Result<&mut CashOrder> {
-        Err(Error::Xfs(
-            "Expected CashOrder CallbackArg, have: null".into(),
-        ))
-    }
-}
-
-/// Function signature for the `Operation Completed` callback used by the XFS API.
-///
-/// Handles device-sent messages indicating an asynchronous operation has completed.
-///
-/// # Parameters
-///
-/// - `call_id`: callback ID returned by the initial async call
-/// - `operation_id`: async operation ID to uniquely identify the type of call
-/// - `result`: the result status of the call
-/// - `extended_result`: the extended result of the call
-/// - `callback_arg`: callback call argument (may be the `unit` type if not supplied)
-pub type OperationCompletedFn = fn(i32, i32, i32, i32, &mut dyn CallbackArg);
-
-/// Function signature for the `Intermediate Occurred` callback used by the XFS API.
-///
-/// Handles an intermediate state transition occurred during an ongoing transaction.
-///
-/// # Parameters
-///
-/// - `call_id`: callback ID returned by the initial async call
-/// - `operation_id`: async operation ID to uniquely identify the type of call
-/// - `reason`: specifies the reason for the intermediate event
-/// - `callback_arg`: callback call argument (may be the `unit` type if not supplied)
-pub type IntermediateOccurredFn = fn(i32, i32, i32, &mut dyn CallbackArg);
-
-/// Function signature for the `Status Occured` callback used by the XFS API.
-///
-/// Handles a status event that occurred on the device.
-///
-/// # Parameters
-///
-/// - `status`: the status that occurred on the device (e.g. bill inserted)
-/// - `result`: the result of the status event
-/// - `extended_result`: the extended result of the status event
-/// - `callback_arg`: callback call argument (may be the `unit` type if not supplied)
-pub type StatusOccurredFn = fn(i32, i32, i32, &mut dyn CallbackArg);
-
 /// BNR XFS device handle for communication over USB.
 pub struct DeviceHandle {
     usb: Arc<UsbDeviceHandle>,
@@ -124,6 +68,11 @@ pub struct DeviceHandle {
     intermediate_occurred_callback: Option<IntermediateOccurredFn>,
     status_occurred_callback: Option<StatusOccurredFn>,
     response_rx: mpsc::Receiver<xfs::method_call::XfsMethodCall>,
+    early_responses: Mutex<Vec<xfs::method_call::XfsMethodCall>>,
+    resync_report: ResyncReport,
+    cash_in: Arc<Mutex<Option<CashInTransaction>>>,
+    journal: Arc<Mutex<Option<Journal>>>,
+    recovery_report: RecoveryReport,
 }
 
 impl DeviceHandle {
@@ -172,20 +121,79 @@ impl DeviceHandle {
         )
     }
 
+    /// Opens a new connection to the BNR XFS device, with the [Journal] at `path` enabled.
+    ///
+    /// See [open](Self::open) for the callback parameters, and
+    /// [enable_journal](Self::enable_journal) for details on the journal.
+    pub fn open_with_journal<P: AsRef<Path>>(
+        path: P,
+        op_completed_callback: Option<OperationCompletedFn>,
+        intermediate_occurred_callback: Option<IntermediateOccurredFn>,
+        status_occurred_callback: Option<StatusOccurredFn>,
+    ) -> Result<Self> {
+        let mut handle = Self::open(
+            op_completed_callback,
+            intermediate_occurred_callback,
+            status_occurred_callback,
+        )?;
+
+        handle.enable_journal(path)?;
+
+        Ok(handle)
+    }
+
     /// Reconnects to the BNR XFS device
+    ///
+    /// If the [Journal] is enabled, its unfinished entries are reconciled again, see
+    /// [recovery_report](Self::recovery_report).
     pub fn reconnect(&mut self) -> Result<()> {
         self.stop_background_listener();
         self.usb = Arc::new(UsbDeviceHandle::find_usb()?);
         self.stop_listener.store(false, Ordering::SeqCst);
+        self.resync_report = self.resync_inner()?;
         let (response_tx, response_rx) = mpsc::channel();
 
         self.start_background_listener(response_tx, Arc::clone(&self.stop_listener))?;
 
         self.response_rx = response_rx;
+        self.early_responses.lock()?.clear();
+
+        self.recovery_report = self.recover_journal_inner()?;
 
         Ok(())
     }
 
+    /// Enables the append-only [Journal] of money-moving calls, stored in the file at `path`.
+    ///
+    /// Every `CashIn*`, `Dispense`, `Present`, `Retract` and `Empty` call is recorded before it
+    /// is sent, with its call ID and completion. Each record is synced to disk.
+    ///
+    /// Unfinished entries from a previous session are reconciled with the operations drained when
+    /// the connection was opened, the cash unit counts and the device status. Operations are only
+    /// matched by call ID if the device restart count, see [get_restart_history](Self::get_restart_history),
+    /// did not change since the entry was journaled. Should be called
+    /// right after [open](Self::open), before any other cash operation.
+    ///
+    /// Returns the [RecoveryReport] describing what actually happened to the unfinished calls.
+    pub fn enable_journal<P: AsRef<Path>>(&mut self, path: P) -> Result<RecoveryReport> {
+        self.enable_journal_inner(path.as_ref())
+    }
+
+    /// Gets the [RecoveryReport] from when the [Journal] was last enabled, or the connection
+    /// reconnected.
+    pub fn recovery_report(&self) -> &RecoveryReport {
+        &self.recovery_report
+    }
+
+    /// Gets the [ResyncReport] describing leftover state found on the device when the connection
+    /// was last opened, or reconnected.
+    ///
+    /// Callers should check [ResyncReport::pending_operation] and [ResyncReport::cash_presented]
+    /// before starting new cash operations, e.g. after recovering from a crash mid-dispense.
+    pub fn resync_report(&self) -> &ResyncReport {
+        &self.resync_report
+    }
+
     /// Resets the BNR device.
     pub fn reset(&self) -> Result<()> {
         self.reset_inner()
@@ -281,16 +289,45 @@ impl DeviceHandle {
     /// NULL or the string is empty, any currency will be accepted by the BNR.
     /// ```
     pub fn cash_in(&self, limit: Option<u32>, currency: Option<CurrencyCode>) -> Result<()> {
-        self.cash_in_inner(limit, currency)
+        self.cash_in_inner(limit, currency).map(|_| ())
+    }
+
+    /// Sends the follow-up message to start a `CashIn` transaction for an amount of [Money].
+    ///
+    /// The amount is converted to MDU, and only bills of the [Money] currency are accepted.
+    ///
+    /// params:
+    ///
+    /// - `amount`: optional amount to accept.
+    ///   - `None` will tell the device to accept one note of any currency.
+    ///   - a zero amount will accept notes until the escrow is full, or the operation is cancelled.
+    pub fn cash_in_amount(&self, amount: Option<Money>) -> Result<()> {
+        match amount {
+            Some(amount) => {
+                let currency = Currency::from(amount.currency());
+                let mdu = amount.to_currency_mdu(&currency)?;
+                self.cash_in_inner(Some(mdu), Some(amount.currency()))
+            }
+            None => self.cash_in_inner(None, None),
+        }
+        .map(|_| ())
     }
 
     /// Sends the message to end a `CashIn` transaction.
     ///
     /// The caller will need to call [cash_in_start](Self::cash_in_start) and [cash_in](Self::cash_in) to begin accepting notes again.
-    pub fn cash_in_end(&self) -> Result<()> {
+    ///
+    /// Returns the [CashInResult] of the transaction, built from the `CashIn` operation complete
+    /// events and the cash unit counts since [cash_in_start](Self::cash_in_start).
+    pub fn cash_in_end(&self) -> Result<CashInResult> {
         self.cash_in_end_inner()
     }
 
+    /// Gets a snapshot of the ongoing [CashInTransaction], if any.
+    pub fn cash_in_transaction(&self) -> Option<CashInTransaction> {
+        self.cash_in.lock().ok().and_then(|t| t.clone())
+    }
+
     /// Sends the message to rollback a `CashIn` transaction, returning any inserted notes to the
     /// customer.
     ///
@@ -314,7 +351,7 @@ impl DeviceHandle {
     /// - `pcu_name`: Name of the physical cash unit to empty.
     /// - `to_float` If `true`, the command empties up to the low threshold of the Physical Cash Unit, otherwise to zero.
     pub fn empty(&self, pcu_name: &str, to_float: bool) -> Result<()> {
-        self.empty_inner(pcu_name, to_float)
+        self.empty_inner(pcu_name, to_float).map(|_| ())
     }
 
     /// Activates the presentation of the cash.
@@ -327,7 +364,27 @@ impl DeviceHandle {
     /// After #XFS_S_CDR_CASH_AVAILABLE status event, if no #XFS_S_CDR_CASH_TAKEN status event is received within a reasonable time period,
     /// the application should send a [cancel_waiting_cash_taken] to terminate the command, then send a [retract] to clear the bills from the outlet.
     pub fn present(&self) -> Result<()> {
-        self.present_inner()
+        self.present_inner().map(|_| ())
+    }
+
+    /// Presents the cash, and supervises its removal.
+    ///
+    /// Waits up to `timeout` for the customer to take the bills, i.e. for the `Present` operation
+    /// to complete after the #XFS_S_CDR_CASH_TAKEN status event. If the bills are not taken in
+    /// time, sends [cancel_waiting_cash_taken](Self::cancel_waiting_cash_taken), then
+    /// [retract](Self::retract) to clear the bills from the outlet.
+    ///
+    /// Returns the [PresentReport] with the [PresentOutcome], and the number of bills retracted to
+    /// the bundler logical cash unit. Once the `Present` is sent, a failure to wait, cancel or
+    /// retract gives a [PresentOutcome::Unknown] report, rather than an error.
+    ///
+    /// Accounting of the retracted bills: the device adds them to the bundler logical cash unit
+    /// count itself, and the [PresentReport] gives the count delta. With the [Journal] enabled,
+    /// the retract is journaled with the cash-taken timeout as its detail. Clearing the bundler,
+    /// e.g. with another [present](Self::present), and booking the retracted amount against the
+    /// dispense, are left to the caller.
+    pub fn present_supervised(&self, timeout: Duration) -> Result<PresentReport> {
+        self.present_supervised_inner(timeout)
     }
 
     /// Asks the BNR to stop waiting for cash removal at the Bezel if any.
@@ -355,7 +412,7 @@ impl DeviceHandle {
     /// the preceding command must be terminated by calling
     /// [cancel_waiting_cash_taken](Self::cancel_waiting_cash_taken).
     pub fn retract(&self) -> Result<()> {
-        self.retract_inner()
+        self.retract_inner("").map(|_| ())
     }
 
     /// Gets the complete state of all physical and logical cash units in the BNR.
@@ -403,6 +460,19 @@ impl DeviceHandle {
         self.update_cash_unit_inner(transport_count, lcu_list, pcu_list)
     }
 
+    /// Applies a declarative [CashUnitLayout] profile to the BNR’s cash unit.
+    ///
+    /// The profile is validated against the current [CashUnit] before anything is sent to the
+    /// device, see [CashUnitLayout::apply].
+    ///
+    /// Returns the resulting [LogicalCashUnitList] and [PhysicalCashUnitList].
+    pub fn apply_cash_unit_layout(
+        &self,
+        layout: &CashUnitLayout,
+    ) -> Result<(LogicalCashUnitList, PhysicalCashUnitList)> {
+        self.apply_cash_unit_layout_inner(layout)
+    }
+
     /// BNR_CASH_OPERATIONS Determines if the amount requested by value or by bill list, is available for dispense.
     ///
     /// From the MEI/CPI documentation:
@@ -420,7 +490,10 @@ impl DeviceHandle {
     ///   - for each item of denominateRequest->denomination.items from 0 to (denominateRequest->denomination.size - 1):
     ///     - denominateRequest->denomination.items[item].unit contains the number of a LCU from where banknotes must be distributed.
     ///     - denominateRequest->denomination.items[item].count gives the number of notes to distribute from the LCU.
-    pub fn denominate(&self, request: &DispenseRequest) -> Result<()> {
+    ///
+    /// Returns the [DenominateResult] with the bills the BNR would dispense, taken from the
+    /// operation complete callback.
+    pub fn denominate(&self, request: &DispenseRequest) -> Result<DenominateResult> {
         self.denominate_inner(request)
     }
 
@@ -459,6 +532,171 @@ impl DeviceHandle {
         self.dispense_inner(request)
     }
 
+    /// Dispenses an amount of [Money] using the [MixNumber::MinBills] algorithm.
+    ///
+    /// See [dispense](Self::dispense) for details, and [DispenseRequest::create_amount] for other
+    /// mix algorithms.
+    pub fn dispense_amount(&self, amount: &Money) -> Result<()> {
+        self.dispense_inner(&DispenseRequest::create_amount(
+            MixNumber::MinBills,
+            amount,
+        )?)
+    }
+
+    /// Plans the dispense of an amount of [Money] without moving any bills.
+    ///
+    /// Queries the current [CashUnit] counts and [Capabilities], and previews the bills the
+    /// `mix_number` algorithm dispenses. See [DispensePlanner] for details.
+    ///
+    /// The [DispensePlan::request] can be passed to [dispense](Self::dispense).
+    pub fn plan_dispense(&self, amount: &Money, mix_number: MixNumber) -> Result<DispensePlan> {
+        let caps = self.get_capabilities()?;
+        let cash_unit = self.query_cash_unit()?;
+
+        DispensePlanner::create(&cash_unit)
+            .with_capabilities(&caps)
+            .plan(amount, mix_number)
+    }
+
+    /// Plans the dispense of an amount of [Money] with a user-defined [MixTable].
+    ///
+    /// See [DispensePlanner::plan_table] for details.
+    pub fn plan_table(&self, amount: &Money, table: &MixTable) -> Result<DispensePlan> {
+        let caps = self.get_capabilities()?;
+        let cash_unit = self.query_cash_unit()?;
+
+        DispensePlanner::create(&cash_unit)
+            .with_capabilities(&caps)
+            .plan_table(amount, table)
+    }
+
+    /// Dispenses an amount of [Money] with a user-defined [MixTable].
+    ///
+    /// The table is resolved against the current stock into a [MixNumber::Denom] request, see
+    /// [plan_table](Self::plan_table), and sent with [dispense](Self::dispense).
+    ///
+    /// Returns the [DispensePlan] sent to the device.
+    pub fn dispense_table(&self, amount: &Money, table: &MixTable) -> Result<DispensePlan> {
+        let plan = self.plan_table(amount, table)?;
+        self.dispense_inner(plan.request())?;
+        Ok(plan)
+    }
+
+    /// Collects a payment of `price`, and gives the change.
+    ///
+    /// Runs the vending flow on top of [cash_in](Self::cash_in), [cash_in_end](Self::cash_in_end),
+    /// [denominate](Self::denominate), [dispense](Self::dispense) and
+    /// [present_supervised](Self::present_supervised):
+    ///
+    /// - checks up front that the change can be made for the price, see [ChangeGuard](crate::ChangeGuard)
+    /// - accepts one note at a time until the price is covered, and returns the notes in escrow
+    ///   when a note makes the change impossible
+    /// - returns the notes if the customer cancels, see [PaymentOptions::cancel_flag], or does not
+    ///   insert a note in time
+    /// - stores the notes, then dispenses and presents the change, retracting it if not taken
+    ///
+    /// If the change cannot be dispensed after the notes are stored, the receipt has the
+    /// [PaymentStatus::ChangeFailed](crate::PaymentStatus::ChangeFailed) status, and records the
+    /// amount still owed, see [PaymentReceipt::owed](crate::PaymentReceipt::owed). If the change
+    /// was dispensed, but its presentation was not confirmed, the receipt has the
+    /// [PaymentStatus::ChangeUnconfirmed](crate::PaymentStatus::ChangeUnconfirmed) status.
+    ///
+    /// Returns the [PaymentReceipt], or an error if the change cannot be made for the price, or
+    /// the device fails before the notes are stored.
+    pub fn collect_payment(
+        &self,
+        price: &Money,
+        options: &PaymentOptions,
+    ) -> Result<PaymentReceipt> {
+        crate::payment::collect_payment(self, price, options)
+    }
+
+    /// Starts an end-of-day cashbox collection.
+    ///
+    /// Empties every recycler to its float with [empty](Self::empty), waiting for each operation
+    /// to complete, then [parks](Self::park) the modules, and records the cashbox count before
+    /// removal.
+    ///
+    /// If a recycler cannot be emptied, the modules are not parked, and the partial
+    /// [PendingCollection] is returned with the error, see [PendingCollection::error].
+    ///
+    /// Once the operator has emptied and reinserted the cashbox, call
+    /// [finish_collection](Self::finish_collection) with the returned [PendingCollection].
+    pub fn start_collection(&self) -> Result<PendingCollection> {
+        crate::collection::start_collection(self)
+    }
+
+    /// Finishes an end-of-day cashbox collection after the emptied cashbox is reinserted.
+    ///
+    /// Resets the cashbox counts with [update_cash_unit](Self::update_cash_unit), and returns the
+    /// [CollectionReport] signed with the HMAC `key`, see [CollectionReport::verify].
+    ///
+    /// The device stays parked, use [reset](Self::reset) to resume operation.
+    pub fn finish_collection(
+        &self,
+        pending: PendingCollection,
+        key: &[u8],
+    ) -> Result<CollectionReport> {
+        crate::collection::finish_collection(self, pending, key)
+    }
+
+    /// Records a loader refill.
+    ///
+    /// Adds the `loaded` bills, stacked in the loader by the operator, to the loader counts with
+    /// [update_cash_unit](Self::update_cash_unit), and sets the loader initial counts to the new
+    /// counts, see [loader_refill_lists](crate::loader_refill_lists).
+    ///
+    /// Returns the [RefillRecord], with the [LoaderAcceptanceHistory](crate::LoaderAcceptanceHistory)
+    /// baseline for [reconcile_refill](Self::reconcile_refill).
+    ///
+    /// **Note** The BNR protocol has no call to transfer bills from the loader to the recyclers:
+    /// loader bills stay in the loader until dispensed, or emptied to the cashbox with
+    /// [empty](Self::empty).
+    pub fn refill_loader(&self, loaded: &[LoadedNotes]) -> Result<RefillRecord> {
+        crate::refill::refill_loader(self, loaded)
+    }
+
+    /// Reconciles the loader count with the bills extracted from the loader since the refill,
+    /// according to the [LoaderAcceptanceHistory](crate::LoaderAcceptanceHistory).
+    pub fn reconcile_refill(&self, record: &RefillRecord) -> Result<RefillReconciliation> {
+        crate::refill::reconcile_refill(self, record)
+    }
+
+    /// Corrects the cash unit counts with physically counted values, e.g. after a jam is cleared
+    /// by hand.
+    ///
+    /// Computes the [CountAdjustment](crate::CountAdjustment)s against the
+    /// [query_cash_unit](Self::query_cash_unit) counts, and applies them with
+    /// [update_cash_unit](Self::update_cash_unit). Only the cashbox and loader counts are
+    /// writable: differences on bundler and recycler units are logged, and left unchanged.
+    ///
+    /// Every adjustment is logged with the operator and the reason of the [CountCorrection].
+    pub fn correct_counts(&self, correction: &CountCorrection) -> Result<CorrectionRecord> {
+        crate::correction::correct_counts(self, correction)
+    }
+
+    /// Evaluates the overall device health.
+    ///
+    /// Combines [get_status](Self::get_status), [query_cash_unit](Self::query_cash_unit) and the
+    /// `last_error` observed by the caller into a single [HealthStatus](crate::HealthStatus)
+    /// verdict, with machine-readable [HealthReason](crate::HealthReason)s.
+    ///
+    /// If the device status cannot be read, the device is reported out of service.
+    pub fn evaluate_health(&self, last_error: Option<&Error>) -> HealthReport {
+        self.evaluate_health_inner(last_error)
+    }
+
+    /// Determines if an amount of [Money] is available for dispense using the
+    /// [MixNumber::MinBills] algorithm.
+    ///
+    /// See [denominate](Self::denominate) for details.
+    pub fn denominate_amount(&self, amount: &Money) -> Result<DenominateResult> {
+        self.denominate_inner(&DispenseRequest::create_amount(
+            MixNumber::MinBills,
+            amount,
+        )?)
+    }
+
     /// Stops any active sessions on the BNR device.
     pub fn stop_session(&self) -> Result<()> {
         self.stop_session_inner()
@@ -507,6 +745,33 @@ impl DeviceHandle {
         self.query_billset_ids_inner()
     }
 
+    /// Takes a [DeviceConfig] snapshot of the persistent device configuration.
+    ///
+    /// Includes the writable [Capabilities], the [DenominationList] settings, and the cash unit
+    /// configuration with thresholds.
+    pub fn backup_config(&self) -> Result<DeviceConfig> {
+        self.backup_config_inner()
+    }
+
+    /// Restores a [DeviceConfig] snapshot onto the device.
+    ///
+    /// Uses [set_capabilities](Self::set_capabilities), [update_denominations](Self::update_denominations),
+    /// and [configure_cash_unit](Self::configure_cash_unit) to apply the configuration sections with changes.
+    ///
+    /// Params:
+    ///
+    /// - `config`: the configuration to restore.
+    /// - `dry_run`: only compute the changes, without writing anything to the device.
+    ///
+    /// Returns the list of [ConfigChange]s from the current device configuration.
+    pub fn restore_config(
+        &self,
+        config: &DeviceConfig,
+        dry_run: bool,
+    ) -> Result<Vec<ConfigChange>> {
+        self.restore_config_inner(config, dry_run)
+    }
+
     /// Gets the BNR [BillAcceptanceHistory].
     pub fn get_bill_acceptance_history(&self) -> Result<BillAcceptanceHistory> {
         self.get_bill_acceptance_history_inner()
@@ -1,22 +1,28 @@
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::{mpsc, Arc};
+use std::time::Instant;
 
 use base64::Engine;
 use datetime::format_description::well_known::iso8601::{Config, TimePrecision};
 use datetime::format_description::well_known::Iso8601;
 use time as datetime;
 
-use super::usb::UsbDeviceHandle;
+use super::usb::{UsbDeviceHandle, DRAIN_TIMEOUT};
 use super::*;
 use crate::currency::{Currency, Denomination};
 use crate::xfs::method_call::{XfsMethodCall, XfsMethodName};
 use crate::xfs::method_response::XfsMethodResponse;
 use crate::xfs::params::{XfsParam, XfsParams};
 use crate::xfs::value::XfsValue;
-use crate::TransportCount;
+use crate::xfs::xfs_struct::XfsStruct;
+use crate::xfs::OperationId;
 use crate::{CallbackIntermediateResponse, CallbackOperationResponse, CallbackStatusResponse};
+use crate::{IntermediateEvent, TransportCount, PRESENT_OPERATION_TIMEOUT};
+use crate::{Journal, JournalOperation, RecoveryOutcome, RecoveryReport};
 
 const INIT_COUNT: u64 = 1;
+// Maximum number of completions kept for other calls while waiting for an async call.
+const MAX_EARLY_RESPONSES: usize = 16;
 static CALL_COUNTER: AtomicU64 = AtomicU64::new(INIT_COUNT);
 
 /// Class identifier of the `MainModule`.
@@ -52,6 +58,30 @@ pub(crate) fn reset_call_counter() {
     CALL_COUNTER.store(INIT_COUNT, Ordering::SeqCst)
 }
 
+/// Creates the response to a device-sent callback call.
+///
+/// Returns `None` if the call is not a callback call.
+pub(crate) fn callback_response(msg: &XfsMethodCall) -> Option<XfsMethodResponse> {
+    let res_id = msg.call_id().unwrap_or(-1);
+    let op_id: i32 = msg.operation_id().unwrap_or_default().into();
+    let result = msg.result().unwrap_or(0);
+
+    let response: XfsStruct = match msg.name().ok()? {
+        XfsMethodName::OperationCompleteOccurred => {
+            CallbackOperationResponse::create(op_id, res_id).into()
+        }
+        XfsMethodName::IntermediateOccurred => {
+            CallbackIntermediateResponse::create(op_id, res_id).into()
+        }
+        XfsMethodName::StatusOccurred => CallbackStatusResponse::create(res_id, result).into(),
+        _ => return None,
+    };
+
+    Some(XfsMethodResponse::new_params([XfsParam::create(
+        XfsValue::new().with_xfs_struct(response),
+    )]))
+}
+
 impl DeviceHandle {
     pub(crate) fn open_inner(
         usb: UsbDeviceHandle,
@@ -61,18 +91,65 @@ impl DeviceHandle {
     ) -> Result<Self> {
         let (response_tx, response_rx) = mpsc::channel();
 
-        let ret = Self {
+        let mut ret = Self {
             usb: Arc::new(usb),
             stop_listener: Arc::new(AtomicBool::new(false)),
             op_completed_callback,
             intermediate_occurred_callback,
             status_occurred_callback,
             response_rx,
+            early_responses: Mutex::new(Vec::new()),
+            resync_report: ResyncReport::new(),
+            cash_in: Arc::new(Mutex::new(None)),
+            journal: Arc::new(Mutex::new(None)),
+            recovery_report: RecoveryReport::new(),
         };
 
+        // resynchronise before the listener starts reading from the callback endpoint
+        ret.resync_report = ret.resync_inner()?;
+
         ret.start_background_listener(response_tx, Arc::clone(&ret.stop_listener))?;
 
-        let usb = ret.usb();
+        Ok(ret)
+    }
+
+    /// Drains leftover messages from a previous session, and sends the `getIdentification` call
+    /// to start the new session.
+    ///
+    /// **NOTE** must be called before the background listener is started.
+    pub(crate) fn resync_inner(&self) -> Result<ResyncReport> {
+        let usb = self.usb();
+        let mut report = ResyncReport::new();
+
+        for msg in usb.drain(BNR_RESPONSE_EP, DRAIN_TIMEOUT) {
+            log::debug!(
+                "Discarding stale response: {}",
+                String::from_utf8_lossy(msg.as_ref())
+            );
+            report.discard_response();
+        }
+
+        for msg in usb.drain(BNR_CALLBACK_CALL_EP, DRAIN_TIMEOUT) {
+            log::debug!(
+                "Discarding stale callback call: {}",
+                String::from_utf8_lossy(msg.as_ref())
+            );
+
+            match xfs::from_bytes::<XfsMethodCall>(msg.as_ref()) {
+                Ok(call) => {
+                    report.discard_callback(&call);
+
+                    // acknowledge the callback, so the device does not wait on a response
+                    if let (Ok(name), Some(res)) = (call.name(), callback_response(&call)) {
+                        usb.write_callback_response(&res, name).ok();
+                    }
+                }
+                Err(err) => {
+                    log::warn!("Error parsing stale callback call: {err}");
+                    report.discard_callback(&XfsMethodCall::new());
+                }
+            }
+        }
 
         let call = XfsMethodCall::create(
             XfsMethodName::GetIdentification,
@@ -87,7 +164,39 @@ impl DeviceHandle {
         // read the response
         usb.read_response(call.name()?).ok();
 
-        Ok(ret)
+        // responses are sent in call order, so anything following the first response means it
+        // answered a call from the previous session
+        for msg in usb.drain(BNR_RESPONSE_EP, DRAIN_TIMEOUT) {
+            log::debug!(
+                "Discarding out-of-order response: {}",
+                String::from_utf8_lossy(msg.as_ref())
+            );
+            report.discard_response();
+        }
+
+        // an interrupted operation does not always leave callbacks behind, so check the device
+        match self.get_status_inner() {
+            Ok(status) => report.check_status(&status),
+            Err(err) => log::warn!("Error getting status during resync: {err}"),
+        }
+
+        if let Some(op) = report.pending_operation() {
+            log::warn!("Operation still running from a previous session: {op}");
+        }
+
+        if report.cash_presented() {
+            log::warn!("Cash left presented at the outlet from a previous session");
+        }
+
+        if report.stacker_not_empty() {
+            log::warn!("Bills left in the intermediate stacker from a previous session");
+        }
+
+        if !report.is_clean() {
+            log::info!("Resynchronised with BNR device: {report}");
+        }
+
+        Ok(report)
     }
 
     pub(crate) fn start_background_listener(
@@ -107,6 +216,9 @@ impl DeviceHandle {
             .status_occurred_callback()
             .unwrap_or(STATUS_OCCURRED_FN_NOP);
 
+        let cash_in = Arc::clone(&self.cash_in);
+        let journal = Arc::clone(&self.journal);
+
         std::thread::spawn(move || -> Result<()> {
             while !stop.load(Ordering::Relaxed) {
                 if let Ok(msg) = usb.read_callback_call() {
@@ -128,33 +240,49 @@ impl DeviceHandle {
                     let result = msg.result().unwrap_or(0);
                     let ext_result = msg.ext_result().unwrap_or(0);
                     log::trace!("Callback message name: {msg_name}");
-                    let xfs_res = match msg_name {
+                    let xfs_res = callback_response(&msg);
+                    match msg_name {
                         XfsMethodName::OperationCompleteOccurred => {
                             log::trace!("OperationComplete occurred: {msg}");
+                            if op_id == OperationId::CashIn.inner() as i32 && result == 0 {
+                                if let (Some(order), Ok(mut txn)) =
+                                    (callback_arg.as_ref(), cash_in.lock())
+                                {
+                                    if let Some(txn) = txn.as_mut() {
+                                        txn.push_order(order.clone());
+                                    }
+                                }
+                            }
+                            if let Ok(mut journal) = journal.lock() {
+                                if let Some(journal) = journal.as_mut() {
+                                    if let Err(err) = journal.complete_call(res_id, result) {
+                                        log::error!("Error recording operation complete: {err}");
+                                    }
+                                }
+                            }
                             if let Some(cash_order) = callback_arg.as_mut() {
                                 op_complete(res_id, op_id, result, ext_result, cash_order);
                             } else {
                                 op_complete(res_id, op_id, result, ext_result, &mut ());
                             }
                             response_tx.send(msg)?;
-
-                            let response = CallbackOperationResponse::create(op_id, res_id);
-                            Some(XfsMethodResponse::new_params([XfsParam::create(
-                                XfsValue::new().with_xfs_struct(response.into()),
-                            )]))
                         }
                         XfsMethodName::IntermediateOccurred => {
                             log::trace!("Intermediate occurred: {msg}");
+                            if IntermediateEvent::create(result as u32)
+                                == IntermediateEvent::InputRefused
+                            {
+                                if let Ok(mut txn) = cash_in.lock() {
+                                    if let Some(txn) = txn.as_mut() {
+                                        txn.add_refused();
+                                    }
+                                }
+                            }
                             if let Some(cash_order) = callback_arg.as_mut() {
                                 intermediate_occurred(res_id, op_id, result, cash_order);
                             } else {
                                 intermediate_occurred(res_id, op_id, result, &mut ());
                             }
-
-                            let response = CallbackIntermediateResponse::create(op_id, res_id);
-                            Some(XfsMethodResponse::new_params([XfsParam::create(
-                                XfsValue::new().with_xfs_struct(response.into()),
-                            )]))
                         }
                         XfsMethodName::StatusOccurred => {
                             log::trace!("Status occurred: {msg}");
@@ -163,13 +291,9 @@ impl DeviceHandle {
                             } else {
                                 status_occurred(res_id, op_id, result, &mut ());
                             }
-                            let response = CallbackStatusResponse::create(res_id, result);
-                            Some(XfsMethodResponse::new_params([XfsParam::create(
-                                XfsValue::new().with_xfs_struct(response.into()),
-                            )]))
                         }
-                        _ => None,
-                    };
+                        _ => (),
+                    }
                     if let Some(res) = xfs_res {
                         usb.write_callback_response(&res, msg_name)?;
                     }
@@ -187,24 +311,19 @@ impl DeviceHandle {
     }
 
     pub(crate) fn handle_async_call(&self, call_id: i32) -> Result<()> {
-        let mut res_call: Option<XfsMethodCall> = None;
-        let response_timeout = std::time::Duration::from_millis(4250);
-        let now = std::time::SystemTime::now();
+        self.handle_async_call_response(call_id).map(|_| ())
+    }
 
-        while res_call.is_none() && now.elapsed()? < response_timeout {
-            if let Ok(msg) = self.response_rx.try_recv() {
-                let res_id = msg.call_id().unwrap_or(-1);
-                if res_id != -1 && res_id == call_id {
-                    res_call = Some(msg);
-                }
-            }
-        }
+    /// Waits for the `OperationCompleteOccurred` callback of an async call, and returns it on
+    /// success.
+    pub(crate) fn handle_async_call_response(&self, call_id: i32) -> Result<XfsMethodCall> {
+        let response_timeout = std::time::Duration::from_millis(4250);
 
-        if let Some(msg) = res_call {
+        if let Some(msg) = self.wait_async_call(call_id, response_timeout)? {
             log::debug!("async response: {msg}");
             let result = msg.result().unwrap_or(-1);
             match result {
-                0 => Ok(()),
+                0 => Ok(msg),
                 -1 => {
                     let err_msg = format!("async response: missing event result: {msg}");
                     log::error!("{err_msg}");
@@ -223,6 +342,156 @@ impl DeviceHandle {
         }
     }
 
+    /// Waits up to `timeout` for the `OperationCompleteOccurred` callback of an async call.
+    ///
+    /// Returns `None` on timeout, the callback is returned regardless of the operation result.
+    ///
+    /// Completions of other calls received while waiting are kept for their own wait.
+    pub(crate) fn wait_async_call(
+        &self,
+        call_id: i32,
+        timeout: std::time::Duration,
+    ) -> Result<Option<XfsMethodCall>> {
+        {
+            let mut early = self.early_responses.lock()?;
+            if let Some(idx) = early
+                .iter()
+                .position(|m| m.call_id().is_ok_and(|id| id == call_id))
+            {
+                return Ok(Some(early.remove(idx)));
+            }
+        }
+
+        let deadline = Instant::now() + timeout;
+
+        loop {
+            let msg = match self
+                .response_rx
+                .recv_timeout(deadline.saturating_duration_since(Instant::now()))
+            {
+                Ok(msg) => msg,
+                Err(mpsc::RecvTimeoutError::Timeout) => return Ok(None),
+                Err(mpsc::RecvTimeoutError::Disconnected) => {
+                    return Err(Error::Xfs(
+                        "async response: background listener stopped".into(),
+                    ))
+                }
+            };
+
+            match msg.call_id() {
+                Ok(id) if id == call_id => return Ok(Some(msg)),
+                Ok(id) => {
+                    log::debug!("Keeping the completion of call ID {id}, waiting for {call_id}");
+                    let mut early = self.early_responses.lock()?;
+                    if early.len() >= MAX_EARLY_RESPONSES {
+                        let dropped = early.remove(0);
+                        log::warn!("Dropping an unclaimed completion: {dropped}");
+                    }
+                    early.push(msg);
+                }
+                Err(err) => log::warn!("Dropping a completion without a call ID: {err}"),
+            }
+        }
+    }
+
+    /// Opens the [Journal] at `path`, and reconciles its unfinished entries with the device state.
+    pub(crate) fn enable_journal_inner(&mut self, path: &Path) -> Result<RecoveryReport> {
+        *self.journal.lock()? = Some(Journal::open(path)?);
+        self.recovery_report = self.recover_journal_inner()?;
+
+        Ok(self.recovery_report.clone())
+    }
+
+    // Gets the device restart count: power ups and internal resets.
+    fn restart_count_inner(&self) -> Option<u32> {
+        match self.get_restart_history_inner() {
+            Ok(history) => Some(
+                history
+                    .power_up_count()
+                    .inner()
+                    .wrapping_add(history.internal_reset_count().inner()),
+            ),
+            Err(err) => {
+                log::warn!(
+                    "Error getting the device restart count, call IDs are not matched: {err}"
+                );
+                None
+            }
+        }
+    }
+
+    /// Reconciles the unfinished [Journal] entries with the device state found when the
+    /// connection was opened.
+    ///
+    /// Entries for operations still running on the device stay pending, and are completed by the
+    /// background listener.
+    pub(crate) fn recover_journal_inner(&self) -> Result<RecoveryReport> {
+        let pending = match self.journal.lock()?.as_ref() {
+            Some(journal) => journal.pending().to_vec(),
+            None => return Ok(RecoveryReport::new()),
+        };
+
+        let cash_unit = self.query_cash_unit_inner()?;
+        let status = self.get_status_inner()?;
+        let restarts = self.restart_count_inner();
+        let report =
+            RecoveryReport::reconcile(&pending, &self.resync_report, &cash_unit, &status, restarts);
+
+        if let Some(journal) = self.journal.lock()?.as_mut() {
+            for entry in report
+                .entries()
+                .iter()
+                .filter(|e| e.outcome() != RecoveryOutcome::Running)
+            {
+                journal.recover(entry.entry().seq(), entry.outcome())?;
+            }
+
+            // the calls journaled from now on belong to the current device session
+            journal.session(restarts)?;
+        }
+
+        if !report.is_clean() {
+            log::warn!("Recovered unfinished journal entries: {report}");
+        }
+
+        Ok(report)
+    }
+
+    /// Records a money-moving call in the [Journal], with the current cash unit counts.
+    ///
+    /// Returns the journal sequence number, or `None` if the journal is not enabled.
+    fn journal_begin(&self, operation: JournalOperation, detail: &str) -> Result<Option<u64>> {
+        if self.journal.lock()?.is_none() {
+            return Ok(None);
+        }
+
+        let cash_unit = self.query_cash_unit_inner()?;
+
+        match self.journal.lock()?.as_mut() {
+            Some(journal) => journal.begin(operation, detail, &cash_unit).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Sends a journaled call, and records the call ID returned by the device, or the error.
+    fn journal_call(&self, seq: Option<u64>, call: impl FnOnce() -> Result<i32>) -> Result<i32> {
+        let res = call();
+
+        if let (Some(seq), Some(journal)) = (seq, self.journal.lock()?.as_mut()) {
+            let record = match res.as_ref() {
+                Ok(call_id) => journal.accepted(seq, *call_id),
+                Err(err) => journal.fail(seq, &format!("{err}")),
+            };
+
+            // the call was already sent, so only report the journal error
+            if let Err(err) = record {
+                log::error!("Error recording journaled call: {err}");
+            }
+        }
+
+        res
+    }
+
     pub(crate) fn reset_inner(&self) -> Result<()> {
         increment_call_counter();
         let count = XfsParam::create(XfsValue::new().with_base64(call_counter_base64()));
@@ -364,7 +633,7 @@ impl DeviceHandle {
 
         let usb = self.usb();
         usb.write_call(&call)?;
-        usb.read_response(call.name()?)?.try_into()
+        usb.read_response_as(call.name()?)
     }
 
     pub(crate) fn park_inner(&self) -> Result<()> {
@@ -383,7 +652,7 @@ impl DeviceHandle {
 
         let usb = self.usb();
         usb.write_call(&call)?;
-        usb.read_response(call.name()?)?.try_into()
+        usb.read_response_as(call.name()?)
     }
 
     pub(crate) fn set_capabilities_inner(&self, caps: &Capabilities) -> Result<Capabilities> {
@@ -396,11 +665,13 @@ impl DeviceHandle {
 
         match Capabilities::try_from(&res) {
             Ok(c) => Ok(c),
-            Err(_err) => Ok(*caps),
+            Err(_err) => Ok(caps.clone()),
         }
     }
 
     pub(crate) fn cash_in_start_inner(&self) -> Result<()> {
+        let seq = self.journal_begin(JournalOperation::CashInStart, "")?;
+
         let name = XfsMethodName::CashInStart;
         let count = XfsParam::create(XfsValue::new().with_base64(call_counter_base64()));
 
@@ -408,24 +679,33 @@ impl DeviceHandle {
             .with_name(name)
             .with_params(XfsParams::create([count]));
 
-        let call_id = {
+        let call_id = self.journal_call(seq, || {
             let usb = self.usb();
             usb.write_call(&call)?;
-            usb.read_response(call.name()?)?.call_id()?
-        };
+            usb.read_response(call.name()?)?.call_id()
+        })?;
 
         self.handle_async_call(call_id)?;
 
         set_call_counter(call_id as u64);
 
+        let baseline = self.query_cash_unit_inner()?;
+        *self.cash_in.lock()? = Some(CashInTransaction::create(baseline));
+
         Ok(())
     }
 
+    /// Sends the `CashIn` call, and returns the call ID of the async operation.
     pub(crate) fn cash_in_inner(
         &self,
         limit: Option<u32>,
         currency: Option<CurrencyCode>,
-    ) -> Result<()> {
+    ) -> Result<i32> {
+        let seq = self.journal_begin(
+            JournalOperation::CashIn,
+            &format!("limit: {limit:?}, currency: {currency:?}"),
+        )?;
+
         let name = XfsMethodName::CashIn;
         let count = XfsParam::create(XfsValue::new().with_base64(call_counter_base64()));
 
@@ -456,18 +736,20 @@ impl DeviceHandle {
             }
         };
 
-        let call_id = {
+        let call_id = self.journal_call(seq, || {
             let usb = self.usb();
             usb.write_call(&call)?;
-            usb.read_response(call.name()?)?.call_id()?
-        };
+            usb.read_response(call.name()?)?.call_id()
+        })?;
 
         set_call_counter(call_id as u64);
 
-        Ok(())
+        Ok(call_id)
     }
 
-    pub(crate) fn cash_in_end_inner(&self) -> Result<()> {
+    pub(crate) fn cash_in_end_inner(&self) -> Result<CashInResult> {
+        let seq = self.journal_begin(JournalOperation::CashInEnd, "")?;
+
         increment_call_counter();
         let count = XfsParam::create(XfsValue::new().with_base64(call_counter_base64()));
 
@@ -475,20 +757,28 @@ impl DeviceHandle {
             .with_name(XfsMethodName::CashInEnd)
             .with_params(XfsParams::create([count]));
 
-        let call_id = {
+        let call_id = self.journal_call(seq, || {
             let usb = self.usb();
             usb.write_call(&call)?;
-            usb.read_response(call.name()?)?.call_id()?
-        };
+            usb.read_response(call.name()?)?.call_id()
+        })?;
 
         self.handle_async_call(call_id)?;
 
         set_call_counter(call_id as u64);
 
-        Ok(())
+        let end = self.query_cash_unit_inner()?;
+        let txn = self.cash_in.lock()?.take().unwrap_or_else(|| {
+            log::warn!("cash_in_end: no CashIn transaction started, counts are not tracked");
+            CashInTransaction::create(end.clone())
+        });
+
+        Ok(txn.result(&end))
     }
 
     pub(crate) fn cash_in_rollback_inner(&self) -> Result<()> {
+        let seq = self.journal_begin(JournalOperation::CashInRollback, "")?;
+
         increment_call_counter();
         let count = XfsParam::create(XfsValue::new().with_base64(call_counter_base64()));
 
@@ -496,9 +786,15 @@ impl DeviceHandle {
             .with_name(XfsMethodName::CashInRollback)
             .with_params(XfsParams::create([count]));
 
-        let usb = self.usb();
-        usb.write_call(&call)?;
-        usb.read_response(call.name()?)?;
+        self.journal_call(seq, || {
+            let usb = self.usb();
+            usb.write_call(&call)?;
+            usb.read_response(call.name()?)?.call_id()
+        })?;
+
+        if let Some(txn) = self.cash_in.lock()?.as_mut() {
+            txn.rollback();
+        }
 
         Ok(())
     }
@@ -518,7 +814,12 @@ impl DeviceHandle {
         Ok(())
     }
 
-    pub(crate) fn empty_inner(&self, pcu_name: &str, to_float: bool) -> Result<()> {
+    pub(crate) fn empty_inner(&self, pcu_name: &str, to_float: bool) -> Result<i32> {
+        let seq = self.journal_begin(
+            JournalOperation::Empty,
+            &format!("pcu_name: {pcu_name}, to_float: {to_float}"),
+        )?;
+
         increment_call_counter();
         let count = XfsParam::create(XfsValue::new().with_base64(call_counter_base64()));
 
@@ -530,15 +831,16 @@ impl DeviceHandle {
                 count,
             ]));
 
-        let usb = self.usb();
-
-        usb.write_call(&call)?;
-        usb.read_response(call.name()?)?;
-
-        Ok(())
+        self.journal_call(seq, || {
+            let usb = self.usb();
+            usb.write_call(&call)?;
+            usb.read_response(call.name()?)?.call_id()
+        })
     }
 
-    pub(crate) fn present_inner(&self) -> Result<()> {
+    pub(crate) fn present_inner(&self) -> Result<i32> {
+        let seq = self.journal_begin(JournalOperation::Present, "")?;
+
         increment_call_counter();
         let count = XfsParam::create(XfsValue::new().with_base64(call_counter_base64()));
 
@@ -546,12 +848,11 @@ impl DeviceHandle {
             .with_name(XfsMethodName::Present)
             .with_params(XfsParams::create([count]));
 
-        let usb = self.usb();
-
-        usb.write_call(&call)?;
-        usb.read_response(call.name()?)?;
-
-        Ok(())
+        self.journal_call(seq, || {
+            let usb = self.usb();
+            usb.write_call(&call)?;
+            usb.read_response(call.name()?)?.call_id()
+        })
     }
 
     pub(crate) fn cancel_waiting_cash_taken_inner(&self) -> Result<()> {
@@ -570,7 +871,9 @@ impl DeviceHandle {
         Ok(())
     }
 
-    pub(crate) fn retract_inner(&self) -> Result<()> {
+    pub(crate) fn retract_inner(&self, detail: &str) -> Result<i32> {
+        let seq = self.journal_begin(JournalOperation::Retract, detail)?;
+
         increment_call_counter();
         let count = XfsParam::create(XfsValue::new().with_base64(call_counter_base64()));
 
@@ -578,13 +881,103 @@ impl DeviceHandle {
             .with_name(XfsMethodName::Retract)
             .with_params(XfsParams::create([count]));
 
-        let usb = self.usb();
+        self.journal_call(seq, || {
+            let usb = self.usb();
+            usb.write_call(&call)?;
+            usb.read_response(call.name()?)?.call_id()
+        })
+    }
 
-        usb.write_call(&call)?;
+    pub(crate) fn present_supervised_inner(&self, timeout: Duration) -> Result<PresentReport> {
+        let before = self.query_cash_unit_inner()?;
+        let call_id = self.present_inner()?;
+
+        // the bills may be at the outlet: errors past this point are reported as an unknown
+        // outcome, with the bundler count delta, instead of losing the report
+        let outcome = match self.wait_async_call(call_id, timeout) {
+            Ok(Some(msg)) => match msg.result().unwrap_or(-1) {
+                0 => PresentOutcome::Taken,
+                res => {
+                    log::error!("Present failed, result: {res}");
+                    PresentOutcome::Unknown
+                }
+            },
+            Ok(None) => {
+                log::info!("Cash not taken after {timeout:?}, cancelling the present");
+                self.cancel_present(call_id, timeout)
+            }
+            Err(err) => {
+                log::error!("Error waiting for the Present operation: {err}");
+                PresentOutcome::Unknown
+            }
+        };
 
-        usb.read_response(call.name()?)?;
+        let after = self.query_cash_unit_inner()?;
+        let report = PresentReport::create(outcome, &before, &after);
+        log::info!("Supervised present: {report}");
 
-        Ok(())
+        Ok(report)
+    }
+
+    pub(crate) fn evaluate_health_inner(&self, last_error: Option<&Error>) -> HealthReport {
+        let report = match self
+            .get_status_inner()
+            .and_then(|status| Ok((status, self.query_cash_unit_inner()?)))
+        {
+            Ok((status, cash_unit)) => HealthReport::create(&status, &cash_unit, last_error),
+            Err(err) => HealthReport::unreachable(&err),
+        };
+
+        if !report.is_operational() {
+            log::warn!("Device health: {report}");
+        }
+
+        report
+    }
+
+    fn cancel_present(&self, call_id: i32, timeout: Duration) -> PresentOutcome {
+        if let Err(err) = self.cancel_waiting_cash_taken_inner() {
+            log::error!("Error cancelling the present: {err}");
+            return PresentOutcome::Unknown;
+        }
+
+        // the present completes with a cancelled result, unless the cash was just taken
+        match self.wait_async_call(call_id, PRESENT_OPERATION_TIMEOUT) {
+            Ok(Some(msg)) if msg.result().unwrap_or(-1) == 0 => PresentOutcome::Taken,
+            Ok(Some(_)) => self.retract_presented(timeout),
+            Ok(None) => {
+                log::error!("No Present operation complete after cancel");
+                PresentOutcome::Unknown
+            }
+            Err(err) => {
+                log::error!("Error waiting for the cancelled Present operation: {err}");
+                PresentOutcome::Unknown
+            }
+        }
+    }
+
+    fn retract_presented(&self, timeout: Duration) -> PresentOutcome {
+        let res = self
+            .retract_inner(&format!("present not taken after {timeout:?}"))
+            .and_then(|call_id| self.wait_async_call(call_id, PRESENT_OPERATION_TIMEOUT));
+
+        match res {
+            Ok(Some(msg)) => match msg.result().unwrap_or(-1) {
+                0 => PresentOutcome::Retracted,
+                res => {
+                    log::error!("Retract failed, result: {res}");
+                    PresentOutcome::Unknown
+                }
+            },
+            Ok(None) => {
+                log::error!("No Retract operation complete");
+                PresentOutcome::Unknown
+            }
+            Err(err) => {
+                log::error!("Error retracting the presented cash: {err}");
+                PresentOutcome::Unknown
+            }
+        }
     }
 
     pub(crate) fn query_cash_unit_inner(&self) -> Result<CashUnit> {
@@ -599,7 +992,7 @@ impl DeviceHandle {
 
         usb.write_call(&call)?;
 
-        usb.read_response(call.name()?)?.try_into()
+        usb.read_response_as(call.name()?)
     }
 
     pub(crate) fn configure_cash_unit_inner(
@@ -648,7 +1041,7 @@ impl DeviceHandle {
         Ok(())
     }
 
-    pub(crate) fn denominate_inner(&self, request: &DispenseRequest) -> Result<()> {
+    pub(crate) fn denominate_inner(&self, request: &DispenseRequest) -> Result<DenominateResult> {
         let call = XfsMethodCall::from(request).with_name(XfsMethodName::Denominate);
 
         let call_id = {
@@ -657,18 +1050,19 @@ impl DeviceHandle {
             usb.read_response(call.name()?)?.call_id()?
         };
 
-        self.handle_async_call(call_id)
+        DenominateResult::try_from(&self.handle_async_call_response(call_id)?)
     }
 
     pub(crate) fn dispense_inner(&self, request: &DispenseRequest) -> Result<()> {
+        let seq = self.journal_begin(JournalOperation::Dispense, &format!("{request}"))?;
+
         let call = XfsMethodCall::from(request).with_name(XfsMethodName::Dispense);
 
-        let call_id = {
+        let call_id = self.journal_call(seq, || {
             let usb = self.usb();
-
             usb.write_call(&call)?;
-            usb.read_response(call.name()?)?.call_id()?
-        };
+            usb.read_response(call.name()?)?.call_id()
+        })?;
 
         self.handle_async_call(call_id)
     }
@@ -688,7 +1082,7 @@ impl DeviceHandle {
         let usb = self.usb();
 
         usb.write_call(&call)?;
-        usb.read_response(call.name()?)?.try_into()
+        usb.read_response_as(call.name()?)
     }
 
     pub(crate) fn update_denominations_inner(&self, request: &DenominationList) -> Result<()> {
@@ -703,12 +1097,77 @@ impl DeviceHandle {
         Ok(())
     }
 
+    pub(crate) fn apply_cash_unit_layout_inner(
+        &self,
+        layout: &CashUnitLayout,
+    ) -> Result<(LogicalCashUnitList, PhysicalCashUnitList)> {
+        let cash_unit = self.query_cash_unit_inner()?;
+        let transport_count = cash_unit.transport_count();
+        let (lcu_list, pcu_list) = layout.apply(&cash_unit)?;
+
+        self.configure_cash_unit_inner(transport_count, &lcu_list, &pcu_list)?;
+        // thresholds are only set by an update
+        self.update_cash_unit_inner(transport_count, &lcu_list, &pcu_list)?;
+
+        Ok((lcu_list, pcu_list))
+    }
+
+    pub(crate) fn backup_config_inner(&self) -> Result<DeviceConfig> {
+        Ok(DeviceConfig::create(
+            &self.get_capabilities_inner()?,
+            &self.query_denominations_inner()?,
+            &self.query_cash_unit_inner()?,
+        ))
+    }
+
+    pub(crate) fn restore_config_inner(
+        &self,
+        config: &DeviceConfig,
+        dry_run: bool,
+    ) -> Result<Vec<ConfigChange>> {
+        let caps = self.get_capabilities_inner()?;
+        let denominations = self.query_denominations_inner()?;
+        let cash_unit = self.query_cash_unit_inner()?;
+
+        let current = DeviceConfig::create(&caps, &denominations, &cash_unit);
+        let changes = config.restore_onto(&current).diff(&current)?;
+
+        for change in changes.iter() {
+            log::info!("Device config change: {change}");
+        }
+
+        if dry_run {
+            return Ok(changes);
+        }
+
+        let changed = |section: &str| changes.iter().any(|c| c.path().starts_with(section));
+
+        if changed("capabilities") {
+            self.set_capabilities_inner(&config.restore_capabilities(&caps))?;
+        }
+
+        if changed("denominations") {
+            self.update_denominations_inner(&config.restore_denominations(&denominations))?;
+        }
+
+        if changed("logical_cash_units") || changed("physical_cash_units") {
+            let transport_count = cash_unit.transport_count();
+            let (lcu_list, pcu_list) = config.restore_cash_units(&cash_unit);
+
+            self.configure_cash_unit_inner(transport_count, &lcu_list, &pcu_list)?;
+            // thresholds are only set by an update
+            self.update_cash_unit_inner(transport_count, &lcu_list, &pcu_list)?;
+        }
+
+        Ok(changes)
+    }
+
     pub(crate) fn query_billset_ids_inner(&self) -> Result<BillsetIdList> {
         let call = XfsMethodCall::create(XfsMethodName::QueryBillsetIds, []);
         let usb = self.usb();
 
         usb.write_call(&call)?;
-        usb.read_response(call.name()?)?.try_into()
+        usb.read_response_as(call.name()?)
     }
 
     pub(crate) fn get_bill_acceptance_history_inner(&self) -> Result<BillAcceptanceHistory> {
@@ -716,7 +1175,7 @@ impl DeviceHandle {
         let usb = self.usb();
 
         usb.write_call(&call)?;
-        usb.read_response(call.name()?)?.try_into()
+        usb.read_response_as(call.name()?)
     }
 
     pub(crate) fn get_bill_dispense_history_inner(&self) -> Result<BillDispenseHistory> {
@@ -724,7 +1183,7 @@ impl DeviceHandle {
         let usb = self.usb();
 
         usb.write_call(&call)?;
-        usb.read_response(call.name()?)?.try_into()
+        usb.read_response_as(call.name()?)
     }
 
     pub(crate) fn get_failure_history_inner(&self) -> Result<SystemFailureHistory> {
@@ -740,7 +1199,7 @@ impl DeviceHandle {
         let usb = self.usb();
 
         usb.write_call(&call)?;
-        usb.read_response(call.name()?)?.try_into()
+        usb.read_response_as(call.name()?)
     }
 
     pub(crate) fn get_use_history_inner(&self) -> Result<SystemUseHistory> {
@@ -748,6 +1207,6 @@ impl DeviceHandle {
         let usb = self.usb();
 
         usb.write_call(&call)?;
-        usb.read_response(call.name()?)?.try_into()
+        usb.read_response_as(call.name()?)
     }
 }
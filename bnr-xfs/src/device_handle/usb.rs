@@ -1,13 +1,27 @@
+use std::future::Future;
+use std::pin::pin;
+use std::sync::Arc;
+use std::task::{Context, Poll, Wake, Waker};
+use std::thread;
+use std::time::{Duration, Instant};
+
 use futures_lite::future::block_on;
 use nusb::transfer::{ControlIn, ControlType, Recipient, RequestBuffer};
 
 use crate::xfs;
+use crate::xfs::codec::{self, XfsDecode};
 use crate::xfs::method_call::{XfsMethodCall, XfsMethodName};
-use crate::xfs::method_response::{XfsMethodResponse, XfsMethodResponseStruct};
+use crate::xfs::method_response::XfsMethodResponse;
 use crate::Result;
 
 use super::*;
 
+/// Period of inactivity after which an `IN` endpoint is considered drained.
+pub const DRAIN_TIMEOUT: Duration = Duration::from_millis(250);
+
+/// Size of the chunks read from the `IN` endpoints.
+const CHUNK_LEN: usize = 4096;
+
 /// Represents a host-side USB device handle.
 pub struct UsbDeviceHandle {
     device: nusb::Device,
@@ -49,44 +63,109 @@ impl UsbDeviceHandle {
         &self.interface
     }
 
-    /// Writes an [XfsMethodCall] to the BNR device.
+    /// Writes an `ISO-8859-1` encoded [XfsMethodCall] to the BNR device.
     pub fn write_call(&self, call: &XfsMethodCall) -> Result<()> {
-        block_on(
-            self.interface
-                .bulk_out(BNR_CALL_EP, xfs::to_string(call)?.into_bytes()),
-        )
-        .into_result()
-        .map(|_| ())
-        .map_err(|err| {
-            let method = call.name_str();
-            let err_msg = format!("error writing {method} message: {err}");
-            log::warn!("{err_msg}");
-            Error::Usb(err_msg)
-        })
+        let msg = xfs::encode_latin1(codec::encode_method_call(call, "ISO-8859-1").as_str())?;
+
+        block_on(self.interface.bulk_out(BNR_CALL_EP, msg))
+            .into_result()
+            .map(|_| ())
+            .map_err(|err| {
+                let method = call.name_str();
+                let err_msg = format!("error writing {method} message: {err}");
+                log::warn!("{err_msg}");
+                Error::Usb(err_msg)
+            })
     }
 
     /// Reads an XFS method response (as a string) from the BNR response endpoint.
     pub fn read_response(&self, method: XfsMethodName) -> Result<XfsMethodResponse> {
-        // Responses can be very large, so read from the endpoint in 4K chunks.
-        let mut res_acc = Vec::with_capacity(4096);
-        let mut res_buf = block_on(
-            self.interface
-                .bulk_in(BNR_RESPONSE_EP, RequestBuffer::new(4096)),
-        )
-        .into_result()
-        .map_err(|err| {
+        let res_str = self.read_response_str(method)?;
+
+        match codec::decode_method_response(res_str.as_str()) {
+            Ok(XfsMethodResponse::Params(p)) => {
+                log::debug!("BNR {method} response: {p}");
+                Ok(XfsMethodResponse::Params(p))
+            }
+            Ok(XfsMethodResponse::Fault(f)) => {
+                let err_msg = format!("BNR {method} response fault: {f}");
+                log::warn!("{err_msg}");
+                Err(Error::Xfs(err_msg))
+            }
+            Err(err) => {
+                log::warn!("Error parsing {method} response: {err}");
+                Err(err)
+            }
+        }
+    }
+
+    /// Reads an XFS method response from the BNR response endpoint, and decodes it directly into
+    /// the type `T`.
+    ///
+    /// Skips building the intermediate [XfsValue](crate::xfs::value::XfsValue) tree, see
+    /// [codec](crate::xfs::codec) for details.
+    pub fn read_response_as<T: XfsDecode>(&self, method: XfsMethodName) -> Result<T> {
+        let res_str = self.read_response_str(method)?;
+
+        codec::decode_response::<T>(res_str.as_str()).map_err(|err| {
+            log::warn!("Error decoding {method} response: {err}");
+            err
+        })
+    }
+
+    fn read_response_str(&self, method: XfsMethodName) -> Result<String> {
+        let res_acc = self.read_message(BNR_RESPONSE_EP).map_err(|err| {
             let err_msg = format!("Error reading {method} response: {err}");
             log::error!("{err_msg}");
             Error::Usb(err_msg)
         })?;
 
+        let res_str = xfs::decode(res_acc.as_ref()).map_err(|err| {
+            log::warn!("Error decoding {method} response: {err}");
+            err
+        })?;
+        log::trace!("Raw {method} response: {res_str}");
+
+        Ok(res_str)
+    }
+
+    /// Reads an XFS callback call response (as a string) from the BNR response endpoint.
+    pub fn read_callback_call(&self) -> Result<XfsMethodCall> {
+        let res_acc = self.read_message(BNR_CALLBACK_CALL_EP).map_err(|err| {
+            let err_msg = format!("Error reading callback call: {err}");
+            log::error!("{err_msg}");
+            Error::Usb(err_msg)
+        })?;
+
+        let call_str = xfs::decode(res_acc.as_ref()).map_err(|err| {
+            log::warn!("Error decoding callback call: {err}");
+            err
+        })?;
+        log::trace!("Raw callback call: {call_str}");
+
+        codec::decode_method_call(call_str.as_str())
+    }
+
+    /// Reads a complete XFS message from an `IN` endpoint, blocking until data arrives.
+    fn read_message(
+        &self,
+        endpoint: u8,
+    ) -> std::result::Result<Vec<u8>, nusb::transfer::TransferError> {
+        // Responses can be very large, so read from the endpoint in 4K chunks.
+        let mut res_acc = Vec::with_capacity(CHUNK_LEN);
+        let mut res_buf = block_on(
+            self.interface
+                .bulk_in(endpoint, RequestBuffer::new(CHUNK_LEN)),
+        )
+        .into_result()?;
+
         let mut read = res_buf.len();
         res_acc.append(&mut res_buf);
-        while read == 4096 {
+        while read == CHUNK_LEN {
             // clear the buffer to avoid leaving old data in the trailing bytes
             res_buf = match block_on(
                 self.interface
-                    .bulk_in(BNR_RESPONSE_EP, RequestBuffer::reuse(res_buf, 4096)),
+                    .bulk_in(endpoint, RequestBuffer::reuse(res_buf, CHUNK_LEN)),
             )
             .into_result()
             {
@@ -99,55 +178,39 @@ impl UsbDeviceHandle {
             }
         }
 
-        let res_str = std::str::from_utf8(res_acc.as_ref()).unwrap_or("");
-        log::trace!("Raw {method} response: {res_str}");
-
-        match xfs::from_str::<XfsMethodResponseStruct>(res_str) {
-            Ok(r) => match r.into_inner() {
-                XfsMethodResponse::Params(p) => {
-                    log::debug!("BNR {method} response: {p}");
-                    Ok(XfsMethodResponse::Params(p))
-                }
-                XfsMethodResponse::Fault(f) => {
-                    let err_msg = format!("BNR {method} response fault: {f}");
-                    log::warn!("{err_msg}");
-                    Err(Error::Xfs(err_msg))
-                }
-            },
-            Err(err) => {
-                log::warn!("Error parsing {method} response: {err}");
-                Err(err)
-            }
-        }
+        Ok(res_acc)
     }
 
-    /// Reads an XFS callback call response (as a string) from the BNR response endpoint.
-    pub fn read_callback_call(&self) -> Result<XfsMethodCall> {
-        // Responses can be very large, so read from the endpoint in 4K chunks.
-        let mut res_acc = Vec::with_capacity(4096);
-        let mut res_buf = block_on(
+    /// Reads a complete XFS message from an `IN` endpoint, waiting at most `timeout` for data to
+    /// arrive.
+    ///
+    /// Returns `Ok(None)` if no data arrived before the timeout expired.
+    pub fn read_timeout(&self, endpoint: u8, timeout: Duration) -> Result<Option<Vec<u8>>> {
+        let mut res_acc = Vec::with_capacity(CHUNK_LEN);
+        let mut res_buf = match with_timeout(
             self.interface
-                .bulk_in(BNR_CALLBACK_CALL_EP, RequestBuffer::new(4096)),
-        )
-        .into_result()
-        .map_err(|err| {
-            let err_msg = format!("Error reading callback call: {err}");
-            log::error!("{err_msg}");
-            Error::Usb(err_msg)
-        })?;
+                .bulk_in(endpoint, RequestBuffer::new(CHUNK_LEN)),
+            timeout,
+        ) {
+            Some(res) => res.into_result().map_err(|err| {
+                let err_msg = format!("Error reading from endpoint {endpoint:#04x}: {err}");
+                log::error!("{err_msg}");
+                Error::Usb(err_msg)
+            })?,
+            None => return Ok(None),
+        };
 
         let mut read = res_buf.len();
         res_acc.append(&mut res_buf);
-        while read == 4096 {
-            // clear the buffer to avoid leaving old data in the trailing bytes
-            res_buf = match block_on(
+        while read == CHUNK_LEN {
+            // the rest of a message follows immediately, so only wait for the timeout period
+            res_buf = match with_timeout(
                 self.interface
-                    .bulk_in(BNR_CALLBACK_CALL_EP, RequestBuffer::reuse(res_buf, 4096)),
-            )
-            .into_result()
-            {
-                Ok(r) => r,
-                Err(_err) => Vec::new(),
+                    .bulk_in(endpoint, RequestBuffer::reuse(res_buf, CHUNK_LEN)),
+                timeout,
+            ) {
+                Some(res) => res.into_result().unwrap_or_default(),
+                None => Vec::new(),
             };
             read = res_buf.len();
             if read > 0 {
@@ -155,19 +218,43 @@ impl UsbDeviceHandle {
             }
         }
 
-        let call_str = std::str::from_utf8(res_acc.as_ref()).unwrap_or("");
-        log::trace!("Raw callback call: {call_str}");
+        Ok(Some(res_acc))
+    }
+
+    /// Drains all queued XFS messages from an `IN` endpoint.
+    ///
+    /// Reads until no data arrives for the `quiet` period, and returns the raw messages in the
+    /// order they were read.
+    pub fn drain(&self, endpoint: u8, quiet: Duration) -> Vec<Vec<u8>> {
+        let mut msgs = Vec::new();
 
-        xfs::from_str::<XfsMethodCall>(call_str)
+        loop {
+            match self.read_timeout(endpoint, quiet) {
+                Ok(Some(msg)) => {
+                    log::trace!(
+                        "Drained message from endpoint {endpoint:#04x}: {}",
+                        String::from_utf8_lossy(msg.as_ref())
+                    );
+                    msgs.push(msg);
+                }
+                Ok(None) => break,
+                Err(err) => {
+                    log::warn!("Error draining endpoint {endpoint:#04x}: {err}");
+                    break;
+                }
+            }
+        }
+
+        msgs
     }
 
-    /// Writes an [XfsMethodCall] to the BNR device.
+    /// Writes an `ISO-8859-1` encoded callback [XfsMethodResponse] to the BNR device.
     pub fn write_callback_response(
         &self,
         res: &XfsMethodResponse,
         name: XfsMethodName,
     ) -> Result<()> {
-        let msg = xfs::to_iso_string(res)?.into_bytes();
+        let msg = xfs::encode_latin1(codec::encode_method_response(res, "ISO-8859-1").as_str())?;
 
         block_on(self.interface.bulk_out(BNR_CALLBACK_RESPONSE_EP, msg))
             .into_result()
@@ -223,3 +310,42 @@ impl UsbDeviceHandle {
         Ok(())
     }
 }
+
+/// Runs a future to completion on the current thread, or until the `timeout` expires.
+///
+/// Returns `None` if the timeout expired first. Dropping an unfinished USB transfer cancels it.
+///
+/// The thread parks between polls with the remaining time as its timeout, so no timer thread is
+/// needed.
+fn with_timeout<F: Future>(fut: F, timeout: Duration) -> Option<F::Output> {
+    let deadline = Instant::now() + timeout;
+    let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+    let mut cx = Context::from_waker(&waker);
+    let mut fut = pin!(fut);
+
+    loop {
+        if let Poll::Ready(out) = fut.as_mut().poll(&mut cx) {
+            return Some(out);
+        }
+
+        let now = Instant::now();
+        if now >= deadline {
+            return None;
+        }
+
+        thread::park_timeout(deadline - now);
+    }
+}
+
+/// Wakes a thread parked in [with_timeout].
+struct ThreadWaker(thread::Thread);
+
+impl Wake for ThreadWaker {
+    fn wake(self: Arc<Self>) {
+        self.0.unpark();
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        self.0.unpark();
+    }
+}
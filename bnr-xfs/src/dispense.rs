@@ -2,16 +2,27 @@
 
 use std::fmt;
 
-use crate::currency::{Currency, Denomination, MixNumber};
-use crate::impl_xfs_struct;
+use crate::currency::{Currency, Denomination, MixNumber, Money};
 use crate::xfs::method_call::XfsMethodCall;
 use crate::xfs::params::{XfsParam, XfsParams};
 use crate::xfs::value::XfsValue;
+use crate::{Result, XfsStruct};
+
+mod denominate_result;
+mod mix_table;
+mod planner;
+mod present;
+
+pub use denominate_result::*;
+pub use mix_table::*;
+pub use planner::*;
+pub use present::*;
 
 /// Structure that defines the parameters of `bnr_Dispense()` or `bnr_Denominate()`, to specify
 /// either an amount or a list of banknotes to dispense.
 #[repr(C)]
-#[derive(Clone, Copy, Debug, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+#[derive(Clone, Debug, Default, PartialEq, serde::Deserialize, serde::Serialize, XfsStruct)]
+#[xfs(name = "dispenseRequest", no_display)]
 pub struct DispenseRequest {
     mix_number: MixNumber,
     denomination: Denomination,
@@ -28,6 +39,26 @@ impl DispenseRequest {
         }
     }
 
+    /// Creates a new [DispenseRequest] for an amount of [Money].
+    ///
+    /// The amount is converted to MDU with the [Currency] exponent, and dispensed using the
+    /// provided `mix_number` algorithm, e.g. [MixNumber::MinBills].
+    pub fn create_amount(mix_number: MixNumber, amount: &Money) -> Result<Self> {
+        let currency = Currency::from(amount.currency());
+        let mdu = amount.to_currency_mdu(&currency)?;
+
+        Ok(Self {
+            mix_number,
+            denomination: Denomination::new().with_amount(mdu),
+            currency,
+        })
+    }
+
+    /// Gets the requested amount as [Money].
+    pub fn amount(&self) -> Result<Money> {
+        self.currency.to_money(self.denomination.amount())
+    }
+
     /// Gets the [MixNumber] of the [DispenseRequest].
     pub const fn mix_number(&self) -> MixNumber {
         self.mix_number
@@ -87,16 +118,6 @@ impl fmt::Display for DispenseRequest {
     }
 }
 
-impl_xfs_struct!(
-    DispenseRequest,
-    "dispenseRequest",
-    [
-        mix_number: MixNumber,
-        denomination: Denomination,
-        currency: Currency
-    ]
-);
-
 impl From<&DispenseRequest> for XfsMethodCall {
     fn from(val: &DispenseRequest) -> Self {
         Self::new().with_params(XfsParams::create([
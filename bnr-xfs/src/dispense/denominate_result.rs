@@ -0,0 +1,205 @@
+use std::fmt;
+
+use crate::currency::{CashOrder, Currency, Denomination, DenominationItem, Money};
+use crate::xfs::method_call::XfsMethodCall;
+use crate::{CallbackCall, CashUnit, Error, Result};
+
+/// Represents the bills the BNR proposes to dispense for a `Denominate` request.
+///
+/// Built from the [CashOrder] carried by the `OperationCompleteOccurred` callback of the
+/// [denominate](crate::DeviceHandle::denominate) call.
+#[repr(C)]
+#[derive(Clone, Debug, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct DenominateResult {
+    currency: Currency,
+    denomination: Denomination,
+}
+
+impl DenominateResult {
+    /// Creates a new [DenominateResult].
+    pub const fn new() -> Self {
+        Self {
+            currency: Currency::new(),
+            denomination: Denomination::new(),
+        }
+    }
+
+    /// Creates a new [DenominateResult] from the provided parameters.
+    pub const fn create(currency: Currency, denomination: Denomination) -> Self {
+        Self {
+            currency,
+            denomination,
+        }
+    }
+
+    /// Gets a reference to the [Currency].
+    pub const fn currency(&self) -> &Currency {
+        &self.currency
+    }
+
+    /// Gets a reference to the [Denomination].
+    pub const fn denomination(&self) -> &Denomination {
+        &self.denomination
+    }
+
+    /// Gets the proposed [DenominationItem]s: the number of bills from each logical cash unit.
+    pub fn items(&self) -> &[DenominationItem] {
+        self.denomination.items()
+    }
+
+    /// Gets the total number of proposed bills.
+    pub fn bill_count(&self) -> u32 {
+        self.items().iter().map(|i| i.count()).sum()
+    }
+
+    /// Gets the total amount in MDU.
+    pub const fn amount(&self) -> u32 {
+        self.denomination.amount()
+    }
+
+    /// Gets the total amount as [Money].
+    pub fn total(&self) -> Result<Money> {
+        self.currency.to_money(self.amount())
+    }
+
+    /// Gets the proposed bills grouped by value, from the highest value, e.g. `2 x 20.00 USD`
+    /// and `1 x 10.00 USD`.
+    ///
+    /// Bill values are looked up from the logical cash units in the [CashUnit].
+    pub fn bills(&self, cash_unit: &CashUnit) -> Result<Vec<(Money, u32)>> {
+        let lcus = cash_unit.logical_cash_unit_list();
+        let mut bills: Vec<(u32, u32)> = Vec::new();
+
+        for item in self.items().iter().filter(|i| i.count() != 0) {
+            let value = lcus
+                .iter()
+                .find(|l| l.number() == item.unit())
+                .map(|l| l.cash_type().value())
+                .ok_or(Error::Xfs(format!(
+                    "denominate result: unknown logical cash unit: {}",
+                    item.unit()
+                )))?;
+
+            match bills.iter_mut().find(|(v, _)| *v == value) {
+                Some((_, count)) => *count += item.count(),
+                None => bills.push((value, item.count())),
+            }
+        }
+
+        bills.sort_by_key(|(v, _)| std::cmp::Reverse(*v));
+        bills
+            .into_iter()
+            .map(|(value, count)| Ok((self.currency.to_money(value)?, count)))
+            .collect()
+    }
+}
+
+impl From<CashOrder> for DenominateResult {
+    fn from(val: CashOrder) -> Self {
+        Self::create(*val.currency(), val.denomination().clone())
+    }
+}
+
+impl From<&CashOrder> for DenominateResult {
+    fn from(val: &CashOrder) -> Self {
+        Self::create(*val.currency(), val.denomination().clone())
+    }
+}
+
+impl TryFrom<&XfsMethodCall> for DenominateResult {
+    type Error = Error;
+
+    fn try_from(val: &XfsMethodCall) -> Result<Self> {
+        CallbackCall::try_from(val)?
+            .cash_order()
+            .map(Self::from)
+            .ok_or(Error::Xfs(
+                "denominate result: missing cashOrder callback argument".into(),
+            ))
+    }
+}
+
+impl TryFrom<XfsMethodCall> for DenominateResult {
+    type Error = Error;
+
+    fn try_from(val: XfsMethodCall) -> Result<Self> {
+        (&val).try_into()
+    }
+}
+
+impl fmt::Display for DenominateResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{{")?;
+        write!(f, r#""currency": {}, "#, self.currency)?;
+        write!(f, r#""denomination": {}"#, self.denomination)?;
+        write!(f, "}}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::currency::{CashType, CurrencyCode};
+    use crate::xfs::method_call::XfsMethodName;
+    use crate::xfs::params::XfsParam;
+    use crate::xfs::value::XfsValue;
+    use crate::xfs::OperationId;
+    use crate::{LogicalCashUnit, LogicalCashUnitList};
+
+    #[test]
+    fn test_denominate_result_from_callback() -> Result<()> {
+        let code = CurrencyCode::from("USD");
+        let items = [
+            DenominationItem::new().with_unit(7).with_count(2),
+            DenominationItem::new().with_unit(8).with_count(1),
+            DenominationItem::new().with_unit(9).with_count(0),
+        ];
+        let cash_order = CashOrder::create(
+            Currency::from(code),
+            Denomination::new().with_amount(5000).with_items(&items),
+        );
+
+        let params = [6, OperationId::Denominate.inner() as i32, 0, 0]
+            .map(|p| XfsParam::create(XfsValue::new().with_i4(p)))
+            .into_iter()
+            .chain([XfsParam::create(XfsValue::from(&cash_order))])
+            .collect::<Vec<XfsParam>>();
+        let call = XfsMethodCall::create(XfsMethodName::OperationCompleteOccurred, params);
+
+        let res = DenominateResult::try_from(&call)?;
+        assert_eq!(res.currency(), cash_order.currency());
+        assert_eq!(res.items(), cash_order.denomination().items());
+        assert_eq!(res.items().len(), 3);
+        assert_eq!(res.bill_count(), 3);
+        assert_eq!(res.total()?, Money::create(code, 5000));
+
+        let lcus: Vec<LogicalCashUnit> = [(7, 2000), (8, 1000), (9, 500)]
+            .map(|(number, value)| {
+                LogicalCashUnit::new()
+                    .with_number(number)
+                    .with_cash_type(CashType::create(code, value, 0))
+            })
+            .into();
+        let cash_unit = CashUnit::new()
+            .with_logical_cash_unit_list(LogicalCashUnitList::new().with_items(&lcus));
+
+        assert_eq!(
+            res.bills(&cash_unit)?,
+            [
+                (Money::create(code, 2000), 2),
+                (Money::create(code, 1000), 1)
+            ]
+        );
+        assert!(res.bills(&CashUnit::new()).is_err());
+
+        // callbacks without a cash order
+        let call = XfsMethodCall::create(
+            XfsMethodName::OperationCompleteOccurred,
+            [6, OperationId::Denominate.inner() as i32, 0, 0]
+                .map(|p| XfsParam::create(XfsValue::new().with_i4(p))),
+        );
+        assert!(DenominateResult::try_from(&call).is_err());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,396 @@
+use std::fmt;
+
+use crate::currency::CurrencyCode;
+use crate::json::JsonStr;
+use crate::{Error, Result};
+
+/// Describes the denominations used to dispense amounts in a range.
+///
+/// The range bounds and denomination values are in minimum dispense units (MDU) of the
+/// [MixTable] currency, like the [DispenseRequest](super::DispenseRequest) amount.
+#[derive(Clone, Debug, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct MixTableRange {
+    min_amount: u32,
+    max_amount: u32,
+    denominations: Vec<u32>,
+}
+
+impl MixTableRange {
+    /// Creates a new [MixTableRange].
+    pub const fn new() -> Self {
+        Self {
+            min_amount: 0,
+            max_amount: 0,
+            denominations: Vec::new(),
+        }
+    }
+
+    /// Creates a new [MixTableRange] from the inclusive amount bounds, and the denomination
+    /// values in priority order.
+    pub fn create(min_amount: u32, max_amount: u32, denominations: &[u32]) -> Self {
+        Self {
+            min_amount,
+            max_amount,
+            denominations: denominations.into(),
+        }
+    }
+
+    /// Gets the inclusive lower bound of the amount range.
+    pub const fn min_amount(&self) -> u32 {
+        self.min_amount
+    }
+
+    /// Gets the inclusive upper bound of the amount range.
+    pub const fn max_amount(&self) -> u32 {
+        self.max_amount
+    }
+
+    /// Gets the denomination values, from the highest to the lowest priority.
+    pub fn denominations(&self) -> &[u32] {
+        self.denominations.as_ref()
+    }
+
+    /// Gets whether the `amount` is in the range.
+    pub const fn contains(&self, amount: u32) -> bool {
+        amount >= self.min_amount && amount <= self.max_amount
+    }
+}
+
+impl fmt::Display for MixTableRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{{")?;
+        write!(f, r#""min_amount": {}, "#, self.min_amount)?;
+        write!(f, r#""max_amount": {}, "#, self.max_amount)?;
+        write!(f, r#""denominations": {:?}"#, self.denominations)?;
+        write!(f, "}}")
+    }
+}
+
+/// Represents a named mix table: a priority list of denominations for each amount range.
+///
+/// This crate has no call to load a table onto the device, so it does not send
+/// [MixNumber::Table](crate::MixNumber::Table) requests. Tables are resolved locally into a
+/// [MixNumber::Denom](crate::MixNumber::Denom) request instead, see
+/// [DispensePlanner::plan_table](super::DispensePlanner::plan_table).
+///
+/// Denominations are used in priority order: as many bills as possible of the first, while the
+/// rest of the amount stays payable with the following ones.
+#[derive(Clone, Debug, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct MixTable {
+    name: String,
+    currency: CurrencyCode,
+    #[serde(default)]
+    ranges: Vec<MixTableRange>,
+}
+
+impl MixTable {
+    /// Creates a new [MixTable].
+    pub const fn new() -> Self {
+        Self {
+            name: String::new(),
+            currency: CurrencyCode::new(),
+            ranges: Vec::new(),
+        }
+    }
+
+    /// Creates a new [MixTable] from the provided name and currency.
+    pub fn create(name: &str, currency: CurrencyCode) -> Self {
+        Self {
+            name: name.into(),
+            currency,
+            ranges: Vec::new(),
+        }
+    }
+
+    /// Gets the table name.
+    pub fn name(&self) -> &str {
+        self.name.as_str()
+    }
+
+    /// Gets the [CurrencyCode].
+    pub const fn currency(&self) -> CurrencyCode {
+        self.currency
+    }
+
+    /// Gets the [MixTableRange]s.
+    pub fn ranges(&self) -> &[MixTableRange] {
+        self.ranges.as_ref()
+    }
+
+    /// Sets the [MixTableRange]s.
+    pub fn set_ranges(&mut self, ranges: &[MixTableRange]) {
+        self.ranges = ranges.into();
+    }
+
+    /// Builder function that sets the [MixTableRange]s.
+    pub fn with_ranges(mut self, ranges: &[MixTableRange]) -> Self {
+        self.set_ranges(ranges);
+        self
+    }
+
+    /// Builder function that adds a [MixTableRange].
+    pub fn with_range(mut self, range: MixTableRange) -> Self {
+        self.ranges.push(range);
+        self
+    }
+
+    /// Gets the [MixTableRange] for the `amount` in MDU, if any.
+    pub fn range(&self, amount: u32) -> Option<&MixTableRange> {
+        self.ranges.iter().find(|r| r.contains(amount))
+    }
+
+    /// Parses and validates a [MixTable] from JSON.
+    pub fn from_json(json: &str) -> Result<Self> {
+        let table: Self = serde_json::from_str(json)?;
+        table.validate()?;
+        Ok(table)
+    }
+
+    /// Writes the [MixTable] to JSON.
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /// Gets the list of inconsistencies in the table.
+    ///
+    /// Checks the name, the range bounds, overlapping ranges, and the denomination lists.
+    pub fn issues(&self) -> Vec<String> {
+        let mut issues = Vec::new();
+        let name = self.name();
+
+        if name.is_empty() {
+            issues.push("mix table name is empty".into());
+        }
+
+        if self.ranges.is_empty() {
+            issues.push(format!("mix table {name:?} has no ranges"));
+        }
+
+        for (i, range) in self.ranges.iter().enumerate() {
+            if range.min_amount > range.max_amount {
+                issues.push(format!("mix table {name:?}: range {range} has min > max"));
+            }
+
+            if let Some(other) = self.ranges[..i]
+                .iter()
+                .find(|r| r.min_amount <= range.max_amount && range.min_amount <= r.max_amount)
+            {
+                issues.push(format!(
+                    "mix table {name:?}: range {range} overlaps range {other}"
+                ));
+            }
+
+            if range.denominations.is_empty() {
+                issues.push(format!(
+                    "mix table {name:?}: range {range} has no denominations"
+                ));
+            }
+
+            for (j, value) in range.denominations.iter().enumerate() {
+                if *value == 0 {
+                    issues.push(format!(
+                        "mix table {name:?}: range {range} has a zero value"
+                    ));
+                } else if range.denominations[..j].contains(value) {
+                    issues.push(format!(
+                        "mix table {name:?}: range {range} has duplicate value {value}"
+                    ));
+                }
+            }
+        }
+
+        issues
+    }
+
+    /// Validates the table.
+    ///
+    /// Returns an [Error::Config] listing every issue found, see [issues](Self::issues).
+    pub fn validate(&self) -> Result<()> {
+        let issues = self.issues();
+        if issues.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::Config(issues.join("; ")))
+        }
+    }
+}
+
+impl fmt::Display for MixTable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{{")?;
+        write!(f, r#""name": {}, "#, JsonStr(&self.name))?;
+        write!(f, r#""currency": "{}", "#, <&str>::from(self.currency))?;
+        write!(f, r#""ranges": ["#)?;
+        for (i, range) in self.ranges.iter().enumerate() {
+            if i != 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{range}")?;
+        }
+        write!(f, "]}}")
+    }
+}
+
+/// Represents a registry of named [MixTable]s.
+///
+/// Deserializing validates every table, and rejects duplicate table names.
+#[derive(Clone, Debug, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(try_from = "MixTableList")]
+pub struct MixTableRegistry {
+    tables: Vec<MixTable>,
+}
+
+// Unvalidated registry contents, checked when deserializing a [MixTableRegistry].
+#[derive(serde::Deserialize)]
+struct MixTableList {
+    tables: Vec<MixTable>,
+}
+
+impl TryFrom<MixTableList> for MixTableRegistry {
+    type Error = Error;
+
+    fn try_from(val: MixTableList) -> Result<Self> {
+        for (i, table) in val.tables.iter().enumerate() {
+            table.validate()?;
+
+            if val.tables[..i].iter().any(|t| t.name() == table.name()) {
+                return Err(Error::Config(format!(
+                    "duplicate mix table name: {:?}",
+                    table.name()
+                )));
+            }
+        }
+
+        Ok(Self { tables: val.tables })
+    }
+}
+
+impl MixTableRegistry {
+    /// Creates a new [MixTableRegistry].
+    pub const fn new() -> Self {
+        Self { tables: Vec::new() }
+    }
+
+    /// Gets the registered [MixTable]s.
+    pub fn tables(&self) -> &[MixTable] {
+        self.tables.as_ref()
+    }
+
+    /// Gets the [MixTable] registered under `name`, if any.
+    pub fn get(&self, name: &str) -> Option<&MixTable> {
+        self.tables.iter().find(|t| t.name() == name)
+    }
+
+    /// Validates and registers a [MixTable], replacing any table with the same name.
+    pub fn register(&mut self, table: MixTable) -> Result<()> {
+        table.validate()?;
+
+        match self.tables.iter_mut().find(|t| t.name() == table.name()) {
+            Some(existing) => *existing = table,
+            None => self.tables.push(table),
+        }
+
+        Ok(())
+    }
+
+    /// Removes the [MixTable] registered under `name`, returning it if present.
+    pub fn unregister(&mut self, name: &str) -> Option<MixTable> {
+        let idx = self.tables.iter().position(|t| t.name() == name)?;
+        Some(self.tables.remove(idx))
+    }
+}
+
+impl fmt::Display for MixTableRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[")?;
+        for (i, table) in self.tables.iter().enumerate() {
+            if i != 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{table}")?;
+        }
+        write!(f, "]")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mix_table_registry() -> Result<()> {
+        let usd = CurrencyCode::from("USD");
+        let table = MixTable::create("change", usd)
+            .with_range(MixTableRange::create(0, 2000, &[500, 100]))
+            .with_range(MixTableRange::create(2001, 10000, &[2000, 1000, 500]));
+
+        assert!(table.issues().is_empty());
+        assert_eq!(
+            table.range(2000).map(|r| r.denominations()),
+            Some([500, 100].as_ref())
+        );
+        assert!(table.range(10001).is_none());
+        assert_eq!(MixTable::from_json(&table.to_json()?)?, table);
+
+        // the name is escaped as a JSON string
+        let quoted = MixTable::create(r#"change "small""#, usd);
+        let json: serde_json::Value = serde_json::from_str(&quoted.to_string())?;
+        assert_eq!(json["name"], r#"change "small""#);
+
+        let bad = MixTable::create("", usd)
+            .with_range(MixTableRange::create(100, 0, &[]))
+            .with_range(MixTableRange::create(0, 500, &[100, 100, 0]));
+        assert_eq!(bad.issues().len(), 6);
+        assert!(matches!(bad.validate(), Err(Error::Config(_))));
+
+        let mut registry = MixTableRegistry::new();
+        registry.register(table.clone())?;
+        registry.register(
+            table
+                .clone()
+                .with_ranges(&[MixTableRange::create(0, 100, &[100])]),
+        )?;
+        assert_eq!(registry.tables().len(), 1);
+        assert_eq!(registry.get("change").map(|t| t.ranges().len()), Some(1));
+        assert!(registry.register(bad).is_err());
+        assert!(registry.unregister("change").is_some());
+        assert!(registry.get("change").is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_mix_table_registry_deserialize() -> Result<()> {
+        let usd = CurrencyCode::from("USD");
+        let table =
+            MixTable::create("change", usd).with_range(MixTableRange::create(0, 2000, &[500, 100]));
+
+        let mut registry = MixTableRegistry::new();
+        registry.register(table.clone())?;
+        let json = serde_json::to_string(&registry)?;
+        assert_eq!(serde_json::from_str::<MixTableRegistry>(&json)?, registry);
+
+        // invalid tables are rejected
+        let bad = MixTableRegistry {
+            tables: vec![MixTable::create("", usd)],
+        };
+        let err = serde_json::from_str::<MixTableRegistry>(&serde_json::to_string(&bad)?)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("mix table name is empty"), "{err}");
+
+        // duplicate names are rejected
+        let duplicate = MixTableRegistry {
+            tables: vec![table.clone(), table],
+        };
+        let err = serde_json::from_str::<MixTableRegistry>(&serde_json::to_string(&duplicate)?)
+            .unwrap_err()
+            .to_string();
+        assert!(
+            err.contains(r#"duplicate mix table name: "change""#),
+            "{err}"
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,735 @@
+use std::{cmp, fmt};
+
+use crate::currency::{
+    CuKind, Currency, CurrencyCode, Denomination, DenominationItem, MixNumber, Money, LCU_INOP,
+    LCU_MISSING, LCU_NOT_DISPENSABLE, LCU_NO_REF, LCU_NO_VALUE,
+};
+use crate::{Capabilities, CashUnit, Error, Result};
+
+use super::{DispenseRequest, MixTable};
+
+/// Default maximum number of bills dispensed by one command, see [Capabilities::max_out_bills].
+pub const DEFAULT_MAX_OUT_BILLS: u32 = 15;
+
+/// Numerator of the recycler fill level that triggers [MixNumber::OptimumChange].
+const NEAR_FULL_NUM: u32 = 5;
+/// Denominator of the recycler fill level that triggers [MixNumber::OptimumChange].
+const NEAR_FULL_DEN: u32 = 6;
+
+// A dispensable logical cash unit in the planner snapshot.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct PlanUnit {
+    number: u32,
+    value: u32,
+    count: u32,
+    // bills over the 5/6 of Full threshold level of the recycler
+    excess: u32,
+}
+
+// Dispensable units of the same value.
+#[derive(Clone, Debug, PartialEq)]
+struct PlanGroup {
+    value: u32,
+    available: u32,
+    excess: u32,
+}
+
+/// Plans dispense operations locally over a [CashUnit] snapshot.
+///
+/// Reproduces the [MixNumber::MinBills] and [MixNumber::OptimumChange] device algorithms, to
+/// preview the bills dispensed for an amount, and whether the amount is payable at all.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DispensePlanner {
+    cash_unit: CashUnit,
+    max_out_bills: u32,
+}
+
+impl DispensePlanner {
+    /// Creates a new [DispensePlanner].
+    pub fn new() -> Self {
+        Self {
+            cash_unit: CashUnit::new(),
+            max_out_bills: DEFAULT_MAX_OUT_BILLS,
+        }
+    }
+
+    /// Creates a new [DispensePlanner] over the provided [CashUnit] snapshot.
+    pub fn create(cash_unit: &CashUnit) -> Self {
+        Self {
+            cash_unit: cash_unit.clone(),
+            max_out_bills: DEFAULT_MAX_OUT_BILLS,
+        }
+    }
+
+    /// Gets the [CashUnit] snapshot.
+    pub const fn cash_unit(&self) -> &CashUnit {
+        &self.cash_unit
+    }
+
+    /// Gets the maximum number of bills dispensed by one command.
+    pub const fn max_out_bills(&self) -> u32 {
+        self.max_out_bills
+    }
+
+    /// Sets the maximum number of bills dispensed by one command.
+    pub fn set_max_out_bills(&mut self, max_out_bills: u32) {
+        self.max_out_bills = max_out_bills;
+    }
+
+    /// Builder function that sets the maximum number of bills dispensed by one command.
+    pub fn with_max_out_bills(mut self, max_out_bills: u32) -> Self {
+        self.set_max_out_bills(max_out_bills);
+        self
+    }
+
+    /// Builder function that sets the maximum number of bills from the device [Capabilities].
+    pub fn with_capabilities(self, caps: &Capabilities) -> Self {
+        self.with_max_out_bills(caps.max_out_bills.inner())
+    }
+
+    /// Plans the dispense of an `amount` with the `mix_number` algorithm.
+    ///
+    /// Only [MixNumber::MinBills] and [MixNumber::OptimumChange] are supported.
+    ///
+    /// Returns a [DispensePlan] with a [MixNumber::Denom] request, or an error if the amount is
+    /// not payable from the dispensable units.
+    pub fn plan(&self, amount: &Money, mix_number: MixNumber) -> Result<DispensePlan> {
+        let currency = Currency::from(amount.currency());
+        let mdu = amount.to_currency_mdu(&currency)?;
+
+        let units = self.units(amount.currency());
+        let counts = match mix_number {
+            MixNumber::MinBills => self.min_bills(&units, mdu),
+            MixNumber::OptimumChange => self.optimum_change(&units, mdu),
+            mix => {
+                return Err(Error::Enum(format!(
+                    "unsupported mix number for planning: {mix}"
+                )))
+            }
+        }
+        .ok_or(Error::Money(format!(
+            "{amount} is not payable with at most {} bills",
+            self.max_out_bills
+        )))?;
+
+        let items = distribute(&units, &counts, mix_number == MixNumber::OptimumChange);
+
+        Ok(self.build_plan(currency, mdu, &items))
+    }
+
+    /// Gets whether an `amount` is payable with the `mix_number` algorithm.
+    pub fn is_payable(&self, amount: &Money, mix_number: MixNumber) -> bool {
+        self.plan(amount, mix_number).is_ok()
+    }
+
+    /// Plans the dispense of an `amount` with a user-defined [MixTable].
+    ///
+    /// The table range containing the amount gives the denominations to use in priority order.
+    /// The table is resolved locally into a [MixNumber::Denom] request.
+    ///
+    /// Returns an error if the table currency does not match, no range contains the amount, or
+    /// the amount is not payable from the current stock of the range denominations.
+    pub fn plan_table(&self, amount: &Money, table: &MixTable) -> Result<DispensePlan> {
+        if amount.currency() != table.currency() {
+            return Err(Error::Money(format!(
+                "mix table {:?} is for {}, have: {amount}",
+                table.name(),
+                <&str>::from(table.currency())
+            )));
+        }
+
+        let currency = Currency::from(amount.currency());
+        let mdu = amount.to_currency_mdu(&currency)?;
+
+        let range = table.range(mdu).ok_or(Error::Config(format!(
+            "mix table {:?} has no range for {amount}",
+            table.name()
+        )))?;
+
+        let units: Vec<PlanUnit> = self
+            .units(amount.currency())
+            .into_iter()
+            .filter(|u| range.denominations().contains(&u.value))
+            .collect();
+
+        let counts = self
+            .table_counts(&units, range.denominations(), mdu)
+            .ok_or(Error::Money(format!(
+                "{amount} is not payable with mix table {:?} and at most {} bills",
+                table.name(),
+                self.max_out_bills
+            )))?;
+
+        let items = distribute(&units, &counts, false);
+
+        Ok(self.build_plan(currency, mdu, &items))
+    }
+
+    /// Gets the list of [MixTable] denominations with no dispensable stock in the snapshot.
+    ///
+    /// Tables with issues may still be usable, as long as the amount is payable with the other
+    /// denominations of the range.
+    pub fn table_issues(&self, table: &MixTable) -> Vec<String> {
+        let units = self.units(table.currency());
+        let mut issues = Vec::new();
+
+        for range in table.ranges().iter() {
+            for value in range.denominations().iter() {
+                if !units.iter().any(|u| u.value == *value) {
+                    issues.push(format!(
+                        "mix table {:?}: no dispensable stock for value {value} in range {range}",
+                        table.name()
+                    ));
+                }
+            }
+        }
+
+        issues
+    }
+
+    fn build_plan(&self, currency: Currency, mdu: u32, items: &[DenominationItem]) -> DispensePlan {
+        let request = DispenseRequest::new()
+            .with_mix_number(MixNumber::Denom)
+            .with_currency(currency)
+            .with_denomination(Denomination::new().with_amount(mdu).with_items(items));
+
+        DispensePlan {
+            request,
+            cash_unit: self.expected_cash_unit(items),
+        }
+    }
+
+    // Gets the dispensable units of a currency: recyclers and loaders that are usable, and not
+    // locked.
+    fn units(&self, currency: CurrencyCode) -> Vec<PlanUnit> {
+        let pcus = self.cash_unit.physical_cash_unit_list().items();
+
+        self.cash_unit
+            .logical_cash_unit_list()
+            .iter()
+            .filter(|lcu| matches!(lcu.cu_kind(), CuKind::Recycle | CuKind::Dispense))
+            .filter(|lcu| lcu.cash_type().currency_code() == currency)
+            .filter(|lcu| lcu.cash_type().value() != 0 && lcu.count() != 0)
+            .filter(|lcu| {
+                !matches!(
+                    lcu.status(),
+                    LCU_INOP | LCU_MISSING | LCU_NO_VALUE | LCU_NO_REF | LCU_NOT_DISPENSABLE
+                )
+            })
+            .filter_map(|lcu| {
+                let pcu = pcus.get(lcu.physical_cu_index() as usize);
+                if pcu.is_some_and(|p| p.lock()) {
+                    return None;
+                }
+
+                let full = pcu.map(|p| p.threshold().full()).unwrap_or(0);
+                let near_full = full * NEAR_FULL_NUM / NEAR_FULL_DEN;
+                let excess = if lcu.cu_kind() == CuKind::Recycle && full != 0 {
+                    lcu.count().saturating_sub(near_full)
+                } else {
+                    0
+                };
+
+                Some(PlanUnit {
+                    number: lcu.number(),
+                    value: lcu.cash_type().value(),
+                    count: lcu.count(),
+                    excess,
+                })
+            })
+            .collect()
+    }
+
+    // Groups units by value, in descending value order.
+    fn groups(&self, units: &[PlanUnit]) -> Vec<PlanGroup> {
+        let mut groups: Vec<PlanGroup> = Vec::new();
+
+        for unit in units.iter() {
+            match groups.iter_mut().find(|g| g.value == unit.value) {
+                Some(group) => {
+                    group.available = group.available.saturating_add(unit.count);
+                    group.excess = group.excess.saturating_add(unit.excess);
+                }
+                None => groups.push(PlanGroup {
+                    value: unit.value,
+                    available: unit.count,
+                    excess: unit.excess,
+                }),
+            }
+        }
+
+        for group in groups.iter_mut() {
+            group.available = group.available.min(self.max_out_bills);
+            group.excess = group.excess.min(group.available);
+        }
+
+        groups.sort_by_key(|g| cmp::Reverse(g.value));
+        groups
+    }
+
+    fn min_bills(&self, units: &[PlanUnit], amount: u32) -> Option<Vec<(u32, u32)>> {
+        let groups = self.groups(units);
+        min_bills(&groups, amount, self.max_out_bills)
+    }
+
+    // Dispenses as MinBills, but first empties recyclers over 5/6 of their Full threshold back to
+    // that level, lowest denomination first, as far as the rest of the amount stays payable.
+    fn optimum_change(&self, units: &[PlanUnit], amount: u32) -> Option<Vec<(u32, u32)>> {
+        let groups = self.groups(units);
+
+        let mut near_full: Vec<&PlanGroup> = groups.iter().filter(|g| g.excess != 0).collect();
+        near_full.sort_by_key(|g| g.value);
+
+        let mut preferred: Vec<(u32, u32)> = Vec::new();
+        let mut remaining = amount;
+        let mut bills = 0u32;
+
+        for group in near_full {
+            let max = group
+                .excess
+                .min(remaining / group.value)
+                .min(self.max_out_bills - bills);
+
+            // take as many as possible, while the rest stays payable
+            let take = (0..=max).rev().find(|&n| {
+                let mut taken = preferred.clone();
+                taken.push((group.value, n));
+
+                min_bills(
+                    &remaining_groups(&groups, &taken),
+                    remaining - n * group.value,
+                    self.max_out_bills - bills - n,
+                )
+                .is_some()
+            })?;
+
+            if take != 0 {
+                preferred.push((group.value, take));
+                remaining -= take * group.value;
+                bills += take;
+            }
+        }
+
+        let rest = remaining_groups(&groups, &preferred);
+        let mut counts = min_bills(&rest, remaining, self.max_out_bills - bills)?;
+        for (value, count) in preferred {
+            match counts.iter_mut().find(|(v, _)| *v == value) {
+                Some((_, c)) => *c += count,
+                None => counts.push((value, count)),
+            }
+        }
+
+        Some(counts)
+    }
+
+    // Takes as many bills as possible of each denomination in priority order, while the rest of
+    // the amount stays payable with the following denominations.
+    fn table_counts(
+        &self,
+        units: &[PlanUnit],
+        priority: &[u32],
+        amount: u32,
+    ) -> Option<Vec<(u32, u32)>> {
+        let groups = self.groups(units);
+
+        let mut counts: Vec<(u32, u32)> = Vec::new();
+        let mut remaining = amount;
+        let mut bills = 0u32;
+
+        for (i, value) in priority.iter().enumerate() {
+            if remaining == 0 {
+                break;
+            }
+
+            let Some(group) = groups.iter().find(|g| g.value == *value) else {
+                continue;
+            };
+
+            let rest: Vec<PlanGroup> = groups
+                .iter()
+                .filter(|g| priority[i + 1..].contains(&g.value))
+                .cloned()
+                .collect();
+
+            let max = group
+                .available
+                .min(remaining / group.value)
+                .min(self.max_out_bills - bills);
+
+            let take = (0..=max).rev().find(|&n| {
+                min_bills(
+                    &rest,
+                    remaining - n * group.value,
+                    self.max_out_bills - bills - n,
+                )
+                .is_some()
+            })?;
+
+            if take != 0 {
+                counts.push((group.value, take));
+                remaining -= take * group.value;
+                bills += take;
+            }
+        }
+
+        (remaining == 0).then_some(counts)
+    }
+
+    fn expected_cash_unit(&self, items: &[DenominationItem]) -> CashUnit {
+        let mut cash_unit = self.cash_unit.clone();
+        let mut lcus = cash_unit.logical_cash_unit_list().clone();
+        let mut pcus = cash_unit.physical_cash_unit_list().clone();
+
+        for item in items.iter() {
+            if let Some(lcu) = lcus.iter_mut().find(|l| l.number() == item.unit()) {
+                lcu.set_count(lcu.count().saturating_sub(item.count()));
+
+                if let Some(pcu) = pcus.items_mut().get_mut(lcu.physical_cu_index() as usize) {
+                    pcu.set_count(pcu.count().saturating_sub(item.count()));
+                }
+            }
+        }
+
+        cash_unit.set_logical_cash_unit_list(lcus);
+        cash_unit.set_physical_cash_unit_list(pcus);
+        cash_unit
+    }
+}
+
+/// Represents the planned result of a dispense operation.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DispensePlan {
+    request: DispenseRequest,
+    cash_unit: CashUnit,
+}
+
+impl DispensePlan {
+    /// Gets the [DispenseRequest] in [MixNumber::Denom] form.
+    ///
+    /// Sending the request with [dispense](crate::DeviceHandle::dispense) dispenses exactly the
+    /// planned bills.
+    pub const fn request(&self) -> &DispenseRequest {
+        &self.request
+    }
+
+    /// Gets the planned [DenominationItem]s: the number of bills from each logical cash unit.
+    pub fn items(&self) -> &[DenominationItem] {
+        self.request.denomination().items()
+    }
+
+    /// Gets the total number of planned bills.
+    pub fn bill_count(&self) -> u32 {
+        self.items().iter().map(|i| i.count()).sum()
+    }
+
+    /// Gets the expected [CashUnit] counts after the dispense.
+    pub const fn expected_cash_unit(&self) -> &CashUnit {
+        &self.cash_unit
+    }
+
+    /// Converts the [DispensePlan] into the [DispenseRequest].
+    pub fn into_request(self) -> DispenseRequest {
+        self.request
+    }
+}
+
+impl fmt::Display for DispensePlan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{{")?;
+        write!(f, r#""request": {}, "#, self.request)?;
+        write!(f, r#""expected_cash_unit": {}"#, self.cash_unit)?;
+        write!(f, "}}")
+    }
+}
+
+// Finds the combination with the minimum number of bills, as `(value, count)` pairs.
+//
+// Groups are in descending value order, the search is a branch-and-bound over the group counts.
+fn min_bills(groups: &[PlanGroup], amount: u32, max_bills: u32) -> Option<Vec<(u32, u32)>> {
+    let mut cur = vec![0u32; groups.len()];
+    let mut best: Option<(u32, Vec<u32>)> = None;
+
+    search(groups, 0, amount, 0, max_bills, &mut cur, &mut best);
+
+    best.map(|(_, counts)| {
+        groups
+            .iter()
+            .zip(counts)
+            .filter(|(_, c)| *c != 0)
+            .map(|(g, c)| (g.value, c))
+            .collect()
+    })
+}
+
+fn search(
+    groups: &[PlanGroup],
+    idx: usize,
+    remaining: u32,
+    used: u32,
+    max_bills: u32,
+    cur: &mut [u32],
+    best: &mut Option<(u32, Vec<u32>)>,
+) {
+    if remaining == 0 {
+        if best.as_ref().is_none_or(|(b, _)| used < *b) {
+            *best = Some((used, cur.to_vec()));
+        }
+        return;
+    }
+
+    let Some(group) = groups.get(idx) else {
+        return;
+    };
+
+    // no group left can make up the rest with fewer bills than the best so far
+    let min_rest = remaining.div_ceil(group.value);
+    let limit = best
+        .as_ref()
+        .map_or(max_bills, |(b, _)| b.saturating_sub(1));
+    if used + min_rest > limit {
+        return;
+    }
+
+    let max = group
+        .available
+        .min(remaining / group.value)
+        .min(max_bills - used);
+
+    for n in (0..=max).rev() {
+        cur[idx] = n;
+        search(
+            groups,
+            idx + 1,
+            remaining - n * group.value,
+            used + n,
+            max_bills,
+            cur,
+            best,
+        );
+    }
+    cur[idx] = 0;
+}
+
+// Gets the groups left after taking the `(value, count)` bills.
+fn remaining_groups(groups: &[PlanGroup], taken: &[(u32, u32)]) -> Vec<PlanGroup> {
+    groups
+        .iter()
+        .map(|g| {
+            let used: u32 = taken
+                .iter()
+                .filter(|(v, _)| *v == g.value)
+                .map(|(_, c)| c)
+                .sum();
+
+            PlanGroup {
+                value: g.value,
+                available: g.available.saturating_sub(used),
+                excess: 0,
+            }
+        })
+        .collect()
+}
+
+// Distributes the group counts over the units, fullest unit first.
+fn distribute(
+    units: &[PlanUnit],
+    counts: &[(u32, u32)],
+    near_full_first: bool,
+) -> Vec<DenominationItem> {
+    let mut items = Vec::new();
+
+    for &(value, count) in counts.iter() {
+        let mut group: Vec<&PlanUnit> = units.iter().filter(|u| u.value == value).collect();
+        group.sort_by(|a, b| {
+            let excess = if near_full_first {
+                b.excess.cmp(&a.excess)
+            } else {
+                cmp::Ordering::Equal
+            };
+            excess.then(b.count.cmp(&a.count))
+        });
+
+        let mut left = count;
+        for unit in group {
+            if left == 0 {
+                break;
+            }
+
+            let take = left.min(unit.count);
+            items.push(
+                DenominationItem::new()
+                    .with_unit(unit.number)
+                    .with_count(take),
+            );
+            left -= take;
+        }
+    }
+
+    items.sort_by_key(|i| i.unit());
+    items
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::currency::{CashType, CuType};
+    use crate::MixTableRange;
+    use crate::{
+        LogicalCashUnit, LogicalCashUnitList, PcuName, PhysicalCashUnit, PhysicalCashUnitList,
+        Threshold,
+    };
+
+    fn usd(minor_units: i64) -> Money {
+        Money::create(CurrencyCode::from("USD"), minor_units)
+    }
+
+    // (value, count, full threshold) for each recycler
+    fn cash_unit(recyclers: &[(u32, u32, u32)]) -> CashUnit {
+        let lcus: Vec<LogicalCashUnit> = recyclers
+            .iter()
+            .enumerate()
+            .map(|(i, &(value, count, _))| {
+                LogicalCashUnit::new()
+                    .with_number(i as u32 + 1)
+                    .with_cu_kind(CuKind::Recycle)
+                    .with_cu_type(CuType::BillCassette)
+                    .with_cash_type(CashType::create(CurrencyCode::from("USD"), value, 0))
+                    .with_count(count)
+                    .with_physical_cash_unit(i as u32)
+            })
+            .collect();
+
+        let pcus: Vec<PhysicalCashUnit> = recyclers
+            .iter()
+            .enumerate()
+            .map(|(i, &(_, count, full))| {
+                PhysicalCashUnit::new()
+                    .with_name(PcuName::from(format!("RE{}", i + 3).as_str()))
+                    .with_count(count)
+                    .with_threshold(Threshold::new().with_full(full))
+            })
+            .collect();
+
+        CashUnit::new()
+            .with_logical_cash_unit_list(LogicalCashUnitList::new().with_items(&lcus))
+            .with_physical_cash_unit_list(PhysicalCashUnitList::new().with_items(&pcus))
+    }
+
+    fn counts(plan: &DispensePlan) -> Vec<(u32, u32)> {
+        plan.items().iter().map(|i| (i.unit(), i.count())).collect()
+    }
+
+    #[test]
+    fn test_plan_min_bills() -> Result<()> {
+        // $20, $10, $5, $1
+        let planner = DispensePlanner::create(&cash_unit(&[
+            (2000, 10, 0),
+            (1000, 10, 0),
+            (500, 10, 0),
+            (100, 10, 0),
+        ]));
+
+        let plan = planner.plan(&usd(3800), MixNumber::MinBills)?;
+        assert_eq!(counts(&plan), [(1, 1), (2, 1), (3, 1), (4, 3)]);
+        assert_eq!(plan.bill_count(), 6);
+        assert_eq!(plan.request().mix_number(), MixNumber::Denom);
+        assert_eq!(plan.request().amount()?, usd(3800));
+
+        let lcu_counts: Vec<u32> = plan
+            .expected_cash_unit()
+            .logical_cash_unit_list()
+            .iter()
+            .map(|l| l.count())
+            .collect();
+        assert_eq!(lcu_counts, [9, 9, 9, 7]);
+        assert_eq!(
+            plan.expected_cash_unit().physical_cash_unit_list().items()[3].count(),
+            7
+        );
+
+        // not payable: no coins, and over the bill limit
+        assert!(!planner.is_payable(&usd(3850), MixNumber::MinBills));
+        assert!(!planner.is_payable(&usd(100_000), MixNumber::MinBills));
+        assert!(planner
+            .clone()
+            .with_max_out_bills(2)
+            .plan(&usd(3800), MixNumber::MinBills)
+            .is_err());
+        assert!(planner.plan(&usd(3800), MixNumber::Table).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_plan_min_bills_not_greedy() -> Result<()> {
+        // greedy takes the $50 first, and cannot pay the remaining $10 with $20 bills
+        let planner = DispensePlanner::create(&cash_unit(&[(5000, 1, 0), (2000, 5, 0)]));
+
+        let plan = planner.plan(&usd(6000), MixNumber::MinBills)?;
+        assert_eq!(counts(&plan), [(2, 3)]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_plan_optimum_change() -> Result<()> {
+        // the $5 recycler is at 55 of 60 bills, over the 5/6 of Full level of 50
+        let cash_unit = cash_unit(&[(1000, 10, 60), (500, 55, 60)]);
+        let planner = DispensePlanner::create(&cash_unit);
+
+        let plan = planner.plan(&usd(3000), MixNumber::MinBills)?;
+        assert_eq!(counts(&plan), [(1, 3)]);
+
+        // the 5 excess bills, then the rest as MinBills from the remaining units
+        let plan = planner.plan(&usd(3000), MixNumber::OptimumChange)?;
+        assert_eq!(counts(&plan), [(2, 6)]);
+        assert_eq!(
+            plan.expected_cash_unit().physical_cash_unit_list().items()[1].count(),
+            49
+        );
+
+        // below the 5/6 level, the same as MinBills
+        let planner = DispensePlanner::create(&self::cash_unit(&[(1000, 10, 60), (500, 50, 60)]));
+        let plan = planner.plan(&usd(3000), MixNumber::OptimumChange)?;
+        assert_eq!(counts(&plan), [(1, 3)]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_plan_table() -> Result<()> {
+        // $20, $10, $5
+        let planner =
+            DispensePlanner::create(&cash_unit(&[(2000, 10, 0), (1000, 10, 0), (500, 2, 0)]));
+        let table = MixTable::create("small-first", CurrencyCode::from("USD"))
+            .with_range(MixTableRange::create(0, 5000, &[500, 1000]))
+            .with_range(MixTableRange::create(5001, 30000, &[2000, 1000, 100]));
+
+        // as many $5 as in stock, then $10
+        let plan = planner.plan_table(&usd(4000), &table)?;
+        assert_eq!(counts(&plan), [(2, 3), (3, 2)]);
+        assert_eq!(plan.request().mix_number(), MixNumber::Denom);
+
+        // $20 first, keeping the rest payable with $10
+        let plan = planner.plan_table(&usd(7000), &table)?;
+        assert_eq!(counts(&plan), [(1, 3), (2, 1)]);
+
+        // no range, wrong currency, not payable with the range denominations
+        assert!(matches!(
+            planner.plan_table(&usd(40000), &table),
+            Err(Error::Config(_))
+        ));
+        assert!(planner
+            .plan_table(&Money::create(CurrencyCode::from("EUR"), 4000), &table)
+            .is_err());
+        assert!(matches!(
+            planner.plan_table(&usd(4700), &table),
+            Err(Error::Money(_))
+        ));
+
+        // no $1 stock
+        assert_eq!(planner.table_issues(&table).len(), 1);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,145 @@
+use std::fmt;
+use std::time::Duration;
+
+use crate::CashUnit;
+
+/// Default time to wait for the customer to take the presented bills.
+pub const DEFAULT_CASH_TAKEN_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Time to wait for the device to complete a cancelled `Present`, or a `Retract` operation.
+pub const PRESENT_OPERATION_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Represents where the presented bills ended up after a supervised present.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
+pub enum PresentOutcome {
+    /// The customer took the bills.
+    Taken,
+    /// The bills were not taken in time, and were retracted to the bundler.
+    Retracted,
+    /// The device did not confirm the bills were taken or retracted: they may still be at the
+    /// outlet.
+    #[default]
+    Unknown,
+}
+
+impl From<&PresentOutcome> for &'static str {
+    fn from(val: &PresentOutcome) -> Self {
+        match val {
+            PresentOutcome::Taken => "taken",
+            PresentOutcome::Retracted => "retracted",
+            PresentOutcome::Unknown => "unknown",
+        }
+    }
+}
+
+impl From<PresentOutcome> for &'static str {
+    fn from(val: PresentOutcome) -> Self {
+        (&val).into()
+    }
+}
+
+impl fmt::Display for PresentOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, r#""{}""#, <&str>::from(self))
+    }
+}
+
+/// Represents the result of a supervised present, see
+/// [present_supervised](crate::DeviceHandle::present_supervised).
+#[derive(Clone, Copy, Debug, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct PresentReport {
+    outcome: PresentOutcome,
+    retracted: u32,
+    bundler_count: u32,
+}
+
+impl PresentReport {
+    /// Creates a new [PresentReport].
+    pub const fn new() -> Self {
+        Self {
+            outcome: PresentOutcome::Unknown,
+            retracted: 0,
+            bundler_count: 0,
+        }
+    }
+
+    /// Creates a new [PresentReport] from the [CashUnit] counts before the present, and after
+    /// the supervision completed.
+    ///
+    /// Retracted bills are accounted from the count delta of the bundler
+    /// [LogicalCashUnit](crate::LogicalCashUnit), see [CashUnit::bundler].
+    pub fn create(outcome: PresentOutcome, before: &CashUnit, after: &CashUnit) -> Self {
+        let prev = before.bundler().map(|l| l.count()).unwrap_or(0);
+        let bundler_count = after.bundler().map(|l| l.count()).unwrap_or(0);
+
+        Self {
+            outcome,
+            retracted: bundler_count.saturating_sub(prev),
+            bundler_count,
+        }
+    }
+
+    /// Gets the [PresentOutcome].
+    pub const fn outcome(&self) -> PresentOutcome {
+        self.outcome
+    }
+
+    /// Gets whether the customer took the bills.
+    pub const fn taken(&self) -> bool {
+        matches!(self.outcome, PresentOutcome::Taken)
+    }
+
+    /// Gets the number of bills retracted to the bundler.
+    pub const fn retracted(&self) -> u32 {
+        self.retracted
+    }
+
+    /// Gets the count of the bundler logical cash unit after the supervision completed.
+    pub const fn bundler_count(&self) -> u32 {
+        self.bundler_count
+    }
+}
+
+impl fmt::Display for PresentReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{{")?;
+        write!(f, r#""outcome": {}, "#, self.outcome)?;
+        write!(f, r#""retracted": {}, "#, self.retracted)?;
+        write!(f, r#""bundler_count": {}"#, self.bundler_count)?;
+        write!(f, "}}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cash_unit::tests::cash_unit;
+    use crate::Result;
+
+    #[test]
+    fn test_present_report() -> Result<()> {
+        let before = cash_unit()?;
+
+        let mut after = before.clone();
+        if let Some(lcu) = after
+            .logical_cash_unit_list_mut()
+            .iter_mut()
+            .find(|l| l.number() == 6)
+        {
+            lcu.set_count(lcu.count() + 3);
+        }
+
+        let report = PresentReport::create(PresentOutcome::Retracted, &before, &after);
+        assert_eq!(report.outcome(), PresentOutcome::Retracted);
+        assert!(!report.taken());
+        assert_eq!(report.retracted(), 3);
+        assert_eq!(report.bundler_count(), 3);
+
+        let report = PresentReport::create(PresentOutcome::Taken, &before, &before);
+        assert!(report.taken());
+        assert_eq!(report.retracted(), 0);
+
+        Ok(())
+    }
+}
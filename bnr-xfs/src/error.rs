@@ -25,6 +25,8 @@ pub enum Error {
     Json(String),
     Xfs(String),
     DateTime(String),
+    Config(String),
+    Money(String),
     Bnr(BnrError),
     BnrUsb(UsbError),
 }
@@ -112,6 +114,8 @@ impl fmt::Display for Error {
             Self::Io(err) => write!(f, "I/O error: {err}"),
             Self::Xfs(err) => write!(f, "XFS error: {err}"),
             Self::DateTime(err) => write!(f, "DateTime error: {err}"),
+            Self::Config(err) => write!(f, "Configuration error: {err}"),
+            Self::Money(err) => write!(f, "Money error: {err}"),
             Self::Bnr(err) => write!(f, "BNR error: {err}"),
             Self::BnrUsb(err) => write!(f, "BNR USB error: {err}"),
             Self::Json(err) => write!(f, "JSON error: {err}"),
@@ -119,6 +123,7 @@ impl fmt::Display for Error {
     }
 }
 
+#[cfg(feature = "usb")]
 impl From<nusb::transfer::TransferError> for Error {
     fn from(err: nusb::transfer::TransferError) -> Self {
         Self::Usb(format!("{err}"))
@@ -2337,6 +2337,14 @@ pub enum BnrError {
     E_CB09 = 235012132,
 }
 
+impl BnrError {
+    /// Gets whether the error is a warning (`W_` codes): the BNR keeps operating, possibly with
+    /// reduced functionality.
+    pub fn is_warning(&self) -> bool {
+        <&str>::from(self).starts_with("W_")
+    }
+}
+
 impl From<BnrError> for &'static str {
     fn from(err: BnrError) -> Self {
         match err {
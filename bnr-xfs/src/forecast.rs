@@ -0,0 +1,860 @@
+//! Types for forecasting cash unit levels, and raising replenishment alerts.
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::time::{Duration, SystemTime};
+
+use crate::currency::{CashType, CurrencyCode};
+use crate::{CashLocation, CashUnit, CuKind, PcuName};
+
+#[cfg(feature = "usb")]
+use crate::{DeviceHandle, Result};
+
+/// Default number of [CashUnit] samples kept by the [CashUnitMonitor].
+pub const DEFAULT_FORECAST_WINDOW: usize = 96;
+
+/// Default lead time of the replenishment alerts.
+pub const DEFAULT_ALERT_HORIZON: Duration = Duration::from_secs(4 * 60 * 60);
+
+/// Represents the replenishment thresholds of the recyclers and loaders storing a denomination.
+///
+/// Overrides the [Threshold](crate::Threshold) levels of the physical cash units.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct DenominationThreshold {
+    currency_code: CurrencyCode,
+    value: u32,
+    low: u32,
+    high: u32,
+}
+
+impl DenominationThreshold {
+    /// Creates a new [DenominationThreshold].
+    ///
+    /// Params:
+    ///
+    /// - `currency_code`, `value`: denomination, the value is in MDU.
+    /// - `low`: count at or below which the units need replenishment.
+    /// - `high`: count at or above which the units need collection, `0` to use the unit levels.
+    pub const fn create(currency_code: CurrencyCode, value: u32, low: u32, high: u32) -> Self {
+        Self {
+            currency_code,
+            value,
+            low,
+            high,
+        }
+    }
+
+    /// Gets the [CurrencyCode].
+    pub const fn currency_code(&self) -> CurrencyCode {
+        self.currency_code
+    }
+
+    /// Gets the denomination value in MDU.
+    pub const fn value(&self) -> u32 {
+        self.value
+    }
+
+    /// Gets the low count.
+    pub const fn low(&self) -> u32 {
+        self.low
+    }
+
+    /// Gets the high count.
+    pub const fn high(&self) -> u32 {
+        self.high
+    }
+
+    /// Gets whether the threshold applies to the [CashType].
+    pub fn matches(&self, cash_type: &CashType) -> bool {
+        self.currency_code == cash_type.currency_code() && self.value == cash_type.value()
+    }
+}
+
+impl fmt::Display for DenominationThreshold {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{{")?;
+        write!(
+            f,
+            r#""currency_code": "{}", "#,
+            <&str>::from(self.currency_code)
+        )?;
+        write!(f, r#""value": {}, "#, self.value)?;
+        write!(f, r#""low": {}, "#, self.low)?;
+        write!(f, r#""high": {}"#, self.high)?;
+        write!(f, "}}")
+    }
+}
+
+/// Represents the configuration of a [CashUnitMonitor].
+#[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct MonitorConfig {
+    window: usize,
+    horizon: Duration,
+    cashbox_capacity: Option<u32>,
+    thresholds: Vec<DenominationThreshold>,
+}
+
+impl MonitorConfig {
+    /// Creates a new [MonitorConfig].
+    pub const fn new() -> Self {
+        Self {
+            window: DEFAULT_FORECAST_WINDOW,
+            horizon: DEFAULT_ALERT_HORIZON,
+            cashbox_capacity: None,
+            thresholds: Vec::new(),
+        }
+    }
+
+    /// Gets the number of [CashUnit] samples kept for the forecast.
+    pub const fn window(&self) -> usize {
+        self.window
+    }
+
+    /// Sets the number of [CashUnit] samples kept for the forecast.
+    pub fn set_window(&mut self, window: usize) {
+        self.window = window.max(2);
+    }
+
+    /// Builder function that sets the number of [CashUnit] samples kept for the forecast.
+    pub fn with_window(mut self, window: usize) -> Self {
+        self.set_window(window);
+        self
+    }
+
+    /// Gets the lead time of the alerts: units forecast to reach a threshold within the horizon
+    /// raise an alert.
+    pub const fn horizon(&self) -> Duration {
+        self.horizon
+    }
+
+    /// Sets the lead time of the alerts.
+    pub fn set_horizon(&mut self, horizon: Duration) {
+        self.horizon = horizon;
+    }
+
+    /// Builder function that sets the lead time of the alerts.
+    pub fn with_horizon(mut self, horizon: Duration) -> Self {
+        self.set_horizon(horizon);
+        self
+    }
+
+    /// Gets the number of bills the cashbox holds.
+    ///
+    /// If unset, the full [Threshold](crate::Threshold) level of the cashbox is used.
+    pub const fn cashbox_capacity(&self) -> Option<u32> {
+        self.cashbox_capacity
+    }
+
+    /// Sets the number of bills the cashbox holds.
+    pub fn set_cashbox_capacity(&mut self, capacity: u32) {
+        self.cashbox_capacity = Some(capacity);
+    }
+
+    /// Builder function that sets the number of bills the cashbox holds.
+    pub fn with_cashbox_capacity(mut self, capacity: u32) -> Self {
+        self.set_cashbox_capacity(capacity);
+        self
+    }
+
+    /// Gets the [DenominationThreshold]s.
+    pub fn thresholds(&self) -> &[DenominationThreshold] {
+        self.thresholds.as_ref()
+    }
+
+    /// Sets a [DenominationThreshold], replacing any threshold for the same denomination.
+    pub fn set_threshold(&mut self, threshold: DenominationThreshold) {
+        self.thresholds
+            .retain(|t| t.currency_code != threshold.currency_code || t.value != threshold.value);
+        self.thresholds.push(threshold);
+    }
+
+    /// Builder function that sets a [DenominationThreshold].
+    pub fn with_threshold(mut self, threshold: DenominationThreshold) -> Self {
+        self.set_threshold(threshold);
+        self
+    }
+
+    /// Gets the [DenominationThreshold] for the [CashType], if configured.
+    pub fn threshold(&self, cash_type: &CashType) -> Option<&DenominationThreshold> {
+        self.thresholds.iter().find(|t| t.matches(cash_type))
+    }
+}
+
+impl Default for MonitorConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Represents the forecast of a [PhysicalCashUnit](crate::PhysicalCashUnit) count.
+///
+/// The fill rate is the least-squares slope of the count samples.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct PcuForecast {
+    name: PcuName,
+    location: CashLocation,
+    cash_type: CashType,
+    count: u32,
+    low: u32,
+    high: u32,
+    full: u32,
+    rate: f64,
+}
+
+impl PcuForecast {
+    /// Gets the name of the [PhysicalCashUnit](crate::PhysicalCashUnit).
+    pub const fn name(&self) -> &PcuName {
+        &self.name
+    }
+
+    /// Gets the [CashLocation].
+    pub const fn location(&self) -> CashLocation {
+        self.location
+    }
+
+    /// Gets the [CashType] stored in the unit, empty for units storing several denominations.
+    pub const fn cash_type(&self) -> CashType {
+        self.cash_type
+    }
+
+    /// Gets the latest count.
+    pub const fn count(&self) -> u32 {
+        self.count
+    }
+
+    /// Gets the count at or below which the unit needs replenishment.
+    pub const fn low(&self) -> u32 {
+        self.low
+    }
+
+    /// Gets the count at or above which the unit needs collection, `0` if unknown.
+    pub const fn high(&self) -> u32 {
+        self.high
+    }
+
+    /// Gets the count at which the unit is full, `0` if unknown.
+    pub const fn full(&self) -> u32 {
+        self.full
+    }
+
+    /// Gets the fill rate in bills per hour, negative when the unit is emptying.
+    pub const fn rate(&self) -> f64 {
+        self.rate
+    }
+
+    /// Gets the estimated time until the count reaches `target`.
+    ///
+    /// Returns `None` if the count is not moving towards the target.
+    pub fn time_to(&self, target: u32) -> Option<Duration> {
+        let remaining = target as f64 - self.count as f64;
+
+        if remaining == 0.0 {
+            Some(Duration::ZERO)
+        } else if self.rate == 0.0 || remaining.signum() != self.rate.signum() {
+            None
+        } else {
+            Duration::try_from_secs_f64(remaining / self.rate * 3600.0).ok()
+        }
+    }
+
+    /// Gets the estimated time until the unit is empty.
+    pub fn time_to_empty(&self) -> Option<Duration> {
+        self.time_to(0)
+    }
+
+    /// Gets the estimated time until the unit reaches the [high](Self::high) count.
+    ///
+    /// Returns `None` if the high count is unknown.
+    pub fn time_to_high(&self) -> Option<Duration> {
+        (self.high != 0).then(|| self.time_to(self.high)).flatten()
+    }
+
+    /// Gets the estimated time until the unit reaches the [full](Self::full) count.
+    ///
+    /// Returns `None` if the full count is unknown.
+    pub fn time_to_full(&self) -> Option<Duration> {
+        (self.full != 0).then(|| self.time_to(self.full)).flatten()
+    }
+
+    /// Gets the estimated time until the unit reaches the [low](Self::low) count.
+    pub fn time_to_low(&self) -> Option<Duration> {
+        self.time_to(self.low)
+    }
+}
+
+impl fmt::Display for PcuForecast {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{{")?;
+        write!(f, r#""name": {}, "#, self.name)?;
+        write!(f, r#""location": {}, "#, self.location)?;
+        write!(f, r#""cash_type": {}, "#, self.cash_type)?;
+        write!(f, r#""count": {}, "#, self.count)?;
+        write!(f, r#""low": {}, "#, self.low)?;
+        write!(f, r#""high": {}, "#, self.high)?;
+        write!(f, r#""full": {}, "#, self.full)?;
+        write!(f, r#""rate": {:.2}"#, self.rate)?;
+        write!(f, "}}")
+    }
+}
+
+/// Represents the action a [ReplenishmentAlert] asks for.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
+pub enum AlertKind {
+    /// The unit is running out of bills: refill the loader, or deposit bills for recycling.
+    #[default]
+    Replenish,
+    /// The unit is filling up: collect the cashbox, or empty the recycler.
+    Collect,
+}
+
+impl From<&AlertKind> for &'static str {
+    fn from(val: &AlertKind) -> Self {
+        match val {
+            AlertKind::Replenish => "replenish",
+            AlertKind::Collect => "collect",
+        }
+    }
+}
+
+impl From<AlertKind> for &'static str {
+    fn from(val: AlertKind) -> Self {
+        (&val).into()
+    }
+}
+
+impl fmt::Display for AlertKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, r#""{}""#, <&str>::from(self))
+    }
+}
+
+/// Represents an alert raised when a unit reached, or is forecast to reach, a threshold.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct ReplenishmentAlert {
+    kind: AlertKind,
+    forecast: PcuForecast,
+    eta: Duration,
+}
+
+impl ReplenishmentAlert {
+    /// Gets the [AlertKind].
+    pub const fn kind(&self) -> AlertKind {
+        self.kind
+    }
+
+    /// Gets the [PcuForecast] of the unit.
+    pub const fn forecast(&self) -> &PcuForecast {
+        &self.forecast
+    }
+
+    /// Gets the estimated time until the threshold is reached, zero if already reached.
+    pub const fn eta(&self) -> Duration {
+        self.eta
+    }
+
+    /// Gets whether the threshold is already reached.
+    pub const fn is_reached(&self) -> bool {
+        self.eta.is_zero()
+    }
+}
+
+impl fmt::Display for ReplenishmentAlert {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{{")?;
+        write!(f, r#""kind": {}, "#, self.kind)?;
+        write!(f, r#""forecast": {}, "#, self.forecast)?;
+        write!(f, r#""eta_secs": {}"#, self.eta.as_secs())?;
+        write!(f, "}}")
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Sample {
+    time: SystemTime,
+    counts: Vec<(PcuName, u32)>,
+}
+
+/// Keeps a time series of [PhysicalCashUnit](crate::PhysicalCashUnit) counts from periodic
+/// [CashUnit] queries, and forecasts when units need replenishment or collection.
+///
+/// Thresholds:
+///
+/// - recyclers and loaders need replenishment at the low count
+/// - recyclers need collection at the high count, or the full count if no high count is set
+/// - the cashbox needs collection at its capacity
+///
+/// Counts come from the [DenominationThreshold] of the unit, or its
+/// [Threshold](crate::Threshold) levels.
+///
+/// Refills and collections break the fill rate: call [reset](Self::reset) after them.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CashUnitMonitor {
+    config: MonitorConfig,
+    samples: VecDeque<Sample>,
+    latest: CashUnit,
+}
+
+impl CashUnitMonitor {
+    /// Creates a new [CashUnitMonitor].
+    pub fn create(config: MonitorConfig) -> Self {
+        Self {
+            config,
+            samples: VecDeque::new(),
+            latest: CashUnit::new(),
+        }
+    }
+
+    /// Gets the [MonitorConfig].
+    pub const fn config(&self) -> &MonitorConfig {
+        &self.config
+    }
+
+    /// Gets a mutable reference to the [MonitorConfig].
+    pub fn config_mut(&mut self) -> &mut MonitorConfig {
+        &mut self.config
+    }
+
+    /// Gets the number of samples in the time series.
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    /// Gets whether the time series is empty.
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+
+    /// Records the [CashUnit] counts at the provided time.
+    pub fn record(&mut self, time: SystemTime, cash_unit: &CashUnit) {
+        self.samples.push_back(Sample {
+            time,
+            counts: cash_unit
+                .physical_cash_unit_list()
+                .iter()
+                .map(|p| (*p.name(), p.count()))
+                .collect(),
+        });
+
+        while self.samples.len() > self.config.window.max(2) {
+            self.samples.pop_front();
+        }
+
+        self.latest = cash_unit.clone();
+    }
+
+    /// Records the [CashUnit] counts at the current time.
+    pub fn record_now(&mut self, cash_unit: &CashUnit) {
+        self.record(SystemTime::now(), cash_unit);
+    }
+
+    /// Clears the time series, e.g. after a refill or a collection.
+    pub fn reset(&mut self) {
+        self.samples.clear();
+    }
+
+    /// Gets the [PcuForecast] of every recycler, loader and cashbox.
+    pub fn forecasts(&self) -> Vec<PcuForecast> {
+        let lcus = self.latest.logical_cash_unit_list();
+
+        self.latest
+            .physical_cash_unit_list()
+            .iter()
+            .enumerate()
+            .filter_map(|(i, pcu)| {
+                let active: Vec<_> = lcus
+                    .iter()
+                    .filter(|l| {
+                        l.physical_cu_index() as usize == i && l.cu_kind() != CuKind::NotAvailable
+                    })
+                    .collect();
+                let location = CashLocation::create(active.first()?.cu_kind());
+
+                let first = active[0].cash_type();
+                let cash_type = if active.iter().all(|l| {
+                    l.cash_type().currency_code() == first.currency_code()
+                        && l.cash_type().value() == first.value()
+                }) {
+                    first
+                } else {
+                    CashType::new()
+                };
+
+                let threshold = pcu.threshold();
+                let configured = self.config.threshold(&cash_type);
+                let full = match location {
+                    CashLocation::Cashbox => {
+                        self.config.cashbox_capacity.unwrap_or(threshold.full())
+                    }
+                    _ => threshold.full(),
+                };
+                let (low, high) = match location {
+                    CashLocation::Cashbox => (0, full),
+                    CashLocation::Loader => {
+                        (configured.map(|t| t.low).unwrap_or(threshold.low()), 0)
+                    }
+                    _ => (
+                        configured.map(|t| t.low).unwrap_or(threshold.low()),
+                        configured
+                            .map(|t| t.high)
+                            .filter(|&h| h != 0)
+                            .or(Some(threshold.high()).filter(|&h| h != 0))
+                            .unwrap_or(full),
+                    ),
+                };
+
+                Some(PcuForecast {
+                    name: *pcu.name(),
+                    location,
+                    cash_type,
+                    count: pcu.count(),
+                    low,
+                    high,
+                    full,
+                    rate: self.rate(pcu.name()),
+                })
+            })
+            .collect()
+    }
+
+    /// Gets the [ReplenishmentAlert]s for units that reached a threshold, or are forecast to reach
+    /// it within the [horizon](MonitorConfig::horizon).
+    pub fn alerts(&self) -> Vec<ReplenishmentAlert> {
+        let horizon = self.config.horizon;
+        let mut alerts = Vec::new();
+
+        for forecast in self.forecasts() {
+            if forecast.location.is_dispensable() {
+                let eta = if forecast.count <= forecast.low {
+                    Some(Duration::ZERO)
+                } else {
+                    forecast.time_to_low()
+                };
+
+                if let Some(eta) = eta.filter(|&e| e <= horizon) {
+                    alerts.push(ReplenishmentAlert {
+                        kind: AlertKind::Replenish,
+                        forecast,
+                        eta,
+                    });
+                }
+            }
+
+            if forecast.high != 0 {
+                let eta = if forecast.count >= forecast.high {
+                    Some(Duration::ZERO)
+                } else {
+                    forecast.time_to_high()
+                };
+
+                if let Some(eta) = eta.filter(|&e| e <= horizon) {
+                    alerts.push(ReplenishmentAlert {
+                        kind: AlertKind::Collect,
+                        forecast,
+                        eta,
+                    });
+                }
+            }
+        }
+
+        alerts.sort_by_key(|a| a.eta);
+
+        alerts
+    }
+
+    // Least-squares slope of the PCU counts, in bills per hour.
+    fn rate(&self, name: &PcuName) -> f64 {
+        let start = match self.samples.front() {
+            Some(s) => s.time,
+            None => return 0.0,
+        };
+
+        let points: Vec<(f64, f64)> = self
+            .samples
+            .iter()
+            .filter_map(|s| {
+                let count = s.counts.iter().find(|(n, _)| n == name)?.1;
+                let hours = match s.time.duration_since(start) {
+                    Ok(d) => d.as_secs_f64(),
+                    Err(e) => -e.duration().as_secs_f64(),
+                } / 3600.0;
+                Some((hours, count as f64))
+            })
+            .collect();
+
+        if points.len() < 2 {
+            return 0.0;
+        }
+
+        let n = points.len() as f64;
+        let mean_t = points.iter().map(|(t, _)| t).sum::<f64>() / n;
+        let mean_c = points.iter().map(|(_, c)| c).sum::<f64>() / n;
+
+        let (num, den) = points.iter().fold((0.0, 0.0), |(num, den), (t, c)| {
+            let dt = t - mean_t;
+            (num + dt * (c - mean_c), den + dt * dt)
+        });
+
+        if den == 0.0 {
+            0.0
+        } else {
+            num / den
+        }
+    }
+}
+
+#[cfg(feature = "usb")]
+impl CashUnitMonitor {
+    /// Queries the [CashUnit] from the device, records it, and returns the current
+    /// [ReplenishmentAlert]s.
+    pub fn poll(&mut self, handle: &DeviceHandle) -> Result<Vec<ReplenishmentAlert>> {
+        let cash_unit = handle.query_cash_unit()?;
+        self.record_now(&cash_unit);
+
+        let alerts = self.alerts();
+        for alert in alerts.iter() {
+            log::info!("Replenishment alert: {alert}");
+        }
+
+        Ok(alerts)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cash_unit::tests::cash_unit;
+    use crate::Result;
+
+    fn with_counts(cash_unit: &CashUnit, counts: &[(&str, u32)]) -> CashUnit {
+        let mut pcus = cash_unit.physical_cash_unit_list().clone();
+        for pcu in pcus.iter_mut() {
+            if let Some((_, count)) = counts
+                .iter()
+                .find(|(n, _)| pcu.name() == &PcuName::from(*n))
+            {
+                pcu.set_count(*count);
+            }
+        }
+        cash_unit.clone().with_physical_cash_unit_list(pcus)
+    }
+
+    fn hours(eta: Option<Duration>) -> Option<u64> {
+        eta.map(|d| (d.as_secs_f64() / 3600.0).round() as u64)
+    }
+
+    #[test]
+    fn test_cash_unit_monitor() -> Result<()> {
+        let usd = CurrencyCode::from("USD");
+        let base = cash_unit()?;
+        let hour = Duration::from_secs(3600);
+        let start = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+
+        let config = MonitorConfig::new()
+            .with_cashbox_capacity(150)
+            .with_threshold(DenominationThreshold::create(usd, 10_000, 2, 40))
+            .with_threshold(DenominationThreshold::create(usd, 2_000, 0, 30));
+        let mut monitor = CashUnitMonitor::create(config);
+
+        // RE3 ($100) dispenses 2 bills per hour, RE6 ($20) recycles 3 bills per hour, the cashbox
+        // takes 5 bills per hour
+        for i in 0..4u32 {
+            monitor.record(
+                start + hour * i,
+                &with_counts(
+                    &base,
+                    &[
+                        ("RE3", 20 - 2 * i),
+                        ("RE6", 18 + 3 * i),
+                        ("CB", 120 + 5 * i),
+                    ],
+                ),
+            );
+        }
+        assert_eq!(monitor.len(), 4);
+
+        let forecasts = monitor.forecasts();
+        let names: Vec<PcuName> = forecasts.iter().map(|f| *f.name()).collect();
+        assert_eq!(
+            names,
+            ["CB", "LO1", "RE3", "RE4", "RE5", "RE6"].map(PcuName::from)
+        );
+
+        let re3 = forecasts[2];
+        assert_eq!(re3.location(), CashLocation::Recycler);
+        assert_eq!(re3.cash_type().value(), 10_000);
+        assert_eq!((re3.count(), re3.low(), re3.high()), (14, 2, 40));
+        assert!((re3.rate() + 2.0).abs() < 1e-9);
+        assert_eq!(hours(re3.time_to_low()), Some(6));
+        assert_eq!(hours(re3.time_to_empty()), Some(7));
+        assert_eq!(re3.time_to_high(), None);
+
+        let cb = forecasts[0];
+        assert_eq!(cb.location(), CashLocation::Cashbox);
+        assert_eq!(cb.cash_type(), CashType::new());
+        assert_eq!((cb.high(), cb.full()), (150, 150));
+        assert_eq!(hours(cb.time_to_high()), Some(3));
+        assert_eq!(hours(cb.time_to_full()), Some(3));
+
+        let re6 = forecasts[5];
+        assert_eq!((re6.high(), re6.full()), (30, 0));
+        assert_eq!(hours(re6.time_to_high()), Some(1));
+        assert_eq!(re6.time_to_full(), None);
+
+        let alerts: Vec<(AlertKind, PcuName, u64)> = monitor
+            .alerts()
+            .iter()
+            .map(|a| {
+                (
+                    a.kind(),
+                    *a.forecast().name(),
+                    hours(Some(a.eta())).unwrap_or(0),
+                )
+            })
+            .collect();
+        assert_eq!(
+            alerts,
+            [
+                (AlertKind::Replenish, PcuName::from("LO1"), 0),
+                (AlertKind::Replenish, PcuName::from("RE4"), 0),
+                (AlertKind::Replenish, PcuName::from("RE5"), 0),
+                (AlertKind::Collect, PcuName::from("RE6"), 1),
+                (AlertKind::Collect, PcuName::from("CB"), 3),
+            ]
+        );
+
+        monitor.config_mut().set_horizon(hour * 6);
+        assert!(monitor
+            .alerts()
+            .iter()
+            .any(|a| a.kind() == AlertKind::Replenish
+                && a.forecast().name() == &PcuName::from("RE3")));
+
+        monitor.reset();
+        assert!(monitor.is_empty());
+        assert_eq!(monitor.forecasts()[2].rate(), 0.0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_forecast_time_to_full() {
+        let forecast = PcuForecast {
+            count: 20,
+            low: 5,
+            high: 40,
+            full: 60,
+            rate: 10.0,
+            ..Default::default()
+        };
+
+        assert_eq!(hours(forecast.time_to_high()), Some(2));
+        assert_eq!(hours(forecast.time_to_full()), Some(4));
+        assert_eq!(forecast.time_to_low(), None);
+        assert_eq!(forecast.time_to_empty(), None);
+        assert_eq!(forecast.time_to(20), Some(Duration::ZERO));
+    }
+
+    #[test]
+    fn test_forecast_flat_rate() {
+        let forecast = PcuForecast {
+            count: 20,
+            low: 5,
+            high: 40,
+            full: 60,
+            rate: 0.0,
+            ..Default::default()
+        };
+
+        assert_eq!(forecast.time_to_high(), None);
+        assert_eq!(forecast.time_to_full(), None);
+        assert_eq!(forecast.time_to_low(), None);
+        assert_eq!(forecast.time_to(20), Some(Duration::ZERO));
+    }
+
+    #[test]
+    fn test_forecast_negative_rate() {
+        let forecast = PcuForecast {
+            count: 20,
+            low: 5,
+            high: 40,
+            full: 60,
+            rate: -5.0,
+            ..Default::default()
+        };
+
+        assert_eq!(forecast.time_to_high(), None);
+        assert_eq!(forecast.time_to_full(), None);
+        assert_eq!(hours(forecast.time_to_low()), Some(3));
+        assert_eq!(hours(forecast.time_to_empty()), Some(4));
+    }
+
+    #[test]
+    fn test_monitor_single_sample() -> Result<()> {
+        let base = cash_unit()?;
+        let mut monitor = CashUnitMonitor::create(MonitorConfig::new().with_cashbox_capacity(150));
+
+        monitor.record(
+            SystemTime::UNIX_EPOCH,
+            &with_counts(&base, &[("RE3", 20), ("CB", 120)]),
+        );
+        assert_eq!(monitor.len(), 1);
+
+        let forecasts = monitor.forecasts();
+        assert!(forecasts.iter().all(|f| f.rate() == 0.0));
+        assert_eq!(forecasts[0].time_to_full(), None);
+
+        // with a flat rate only the units already past a threshold alert, RE3 is above its
+        // device high threshold of one bill
+        let alerts: Vec<(AlertKind, PcuName, Duration)> = monitor
+            .alerts()
+            .iter()
+            .map(|a| (a.kind(), *a.forecast().name(), a.eta()))
+            .collect();
+        assert_eq!(
+            alerts,
+            [
+                (AlertKind::Replenish, PcuName::from("LO1"), Duration::ZERO),
+                (AlertKind::Collect, PcuName::from("RE3"), Duration::ZERO),
+                (AlertKind::Replenish, PcuName::from("RE4"), Duration::ZERO),
+                (AlertKind::Replenish, PcuName::from("RE5"), Duration::ZERO),
+                (AlertKind::Replenish, PcuName::from("RE6"), Duration::ZERO),
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_monitor_no_thresholds() -> Result<()> {
+        let base = cash_unit()?;
+        let hour = Duration::from_secs(3600);
+        let mut monitor = CashUnitMonitor::create(MonitorConfig::new());
+
+        // RE6 has no device thresholds, and no configured threshold for its denomination
+        for i in 0..3u32 {
+            monitor.record(
+                SystemTime::UNIX_EPOCH + hour * i,
+                &with_counts(&base, &[("RE6", 10 + 10 * i)]),
+            );
+        }
+
+        let re6 = monitor.forecasts()[5];
+        assert_eq!(re6.name(), &PcuName::from("RE6"));
+        assert_eq!((re6.low(), re6.high(), re6.full()), (0, 0, 0));
+        assert!((re6.rate() - 10.0).abs() < 1e-9);
+        assert_eq!(re6.time_to_high(), None);
+        assert_eq!(re6.time_to_full(), None);
+        assert_eq!(re6.time_to_low(), None);
+
+        assert!(!monitor
+            .alerts()
+            .iter()
+            .any(|a| a.forecast().name() == &PcuName::from("RE6")));
+
+        Ok(())
+    }
+}
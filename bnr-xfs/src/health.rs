@@ -0,0 +1,588 @@
+//! Types for evaluating the overall health of the BNR device.
+
+use std::fmt;
+
+use crate::currency::{CuKind, LCU};
+use crate::json::JsonStr;
+use crate::status::{
+    CdrStatus, ContentStatus, DeviceStatus, DispenserStatus, IntermediateStackerStatus,
+    SafeDoorStatus, TransportStatus,
+};
+use crate::{BnrError, CashLocation, CashUnit, Error, PcuName, ThresholdStatus};
+
+/// Represents the overall health verdict of the BNR device.
+#[repr(C)]
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    Default,
+    Eq,
+    Ord,
+    PartialEq,
+    PartialOrd,
+    serde::Deserialize,
+    serde::Serialize,
+)]
+pub enum HealthStatus {
+    /// The device is fully operational.
+    #[default]
+    Operational,
+    /// The device operates with reduced functionality, e.g. it cannot dispense.
+    Degraded,
+    /// The device cannot accept or dispense cash.
+    OutOfService,
+}
+
+impl From<&HealthStatus> for &'static str {
+    fn from(val: &HealthStatus) -> Self {
+        match val {
+            HealthStatus::Operational => "operational",
+            HealthStatus::Degraded => "degraded",
+            HealthStatus::OutOfService => "out of service",
+        }
+    }
+}
+
+impl From<HealthStatus> for &'static str {
+    fn from(val: HealthStatus) -> Self {
+        (&val).into()
+    }
+}
+
+impl fmt::Display for HealthStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, r#""{}""#, <&str>::from(self))
+    }
+}
+
+/// Represents a machine-readable reason for a [HealthStatus] verdict.
+#[repr(C)]
+#[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub enum HealthReason {
+    /// The device status could not be read.
+    DeviceUnreachable { error: String },
+    /// The device is not online.
+    DeviceNotOnline { status: DeviceStatus },
+    /// The transport system is inoperable.
+    TransportInoperable,
+    /// The transport system status is unknown.
+    TransportUnknown,
+    /// The safe door is open.
+    SafeDoorOpen,
+    /// The dispenser cannot dispense.
+    DispenserNotOk { status: DispenserStatus },
+    /// Bills are held in the intermediate stacker (escrow).
+    EscrowNotEmpty,
+    /// Bills are left at an inlet or outlet position.
+    CashAtPosition,
+    /// A physical cash unit is missing.
+    UnitMissing {
+        location: CashLocation,
+        pcu_name: PcuName,
+    },
+    /// A physical cash unit is inoperable.
+    UnitInoperable {
+        location: CashLocation,
+        pcu_name: PcuName,
+    },
+    /// The cashbox is full.
+    CashboxFull { pcu_name: PcuName },
+    /// The cashbox is almost full.
+    CashboxHigh { pcu_name: PcuName },
+    /// The last error reported by the BNR.
+    DeviceError { error: BnrError },
+    /// The last error was a USB communication failure.
+    CommunicationError { error: String },
+    /// The last error, of another kind.
+    LastError { error: String },
+}
+
+impl HealthReason {
+    /// Gets the machine-readable code of the reason.
+    pub const fn code(&self) -> &'static str {
+        match self {
+            Self::DeviceUnreachable { .. } => "device_unreachable",
+            Self::DeviceNotOnline { .. } => "device_not_online",
+            Self::TransportInoperable => "transport_inoperable",
+            Self::TransportUnknown => "transport_unknown",
+            Self::SafeDoorOpen => "safe_door_open",
+            Self::DispenserNotOk { .. } => "dispenser_not_ok",
+            Self::EscrowNotEmpty => "escrow_not_empty",
+            Self::CashAtPosition => "cash_at_position",
+            Self::UnitMissing { .. } => "unit_missing",
+            Self::UnitInoperable { .. } => "unit_inoperable",
+            Self::CashboxFull { .. } => "cashbox_full",
+            Self::CashboxHigh { .. } => "cashbox_high",
+            Self::DeviceError { .. } => "device_error",
+            Self::CommunicationError { .. } => "communication_error",
+            Self::LastError { .. } => "last_error",
+        }
+    }
+
+    /// Gets the [HealthStatus] implied by the reason.
+    ///
+    /// A missing or inoperable cashbox stops the device from storing bills, while a missing or
+    /// inoperable recycler, loader or bundler only reduces its functionality.
+    pub fn severity(&self) -> HealthStatus {
+        match self {
+            Self::DeviceUnreachable { .. }
+            | Self::DeviceNotOnline { .. }
+            | Self::TransportInoperable
+            | Self::SafeDoorOpen
+            | Self::CashboxFull { .. }
+            | Self::CommunicationError { .. } => HealthStatus::OutOfService,
+            Self::UnitMissing { location, .. } | Self::UnitInoperable { location, .. } => {
+                if location.is_deposit_only() {
+                    HealthStatus::OutOfService
+                } else {
+                    HealthStatus::Degraded
+                }
+            }
+            Self::DeviceError { error } => {
+                if error.is_warning() {
+                    HealthStatus::Degraded
+                } else {
+                    HealthStatus::OutOfService
+                }
+            }
+            Self::TransportUnknown
+            | Self::DispenserNotOk { .. }
+            | Self::EscrowNotEmpty
+            | Self::CashAtPosition
+            | Self::CashboxHigh { .. }
+            | Self::LastError { .. } => HealthStatus::Degraded,
+        }
+    }
+}
+
+impl fmt::Display for HealthReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, r#"{{"code": "{}""#, self.code())?;
+        match self {
+            Self::DeviceUnreachable { error }
+            | Self::CommunicationError { error }
+            | Self::LastError { error } => write!(f, r#", "error": {}"#, JsonStr(error))?,
+            Self::DeviceNotOnline { status } => write!(f, r#", "status": "{status}""#)?,
+            Self::DispenserNotOk { status } => write!(f, r#", "status": "{status}""#)?,
+            Self::UnitMissing { location, pcu_name }
+            | Self::UnitInoperable { location, pcu_name } => {
+                write!(f, r#", "location": {location}, "pcu_name": {pcu_name}"#)?
+            }
+            Self::CashboxFull { pcu_name } | Self::CashboxHigh { pcu_name } => {
+                write!(f, r#", "pcu_name": {pcu_name}"#)?
+            }
+            Self::DeviceError { error } => write!(f, r#", "error": {error}"#)?,
+            Self::TransportInoperable
+            | Self::TransportUnknown
+            | Self::SafeDoorOpen
+            | Self::EscrowNotEmpty
+            | Self::CashAtPosition => (),
+        }
+        write!(f, "}}")
+    }
+}
+
+/// Combines the [CdrStatus], the [CashUnit] statuses and the last error into a single
+/// [HealthStatus] verdict, with the [HealthReason]s behind it.
+///
+/// The verdict is the most severe [HealthReason::severity], or
+/// [Operational](HealthStatus::Operational) without any reason.
+#[derive(Clone, Debug, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct HealthReport {
+    status: HealthStatus,
+    reasons: Vec<HealthReason>,
+}
+
+impl HealthReport {
+    /// Creates a new [HealthReport].
+    pub const fn new() -> Self {
+        Self {
+            status: HealthStatus::Operational,
+            reasons: Vec::new(),
+        }
+    }
+
+    /// Evaluates the device health from the [CdrStatus], the [CashUnit] and the last error.
+    pub fn create(status: &CdrStatus, cash_unit: &CashUnit, last_error: Option<&Error>) -> Self {
+        let mut reasons = Vec::new();
+
+        status_reasons(status, &mut reasons);
+        cash_unit_reasons(cash_unit, &mut reasons);
+
+        if let Some(err) = last_error {
+            reasons.push(error_reason(err));
+        }
+
+        Self::from_reasons(reasons)
+    }
+
+    /// Creates a new [HealthReport] for a device whose status could not be read.
+    pub fn unreachable(err: &Error) -> Self {
+        Self::from_reasons(vec![HealthReason::DeviceUnreachable {
+            error: format!("{err}"),
+        }])
+    }
+
+    fn from_reasons(reasons: Vec<HealthReason>) -> Self {
+        Self {
+            status: reasons
+                .iter()
+                .map(|r| r.severity())
+                .max()
+                .unwrap_or_default(),
+            reasons,
+        }
+    }
+
+    /// Gets the [HealthStatus] verdict.
+    pub const fn status(&self) -> HealthStatus {
+        self.status
+    }
+
+    /// Gets the list of [HealthReason]s.
+    pub fn reasons(&self) -> &[HealthReason] {
+        self.reasons.as_ref()
+    }
+
+    /// Gets whether the device is fully operational.
+    pub fn is_operational(&self) -> bool {
+        self.status == HealthStatus::Operational
+    }
+
+    /// Gets whether the device can accept cash, i.e. it is not out of service.
+    pub fn accepts_cash(&self) -> bool {
+        self.status != HealthStatus::OutOfService
+    }
+}
+
+impl fmt::Display for HealthReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{{")?;
+        write!(f, r#""status": {}, "#, self.status)?;
+        write!(f, r#""reasons": ["#)?;
+        for (i, reason) in self.reasons.iter().enumerate() {
+            if i != 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{reason}")?;
+        }
+        write!(f, "]}}")
+    }
+}
+
+fn status_reasons(status: &CdrStatus, reasons: &mut Vec<HealthReason>) {
+    match status.device_status {
+        DeviceStatus::Online | DeviceStatus::Changed => (),
+        device_status => reasons.push(HealthReason::DeviceNotOnline {
+            status: device_status,
+        }),
+    }
+
+    match status.transport_status {
+        TransportStatus::Inoperable => reasons.push(HealthReason::TransportInoperable),
+        TransportStatus::Unknown => reasons.push(HealthReason::TransportUnknown),
+        TransportStatus::Ok | TransportStatus::Changed => (),
+    }
+
+    if status.safe_door_status == SafeDoorStatus::Open {
+        reasons.push(HealthReason::SafeDoorOpen);
+    }
+
+    if matches!(
+        status.dispenser_status,
+        DispenserStatus::State | DispenserStatus::Stop | DispenserStatus::Unknown
+    ) {
+        reasons.push(HealthReason::DispenserNotOk {
+            status: status.dispenser_status,
+        });
+    }
+
+    if status.intermediate_stacker_status == IntermediateStackerStatus::NotEmpty {
+        reasons.push(HealthReason::EscrowNotEmpty);
+    }
+
+    if status
+        .position_status_list
+        .iter()
+        .any(|p| p.content_status == ContentStatus::NotEmpty)
+    {
+        reasons.push(HealthReason::CashAtPosition);
+    }
+}
+
+fn cash_unit_reasons(cash_unit: &CashUnit, reasons: &mut Vec<HealthReason>) {
+    let lcus = cash_unit.logical_cash_unit_list();
+
+    for (idx, pcu) in cash_unit.physical_cash_unit_list().iter().enumerate() {
+        let location = lcus
+            .iter()
+            .find(|l| l.physical_cu_index() as usize == idx && l.cu_kind() != CuKind::NotAvailable)
+            .map(|l| CashLocation::create(l.cu_kind()))
+            .unwrap_or_default();
+        let pcu_name = *pcu.name();
+
+        match LCU::create(pcu.status()) {
+            LCU::Missing => reasons.push(HealthReason::UnitMissing { location, pcu_name }),
+            LCU::Inoperable => reasons.push(HealthReason::UnitInoperable { location, pcu_name }),
+            status if location == CashLocation::Cashbox => {
+                if status == LCU::Full || pcu.threshold_status() == ThresholdStatus::Full {
+                    reasons.push(HealthReason::CashboxFull { pcu_name });
+                } else if status == LCU::High || pcu.threshold_status() == ThresholdStatus::High {
+                    reasons.push(HealthReason::CashboxHigh { pcu_name });
+                }
+            }
+            _ => (),
+        }
+    }
+}
+
+fn error_reason(err: &Error) -> HealthReason {
+    match err {
+        Error::Bnr(error) => HealthReason::DeviceError { error: *error },
+        Error::Usb(_) | Error::BnrUsb(_) => HealthReason::CommunicationError {
+            error: format!("{err}"),
+        },
+        _ => HealthReason::LastError {
+            error: format!("{err}"),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cash_unit::tests::cash_unit;
+    use crate::currency::{LCU_FULL, LCU_MISSING};
+    use crate::status::{CdrPositionStatusList, ShutterStatus};
+    use crate::Result;
+
+    fn status() -> CdrStatus {
+        CdrStatus {
+            device_status: DeviceStatus::Online,
+            dispenser_status: DispenserStatus::Ok,
+            intermediate_stacker_status: IntermediateStackerStatus::Empty,
+            safe_door_status: SafeDoorStatus::Locked,
+            shutter_status: ShutterStatus::NotSupported,
+            transport_status: TransportStatus::Ok,
+            position_status_list: CdrPositionStatusList::new(),
+        }
+    }
+
+    fn with_pcu_status(cash_unit: &CashUnit, idx: usize, status: u32) -> CashUnit {
+        let mut pcu_list = cash_unit.physical_cash_unit_list().clone();
+        pcu_list.items_mut()[idx].set_status(status);
+        cash_unit.clone().with_physical_cash_unit_list(pcu_list)
+    }
+
+    fn codes(report: &HealthReport) -> Vec<&'static str> {
+        report.reasons().iter().map(|r| r.code()).collect()
+    }
+
+    #[test]
+    fn test_health_report_operational() -> Result<()> {
+        let report = HealthReport::create(&status(), &cash_unit()?, None);
+
+        assert_eq!(report.status(), HealthStatus::Operational);
+        assert!(report.reasons().is_empty());
+        assert!(report.is_operational());
+        assert!(report.accepts_cash());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_health_report_degraded() -> Result<()> {
+        // recycler RE3 missing, escrow not empty
+        let degraded = with_pcu_status(&cash_unit()?, 3, LCU_MISSING);
+        let mut escrow = status();
+        escrow.intermediate_stacker_status = IntermediateStackerStatus::NotEmpty;
+
+        let report = HealthReport::create(&escrow, &degraded, None);
+        assert_eq!(report.status(), HealthStatus::Degraded);
+        assert_eq!(
+            report.reasons(),
+            [
+                HealthReason::EscrowNotEmpty,
+                HealthReason::UnitMissing {
+                    location: CashLocation::Recycler,
+                    pcu_name: PcuName::from("RE3"),
+                },
+            ]
+        );
+        assert!(!report.is_operational());
+        assert!(report.accepts_cash());
+        assert_eq!(
+            format!("{}", report.reasons()[1]),
+            r#"{"code": "unit_missing", "location": "recycler", "pcu_name": "RE3"}"#
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_health_report_out_of_service() -> Result<()> {
+        // cashbox full, transport inoperable
+        let full = with_pcu_status(&cash_unit()?, 1, LCU_FULL);
+        let mut inoperable = status();
+        inoperable.transport_status = TransportStatus::Inoperable;
+
+        let report = HealthReport::create(&inoperable, &full, None);
+        assert_eq!(report.status(), HealthStatus::OutOfService);
+        assert_eq!(codes(&report), ["transport_inoperable", "cashbox_full"]);
+        assert!(!report.accepts_cash());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_health_report_out_of_service_outranks_degraded() -> Result<()> {
+        // cashbox missing is out of service, the recycler missing only degraded
+        let cash_unit = with_pcu_status(&cash_unit()?, 1, LCU_MISSING);
+        let cash_unit = with_pcu_status(&cash_unit, 3, LCU_MISSING);
+
+        let report = HealthReport::create(&status(), &cash_unit, None);
+        assert_eq!(report.status(), HealthStatus::OutOfService);
+        assert_eq!(
+            report
+                .reasons()
+                .iter()
+                .map(|r| r.severity())
+                .collect::<Vec<_>>(),
+            [HealthStatus::OutOfService, HealthStatus::Degraded]
+        );
+        assert!(!report.accepts_cash());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_health_report_device_status() -> Result<()> {
+        let cash_unit = cash_unit()?;
+
+        let mut offline = status();
+        offline.device_status = DeviceStatus::HardwareError;
+        let report = HealthReport::create(&offline, &cash_unit, None);
+        assert_eq!(report.status(), HealthStatus::OutOfService);
+        assert_eq!(codes(&report), ["device_not_online"]);
+
+        let mut door = status();
+        door.safe_door_status = SafeDoorStatus::Open;
+        let report = HealthReport::create(&door, &cash_unit, None);
+        assert_eq!(report.status(), HealthStatus::OutOfService);
+        assert_eq!(codes(&report), ["safe_door_open"]);
+
+        let mut changed = status();
+        changed.device_status = DeviceStatus::Changed;
+        let report = HealthReport::create(&changed, &cash_unit, None);
+        assert_eq!(report.status(), HealthStatus::Operational);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_health_report_last_error_bnr() -> Result<()> {
+        let cash_unit = cash_unit()?;
+
+        let warning =
+            HealthReport::create(&status(), &cash_unit, Some(&Error::Bnr(BnrError::W_CB01)));
+        assert_eq!(warning.status(), HealthStatus::Degraded);
+        assert_eq!(
+            warning.reasons(),
+            [HealthReason::DeviceError {
+                error: BnrError::W_CB01
+            }]
+        );
+
+        let fatal =
+            HealthReport::create(&status(), &cash_unit, Some(&Error::Bnr(BnrError::E_CB09)));
+        assert_eq!(fatal.status(), HealthStatus::OutOfService);
+        assert_eq!(codes(&fatal), ["device_error"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_health_report_last_error_communication() -> Result<()> {
+        let report = HealthReport::create(
+            &status(),
+            &cash_unit()?,
+            Some(&Error::Usb("timeout".into())),
+        );
+
+        assert_eq!(report.status(), HealthStatus::OutOfService);
+        assert_eq!(codes(&report), ["communication_error"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_health_report_last_error_other() -> Result<()> {
+        let report = HealthReport::create(
+            &status(),
+            &cash_unit()?,
+            Some(&Error::Config("no mix table".into())),
+        );
+
+        assert_eq!(report.status(), HealthStatus::Degraded);
+        assert_eq!(
+            report.reasons(),
+            [HealthReason::LastError {
+                error: "Configuration error: no mix table".into()
+            }]
+        );
+        assert!(report.accepts_cash());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_health_report_last_error_adds_to_status() -> Result<()> {
+        let mut escrow = status();
+        escrow.intermediate_stacker_status = IntermediateStackerStatus::NotEmpty;
+
+        let report =
+            HealthReport::create(&escrow, &cash_unit()?, Some(&Error::Bnr(BnrError::E_CB09)));
+
+        assert_eq!(report.status(), HealthStatus::OutOfService);
+        assert_eq!(codes(&report), ["escrow_not_empty", "device_error"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_health_report_unreachable() -> Result<()> {
+        let report = HealthReport::unreachable(&Error::Usb("No such device".into()));
+
+        assert_eq!(report.status(), HealthStatus::OutOfService);
+        assert_eq!(codes(&report), ["device_unreachable"]);
+        assert!(!report.accepts_cash());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_health_report_json() -> Result<()> {
+        // quotes and control characters in the error message do not break the JSON
+        let report = HealthReport::unreachable(&Error::Usb("no \"bnr\" device\n".into()));
+        let json: serde_json::Value = serde_json::from_str(&report.to_string())?;
+
+        assert_eq!(json["status"], "out of service");
+        assert_eq!(json["reasons"][0]["code"], "device_unreachable");
+        assert!(json["reasons"][0]["error"]
+            .as_str()
+            .is_some_and(|e| e.contains("\"bnr\" device\n")));
+
+        let full = with_pcu_status(&cash_unit()?, 1, LCU_FULL);
+        let report = HealthReport::create(&status(), &full, Some(&Error::Bnr(BnrError::W_CB01)));
+        let json: serde_json::Value = serde_json::from_str(&report.to_string())?;
+
+        assert_eq!(json["reasons"][0]["pcu_name"], "CB");
+        assert_eq!(
+            json["reasons"][1]["error"],
+            "W_CB01: Cashbox is almost full"
+        );
+
+        Ok(())
+    }
+}
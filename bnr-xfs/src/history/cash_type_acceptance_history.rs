@@ -5,8 +5,6 @@ use crate::{
 };
 
 pub const CASH_TYPE_HISTORY_LIST_LEN: usize = 61;
-pub const CASH_TYPE_HISTORY_DEFAULT: CashTypeAcceptanceHistory = CashTypeAcceptanceHistory::new();
-
 create_xfs_struct!(
     CashTypeAcceptanceHistory,
     "cashTypeAcceptanceHistory",
@@ -27,6 +25,5 @@ create_xfs_array!(
     "cashTypeAcceptanceHistoryItems",
     CashTypeAcceptanceHistory,
     CASH_TYPE_HISTORY_LIST_LEN,
-    CASH_TYPE_HISTORY_DEFAULT,
     "Represents a list of [CashTypeAcceptanceHistory] items."
 );
@@ -1,7 +1,6 @@
 use crate::{create_xfs_array, create_xfs_struct};
 use crate::{BillNotAvailableCount, BillRequestedCount, CashType};
 
-const CASH_TYPE_HISTORY_DEFAULT: CashTypeDispenseHistory = CashTypeDispenseHistory::new();
 const CASH_TYPE_HISTORY_LEN: usize = 61;
 
 create_xfs_struct!(
@@ -20,6 +19,5 @@ create_xfs_array!(
     "cashTypeDispenseHistoryItems",
     CashTypeDispenseHistory,
     CASH_TYPE_HISTORY_LEN,
-    CASH_TYPE_HISTORY_DEFAULT,
     "Represents a list of [CashTypeDispenseHistory] items."
 );
@@ -2,8 +2,6 @@ use crate::{create_xfs_array, create_xfs_struct};
 use crate::{CashType, StackedWhileRecyclerFullCount};
 
 pub const CASH_TYPE_RECYCLE_LIST_LEN: usize = 10;
-pub const CASH_TYPE_RECYCLE_DEFAULT: CashTypeRecycleHistoryItem = CashTypeRecycleHistoryItem::new();
-
 create_xfs_struct!(
     CashTypeRecycleHistoryItem,
     "cashTypeRecycleHistoryItem",
@@ -19,6 +17,5 @@ create_xfs_array!(
     "cashTypeRecycleHistoryItems",
     CashTypeRecycleHistoryItem,
     CASH_TYPE_RECYCLE_LIST_LEN,
-    CASH_TYPE_RECYCLE_DEFAULT,
     "Represents a list of [CashTypeRecycleHistoryItem] items."
 );
@@ -5,8 +5,6 @@ use crate::{
 };
 
 pub const SLOT_HISTORY_LIST_LEN: usize = 4;
-pub const LOADER_SLOT_DEFAULT: LoaderSlotAcceptanceHistory = LoaderSlotAcceptanceHistory::new();
-
 create_xfs_i4!(SlotNumber, "slotNumber", "Represents the slot number.");
 
 create_xfs_struct!(
@@ -28,6 +26,5 @@ create_xfs_array!(
     "slots",
     LoaderSlotAcceptanceHistory,
     SLOT_HISTORY_LIST_LEN,
-    LOADER_SLOT_DEFAULT,
     "Represents a list of [LoaderSlotAcceptanceHistory] items."
 );
@@ -13,13 +13,8 @@ use crate::{
 const BILL_ENDING_COUNTERS_LEN: usize = 4;
 const INCIDENT_START_COUNTERS_LEN: usize = 25;
 
-const UNNAMED_COUNT_DEFAULT: UnnamedCount = UnnamedCount::new();
-
 const MODULE_COUNT_LIST_LEN: usize = 10;
-const MODULE_COUNT_DEFAULT: ModuleCount = ModuleCount::new();
-
 const INTERFACE_COUNT_LIST_LEN: usize = 10;
-const INTERFACE_COUNT_DEFAULT: InterfaceCount = InterfaceCount::new();
 
 create_xfs_struct!(
     MainModuleSectionCounters,
@@ -53,7 +48,6 @@ create_xfs_array!(
     "moduleCountList",
     ModuleCount,
     MODULE_COUNT_LIST_LEN,
-    MODULE_COUNT_DEFAULT,
     "Represents a list of module count items."
 );
 
@@ -62,7 +56,6 @@ create_xfs_array!(
     "interfaceCountList",
     InterfaceCount,
     INTERFACE_COUNT_LIST_LEN,
-    INTERFACE_COUNT_DEFAULT,
     "Represents a list of interface count items."
 );
 
@@ -71,7 +64,6 @@ create_xfs_array!(
     "billEndingInMMSectionCounters",
     UnnamedCount,
     BILL_ENDING_COUNTERS_LEN,
-    UNNAMED_COUNT_DEFAULT,
     "Represents a list of bill ending in main module section count items."
 );
 
@@ -80,7 +72,6 @@ create_xfs_array!(
     "incidentStartSectionCounters",
     UnnamedCount,
     INCIDENT_START_COUNTERS_LEN,
-    UNNAMED_COUNT_DEFAULT,
     "Represents a list of incident start section count items."
 );
 
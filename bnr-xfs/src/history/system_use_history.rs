@@ -3,8 +3,6 @@ use crate::{create_xfs_array, create_xfs_date_time, create_xfs_i4, create_xfs_st
 use crate::{Error, Result};
 
 const SENSOR_TEMPS_LEN: usize = 4;
-const SENSOR_TEMP_DEFAULT: SensorTemperature = SensorTemperature::new();
-
 create_xfs_date_time!(
     CurrentDateTime,
     "currentDateTime",
@@ -48,7 +46,6 @@ create_xfs_array!(
     "recognitionSensorTemperatures",
     SensorTemperature,
     SENSOR_TEMPS_LEN,
-    SENSOR_TEMP_DEFAULT,
     "Represents the device recognition sensor temperatures."
 );
 
@@ -0,0 +1,996 @@
+//! Types for the persistent journal of money-moving calls.
+
+use std::fmt;
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::json::JsonStr;
+use crate::status::{CdrStatus, ContentStatus, IntermediateStackerStatus};
+use crate::xfs::OperationId;
+use crate::{CashUnit, Result, ResyncReport, StaleOperation};
+
+// Maximum number of completions kept while waiting for their call ID to be recorded.
+const MAX_EARLY_COMPLETIONS: usize = 8;
+
+/// Represents a money-moving call recorded in the [Journal].
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JournalOperation {
+    /// `CashInStart` call.
+    #[default]
+    CashInStart,
+    /// `CashIn` call.
+    CashIn,
+    /// `CashInEnd` call.
+    CashInEnd,
+    /// `CashInRollback` call.
+    CashInRollback,
+    /// `Dispense` call.
+    Dispense,
+    /// `Present` call.
+    Present,
+    /// `Retract` call.
+    Retract,
+    /// `Empty` call.
+    Empty,
+}
+
+impl From<&JournalOperation> for &'static str {
+    fn from(val: &JournalOperation) -> Self {
+        match val {
+            JournalOperation::CashInStart => "cash_in_start",
+            JournalOperation::CashIn => "cash_in",
+            JournalOperation::CashInEnd => "cash_in_end",
+            JournalOperation::CashInRollback => "cash_in_rollback",
+            JournalOperation::Dispense => "dispense",
+            JournalOperation::Present => "present",
+            JournalOperation::Retract => "retract",
+            JournalOperation::Empty => "empty",
+        }
+    }
+}
+
+impl From<JournalOperation> for &'static str {
+    fn from(val: JournalOperation) -> Self {
+        (&val).into()
+    }
+}
+
+impl From<&JournalOperation> for OperationId {
+    fn from(val: &JournalOperation) -> Self {
+        match val {
+            JournalOperation::CashInStart => Self::CashInStart,
+            JournalOperation::CashIn => Self::CashIn,
+            JournalOperation::CashInEnd => Self::CashInEnd,
+            JournalOperation::CashInRollback => Self::CashInRollback,
+            JournalOperation::Dispense => Self::Dispense,
+            JournalOperation::Present => Self::Present,
+            JournalOperation::Retract => Self::Retract,
+            JournalOperation::Empty => Self::Empty,
+        }
+    }
+}
+
+impl From<JournalOperation> for OperationId {
+    fn from(val: JournalOperation) -> Self {
+        (&val).into()
+    }
+}
+
+impl fmt::Display for JournalOperation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, r#""{}""#, <&str>::from(self))
+    }
+}
+
+/// Represents the count of a logical cash unit, recorded when a call is journaled.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct LcuCount {
+    number: u32,
+    count: u32,
+}
+
+impl LcuCount {
+    /// Creates a new [LcuCount].
+    pub const fn create(number: u32, count: u32) -> Self {
+        Self { number, count }
+    }
+
+    /// Gets the logical cash unit number.
+    pub const fn number(&self) -> u32 {
+        self.number
+    }
+
+    /// Gets the count.
+    pub const fn count(&self) -> u32 {
+        self.count
+    }
+
+    /// Gets the counts of all logical cash units in the [CashUnit].
+    pub fn list(cash_unit: &CashUnit) -> Vec<Self> {
+        cash_unit
+            .logical_cash_unit_list()
+            .iter()
+            .map(|l| Self::create(l.number(), l.count()))
+            .collect()
+    }
+}
+
+// A line of the journal file.
+#[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+enum JournalRecord {
+    Begin {
+        seq: u64,
+        time: u64,
+        operation: JournalOperation,
+        detail: String,
+        counts: Vec<LcuCount>,
+    },
+    Accepted {
+        seq: u64,
+        call_id: i32,
+    },
+    Complete {
+        seq: u64,
+        result: i32,
+    },
+    Failed {
+        seq: u64,
+        error: String,
+    },
+    Recovered {
+        seq: u64,
+        outcome: RecoveryOutcome,
+    },
+    Session {
+        seq: u64,
+        restarts: Option<u32>,
+    },
+}
+
+impl JournalRecord {
+    const fn seq(&self) -> u64 {
+        match self {
+            Self::Begin { seq, .. }
+            | Self::Accepted { seq, .. }
+            | Self::Complete { seq, .. }
+            | Self::Failed { seq, .. }
+            | Self::Recovered { seq, .. }
+            | Self::Session { seq, .. } => *seq,
+        }
+    }
+}
+
+/// Represents a journaled call that has not finished: no completion, failure or recovery was
+/// recorded for it.
+#[derive(Clone, Debug, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct JournalEntry {
+    seq: u64,
+    time: u64,
+    operation: JournalOperation,
+    detail: String,
+    counts: Vec<LcuCount>,
+    call_id: Option<i32>,
+    restarts: Option<u32>,
+}
+
+impl JournalEntry {
+    /// Gets the sequence number of the entry in the journal.
+    pub const fn seq(&self) -> u64 {
+        self.seq
+    }
+
+    /// Gets the time the call was journaled, in seconds since the Unix epoch.
+    pub const fn time(&self) -> u64 {
+        self.time
+    }
+
+    /// Gets the [JournalOperation].
+    pub const fn operation(&self) -> JournalOperation {
+        self.operation
+    }
+
+    /// Gets the call parameters, e.g. the dispense request.
+    pub fn detail(&self) -> &str {
+        self.detail.as_str()
+    }
+
+    /// Gets the logical cash unit counts before the call was sent.
+    pub fn counts(&self) -> &[LcuCount] {
+        self.counts.as_ref()
+    }
+
+    /// Gets the call ID returned by the device, if the call was acknowledged.
+    pub const fn call_id(&self) -> Option<i32> {
+        self.call_id
+    }
+
+    /// Gets the device restart count of the session the call was journaled in, if known, see
+    /// [Journal::session].
+    pub const fn restarts(&self) -> Option<u32> {
+        self.restarts
+    }
+}
+
+impl fmt::Display for JournalEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{{")?;
+        write!(f, r#""seq": {}, "#, self.seq)?;
+        write!(f, r#""time": {}, "#, self.time)?;
+        write!(f, r#""operation": {}, "#, self.operation)?;
+        write!(f, r#""detail": {}, "#, JsonStr(&self.detail))?;
+        match self.call_id {
+            Some(id) => write!(f, r#""call_id": {id}"#)?,
+            None => write!(f, r#""call_id": null"#)?,
+        }
+        write!(f, "}}")
+    }
+}
+
+/// Append-only journal of the money-moving calls sent to the device.
+///
+/// Every call is recorded before it is sent, with the logical cash unit counts, then updated with
+/// the call ID returned by the device, and the operation result. Each record is a JSON line,
+/// flushed to disk before the journal call returns.
+///
+/// Entries left unfinished by a crash or power loss are reconciled with the device state when
+/// the journal is enabled again, see [RecoveryReport].
+#[derive(Debug)]
+pub struct Journal {
+    path: PathBuf,
+    file: File,
+    next_seq: u64,
+    pending: Vec<JournalEntry>,
+    early: Vec<(i32, i32)>,
+    restarts: Option<u32>,
+}
+
+impl Journal {
+    /// Opens the journal file at `path`, creating it if missing.
+    ///
+    /// Reads the existing records to find the unfinished entries. A partially written last
+    /// record, e.g. after a power loss, is ignored.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let mut file = OpenOptions::new()
+            .create(true)
+            .read(true)
+            .append(true)
+            .open(&path)?;
+
+        let mut contents = String::new();
+        file.read_to_string(&mut contents)?;
+
+        // terminate a partially written record, so the next record starts on a new line
+        if !contents.is_empty() && !contents.ends_with('\n') {
+            file.write_all(b"\n")?;
+            file.sync_data()?;
+        }
+
+        let mut journal = Self {
+            path,
+            file,
+            next_seq: 1,
+            pending: Vec::new(),
+            early: Vec::new(),
+            restarts: None,
+        };
+
+        for line in contents.lines().filter(|l| !l.trim().is_empty()) {
+            match serde_json::from_str::<JournalRecord>(line) {
+                Ok(record) => journal.apply(record),
+                Err(err) => log::warn!("Ignoring invalid journal record: {err}: {line}"),
+            }
+        }
+
+        Ok(journal)
+    }
+
+    /// Gets the path of the journal file.
+    pub fn path(&self) -> &Path {
+        self.path.as_path()
+    }
+
+    /// Gets the unfinished [JournalEntry]s.
+    pub fn pending(&self) -> &[JournalEntry] {
+        self.pending.as_ref()
+    }
+
+    /// Records a call before it is sent to the device.
+    ///
+    /// Returns the sequence number of the new entry.
+    pub fn begin(
+        &mut self,
+        operation: JournalOperation,
+        detail: &str,
+        cash_unit: &CashUnit,
+    ) -> Result<u64> {
+        let time = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+
+        // completions received before this call was sent cannot belong to it
+        self.early.clear();
+
+        let seq = self.next_seq;
+        self.append(JournalRecord::Begin {
+            seq,
+            time,
+            operation,
+            detail: detail.into(),
+            counts: LcuCount::list(cash_unit),
+        })?;
+
+        Ok(seq)
+    }
+
+    /// Records the call ID returned by the device for the entry.
+    ///
+    /// Also records the result, if the operation completed before the call ID was recorded.
+    pub fn accepted(&mut self, seq: u64, call_id: i32) -> Result<()> {
+        self.append(JournalRecord::Accepted { seq, call_id })?;
+
+        match self.early.iter().position(|&(id, _)| id == call_id) {
+            Some(pos) => {
+                let (_, result) = self.early.remove(pos);
+                self.complete(seq, result)
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// Records the result of the operation started by the entry.
+    pub fn complete(&mut self, seq: u64, result: i32) -> Result<()> {
+        self.append(JournalRecord::Complete { seq, result })
+    }
+
+    /// Records the result of the pending operation with the `call_id`, if any.
+    ///
+    /// Returns whether a pending entry matched the `call_id`. Unmatched results are kept until
+    /// the next call is journaled, in case the operation completes before its call ID is
+    /// recorded with [accepted](Self::accepted).
+    pub fn complete_call(&mut self, call_id: i32, result: i32) -> Result<bool> {
+        match self
+            .pending
+            .iter()
+            .rev()
+            .find(|e| e.call_id == Some(call_id))
+            .map(|e| e.seq)
+        {
+            Some(seq) => self.complete(seq, result).map(|_| true),
+            None => {
+                if self.early.len() >= MAX_EARLY_COMPLETIONS {
+                    self.early.remove(0);
+                }
+                self.early.push((call_id, result));
+                Ok(false)
+            }
+        }
+    }
+
+    /// Records a call that failed before the device returned a call ID.
+    pub fn fail(&mut self, seq: u64, error: &str) -> Result<()> {
+        self.append(JournalRecord::Failed {
+            seq,
+            error: error.into(),
+        })
+    }
+
+    /// Records the start of a session with the device, and its restart count, if known.
+    ///
+    /// Call IDs restart with the device: the entries journaled after this record only match
+    /// device operations while the restart count is unchanged, see [RecoveryReport::reconcile].
+    pub fn session(&mut self, restarts: Option<u32>) -> Result<()> {
+        let seq = self.next_seq;
+        self.append(JournalRecord::Session { seq, restarts })
+    }
+
+    /// Records the [RecoveryOutcome] of an unfinished entry, and closes it.
+    pub fn recover(&mut self, seq: u64, outcome: RecoveryOutcome) -> Result<()> {
+        self.append(JournalRecord::Recovered { seq, outcome })
+    }
+
+    fn append(&mut self, record: JournalRecord) -> Result<()> {
+        let mut line = serde_json::to_string(&record)?;
+        line.push('\n');
+
+        self.file.write_all(line.as_bytes())?;
+        self.file.sync_data()?;
+
+        self.apply(record);
+
+        Ok(())
+    }
+
+    fn apply(&mut self, record: JournalRecord) {
+        self.next_seq = self.next_seq.max(record.seq().saturating_add(1));
+
+        match record {
+            JournalRecord::Begin {
+                seq,
+                time,
+                operation,
+                detail,
+                counts,
+            } => self.pending.push(JournalEntry {
+                seq,
+                time,
+                operation,
+                detail,
+                counts,
+                call_id: None,
+                restarts: self.restarts,
+            }),
+            JournalRecord::Accepted { seq, call_id } => {
+                if let Some(entry) = self.pending.iter_mut().find(|e| e.seq == seq) {
+                    entry.call_id = Some(call_id);
+                }
+            }
+            JournalRecord::Complete { seq, .. }
+            | JournalRecord::Failed { seq, .. }
+            | JournalRecord::Recovered { seq, .. } => self.pending.retain(|e| e.seq != seq),
+            JournalRecord::Session { restarts, .. } => self.restarts = restarts,
+        }
+    }
+}
+
+/// Represents what happened to an unfinished [JournalEntry], as found on the device.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RecoveryOutcome {
+    /// The operation completed successfully while the host was down.
+    Completed,
+    /// The operation completed with an error while the host was down.
+    Failed,
+    /// The operation is still running on the device.
+    Running,
+    /// No completion was found, but the cash unit counts changed: the operation ran at least
+    /// partially.
+    CountsChanged,
+    /// No completion was found, and the cash unit counts did not change.
+    #[default]
+    NoEffect,
+    /// No completion was found, and the cash unit counts changed while an earlier operation was
+    /// also running: the change cannot be attributed to this operation.
+    Unknown,
+}
+
+impl From<&RecoveryOutcome> for &'static str {
+    fn from(val: &RecoveryOutcome) -> Self {
+        match val {
+            RecoveryOutcome::Completed => "completed",
+            RecoveryOutcome::Failed => "failed",
+            RecoveryOutcome::Running => "running",
+            RecoveryOutcome::CountsChanged => "counts changed",
+            RecoveryOutcome::NoEffect => "no effect",
+            RecoveryOutcome::Unknown => "unknown",
+        }
+    }
+}
+
+impl From<RecoveryOutcome> for &'static str {
+    fn from(val: RecoveryOutcome) -> Self {
+        (&val).into()
+    }
+}
+
+impl fmt::Display for RecoveryOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, r#""{}""#, <&str>::from(self))
+    }
+}
+
+/// Represents a change of a logical cash unit count while a call was the last one journaled.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct CountChange {
+    number: u32,
+    before: u32,
+    after: u32,
+}
+
+impl CountChange {
+    /// Creates a new [CountChange].
+    pub const fn create(number: u32, before: u32, after: u32) -> Self {
+        Self {
+            number,
+            before,
+            after,
+        }
+    }
+
+    /// Gets the logical cash unit number.
+    pub const fn number(&self) -> u32 {
+        self.number
+    }
+
+    /// Gets the count when the call was journaled.
+    pub const fn before(&self) -> u32 {
+        self.before
+    }
+
+    /// Gets the count when the next call was journaled, or the current count for the last call.
+    pub const fn after(&self) -> u32 {
+        self.after
+    }
+
+    // Gets the changes between the counts recorded `before` and `after`.
+    fn list(before: &[LcuCount], after: &[LcuCount]) -> Vec<Self> {
+        before
+            .iter()
+            .filter_map(|b| {
+                let count = after
+                    .iter()
+                    .find(|a| a.number == b.number)
+                    .map(|a| a.count)
+                    .unwrap_or(0);
+                (count != b.count).then_some(Self::create(b.number, b.count, count))
+            })
+            .collect()
+    }
+}
+
+impl fmt::Display for CountChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{{")?;
+        write!(f, r#""number": {}, "#, self.number)?;
+        write!(f, r#""before": {}, "#, self.before)?;
+        write!(f, r#""after": {}"#, self.after)?;
+        write!(f, "}}")
+    }
+}
+
+/// Represents an unfinished [JournalEntry] reconciled with the device state.
+#[derive(Clone, Debug, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct RecoveredEntry {
+    entry: JournalEntry,
+    outcome: RecoveryOutcome,
+    result: Option<i32>,
+    count_changes: Vec<CountChange>,
+}
+
+impl RecoveredEntry {
+    /// Gets the unfinished [JournalEntry].
+    pub const fn entry(&self) -> &JournalEntry {
+        &self.entry
+    }
+
+    /// Gets the [RecoveryOutcome].
+    pub const fn outcome(&self) -> RecoveryOutcome {
+        self.outcome
+    }
+
+    /// Gets the operation result reported by the device, if it completed.
+    pub const fn result(&self) -> Option<i32> {
+        self.result
+    }
+
+    /// Gets the logical cash unit count changes between the call, and the next unfinished call,
+    /// or the current counts for the last one.
+    pub fn count_changes(&self) -> &[CountChange] {
+        self.count_changes.as_ref()
+    }
+}
+
+impl fmt::Display for RecoveredEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{{")?;
+        write!(f, r#""entry": {}, "#, self.entry)?;
+        write!(f, r#""outcome": {}, "#, self.outcome)?;
+        match self.result {
+            Some(res) => write!(f, r#""result": {res}, "#)?,
+            None => write!(f, r#""result": null, "#)?,
+        }
+        write!(f, r#""count_changes": ["#)?;
+        for (i, change) in self.count_changes.iter().enumerate() {
+            if i != 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{change}")?;
+        }
+        write!(f, "]}}")
+    }
+}
+
+/// Describes what actually happened to the calls left unfinished in the [Journal].
+///
+/// Each unfinished entry is reconciled with:
+///
+/// - the operations drained from the device when the connection was opened, see [ResyncReport]
+/// - the logical cash unit counts recorded with the next unfinished entry, or the current counts
+///   for the last entry
+///
+/// The device status tells whether bills are left at the outlet, or in the intermediate stacker.
+#[derive(Clone, Debug, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct RecoveryReport {
+    entries: Vec<RecoveredEntry>,
+    cash_at_outlet: bool,
+    cash_in_stacker: bool,
+}
+
+impl RecoveryReport {
+    /// Creates a new [RecoveryReport].
+    pub const fn new() -> Self {
+        Self {
+            entries: Vec::new(),
+            cash_at_outlet: false,
+            cash_in_stacker: false,
+        }
+    }
+
+    /// Reconciles the unfinished [JournalEntry]s with the device state.
+    ///
+    /// `restarts` is the current device restart count, see [Journal::session]. Call IDs restart
+    /// with the device, so an operation drained on resync only matches entries journaled with the
+    /// same restart count, and with the same operation. If the count is unknown, no match is
+    /// trusted.
+    pub fn reconcile(
+        pending: &[JournalEntry],
+        resync: &ResyncReport,
+        cash_unit: &CashUnit,
+        status: &CdrStatus,
+        restarts: Option<u32>,
+    ) -> Self {
+        let current = LcuCount::list(cash_unit);
+        let mut overlapped = false;
+
+        let entries = pending
+            .iter()
+            .enumerate()
+            .map(|(i, entry)| {
+                let next = pending.get(i + 1).map(|e| e.counts()).unwrap_or(&current);
+                let count_changes = CountChange::list(&entry.counts, next);
+
+                let matches = |op: &StaleOperation| {
+                    restarts.is_some()
+                        && entry.restarts == restarts
+                        && Some(op.call_id()) == entry.call_id
+                        && op.operation_id() == OperationId::from(entry.operation)
+                };
+
+                let completed = resync
+                    .completed_operations()
+                    .iter()
+                    .find(|op| matches(op))
+                    .and_then(|op| op.result());
+                let running = resync.pending_operation().is_some_and(matches);
+
+                let outcome = match completed {
+                    Some(0) => RecoveryOutcome::Completed,
+                    Some(_) => RecoveryOutcome::Failed,
+                    None if running => RecoveryOutcome::Running,
+                    None if count_changes.is_empty() => RecoveryOutcome::NoEffect,
+                    None if overlapped => RecoveryOutcome::Unknown,
+                    None => RecoveryOutcome::CountsChanged,
+                };
+
+                // an earlier operation may still move cash after the next call is journaled
+                overlapped |= completed.is_some() || running;
+
+                RecoveredEntry {
+                    entry: entry.clone(),
+                    outcome,
+                    result: completed,
+                    count_changes,
+                }
+            })
+            .collect();
+
+        Self {
+            entries,
+            cash_at_outlet: resync.cash_presented()
+                || status
+                    .position_status_list
+                    .iter()
+                    .any(|p| p.content_status == ContentStatus::NotEmpty),
+            cash_in_stacker: status.intermediate_stacker_status
+                == IntermediateStackerStatus::NotEmpty,
+        }
+    }
+
+    /// Gets the reconciled entries.
+    pub fn entries(&self) -> &[RecoveredEntry] {
+        self.entries.as_ref()
+    }
+
+    /// Gets whether bills are left at the outlet.
+    pub const fn cash_at_outlet(&self) -> bool {
+        self.cash_at_outlet
+    }
+
+    /// Gets whether bills are left in the intermediate stacker.
+    pub const fn cash_in_stacker(&self) -> bool {
+        self.cash_in_stacker
+    }
+
+    /// Gets whether no unfinished calls were found, and no bills are left in transit.
+    pub fn is_clean(&self) -> bool {
+        self.entries.is_empty() && !self.cash_at_outlet && !self.cash_in_stacker
+    }
+}
+
+impl fmt::Display for RecoveryReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{{")?;
+        write!(f, r#""entries": ["#)?;
+        for (i, entry) in self.entries.iter().enumerate() {
+            if i != 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{entry}")?;
+        }
+        write!(f, "], ")?;
+        write!(f, r#""cash_at_outlet": {}, "#, self.cash_at_outlet)?;
+        write!(f, r#""cash_in_stacker": {}"#, self.cash_in_stacker)?;
+        write!(f, "}}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cash_unit::tests::cash_unit;
+    use crate::xfs::method_call::{XfsMethodCall, XfsMethodName};
+    use crate::xfs::{params::XfsParam, value::XfsValue};
+
+    // Gets a journal file path unique to the test.
+    fn journal_path(name: &str) -> PathBuf {
+        let path =
+            std::env::temp_dir().join(format!("bnr-journal-{name}-{}.jsonl", std::process::id()));
+        std::fs::remove_file(&path).ok();
+        path
+    }
+
+    fn entry(seq: u64, operation: JournalOperation, counts: &CashUnit) -> JournalEntry {
+        JournalEntry {
+            seq,
+            time: 1_700_000_000,
+            operation,
+            detail: String::new(),
+            counts: LcuCount::list(counts),
+            call_id: Some(seq as i32),
+            restarts: Some(3),
+        }
+    }
+
+    // Gets a copy of the `cash_unit` with the count of the logical cash unit `number` changed.
+    fn with_count(cash_unit: &CashUnit, number: u32, delta: i64) -> CashUnit {
+        let mut res = cash_unit.clone();
+        if let Some(lcu) = res
+            .logical_cash_unit_list_mut()
+            .iter_mut()
+            .find(|l| l.number() == number)
+        {
+            lcu.set_count((lcu.count() as i64 + delta) as u32);
+        }
+        res
+    }
+
+    fn completed(call_id: i32, operation: OperationId, result: i32) -> ResyncReport {
+        let mut resync = ResyncReport::new();
+        resync.discard_callback(&XfsMethodCall::create(
+            XfsMethodName::OperationCompleteOccurred,
+            [call_id, operation.inner() as i32, result, 0]
+                .map(|p| XfsParam::create(XfsValue::new().with_i4(p))),
+        ));
+        resync
+    }
+
+    fn outcomes(report: &RecoveryReport) -> Vec<RecoveryOutcome> {
+        report.entries().iter().map(|e| e.outcome()).collect()
+    }
+
+    #[test]
+    fn test_journal_replay() -> Result<()> {
+        let path = journal_path("replay");
+        let before = cash_unit()?;
+        {
+            let mut journal = Journal::open(&path)?;
+            journal.session(Some(3))?;
+
+            let done = journal.begin(JournalOperation::CashInStart, "", &before)?;
+            journal.accepted(done, 3)?;
+            assert!(journal.complete_call(3, 0)?);
+
+            let failed = journal.begin(JournalOperation::Present, "", &before)?;
+            journal.fail(failed, "usb error")?;
+
+            let dispense = journal.begin(JournalOperation::Dispense, "20.00 USD", &before)?;
+            journal.accepted(dispense, 7)?;
+            journal.begin(JournalOperation::Empty, "RE3", &before)?;
+
+            assert_eq!(journal.pending().len(), 2);
+        }
+
+        // power loss in the middle of a record
+        OpenOptions::new()
+            .append(true)
+            .open(&path)?
+            .write_all(br#"{"event":"begin","seq":7,"ti"#)?;
+
+        let mut journal = Journal::open(&path)?;
+        let pending = journal.pending();
+        assert_eq!(pending.len(), 2);
+        assert_eq!(pending[0].operation(), JournalOperation::Dispense);
+        assert_eq!(pending[0].detail(), "20.00 USD");
+        assert_eq!(pending[0].call_id(), Some(7));
+        assert_eq!(pending[0].restarts(), Some(3));
+        assert_eq!(pending[0].counts(), LcuCount::list(&before));
+        assert_eq!(pending[1].call_id(), None);
+
+        // the next record starts on a new line, after the partial record
+        let seqs: Vec<u64> = pending.iter().map(|e| e.seq()).collect();
+        for seq in seqs {
+            journal.recover(seq, RecoveryOutcome::NoEffect)?;
+        }
+        let next = journal.begin(JournalOperation::Retract, "", &before)?;
+        assert!(next > 5);
+
+        let journal = Journal::open(&path)?;
+        assert_eq!(journal.pending().len(), 1);
+        assert_eq!(journal.pending()[0].operation(), JournalOperation::Retract);
+
+        std::fs::remove_file(&path).ok();
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_journal_early_completions() -> Result<()> {
+        let path = journal_path("early");
+        let before = cash_unit()?;
+        let mut journal = Journal::open(&path)?;
+
+        // the operation completes before its call ID is recorded
+        let present = journal.begin(JournalOperation::Present, "", &before)?;
+        assert!(!journal.complete_call(9, 0)?);
+        journal.accepted(present, 9)?;
+        assert!(journal.pending().is_empty());
+
+        // only the latest unmatched completions are kept
+        let dispense = journal.begin(JournalOperation::Dispense, "", &before)?;
+        for id in 10..=(10 + MAX_EARLY_COMPLETIONS as i32) {
+            assert!(!journal.complete_call(id, 0)?);
+        }
+        journal.accepted(dispense, 10)?;
+        assert_eq!(journal.pending().len(), 1);
+
+        let retract = journal.begin(JournalOperation::Retract, "", &before)?;
+        assert!(!journal.complete_call(30, 0)?);
+
+        // completions received before a call is journaled cannot belong to it
+        let empty = journal.begin(JournalOperation::Empty, "", &before)?;
+        journal.accepted(empty, 30)?;
+        journal.accepted(retract, 31)?;
+        assert_eq!(journal.pending().len(), 3);
+
+        std::fs::remove_file(&path).ok();
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_recovery_attribution() -> Result<()> {
+        let before = cash_unit()?;
+        let pending = [
+            entry(7, JournalOperation::Dispense, &before),
+            entry(8, JournalOperation::Retract, &before),
+            entry(9, JournalOperation::Empty, &before),
+        ];
+
+        // the dispense completed while the host was down, and took one bill from RE3
+        let after = with_count(&before, 7, -1);
+        let resync = completed(7, OperationId::Dispense, 0);
+
+        // the change is seen after the last entry: not blamed on it, since the dispense was
+        // still running, nor on the retract in between
+        let report =
+            RecoveryReport::reconcile(&pending, &resync, &after, &CdrStatus::new(), Some(3));
+        assert_eq!(
+            outcomes(&report),
+            [
+                RecoveryOutcome::Completed,
+                RecoveryOutcome::NoEffect,
+                RecoveryOutcome::Unknown
+            ]
+        );
+        assert_eq!(report.entries()[0].result(), Some(0));
+        assert!(report.entries()[1].count_changes().is_empty());
+        assert_eq!(
+            report.entries()[2].count_changes(),
+            [CountChange::create(7, 1, 0)]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_recovery_count_windows() -> Result<()> {
+        let before = cash_unit()?;
+        let dispensed = with_count(&before, 7, -1);
+
+        // the dispense moved a bill before the retract was journaled
+        let pending = [
+            entry(7, JournalOperation::Dispense, &before),
+            entry(8, JournalOperation::Retract, &dispensed),
+        ];
+
+        let report = RecoveryReport::reconcile(
+            &pending,
+            &ResyncReport::new(),
+            &dispensed,
+            &CdrStatus::new(),
+            Some(3),
+        );
+        assert_eq!(
+            outcomes(&report),
+            [RecoveryOutcome::CountsChanged, RecoveryOutcome::NoEffect]
+        );
+        assert_eq!(
+            report.entries()[0].count_changes(),
+            [CountChange::create(7, 1, 0)]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_recovery_stale_call_ids() -> Result<()> {
+        let before = cash_unit()?;
+        let pending = [entry(7, JournalOperation::Dispense, &before)];
+        let after = with_count(&before, 7, -1);
+        let resync = completed(7, OperationId::Dispense, 0);
+
+        // the device restarted since the entry was journaled: call ID 7 is another call
+        for restarts in [Some(4), None] {
+            let report =
+                RecoveryReport::reconcile(&pending, &resync, &after, &CdrStatus::new(), restarts);
+            assert_eq!(outcomes(&report), [RecoveryOutcome::CountsChanged]);
+            assert_eq!(report.entries()[0].result(), None);
+        }
+
+        // a completion of another operation with the same call ID does not match
+        let other = completed(7, OperationId::CashIn, 0);
+        let report =
+            RecoveryReport::reconcile(&pending, &other, &after, &CdrStatus::new(), Some(3));
+        assert_eq!(outcomes(&report), [RecoveryOutcome::CountsChanged]);
+
+        let failed = completed(7, OperationId::Dispense, 1);
+        let report =
+            RecoveryReport::reconcile(&pending, &failed, &before, &CdrStatus::new(), Some(3));
+        assert_eq!(outcomes(&report), [RecoveryOutcome::Failed]);
+        assert_eq!(report.entries()[0].result(), Some(1));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_recovery_clean() -> Result<()> {
+        let before = cash_unit()?;
+
+        let report =
+            RecoveryReport::reconcile(&[], &ResyncReport::new(), &before, &CdrStatus::new(), None);
+        assert!(report.entries().is_empty());
+        assert!(report.is_clean());
+
+        let pending = [entry(7, JournalOperation::Dispense, &before)];
+        let report = RecoveryReport::reconcile(
+            &pending,
+            &ResyncReport::new(),
+            &before,
+            &CdrStatus::new(),
+            Some(3),
+        );
+        assert_eq!(outcomes(&report), [RecoveryOutcome::NoEffect]);
+        assert!(!report.is_clean());
+
+        Ok(())
+    }
+}
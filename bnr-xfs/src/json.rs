@@ -0,0 +1,31 @@
+//! Helpers for the JSON-style [Display](std::fmt::Display) implementations.
+
+use std::fmt;
+
+/// Formats a string as a quoted JSON string, escaping quotes, backslashes and control characters.
+///
+/// Used for free-form text, e.g. operator names or error messages, in `Display` implementations.
+pub(crate) struct JsonStr<'a>(pub &'a str);
+
+impl fmt::Display for JsonStr<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // serializing a `str` cannot fail
+        let json = serde_json::to_string(self.0).map_err(|_| fmt::Error)?;
+        f.write_str(json.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_json_str() {
+        let text = "J. \"Jo\" Doe\\\n\tRÉ3 \u{1}";
+        let json = JsonStr(text).to_string();
+
+        assert_eq!(json, r#""J. \"Jo\" Doe\\\n\tRÉ3 \u0001""#);
+        assert_eq!(serde_json::from_str::<String>(&json).unwrap(), text);
+        assert_eq!(JsonStr("").to_string(), r#""""#);
+    }
+}
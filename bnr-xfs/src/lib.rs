@@ -1,34 +1,65 @@
-pub(crate) mod arrays;
+// lets the derive macros refer to `::bnr_xfs` paths from inside the crate
+extern crate self as bnr_xfs;
+
+mod callback;
 mod callback_response;
 mod capabilities;
+mod capture;
+mod cash_in;
 mod cash_unit;
+mod collection;
 mod config;
+mod correction;
 mod counts;
 mod currency;
 mod denominations;
+#[cfg(feature = "usb")]
 pub mod device_handle;
 mod dispense;
 mod error;
+mod forecast;
+mod health;
 mod history;
 mod intermediate_event;
+mod journal;
+mod json;
+mod list;
 #[macro_use]
 mod macros;
+mod payment;
+mod refill;
+mod resync;
 mod status;
 pub mod xfs;
 
+pub use callback::*;
 pub use callback_response::*;
 pub use capabilities::*;
+pub use capture::*;
+pub use cash_in::*;
 pub use cash_unit::*;
+pub use collection::*;
 pub use config::*;
+pub use correction::*;
 pub use counts::*;
 pub use currency::*;
 pub use denominations::*;
+#[cfg(feature = "usb")]
 pub use device_handle::*;
 pub use dispense::*;
 pub use error::*;
+pub use forecast::*;
+pub use health::*;
 pub use history::*;
 pub use intermediate_event::*;
+pub use journal::*;
+pub use list::*;
+pub use payment::*;
+pub use refill::*;
+pub use resync::*;
 pub use status::*;
 
+pub use bnr_xfs_derive::{XfsArray, XfsEnum, XfsStruct};
+
 create_xfs_i4!(Size, "size", "Represents the size of a list.");
 create_xfs_i4!(MaxSize, "maxSize", "Represents the maximum size of a list.");
@@ -0,0 +1,313 @@
+//! Growable list type with a fixed maximum length.
+
+use std::{fmt, ops, slice, sync::Arc};
+
+use crate::{Error, Result};
+
+/// Growable list of up to `N` items.
+///
+/// Used as the storage for the XFS list types, e.g. [LogicalCashUnitItems](crate::LogicalCashUnitItems).
+/// `N` is the device maximum for the list, and operations that would exceed it fail, or truncate the input.
+///
+/// Items are shared between clones, so cloning is cheap. The items are copied on the first write
+/// to a shared list.
+pub struct BoundedList<T, const N: usize> {
+    items: Option<Arc<Vec<T>>>,
+}
+
+impl<T, const N: usize> BoundedList<T, N> {
+    /// Creates a new, empty [BoundedList].
+    pub const fn new() -> Self {
+        Self { items: None }
+    }
+
+    /// Gets the maximum number of items.
+    pub const fn max_size() -> usize {
+        N
+    }
+
+    /// Gets the number of items.
+    pub fn len(&self) -> usize {
+        self.as_slice().len()
+    }
+
+    /// Gets whether the list is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Gets whether the list is at the maximum length.
+    pub fn is_full(&self) -> bool {
+        self.len() >= N
+    }
+
+    /// Gets a reference to the items.
+    pub fn as_slice(&self) -> &[T] {
+        match self.items.as_ref() {
+            Some(items) => items.as_slice(),
+            None => &[],
+        }
+    }
+
+    /// Gets an iterator over the items.
+    pub fn iter(&self) -> slice::Iter<'_, T> {
+        self.as_slice().iter()
+    }
+
+    /// Removes all items.
+    pub fn clear(&mut self) {
+        self.items = None;
+    }
+}
+
+impl<T: Clone, const N: usize> BoundedList<T, N> {
+    /// Creates a new [BoundedList] from the provided items.
+    ///
+    /// Returns an error if there are more than `N` items.
+    pub fn create<I: IntoIterator<Item = T>>(items: I) -> Result<Self> {
+        let mut res = Self::new();
+        for item in items {
+            res.push(item)?;
+        }
+        Ok(res)
+    }
+
+    /// Gets a mutable reference to the items.
+    pub fn as_mut_slice(&mut self) -> &mut [T] {
+        match self.items.as_mut() {
+            Some(items) => Arc::make_mut(items).as_mut_slice(),
+            None => &mut [],
+        }
+    }
+
+    /// Gets a mutable iterator over the items.
+    pub fn iter_mut(&mut self) -> slice::IterMut<'_, T> {
+        self.as_mut_slice().iter_mut()
+    }
+
+    /// Appends an item to the end of the list.
+    ///
+    /// Returns an error if the list is full.
+    pub fn push(&mut self, item: T) -> Result<()> {
+        if self.is_full() {
+            Err(Error::Xfs(format!("list is at the maximum length: {N}")))
+        } else {
+            self.inner_mut().push(item);
+            Ok(())
+        }
+    }
+
+    /// Removes the last item from the list.
+    pub fn pop(&mut self) -> Option<T> {
+        match self.items.as_mut() {
+            Some(items) => Arc::make_mut(items).pop(),
+            None => None,
+        }
+    }
+
+    /// Shortens the list to `len` items.
+    ///
+    /// No-op if the list already has `len` items or less.
+    pub fn truncate(&mut self, len: usize) {
+        if len < self.len() {
+            self.inner_mut().truncate(len);
+        }
+    }
+
+    /// Sets the items, ignoring any items past the maximum length.
+    pub fn set_items(&mut self, items: &[T]) {
+        let len = items.len().min(N);
+        if len < items.len() {
+            log::warn!(
+                "Ignoring {} items past the maximum list length: {N}",
+                items.len() - len
+            );
+        }
+
+        self.items = (len != 0).then(|| Arc::new(items[..len].to_vec()));
+    }
+
+    fn inner_mut(&mut self) -> &mut Vec<T> {
+        Arc::make_mut(self.items.get_or_insert_with(|| Arc::new(Vec::new())))
+    }
+}
+
+impl<T: Clone + Default, const N: usize> BoundedList<T, N> {
+    /// Resizes the list to `len` items, filling new items with the default value.
+    ///
+    /// No-op if `len` is larger than the maximum length.
+    pub fn resize(&mut self, len: usize) {
+        if len <= N && len != self.len() {
+            self.inner_mut().resize_with(len, T::default);
+        }
+    }
+}
+
+impl<T, const N: usize> Clone for BoundedList<T, N> {
+    fn clone(&self) -> Self {
+        Self {
+            items: self.items.clone(),
+        }
+    }
+}
+
+impl<T, const N: usize> Default for BoundedList<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: fmt::Debug, const N: usize> fmt::Debug for BoundedList<T, N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T: PartialEq, const N: usize> PartialEq for BoundedList<T, N> {
+    fn eq(&self, oth: &Self) -> bool {
+        self.as_slice() == oth.as_slice()
+    }
+}
+
+impl<T: Eq, const N: usize> Eq for BoundedList<T, N> {}
+
+impl<T, const N: usize> ops::Deref for BoundedList<T, N> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        self.as_slice()
+    }
+}
+
+impl<T, const N: usize> AsRef<[T]> for BoundedList<T, N> {
+    fn as_ref(&self) -> &[T] {
+        self.as_slice()
+    }
+}
+
+impl<'a, T, const N: usize> IntoIterator for &'a BoundedList<T, N> {
+    type Item = &'a T;
+    type IntoIter = slice::Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<T: Clone, const N: usize> IntoIterator for BoundedList<T, N> {
+    type Item = T;
+    type IntoIter = std::vec::IntoIter<T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        match self.items {
+            Some(items) => Arc::try_unwrap(items)
+                .unwrap_or_else(|items| items.as_ref().clone())
+                .into_iter(),
+            None => Vec::new().into_iter(),
+        }
+    }
+}
+
+impl<T: Clone, const N: usize> TryFrom<Vec<T>> for BoundedList<T, N> {
+    type Error = Error;
+
+    fn try_from(val: Vec<T>) -> Result<Self> {
+        if val.len() > N {
+            Err(Error::Xfs(format!(
+                "list length is over the maximum, have: {}, max: {N}",
+                val.len()
+            )))
+        } else {
+            Ok(Self {
+                items: (!val.is_empty()).then(|| Arc::new(val)),
+            })
+        }
+    }
+}
+
+impl<T: Clone, const N: usize> TryFrom<&[T]> for BoundedList<T, N> {
+    type Error = Error;
+
+    fn try_from(val: &[T]) -> Result<Self> {
+        val.to_vec().try_into()
+    }
+}
+
+impl<T: serde::Serialize, const N: usize> serde::Serialize for BoundedList<T, N> {
+    fn serialize<S: serde::Serializer>(&self, ser: S) -> std::result::Result<S::Ok, S::Error> {
+        self.as_slice().serialize(ser)
+    }
+}
+
+impl<'de, T: Clone + serde::Deserialize<'de>, const N: usize> serde::Deserialize<'de>
+    for BoundedList<T, N>
+{
+    fn deserialize<D: serde::Deserializer<'de>>(de: D) -> std::result::Result<Self, D::Error> {
+        Vec::<T>::deserialize(de)?
+            .try_into()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bounded_list() -> Result<()> {
+        let mut list = BoundedList::<u32, 3>::new();
+
+        assert!(list.is_empty());
+        assert_eq!(BoundedList::<u32, 3>::max_size(), 3);
+
+        list.push(1)?;
+        list.push(2)?;
+        list.push(3)?;
+
+        assert!(list.is_full());
+        assert!(list.push(4).is_err());
+        assert_eq!(list.as_slice(), [1, 2, 3]);
+        assert_eq!(list.iter().sum::<u32>(), 6);
+
+        list.resize(5);
+        assert_eq!(list.len(), 3);
+
+        list.truncate(1);
+        list.resize(2);
+        assert_eq!(list.as_slice(), [1, 0]);
+
+        list.set_items(&[4, 5, 6, 7]);
+        assert_eq!(list.as_slice(), [4, 5, 6]);
+
+        assert!(BoundedList::<u32, 3>::create([1, 2, 3, 4]).is_err());
+        assert!(BoundedList::<u32, 3>::try_from(vec![1, 2, 3, 4]).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_bounded_list_clone_on_write() -> Result<()> {
+        let list = BoundedList::<u32, 3>::create([1, 2])?;
+        let mut copy = list.clone();
+
+        copy.iter_mut().for_each(|i| *i *= 10);
+
+        assert_eq!(list.as_slice(), [1, 2]);
+        assert_eq!(copy.as_slice(), [10, 20]);
+        assert_eq!(copy.into_iter().collect::<Vec<u32>>(), [10, 20]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_bounded_list_serde() -> Result<()> {
+        let list = BoundedList::<u32, 3>::create([1, 2])?;
+        let json = serde_json::to_string(&list)?;
+
+        assert_eq!(json.as_str(), "[1,2]");
+        assert_eq!(serde_json::from_str::<BoundedList<u32, 3>>(&json)?, list);
+        assert!(serde_json::from_str::<BoundedList<u32, 3>>("[1,2,3,4]").is_err());
+
+        Ok(())
+    }
+}
@@ -73,6 +73,27 @@ macro_rules! impl_xfs_enum {
     };
 }
 
+/// Implements [XfsDecode](crate::xfs::codec::XfsDecode) by decoding an
+/// [XfsValue](crate::xfs::value::XfsValue), and converting with `TryFrom<&XfsValue>`.
+///
+/// Used for leaf types, and types with hand-written conversions.
+#[macro_export]
+macro_rules! impl_xfs_decode {
+    ($ty:ident) => {
+        impl $crate::xfs::codec::XfsDecode for $ty {
+            fn decode_value(
+                reader: &mut $crate::xfs::codec::XfsReader<'_>,
+            ) -> $crate::Result<Self> {
+                let val =
+                    <$crate::xfs::value::XfsValue as $crate::xfs::codec::XfsDecode>::decode_value(
+                        reader,
+                    )?;
+                Self::try_from(&val)
+            }
+        }
+    };
+}
+
 /// Creates a new XFS `i4` type.
 #[macro_export]
 macro_rules! create_xfs_i4 {
@@ -247,6 +268,8 @@ macro_rules! impl_xfs_i4 {
                 (&val).try_into()
             }
         }
+
+        $crate::impl_xfs_decode!($ty);
     };
 }
 
@@ -376,6 +399,8 @@ macro_rules! impl_xfs_int {
                 (&val).try_into()
             }
         }
+
+        $crate::impl_xfs_decode!($ty);
     };
 }
 
@@ -553,6 +578,8 @@ macro_rules! impl_xfs_bool {
                 (&val).try_into()
             }
         }
+
+        $crate::impl_xfs_decode!($ty);
     };
 }
 
@@ -568,11 +595,12 @@ macro_rules! impl_xfs_bool {
 /// [XfsMember](crate::xfs::xfs_struct::XfsMember).
 #[macro_export]
 macro_rules! create_xfs_struct {
-    ($ty:ident, $name:expr, [$($field_name:ident: $field_ty:ident),*], $doc:expr) => {
+    ($ty:ident, $name:literal, [$($field_name:ident: $field_ty:ident),*], $doc:expr) => {
         ::paste::paste! {
             #[doc = $doc]
             #[repr(C)]
-            #[derive(Clone, Debug, PartialEq)]
+            #[derive(Clone, Debug, PartialEq, ::serde::Deserialize, ::serde::Serialize, $crate::XfsStruct)]
+            #[xfs(name = $name, no_display)]
             pub struct $ty {
                 $($field_name: $field_ty),*
             }
@@ -620,183 +648,50 @@ macro_rules! create_xfs_struct {
                     $ty::new()
                 }
             }
-
-            $crate::impl_xfs_struct!($ty, $name, [$($field_name: $field_ty),*]);
         }
     }
 }
 
-/// Common functionality for XFS `struct` types.
+/// Creates a new XFS `array` type.
 ///
 /// ## Parameters:
 ///
 /// - `$ty`: the type name of the Rust struct.
 /// - `$name`: the XFS name of the Rust struct.
-/// - `[$field_name: $field_ty]`: list of the Rust struct's field names and types.
-///
-/// **NOTE** all fields must implement `xfs_name`, and convert to/from
-/// [XfsMember](crate::xfs::xfs_struct::XfsMember).
-#[macro_export]
-macro_rules! impl_xfs_struct {
-    ($ty:ident, $name:expr, [$($field_name:ident: $field_ty:ident),*]) => {
-        impl $ty {
-            /// Gets the [XfsMember](crate::xfs::xfs_struct::XfsMember) name.
-            pub const fn xfs_name() -> &'static str {
-                $name
-            }
-        }
-
-        impl From<&$ty> for $crate::xfs::xfs_struct::XfsStruct {
-            fn from(val: &$ty) -> Self {
-                Self::create([
-                    $(val.$field_name.clone().into(),)*
-                ])
-            }
-        }
-
-        impl From<$ty> for $crate::xfs::xfs_struct::XfsStruct {
-            fn from(val: $ty) -> Self {
-                (&val).into()
-            }
-        }
-
-        impl TryFrom<&$crate::xfs::xfs_struct::XfsStruct> for $ty {
-            type Error = $crate::Error;
-
-            #[allow(clippy::needless_update)]
-            fn try_from(val: &$crate::xfs::xfs_struct::XfsStruct) -> $crate::Result<Self> {
-                Ok(Self {
-                    $(
-                        $field_name: match val.find_member($field_ty::xfs_name()) {
-                            Ok(m) => m.try_into()?,
-                            Err(_err) => {
-                                ::log::warn!("Missing member {} from {}", stringify!($field_name), stringify!($ty));
-                                $field_ty::new()
-                            }
-                        },
-                    )*
-                    ..Default::default()
-                })
-            }
-        }
-
-        impl TryFrom<$crate::xfs::xfs_struct::XfsStruct> for $ty {
-            type Error = $crate::Error;
-
-            fn try_from(val: $crate::xfs::xfs_struct::XfsStruct) -> $crate::Result<Self> {
-                (&val).try_into()
-            }
-        }
-
-        impl From<&$ty> for $crate::xfs::value::XfsValue {
-            fn from(val: &$ty) -> Self {
-                Self::new().with_xfs_struct(val.into())
-            }
-        }
-
-        impl From<$ty> for $crate::xfs::value::XfsValue {
-            fn from(val: $ty) -> Self {
-                (&val).into()
-            }
-        }
-
-        impl TryFrom<&$crate::xfs::value::XfsValue> for $ty {
-            type Error = $crate::Error;
-
-            fn try_from(val: &$crate::xfs::value::XfsValue) -> $crate::Result<Self> {
-                let name = $ty::xfs_name();
-                val
-                    .xfs_struct()
-                    .ok_or($crate::Error::Xfs(format!(
-                        "Expected {name} XfsValue, have: {val}"
-                    )))?
-                    .try_into()
-            }
-        }
-
-        impl TryFrom<$crate::xfs::value::XfsValue> for $ty {
-            type Error = $crate::Error;
-
-            fn try_from(val: $crate::xfs::value::XfsValue) -> $crate::Result<Self> {
-                (&val).try_into()
-            }
-        }
-
-        impl From<&$ty> for $crate::xfs::xfs_struct::XfsMember {
-            fn from(val: &$ty) -> Self {
-                $crate::xfs::xfs_struct::XfsMember::create($ty::xfs_name(), val.into())
-            }
-        }
-
-        impl From<$ty> for $crate::xfs::xfs_struct::XfsMember {
-            fn from(val: $ty) -> Self {
-                (&val).into()
-            }
-        }
-
-        impl TryFrom<&$crate::xfs::xfs_struct::XfsMember> for $ty {
-            type Error = $crate::Error;
-
-            fn try_from(val: &$crate::xfs::xfs_struct::XfsMember) -> $crate::Result<Self> {
-                let name = $ty::xfs_name();
-                match (val.name(), val.value().xfs_struct()) {
-                    (n, Some(v)) if n == name => v.try_into(),
-                    _ => Err($crate::Error::Xfs(format!(
-                        "Expected {name} XfsMember, have: {val}"
-                    ))),
-                }
-            }
-        }
-
-        impl TryFrom<$crate::xfs::xfs_struct::XfsMember> for $ty {
-            type Error = $crate::Error;
-
-            fn try_from(val: $crate::xfs::xfs_struct::XfsMember) -> $crate::Result<Self> {
-                (&val).try_into()
-            }
-        }
-    };
-}
-
+/// - `$item`: the type name of the list items.
+/// - `$len`: the maximum number of list items.
+/// - `$doc`: the documentation for the Rust struct.
 #[macro_export]
 macro_rules! create_xfs_array {
-    ($ty:ident, $name:expr, $item:ident, $len:expr, $default:expr, $doc:expr) => {
+    ($ty:ident, $name:literal, $item:ident, $len:expr, $doc:expr) => {
         ::paste::paste! {
             #[doc = $doc]
-            #[repr(C)]
-            #[derive(Clone, Debug, PartialEq)]
-            pub struct $ty{
-                size: usize,
-                items: [$item; $len],
+            #[derive(Clone, Debug, PartialEq, ::serde::Deserialize, ::serde::Serialize, $crate::XfsArray)]
+            #[xfs(name = $name)]
+            pub struct $ty {
+                items: $crate::BoundedList<$item, $len>,
             }
 
             impl $ty {
                 #[doc = "Creates a new [" $ty "]."]
                 pub const fn new() -> Self {
                     Self {
-                        size: 0,
-                        items: [$default; $len],
+                        items: $crate::BoundedList::new(),
                     }
                 }
 
-                #[doc = "Gets the maximum number of [" $ty "] items."]
-                pub const fn max_size() -> usize {
-                    $len
-                }
-
                 #[doc = "Gets the size of the [" $ty "]."]
-                pub const fn size(&self) -> usize {
-                    self.size
+                pub fn size(&self) -> usize {
+                    self.items.len()
                 }
 
                 #[doc = "Sets the size of the [" $ty "]."]
                 #[doc = ""]
+                #[doc = "New items are set to the default value."]
+                #[doc = ""]
                 #[doc = "No-op if `val` is larger than [" $ty "]."]
                 pub fn set_size(&mut self, val: u32) {
-                    let size = val as usize;
-                    if size <= $len {
-                        self.size = size;
-                    }
+                    self.items.resize(val as usize);
                 }
 
                 #[doc = "Builder function that sets the size of the [" $ty "]."]
@@ -806,41 +701,6 @@ macro_rules! create_xfs_array {
                     self.set_size(val);
                     self
                 }
-
-                #[doc = "Gets a reference to the list of set [" $ty "] items."]
-                pub fn items(&self) -> &[$item] {
-                    let len = ::std::cmp::min(self.size, $len);
-                    self.items[..len].as_ref()
-                }
-
-                #[doc = "Sets the list of [" $ty "] items."]
-                pub fn set_items(&mut self, val: &[$item]) {
-                    let len = ::std::cmp::min(val.len(), $len);
-                    self.items[..len]
-                        .iter_mut()
-                        .zip(val[..len].iter())
-                        .for_each(|(dst, src)| *dst = src.clone());
-                    self.items[len..]
-                        .iter_mut()
-                        .for_each(|item| *item = $item::new());
-                    self.size = len;
-                }
-
-                #[doc = "Builder function that sets the list of [" $ty "] items."]
-                pub fn with_items(mut self, val: &[$item]) -> Self {
-                    self.set_items(val);
-                    self
-                }
-
-                #[doc = "Pushes a [" $ty "] onto the end of the list."]
-                #[doc = ""]
-                #[doc = "No-op if the list is at capacity."]
-                pub fn push_item(&mut self, val: $item) {
-                    if self.size < $len {
-                        self.items[self.size] = val;
-                        self.size += 1;
-                    }
-                }
             }
 
             impl Default for $ty {
@@ -848,155 +708,134 @@ macro_rules! create_xfs_array {
                     Self::new()
                 }
             }
-
-            impl ::std::fmt::Display for $ty {
-                fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
-                    write!(f, r#"{{"items":["#)?;
-                    for (i, item) in self.items().iter().enumerate() {
-                        if i != 0 {
-                            write!(f, ",")?;
-                        }
-                        write!(f, "{item}")?;
-                    }
-                    write!(f, "]}}")
-                }
-            }
-
-            $crate::impl_xfs_array!($ty, $name);
         }
     };
 }
 
-/// Common functionality for XFS `struct` types.
+/// Common list functionality for types storing their items in a
+/// [BoundedList](crate::BoundedList).
 ///
 /// ## Parameters:
 ///
 /// - `$ty`: the type name of the Rust struct.
-/// - `$name`: the XFS name of the Rust struct.
+/// - `$item`: the type name of the list items.
+/// - `$len`: the maximum number of list items.
 ///
-/// **NOTE** inner items must convert to/from [XfsValue](crate::xfs::value::XfsValue).
+/// **NOTE** the Rust struct must have a single `items: BoundedList<$item, $len>` field.
 #[macro_export]
-macro_rules! impl_xfs_array {
-    ($ty:ident, $name:expr) => {
-        impl $ty {
-            /// Gets the [XfsMember](crate::xfs::xfs_struct::XfsMember) name.
-            pub const fn xfs_name() -> &'static str {
-                $name
-            }
-        }
+macro_rules! impl_xfs_list {
+    ($ty:ident, $item:ident, $len:expr) => {
+        ::paste::paste! {
+            impl $ty {
+                #[doc = "Gets the maximum number of [" $ty "] items."]
+                pub const fn max_size() -> usize {
+                    $len
+                }
 
-        impl From<&$ty> for $crate::xfs::array::XfsArray {
-            fn from(val: &$ty) -> Self {
-                Self::create(val.items().iter().map($crate::xfs::value::XfsValue::from))
-            }
-        }
+                #[doc = "Gets the number of [" $ty "] items."]
+                pub fn len(&self) -> usize {
+                    self.items.len()
+                }
 
-        impl From<$ty> for $crate::xfs::array::XfsArray {
-            fn from(val: $ty) -> Self {
-                (&val).into()
-            }
-        }
+                #[doc = "Gets whether the [" $ty "] is empty."]
+                pub fn is_empty(&self) -> bool {
+                    self.items.is_empty()
+                }
 
-        impl TryFrom<&$crate::xfs::array::XfsArray> for $ty {
-            type Error = $crate::Error;
+                #[doc = "Gets whether the [" $ty "] is at the maximum number of items."]
+                pub fn is_full(&self) -> bool {
+                    self.items.is_full()
+                }
 
-            fn try_from(val: &$crate::xfs::array::XfsArray) -> $crate::Result<Self> {
-                let data = val.data();
-                let mut res = $ty::new();
-
-                // FIXME: this could be an issue for dynamic-length collection types (Vec, etc.).
-                // Dynamic-length types initialize to zero length, and would require pushing items from `data`.
-                // Currently, all types converting to/from XfsArray are fixed-length.
-                let len = ::std::cmp::min($ty::max_size(), data.len());
-                for (i, (dst, src)) in res.items[..len]
-                    .iter_mut()
-                    .zip(data[..len].iter().map(|m| m.inner()))
-                    .enumerate()
-                {
-                    match src.try_into() {
-                        Ok(d) => *dst = d,
-                        Err(err) => log::warn!("Failed to convert item[{i}]: {err}"),
-                    }
+                #[doc = "Gets a reference to the list of [" $item "] items."]
+                pub fn items(&self) -> &[$item] {
+                    self.items.as_slice()
                 }
 
-                res.set_size(len as u32);
+                #[doc = "Gets a mutable reference to the list of [" $item "] items."]
+                pub fn items_mut(&mut self) -> &mut [$item] {
+                    self.items.as_mut_slice()
+                }
 
-                Ok(res)
-            }
-        }
+                #[doc = "Sets the list of [" $item "] items."]
+                #[doc = ""]
+                #[doc = "Items past the maximum size are ignored."]
+                pub fn set_items(&mut self, val: &[$item]) {
+                    self.items.set_items(val);
+                }
 
-        impl TryFrom<$crate::xfs::array::XfsArray> for $ty {
-            type Error = $crate::Error;
+                #[doc = "Builder function that sets the list of [" $item "] items."]
+                #[doc = ""]
+                #[doc = "Items past the maximum size are ignored."]
+                pub fn with_items(mut self, val: &[$item]) -> Self {
+                    self.set_items(val);
+                    self
+                }
 
-            fn try_from(val: $crate::xfs::array::XfsArray) -> $crate::Result<Self> {
-                (&val).try_into()
-            }
-        }
+                #[doc = "Gets an iterator over the [" $item "] items."]
+                pub fn iter(&self) -> ::std::slice::Iter<'_, $item> {
+                    self.items.iter()
+                }
 
-        impl From<&$ty> for $crate::xfs::value::XfsValue {
-            fn from(val: &$ty) -> Self {
-                Self::new().with_array(val.into())
-            }
-        }
+                #[doc = "Gets a mutable iterator over the [" $item "] items."]
+                pub fn iter_mut(&mut self) -> ::std::slice::IterMut<'_, $item> {
+                    self.items.iter_mut()
+                }
 
-        impl From<$ty> for $crate::xfs::value::XfsValue {
-            fn from(val: $ty) -> Self {
-                (&val).into()
-            }
-        }
+                #[doc = "Pushes a [" $item "] onto the end of the list."]
+                #[doc = ""]
+                #[doc = "Returns an error if the list is at capacity."]
+                pub fn push_item(&mut self, val: $item) -> $crate::Result<()> {
+                    self.items.push(val)
+                }
 
-        impl TryFrom<&$crate::xfs::value::XfsValue> for $ty {
-            type Error = $crate::Error;
+                #[doc = "Pops a [" $item "] from the end of the list."]
+                pub fn pop_item(&mut self) -> Option<$item> {
+                    self.items.pop()
+                }
 
-            fn try_from(val: &$crate::xfs::value::XfsValue) -> $crate::Result<Self> {
-                let name = $ty::xfs_name();
-                val.array()
-                    .ok_or($crate::Error::Xfs(format!(
-                        "Expected {name} XfsValue, have: {val}"
-                    )))?
-                    .try_into()
+                #[doc = "Removes all [" $item "] items."]
+                pub fn clear(&mut self) {
+                    self.items.clear();
+                }
             }
-        }
 
-        impl TryFrom<$crate::xfs::value::XfsValue> for $ty {
-            type Error = $crate::Error;
+            impl<'a> IntoIterator for &'a $ty {
+                type Item = &'a $item;
+                type IntoIter = ::std::slice::Iter<'a, $item>;
 
-            fn try_from(val: $crate::xfs::value::XfsValue) -> $crate::Result<Self> {
-                (&val).try_into()
+                fn into_iter(self) -> Self::IntoIter {
+                    self.iter()
+                }
             }
-        }
 
-        impl From<&$ty> for $crate::xfs::xfs_struct::XfsMember {
-            fn from(val: &$ty) -> Self {
-                $crate::xfs::xfs_struct::XfsMember::create($ty::xfs_name(), val.into())
-            }
-        }
+            impl IntoIterator for $ty {
+                type Item = $item;
+                type IntoIter = ::std::vec::IntoIter<$item>;
 
-        impl From<$ty> for $crate::xfs::xfs_struct::XfsMember {
-            fn from(val: $ty) -> Self {
-                (&val).into()
+                fn into_iter(self) -> Self::IntoIter {
+                    self.items.into_iter()
+                }
             }
-        }
 
-        impl TryFrom<&$crate::xfs::xfs_struct::XfsMember> for $ty {
-            type Error = $crate::Error;
+            impl TryFrom<Vec<$item>> for $ty {
+                type Error = $crate::Error;
 
-            fn try_from(val: &$crate::xfs::xfs_struct::XfsMember) -> $crate::Result<Self> {
-                let name = $ty::xfs_name();
-                match (val.name(), val.value().array()) {
-                    (n, Some(v)) if n == name => v.try_into(),
-                    _ => Err($crate::Error::Xfs(format!(
-                        "Expected {name} XfsMember, have: {val}"
-                    ))),
+                fn try_from(val: Vec<$item>) -> $crate::Result<Self> {
+                    Ok(Self {
+                        items: val.try_into()?,
+                    })
                 }
             }
-        }
 
-        impl TryFrom<$crate::xfs::xfs_struct::XfsMember> for $ty {
-            type Error = $crate::Error;
+            impl TryFrom<&[$item]> for $ty {
+                type Error = $crate::Error;
 
-            fn try_from(val: $crate::xfs::xfs_struct::XfsMember) -> $crate::Result<Self> {
-                (&val).try_into()
+                fn try_from(val: &[$item]) -> $crate::Result<Self> {
+                    Ok(Self {
+                        items: val.try_into()?,
+                    })
+                }
             }
         }
     };
@@ -1007,7 +846,7 @@ macro_rules! create_xfs_string {
     ($ty:ident, $name:expr, $doc:expr) => {
         ::paste::paste! {
             #[doc = $doc]
-            #[derive(Clone, Debug, Eq, PartialEq)]
+            #[derive(Clone, Debug, Eq, PartialEq, ::serde::Deserialize, ::serde::Serialize)]
             pub struct $ty(String);
 
             impl $ty {
@@ -1152,6 +991,8 @@ macro_rules! impl_xfs_string {
                 (&val).try_into()
             }
         }
+
+        $crate::impl_xfs_decode!($ty);
     };
 }
 
@@ -1160,7 +1001,7 @@ macro_rules! create_xfs_date_time {
     ($ty:ident, $name:expr, $doc:expr) => {
         ::paste::paste! {
             #[doc = $doc]
-            #[derive(Clone, Debug, Eq, PartialEq)]
+            #[derive(Clone, Debug, Eq, PartialEq, ::serde::Deserialize, ::serde::Serialize)]
             pub struct $ty(String);
 
             impl $ty {
@@ -1332,5 +1173,7 @@ macro_rules! impl_xfs_date_time {
                 (&val).try_into()
             }
         }
+
+        $crate::impl_xfs_decode!($ty);
     };
 }
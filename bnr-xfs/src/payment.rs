@@ -0,0 +1,569 @@
+//! Types for collecting a payment, and giving change.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::currency::{Currency, MixNumber, Money};
+use crate::{
+    CashInResult, DenominateResult, DispensePlanner, Error, PresentReport, Result,
+    DEFAULT_CASH_TAKEN_TIMEOUT,
+};
+
+#[cfg(feature = "usb")]
+mod collect;
+
+#[cfg(feature = "usb")]
+pub(crate) use collect::*;
+
+/// Default time to wait for the customer to insert the next note.
+pub const DEFAULT_NOTE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Options for [collect_payment](crate::DeviceHandle::collect_payment).
+#[derive(Clone, Debug)]
+pub struct PaymentOptions {
+    mix_number: MixNumber,
+    note_timeout: Duration,
+    present: bool,
+    cash_taken_timeout: Duration,
+    cancel: Arc<AtomicBool>,
+}
+
+impl PaymentOptions {
+    /// Creates a new [PaymentOptions].
+    ///
+    /// Change is given with [MixNumber::MinBills] and presented to the customer, and the customer
+    /// has [DEFAULT_NOTE_TIMEOUT] to insert each note.
+    pub fn new() -> Self {
+        Self {
+            mix_number: MixNumber::MinBills,
+            note_timeout: DEFAULT_NOTE_TIMEOUT,
+            present: true,
+            cash_taken_timeout: DEFAULT_CASH_TAKEN_TIMEOUT,
+            cancel: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Gets the [MixNumber] algorithm used to give change.
+    pub const fn mix_number(&self) -> MixNumber {
+        self.mix_number
+    }
+
+    /// Sets the [MixNumber] algorithm used to give change.
+    ///
+    /// Only [MixNumber::MinBills] and [MixNumber::OptimumChange] are supported.
+    pub fn set_mix_number(&mut self, mix_number: MixNumber) {
+        self.mix_number = mix_number;
+    }
+
+    /// Builder function that sets the [MixNumber] algorithm used to give change.
+    pub fn with_mix_number(mut self, mix_number: MixNumber) -> Self {
+        self.set_mix_number(mix_number);
+        self
+    }
+
+    /// Gets the time to wait for the customer to insert the next note.
+    pub const fn note_timeout(&self) -> Duration {
+        self.note_timeout
+    }
+
+    /// Sets the time to wait for the customer to insert the next note.
+    ///
+    /// When no note is inserted in time, the payment is abandoned and the notes are returned.
+    pub fn set_note_timeout(&mut self, timeout: Duration) {
+        self.note_timeout = timeout;
+    }
+
+    /// Builder function that sets the time to wait for the customer to insert the next note.
+    pub fn with_note_timeout(mut self, timeout: Duration) -> Self {
+        self.set_note_timeout(timeout);
+        self
+    }
+
+    /// Gets whether the change is presented to the customer after the dispense.
+    pub const fn present(&self) -> bool {
+        self.present
+    }
+
+    /// Sets whether the change is presented to the customer after the dispense.
+    pub fn set_present(&mut self, present: bool) {
+        self.present = present;
+    }
+
+    /// Builder function that sets whether the change is presented to the customer.
+    pub fn with_present(mut self, present: bool) -> Self {
+        self.set_present(present);
+        self
+    }
+
+    /// Gets the time to wait for the customer to take the change, see
+    /// [present_supervised](crate::DeviceHandle::present_supervised).
+    pub const fn cash_taken_timeout(&self) -> Duration {
+        self.cash_taken_timeout
+    }
+
+    /// Sets the time to wait for the customer to take the change.
+    pub fn set_cash_taken_timeout(&mut self, timeout: Duration) {
+        self.cash_taken_timeout = timeout;
+    }
+
+    /// Builder function that sets the time to wait for the customer to take the change.
+    pub fn with_cash_taken_timeout(mut self, timeout: Duration) -> Self {
+        self.set_cash_taken_timeout(timeout);
+        self
+    }
+
+    /// Gets the cancellation flag.
+    ///
+    /// Setting the flag, e.g. from a cancel button handler on another thread, cancels the payment
+    /// and returns the inserted notes to the customer.
+    pub fn cancel_flag(&self) -> Arc<AtomicBool> {
+        Arc::clone(&self.cancel)
+    }
+
+    /// Builder function that sets a shared cancellation flag.
+    pub fn with_cancel_flag(mut self, cancel: Arc<AtomicBool>) -> Self {
+        self.cancel = cancel;
+        self
+    }
+
+    /// Gets whether the customer cancelled the payment.
+    pub fn cancelled(&self) -> bool {
+        self.cancel.load(Ordering::SeqCst)
+    }
+}
+
+impl Default for PaymentOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Represents the outcome of a payment.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
+pub enum PaymentStatus {
+    /// The price was covered, and any change was given.
+    Paid,
+    /// The customer cancelled, the notes were returned.
+    #[default]
+    Cancelled,
+    /// The customer did not insert a note in time, the notes were returned.
+    TimedOut,
+    /// A note made the change impossible to give, the notes were returned.
+    ChangeUnavailable,
+    /// The price was covered, but the device failed to give the change, see
+    /// [PaymentReceipt::owed].
+    ChangeFailed,
+    /// The price was covered, and the change was dispensed, but its presentation to the
+    /// customer was not confirmed, see [PaymentReceipt::change_present].
+    ChangeUnconfirmed,
+}
+
+impl PaymentStatus {
+    /// Gets whether the customer's notes were kept.
+    pub const fn is_collected(&self) -> bool {
+        matches!(
+            self,
+            Self::Paid | Self::ChangeFailed | Self::ChangeUnconfirmed
+        )
+    }
+}
+
+impl From<&PaymentStatus> for &'static str {
+    fn from(val: &PaymentStatus) -> Self {
+        match val {
+            PaymentStatus::Paid => "paid",
+            PaymentStatus::Cancelled => "cancelled",
+            PaymentStatus::TimedOut => "timed out",
+            PaymentStatus::ChangeUnavailable => "change unavailable",
+            PaymentStatus::ChangeFailed => "change failed",
+            PaymentStatus::ChangeUnconfirmed => "change unconfirmed",
+        }
+    }
+}
+
+impl From<PaymentStatus> for &'static str {
+    fn from(val: PaymentStatus) -> Self {
+        (&val).into()
+    }
+}
+
+impl fmt::Display for PaymentStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, r#""{}""#, <&str>::from(self))
+    }
+}
+
+/// Represents the receipt of a payment.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PaymentReceipt {
+    status: PaymentStatus,
+    price: Money,
+    inserted: Money,
+    change: Money,
+    owed: Money,
+    deposit: CashInResult,
+    change_bills: Option<DenominateResult>,
+    change_present: Option<PresentReport>,
+}
+
+impl PaymentReceipt {
+    /// Creates a new [PaymentReceipt].
+    pub const fn new() -> Self {
+        Self {
+            status: PaymentStatus::Cancelled,
+            price: Money::new(),
+            inserted: Money::new(),
+            change: Money::new(),
+            owed: Money::new(),
+            deposit: CashInResult::new(),
+            change_bills: None,
+            change_present: None,
+        }
+    }
+
+    /// Creates a [PaymentReceipt] for a payment abandoned before the price was covered.
+    ///
+    /// The `inserted` notes were returned to the customer.
+    pub const fn abandoned(status: PaymentStatus, price: Money, inserted: Money) -> Self {
+        Self {
+            status,
+            price,
+            inserted,
+            change: Money::create(price.currency(), 0),
+            owed: Money::create(price.currency(), 0),
+            deposit: CashInResult::new(),
+            change_bills: None,
+            change_present: None,
+        }
+    }
+
+    /// Creates a [PaymentReceipt] for a covered price.
+    ///
+    /// The change is the `deposit` total less the `price`. Returns an error if the `deposit` does
+    /// not cover the `price`.
+    pub fn collected(
+        status: PaymentStatus,
+        price: Money,
+        deposit: CashInResult,
+        change_bills: Option<DenominateResult>,
+    ) -> Result<Self> {
+        let inserted = deposit.total_or_zero(price.currency())?;
+        let change = inserted.checked_sub(&price)?;
+
+        if change.is_negative() {
+            return Err(Error::Money(format!(
+                "{inserted} inserted does not cover the price of {price}"
+            )));
+        }
+
+        Ok(Self {
+            status,
+            price,
+            inserted,
+            change,
+            owed: Money::create(price.currency(), 0),
+            deposit,
+            change_bills,
+            change_present: None,
+        })
+    }
+
+    /// Gets the [PaymentStatus].
+    pub const fn status(&self) -> PaymentStatus {
+        self.status
+    }
+
+    /// Gets the price.
+    pub const fn price(&self) -> Money {
+        self.price
+    }
+
+    /// Gets the amount inserted by the customer.
+    ///
+    /// Unless the notes were collected, see [PaymentStatus::is_collected], the amount was
+    /// returned to the customer.
+    pub const fn inserted(&self) -> Money {
+        self.inserted
+    }
+
+    /// Gets the change due to the customer.
+    pub const fn change(&self) -> Money {
+        self.change
+    }
+
+    /// Gets the change still owed to the customer.
+    ///
+    /// Non-zero when the device failed to give the change, see [PaymentStatus::ChangeFailed]: the
+    /// notes were kept, and the amount must be refunded by other means.
+    pub const fn owed(&self) -> Money {
+        self.owed
+    }
+
+    /// Builder function that sets the change still owed to the customer.
+    pub fn with_owed(mut self, owed: Money) -> Self {
+        self.owed = owed;
+        self
+    }
+
+    /// Gets the [CashInResult] of the collected notes.
+    pub const fn deposit(&self) -> &CashInResult {
+        &self.deposit
+    }
+
+    /// Gets the bills dispensed as change, if any.
+    pub const fn change_bills(&self) -> Option<&DenominateResult> {
+        self.change_bills.as_ref()
+    }
+
+    /// Gets the [PresentReport] of the change, if it was presented.
+    pub const fn change_present(&self) -> Option<&PresentReport> {
+        self.change_present.as_ref()
+    }
+
+    /// Builder function that sets the [PresentReport] of the change.
+    pub fn with_change_present(mut self, report: Option<PresentReport>) -> Self {
+        self.change_present = report;
+        self
+    }
+}
+
+impl fmt::Display for PaymentReceipt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{{")?;
+        write!(f, r#""status": {}, "#, self.status)?;
+        write!(f, r#""price": "{}", "#, self.price)?;
+        write!(f, r#""inserted": "{}", "#, self.inserted)?;
+        write!(f, r#""change": "{}", "#, self.change)?;
+        write!(f, r#""owed": "{}", "#, self.owed)?;
+        write!(f, r#""deposit": {}, "#, self.deposit)?;
+        match self.change_bills.as_ref() {
+            Some(bills) => write!(f, r#""change_bills": {bills}, "#)?,
+            None => write!(f, r#""change_bills": null, "#)?,
+        }
+        match self.change_present.as_ref() {
+            Some(report) => write!(f, r#""change_present": {report}"#)?,
+            None => write!(f, r#""change_present": null"#)?,
+        }
+        write!(f, "}}")
+    }
+}
+
+/// Decides which notes can be accepted while keeping the change for a price payable.
+///
+/// Change is planned with a [DispensePlanner] over the stock at the start of the payment: the
+/// inserted notes are not counted as available for change.
+///
+/// A note is accepted if, after it, the payment can still be completed: either the change for
+/// the amount inserted is payable, or more notes can cover the price with payable change.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ChangeGuard {
+    planner: DispensePlanner,
+    mix_number: MixNumber,
+    currency: Currency,
+    price: u32,
+    notes: Vec<u32>,
+}
+
+impl ChangeGuard {
+    /// Creates a new [ChangeGuard].
+    ///
+    /// - `planner`: plans the change from the available stock
+    /// - `mix_number`: algorithm used to give change
+    /// - `price`: price to collect
+    /// - `notes`: values of the notes the device accepts, in MDU of the price currency
+    pub fn create(
+        planner: DispensePlanner,
+        mix_number: MixNumber,
+        price: &Money,
+        notes: &[u32],
+    ) -> Result<Self> {
+        let currency = Currency::from(price.currency());
+        let price = price.to_currency_mdu(&currency)?;
+
+        let mut notes: Vec<u32> = notes.iter().copied().filter(|&n| n != 0).collect();
+        notes.sort_unstable();
+        notes.dedup();
+
+        Ok(Self {
+            planner,
+            mix_number,
+            currency,
+            price,
+            notes,
+        })
+    }
+
+    /// Gets the price in MDU.
+    pub const fn price(&self) -> u32 {
+        self.price
+    }
+
+    /// Gets the values of the notes the device accepts, in MDU.
+    pub fn notes(&self) -> &[u32] {
+        self.notes.as_ref()
+    }
+
+    /// Gets whether the change for an amount `inserted` in MDU is payable.
+    pub fn change_payable(&self, inserted: u32) -> bool {
+        match inserted.checked_sub(self.price) {
+            Some(0) => true,
+            Some(change) => self
+                .currency
+                .to_money(change)
+                .is_ok_and(|m| self.planner.is_payable(&m, self.mix_number)),
+            None => false,
+        }
+    }
+
+    /// Gets whether the payment can be completed with payable change, after an amount
+    /// `inserted` in MDU.
+    pub fn can_complete(&self, inserted: u32) -> bool {
+        self.complete(inserted, &mut HashMap::new())
+    }
+
+    /// Gets the values of the notes that can be accepted after an amount `inserted` in MDU.
+    pub fn accepted_notes(&self, inserted: u32) -> Vec<u32> {
+        let mut memo = HashMap::new();
+
+        self.notes
+            .iter()
+            .copied()
+            .filter(|&n| {
+                inserted
+                    .checked_add(n)
+                    .is_some_and(|next| self.complete(next, &mut memo))
+            })
+            .collect()
+    }
+
+    /// Checks that the payment can be completed before any note is inserted.
+    ///
+    /// Returns an [Error::Money] if no sequence of accepted notes covers the price with payable
+    /// change.
+    pub fn check(&self) -> Result<()> {
+        if self.can_complete(0) {
+            Ok(())
+        } else {
+            Err(Error::Money(format!(
+                "change cannot be made for a price of {}",
+                self.currency.to_money(self.price)?
+            )))
+        }
+    }
+
+    fn complete(&self, inserted: u32, memo: &mut HashMap<u32, bool>) -> bool {
+        if inserted >= self.price {
+            return self.change_payable(inserted);
+        }
+
+        if let Some(&res) = memo.get(&inserted) {
+            return res;
+        }
+
+        let res = self.notes.iter().any(|&n| {
+            inserted
+                .checked_add(n)
+                .is_some_and(|next| self.complete(next, memo))
+        });
+        memo.insert(inserted, res);
+
+        res
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::currency::{CashType, CuKind, CuType, CurrencyCode};
+    use crate::{CashUnit, LogicalCashUnit, LogicalCashUnitList};
+
+    fn guard(recyclers: &[(u32, u32)], price: i64) -> Result<ChangeGuard> {
+        let usd = CurrencyCode::from("USD");
+        let lcus: Vec<LogicalCashUnit> = recyclers
+            .iter()
+            .enumerate()
+            .map(|(i, &(value, count))| {
+                LogicalCashUnit::new()
+                    .with_number(i as u32 + 1)
+                    .with_cu_kind(CuKind::Recycle)
+                    .with_cu_type(CuType::BillCassette)
+                    .with_cash_type(CashType::create(usd, value, 0))
+                    .with_count(count)
+                    .with_physical_cash_unit(i as u32)
+            })
+            .collect();
+        let cash_unit = CashUnit::new()
+            .with_logical_cash_unit_list(LogicalCashUnitList::new().with_items(&lcus));
+
+        ChangeGuard::create(
+            DispensePlanner::create(&cash_unit),
+            MixNumber::MinBills,
+            &Money::create(usd, price),
+            &[100, 500, 1000, 2000, 2000],
+        )
+    }
+
+    #[test]
+    fn test_change_guard() -> Result<()> {
+        // $1 and $5 for change, price $7
+        let g = guard(&[(100, 3), (500, 1)], 700)?;
+        assert_eq!(g.notes(), [100, 500, 1000, 2000]);
+        assert!(g.check().is_ok());
+
+        // $10 needs $3 change, $20 needs $13: not enough $1
+        assert_eq!(g.accepted_notes(0), [100, 500, 1000]);
+        assert_eq!(g.accepted_notes(500), [100, 500, 1000]);
+        assert!(g.change_payable(1000));
+        assert!(!g.change_payable(2700));
+        assert!(!g.change_payable(600));
+
+        // no change available: only exact amounts
+        let g = guard(&[], 700)?;
+        assert!(g.check().is_ok());
+        assert_eq!(g.accepted_notes(500), [100]);
+        assert!(g.accepted_notes(600).contains(&100));
+        assert!(!g.can_complete(800));
+
+        // $0.50 cannot be reached, nor given as change
+        let g = guard(&[], 750)?;
+        assert!(matches!(g.check(), Err(Error::Money(_))));
+        assert!(g.accepted_notes(0).is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_payment_receipt() -> Result<()> {
+        let usd = CurrencyCode::from("USD");
+        let price = Money::create(usd, 700);
+
+        let receipt =
+            PaymentReceipt::abandoned(PaymentStatus::TimedOut, price, Money::create(usd, 500));
+        assert!(!receipt.status().is_collected());
+        assert!(receipt.change().is_zero());
+        assert!(receipt.change_bills().is_none());
+        assert!(receipt.owed().is_zero());
+
+        // dispensed change is kept out of the owed amount
+        assert!(PaymentStatus::ChangeUnconfirmed.is_collected());
+        assert_eq!(
+            PaymentStatus::ChangeUnconfirmed.to_string(),
+            r#""change unconfirmed""#
+        );
+
+        let receipt =
+            PaymentReceipt::collected(PaymentStatus::Paid, price, CashInResult::new(), None);
+        assert!(receipt.is_err(), "change cannot be negative");
+
+        let receipt = PaymentReceipt::abandoned(PaymentStatus::ChangeFailed, price, price)
+            .with_owed(Money::create(usd, 300));
+        assert_eq!(receipt.owed(), Money::create(usd, 300));
+        assert!(receipt.to_string().contains(r#""owed": "#));
+
+        Ok(())
+    }
+}
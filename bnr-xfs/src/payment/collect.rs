@@ -0,0 +1,164 @@
+use crate::currency::{Currency, Money};
+use crate::{
+    DepositSession, DeviceHandle, DispensePlanner, DispenseRequest, Error, Escrowed,
+    PresentOutcome, Result,
+};
+
+use super::{ChangeGuard, PaymentOptions, PaymentReceipt, PaymentStatus};
+
+// Gets the amount in MDU reported by the `CashIn` operations of the open transaction.
+fn inserted(handle: &DeviceHandle, currency: &Currency) -> Result<u32> {
+    let mut total = Money::create(currency.currency_code(), 0);
+
+    if let Some(txn) = handle.cash_in_transaction() {
+        for order in txn.orders() {
+            total = total.checked_add(&order.amount()?)?;
+        }
+    }
+
+    total.to_currency_mdu(currency)
+}
+
+// Returns the notes in escrow, or closes the empty transaction.
+fn abandon(
+    session: DepositSession<'_, Escrowed>,
+    status: PaymentStatus,
+    price: &Money,
+    inserted: u32,
+    currency: &Currency,
+) -> Result<PaymentReceipt> {
+    log::info!("Payment {status}: returning {inserted} MDU to the customer");
+
+    if inserted == 0 {
+        session.commit()?;
+    } else {
+        session.rollback()?;
+    }
+
+    Ok(PaymentReceipt::abandoned(
+        status,
+        *price,
+        currency.to_money(inserted)?,
+    ))
+}
+
+pub(crate) fn collect_payment(
+    handle: &DeviceHandle,
+    price: &Money,
+    options: &PaymentOptions,
+) -> Result<PaymentReceipt> {
+    if price.is_zero() || price.is_negative() {
+        return Err(Error::Money(format!("invalid payment price: {price}")));
+    }
+
+    let currency = Currency::from(price.currency());
+    let caps = handle.get_capabilities()?;
+    let cash_unit = handle.query_cash_unit()?;
+    let denominations = handle.query_denominations()?;
+
+    let notes: Vec<u32> = denominations
+        .iter()
+        .filter(|d| {
+            d.enable_denomination().inner() && d.cash_type().currency_code() == price.currency()
+        })
+        .map(|d| d.cash_type().value())
+        .collect();
+
+    let guard = ChangeGuard::create(
+        DispensePlanner::create(&cash_unit).with_capabilities(&caps),
+        options.mix_number(),
+        price,
+        &notes,
+    )?;
+    guard.check()?;
+
+    let cancel = options.cancel_flag();
+    let timeout = options.note_timeout();
+
+    // one note per `CashIn`, so each note is checked against the payable set before the next
+    let mut session = DepositSession::new(handle)
+        .start()?
+        .accept(None)?
+        .wait_or_cancel(timeout, &cancel)?;
+    let mut last = 0;
+
+    loop {
+        let amount = inserted(handle, &currency)?;
+
+        if amount >= guard.price() && guard.change_payable(amount) {
+            break;
+        }
+
+        // a note outside the payable set returns the escrow: the device cannot return one note
+        let status = if !guard.can_complete(amount) {
+            Some(PaymentStatus::ChangeUnavailable)
+        } else if options.cancelled() {
+            Some(PaymentStatus::Cancelled)
+        } else if amount == last {
+            Some(PaymentStatus::TimedOut)
+        } else {
+            None
+        };
+
+        if let Some(status) = status {
+            return abandon(session, status, price, amount, &currency);
+        }
+
+        last = amount;
+        session = session.accept(None)?.wait_or_cancel(timeout, &cancel)?;
+    }
+
+    let deposit = session.commit()?.into_result();
+
+    let change = deposit
+        .total_or_zero(price.currency())?
+        .checked_sub(price)?;
+    if change.is_zero() {
+        return PaymentReceipt::collected(PaymentStatus::Paid, *price, deposit, None);
+    }
+
+    let request = DispenseRequest::create_amount(options.mix_number(), &change)?;
+    let bills = match handle
+        .denominate(&request)
+        .and_then(|bills| handle.dispense(&request).map(|_| bills))
+    {
+        Ok(bills) => bills,
+        Err(err) => {
+            log::error!("Payment: failed to give {change} change, owed to the customer: {err}");
+            return Ok(PaymentReceipt::collected(
+                PaymentStatus::ChangeFailed,
+                *price,
+                deposit,
+                None,
+            )?
+            .with_owed(change));
+        }
+    };
+
+    if !options.present() {
+        return PaymentReceipt::collected(PaymentStatus::Paid, *price, deposit, Some(bills));
+    }
+
+    // the change left the cassettes: a present failure must not report it as owed
+    let (status, report) = match handle.present_supervised(options.cash_taken_timeout()) {
+        Ok(report) if report.outcome() == PresentOutcome::Unknown => {
+            log::error!("Payment: change dispensed, presentation unconfirmed: {report}");
+            (PaymentStatus::ChangeUnconfirmed, Some(report))
+        }
+        Ok(report) => {
+            if !report.taken() {
+                log::warn!("Payment: change not taken by the customer: {report}");
+            }
+            (PaymentStatus::Paid, Some(report))
+        }
+        Err(err) => {
+            log::error!("Payment: change dispensed, presentation unconfirmed: {err}");
+            (PaymentStatus::ChangeUnconfirmed, None)
+        }
+    };
+
+    Ok(
+        PaymentReceipt::collected(status, *price, deposit, Some(bills))?
+            .with_change_present(report),
+    )
+}
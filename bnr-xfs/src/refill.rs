@@ -0,0 +1,521 @@
+//! Types for refilling the loader.
+
+use std::fmt;
+
+use crate::cash_unit::{pcus_of_kind, unix_time};
+use crate::currency::{CashType, CuKind};
+use crate::{
+    CashTotal, CashUnit, Error, LoaderAcceptanceHistory, LogicalCashUnitList, PhysicalCashUnitList,
+    Result,
+};
+
+#[cfg(feature = "usb")]
+mod procedure;
+
+#[cfg(feature = "usb")]
+pub(crate) use procedure::*;
+
+/// Maximum count of a loader [LogicalCashUnit](crate::LogicalCashUnit).
+pub const LOADER_MAX_COUNT: u32 = 65535;
+
+/// Represents the bills of one denomination stacked in the loader during a refill.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct LoadedNotes {
+    cash_type: CashType,
+    count: u32,
+}
+
+impl LoadedNotes {
+    /// Creates a new [LoadedNotes].
+    pub const fn new() -> Self {
+        Self {
+            cash_type: CashType::new(),
+            count: 0,
+        }
+    }
+
+    /// Creates a new [LoadedNotes] from the provided parameters.
+    pub const fn create(cash_type: CashType, count: u32) -> Self {
+        Self { cash_type, count }
+    }
+
+    /// Gets the [CashType] of the bills.
+    pub const fn cash_type(&self) -> CashType {
+        self.cash_type
+    }
+
+    /// Gets the number of bills loaded.
+    pub const fn count(&self) -> u32 {
+        self.count
+    }
+
+    /// Gets the [CashTotal] of the bills loaded.
+    pub fn total(&self) -> CashTotal {
+        let mut total = CashTotal::create(self.cash_type.currency_code().into(), 0, 0);
+        total.add(self.count, self.cash_type.value());
+        total
+    }
+}
+
+impl fmt::Display for LoadedNotes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{{")?;
+        write!(f, r#""cash_type": {}, "#, self.cash_type)?;
+        write!(f, r#""count": {}"#, self.count)?;
+        write!(f, "}}")
+    }
+}
+
+/// Gets the lists for [update_cash_unit](crate::DeviceHandle::update_cash_unit) that add the
+/// `loaded` bills to the loader counts.
+///
+/// Each denomination is added to the first loader [LogicalCashUnit](crate::LogicalCashUnit) of the
+/// same [CashType], and its initial count is set to the new count. The loader
+/// [PhysicalCashUnit](crate::PhysicalCashUnit) count is increased by the total.
+///
+/// Returns an error if the device has no loader unit for a denomination, or a logical or physical
+/// count exceeds [LOADER_MAX_COUNT].
+pub fn loader_refill_lists(
+    cash_unit: &CashUnit,
+    loaded: &[LoadedNotes],
+) -> Result<(LogicalCashUnitList, PhysicalCashUnitList)> {
+    let mut lcu_list = cash_unit.logical_cash_unit_list().clone();
+    let mut pcu_list = cash_unit.physical_cash_unit_list().clone();
+
+    for notes in loaded.iter().filter(|n| n.count != 0) {
+        let cash_type = notes.cash_type;
+
+        let lcu = lcu_list
+            .iter_mut()
+            .find(|l| {
+                l.cu_kind() == CuKind::Dispense
+                    && l.cash_type().currency_code() == cash_type.currency_code()
+                    && l.cash_type().value() == cash_type.value()
+            })
+            .ok_or(Error::Config(format!(
+                "no loader logical cash unit for the loaded notes: {notes}"
+            )))?;
+
+        let count = lcu
+            .count()
+            .checked_add(notes.count)
+            .filter(|&c| c <= LOADER_MAX_COUNT)
+            .ok_or(Error::Config(format!(
+                "loader logical cash unit {} count exceeds the maximum after adding {}: {LOADER_MAX_COUNT}",
+                lcu.number(),
+                notes.count
+            )))?;
+        lcu.set_count(count);
+        lcu.set_initial_count(count);
+
+        let pcu = pcu_list
+            .items_mut()
+            .get_mut(lcu.physical_cu_index() as usize)
+            .ok_or(Error::Config(format!(
+                "loader logical cash unit {} has an invalid physical cash unit index: {}",
+                lcu.number(),
+                lcu.physical_cu_index()
+            )))?;
+        let count = pcu
+            .count()
+            .checked_add(notes.count)
+            .filter(|&c| c <= LOADER_MAX_COUNT)
+            .ok_or(Error::Config(format!(
+                "loader physical cash unit {} count exceeds the maximum after adding {}: {LOADER_MAX_COUNT}",
+                pcu.name(),
+                notes.count
+            )))?;
+        pcu.set_count(count);
+    }
+
+    Ok((lcu_list, pcu_list))
+}
+
+/// Records a loader refill, see [refill_loader](crate::DeviceHandle::refill_loader).
+///
+/// Keeps the loader count after the refill, and the [LoaderAcceptanceHistory] counters at the
+/// time of the refill, as the baseline for [RefillReconciliation].
+#[derive(Clone, Debug, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct RefillRecord {
+    time: u64,
+    loaded: Vec<LoadedNotes>,
+    loader_before: u32,
+    loader_after: u32,
+    bills_extracted: u32,
+    extraction_rejects: u32,
+}
+
+impl RefillRecord {
+    /// Creates a new [RefillRecord].
+    pub const fn new() -> Self {
+        Self {
+            time: 0,
+            loaded: Vec::new(),
+            loader_before: 0,
+            loader_after: 0,
+            bills_extracted: 0,
+            extraction_rejects: 0,
+        }
+    }
+
+    /// Creates a new [RefillRecord] from the [CashUnit] counts before and after the refill, and
+    /// the [LoaderAcceptanceHistory] at the time of the refill.
+    pub fn create(
+        loaded: &[LoadedNotes],
+        before: &CashUnit,
+        after: &CashUnit,
+        history: &LoaderAcceptanceHistory,
+    ) -> Self {
+        Self {
+            time: unix_time(),
+            loaded: loaded.to_vec(),
+            loader_before: loader_count(before),
+            loader_after: loader_count(after),
+            bills_extracted: history.bill_extracted_count().inner(),
+            extraction_rejects: history.extraction_reject_count().inner(),
+        }
+    }
+
+    /// Gets the time of the refill, in seconds since the Unix epoch.
+    pub const fn time(&self) -> u64 {
+        self.time
+    }
+
+    /// Gets the bills loaded, by denomination.
+    pub fn loaded(&self) -> &[LoadedNotes] {
+        self.loaded.as_ref()
+    }
+
+    /// Gets the [CashTotal] loaded for each currency.
+    pub fn loaded_totals(&self) -> Vec<CashTotal> {
+        let mut totals: Vec<CashTotal> = Vec::new();
+
+        for notes in self.loaded.iter() {
+            let code = notes.cash_type.currency_code();
+            match totals
+                .iter_mut()
+                .find(|t| t.currency().currency_code() == code)
+            {
+                Some(total) => total.add(notes.count, notes.cash_type.value()),
+                None => totals.push(notes.total()),
+            }
+        }
+
+        totals
+    }
+
+    /// Gets the loader count before the refill.
+    pub const fn loader_before(&self) -> u32 {
+        self.loader_before
+    }
+
+    /// Gets the loader count after the refill.
+    pub const fn loader_after(&self) -> u32 {
+        self.loader_after
+    }
+
+    /// Gets the bill extracted count of the [LoaderAcceptanceHistory] at the time of the refill.
+    pub const fn bills_extracted(&self) -> u32 {
+        self.bills_extracted
+    }
+
+    /// Gets the extraction reject count of the [LoaderAcceptanceHistory] at the time of the
+    /// refill.
+    pub const fn extraction_rejects(&self) -> u32 {
+        self.extraction_rejects
+    }
+}
+
+impl fmt::Display for RefillRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{{")?;
+        write!(f, r#""time": {}, "#, self.time)?;
+        write!(f, r#""loaded": ["#)?;
+        for (i, notes) in self.loaded.iter().enumerate() {
+            if i != 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{notes}")?;
+        }
+        write!(f, "], ")?;
+        write!(f, r#""loader_before": {}, "#, self.loader_before)?;
+        write!(f, r#""loader_after": {}, "#, self.loader_after)?;
+        write!(f, r#""bills_extracted": {}, "#, self.bills_extracted)?;
+        write!(f, r#""extraction_rejects": {}"#, self.extraction_rejects)?;
+        write!(f, "}}")
+    }
+}
+
+/// Compares the loader count with the bills extracted from the loader since a refill.
+///
+/// Bills extracted from the loader, and bills rejected during extraction, both leave the loader:
+/// the expected loader count is the count after the refill minus both counts since the refill.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct RefillReconciliation {
+    extracted: u32,
+    extraction_rejects: u32,
+    expected_count: u32,
+    actual_count: u32,
+}
+
+impl RefillReconciliation {
+    /// Creates a new [RefillReconciliation].
+    pub const fn new() -> Self {
+        Self {
+            extracted: 0,
+            extraction_rejects: 0,
+            expected_count: 0,
+            actual_count: 0,
+        }
+    }
+
+    /// Reconciles the [RefillRecord] with the current [CashUnit] counts and
+    /// [LoaderAcceptanceHistory].
+    pub fn create(
+        record: &RefillRecord,
+        cash_unit: &CashUnit,
+        history: &LoaderAcceptanceHistory,
+    ) -> Self {
+        let extracted = history
+            .bill_extracted_count()
+            .inner()
+            .saturating_sub(record.bills_extracted);
+        let extraction_rejects = history
+            .extraction_reject_count()
+            .inner()
+            .saturating_sub(record.extraction_rejects);
+
+        Self {
+            extracted,
+            extraction_rejects,
+            expected_count: record
+                .loader_after
+                .saturating_sub(extracted)
+                .saturating_sub(extraction_rejects),
+            actual_count: loader_count(cash_unit),
+        }
+    }
+
+    /// Gets the number of bills extracted from the loader since the refill.
+    pub const fn extracted(&self) -> u32 {
+        self.extracted
+    }
+
+    /// Gets the number of bills rejected during extraction since the refill.
+    pub const fn extraction_rejects(&self) -> u32 {
+        self.extraction_rejects
+    }
+
+    /// Gets the expected loader count.
+    pub const fn expected_count(&self) -> u32 {
+        self.expected_count
+    }
+
+    /// Gets the loader count reported by the device.
+    pub const fn actual_count(&self) -> u32 {
+        self.actual_count
+    }
+
+    /// Gets the difference between the actual and expected loader counts.
+    pub const fn discrepancy(&self) -> i64 {
+        self.actual_count as i64 - self.expected_count as i64
+    }
+
+    /// Gets whether the loader count matches the bills extracted since the refill.
+    pub const fn is_reconciled(&self) -> bool {
+        self.discrepancy() == 0
+    }
+}
+
+impl fmt::Display for RefillReconciliation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{{")?;
+        write!(f, r#""extracted": {}, "#, self.extracted)?;
+        write!(f, r#""extraction_rejects": {}, "#, self.extraction_rejects)?;
+        write!(f, r#""expected_count": {}, "#, self.expected_count)?;
+        write!(f, r#""actual_count": {}"#, self.actual_count)?;
+        write!(f, "}}")
+    }
+}
+
+fn loader_count(cash_unit: &CashUnit) -> u32 {
+    let pcus = cash_unit.physical_cash_unit_list().items();
+
+    pcus_of_kind(cash_unit, CuKind::Dispense)
+        .filter_map(|(idx, _)| pcus.get(idx))
+        .map(|p| p.count())
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cash_unit::tests::cash_unit;
+    use crate::currency::CurrencyCode;
+    use crate::{BillExtractedCount, ExtractionRejectCount};
+
+    fn history(extracted: u32, rejects: u32) -> LoaderAcceptanceHistory {
+        LoaderAcceptanceHistory::new()
+            .with_bill_extracted_count(BillExtractedCount::create(extracted))
+            .with_extraction_reject_count(ExtractionRejectCount::create(rejects))
+    }
+
+    fn hundreds(count: u32) -> LoadedNotes {
+        LoadedNotes::create(
+            CashType::create(CurrencyCode::from("USD"), 10_000, 0),
+            count,
+        )
+    }
+
+    fn refilled(before: &CashUnit, loaded: &[LoadedNotes]) -> Result<CashUnit> {
+        let (lcu_list, pcu_list) = loader_refill_lists(before, loaded)?;
+        Ok(before
+            .clone()
+            .with_logical_cash_unit_list(lcu_list)
+            .with_physical_cash_unit_list(pcu_list))
+    }
+
+    fn with_loader_count(cash_unit: &CashUnit, count: u32) -> CashUnit {
+        let mut pcu_list = cash_unit.physical_cash_unit_list().clone();
+        pcu_list.items_mut()[2].set_count(count);
+        cash_unit.clone().with_physical_cash_unit_list(pcu_list)
+    }
+
+    #[test]
+    fn test_loader_refill_lists() -> Result<()> {
+        let before = cash_unit()?;
+
+        // notes without a count are skipped
+        let (lcu_list, pcu_list) = loader_refill_lists(&before, &[hundreds(50), hundreds(0)])?;
+
+        let lcu = lcu_list.iter().find(|l| l.number() == 11).unwrap();
+        assert_eq!(lcu.count(), 50);
+        assert_eq!(lcu.initial_count(), 50);
+        assert_eq!(pcu_list.items()[2].count(), 50);
+
+        // other units are left unchanged
+        assert_eq!(
+            lcu_list.iter().find(|l| l.number() == 7).map(|l| l.count()),
+            Some(1)
+        );
+        assert_eq!(pcu_list.items()[1].count(), 120);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_loader_refill_unknown_denomination() -> Result<()> {
+        let before = cash_unit()?;
+
+        // no loader unit for $20 bills, or for €100 bills
+        for cash_type in [
+            CashType::create(CurrencyCode::from("USD"), 2_000, 0),
+            CashType::create(CurrencyCode::from("EUR"), 10_000, 0),
+        ] {
+            match loader_refill_lists(&before, &[hundreds(10), LoadedNotes::create(cash_type, 10)])
+            {
+                Err(Error::Config(msg)) => {
+                    assert!(msg.starts_with("no loader logical cash unit"), "{msg}")
+                }
+                res => panic!("unexpected result: {res:?}"),
+            }
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_loader_refill_max_count() -> Result<()> {
+        let before = cash_unit()?;
+
+        assert!(matches!(
+            loader_refill_lists(&before, &[hundreds(LOADER_MAX_COUNT + 1)]),
+            Err(Error::Config(_))
+        ));
+
+        // the additions do not wrap around
+        let mut full = before.clone();
+        if let Some(lcu) = full
+            .logical_cash_unit_list_mut()
+            .iter_mut()
+            .find(|l| l.number() == 11)
+        {
+            lcu.set_count(1);
+        }
+        assert!(matches!(
+            loader_refill_lists(&full, &[hundreds(u32::MAX)]),
+            Err(Error::Config(_))
+        ));
+
+        // the loader physical count is checked, even if the logical count fits
+        let full = with_loader_count(&before, LOADER_MAX_COUNT);
+        assert!(matches!(
+            loader_refill_lists(&full, &[hundreds(50)]),
+            Err(Error::Config(_))
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_refill_record() -> Result<()> {
+        let before = cash_unit()?;
+        let loaded = [hundreds(50), hundreds(10)];
+        let after = refilled(&before, &loaded)?;
+
+        let record = RefillRecord::create(&loaded, &before, &after, &history(100, 2));
+        assert_eq!(record.loaded(), loaded);
+        assert_eq!(record.loader_before(), 0);
+        assert_eq!(record.loader_after(), 60);
+        assert_eq!(record.bills_extracted(), 100);
+        assert_eq!(record.extraction_rejects(), 2);
+
+        let totals = record.loaded_totals();
+        assert_eq!(totals.len(), 1);
+        assert_eq!(totals[0].amount_str(), "6000.00");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_refill_reconciliation() -> Result<()> {
+        let before = cash_unit()?;
+        let loaded = [hundreds(50)];
+        let after = refilled(&before, &loaded)?;
+        let record = RefillRecord::create(&loaded, &before, &after, &history(100, 2));
+
+        // 8 bills extracted, 1 rejected during extraction
+        let current = with_loader_count(&after, 41);
+        let reconciliation = RefillReconciliation::create(&record, &current, &history(108, 3));
+        assert_eq!(reconciliation.extracted(), 8);
+        assert_eq!(reconciliation.extraction_rejects(), 1);
+        assert_eq!(reconciliation.expected_count(), 41);
+        assert_eq!(reconciliation.actual_count(), 41);
+        assert!(reconciliation.is_reconciled());
+
+        // the device still counts the extracted bills in the loader
+        let reconciliation = RefillReconciliation::create(&record, &after, &history(108, 3));
+        assert_eq!(reconciliation.discrepancy(), 9);
+        assert!(!reconciliation.is_reconciled());
+
+        // bills missing from the loader without an extraction
+        let reconciliation = RefillReconciliation::create(&record, &current, &history(100, 2));
+        assert_eq!(reconciliation.expected_count(), 50);
+        assert_eq!(reconciliation.discrepancy(), -9);
+
+        // history counters lower than the record, e.g. after a reset, count as no extraction
+        let reconciliation = RefillReconciliation::create(&record, &after, &history(0, 0));
+        assert_eq!(
+            (
+                reconciliation.extracted(),
+                reconciliation.extraction_rejects()
+            ),
+            (0, 0)
+        );
+        assert!(reconciliation.is_reconciled());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,35 @@
+use crate::{DeviceHandle, Result};
+
+use super::{loader_refill_lists, LoadedNotes, RefillReconciliation, RefillRecord};
+
+/// Adds the `loaded` bills to the loader counts, and records the refill.
+pub(crate) fn refill_loader(handle: &DeviceHandle, loaded: &[LoadedNotes]) -> Result<RefillRecord> {
+    let before = handle.query_cash_unit_inner()?;
+    let history = handle.get_bill_acceptance_history_inner()?;
+
+    let (lcu_list, pcu_list) = loader_refill_lists(&before, loaded)?;
+    handle.update_cash_unit_inner(before.transport_count(), &lcu_list, &pcu_list)?;
+
+    let after = handle.query_cash_unit_inner()?;
+    let record = RefillRecord::create(loaded, &before, &after, history.loader_acceptance_history());
+    log::info!("Refill: {record}");
+
+    Ok(record)
+}
+
+/// Reconciles the loader count with the bills extracted since the refill.
+pub(crate) fn reconcile_refill(
+    handle: &DeviceHandle,
+    record: &RefillRecord,
+) -> Result<RefillReconciliation> {
+    let cash_unit = handle.query_cash_unit_inner()?;
+    let history = handle.get_bill_acceptance_history_inner()?;
+
+    let reconciliation =
+        RefillReconciliation::create(record, &cash_unit, history.loader_acceptance_history());
+    if !reconciliation.is_reconciled() {
+        log::warn!("Refill: loader count discrepancy: {reconciliation}");
+    }
+
+    Ok(reconciliation)
+}
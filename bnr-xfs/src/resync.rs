@@ -0,0 +1,363 @@
+//! Types for resynchronising with the BNR device when a connection is opened.
+
+use std::fmt;
+
+use crate::status::{
+    CdrStatus, ContentStatus, IntermediateStackerStatus, CASH_AVAILABLE, CASH_TAKEN,
+};
+use crate::xfs::method_call::{XfsMethodCall, XfsMethodName};
+use crate::xfs::OperationId;
+
+/// Represents an asynchronous operation left over from a previous session.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct StaleOperation {
+    call_id: i32,
+    operation_id: OperationId,
+    result: Option<i32>,
+    ext_result: Option<i32>,
+}
+
+impl StaleOperation {
+    /// Creates a new [StaleOperation].
+    pub const fn new() -> Self {
+        Self {
+            call_id: 0,
+            operation_id: OperationId::new(),
+            result: None,
+            ext_result: None,
+        }
+    }
+
+    /// Creates a new [StaleOperation] from the provided parameters.
+    pub const fn create(call_id: i32, operation_id: OperationId) -> Self {
+        Self {
+            call_id,
+            operation_id,
+            result: None,
+            ext_result: None,
+        }
+    }
+
+    /// Gets the callback ID returned by the call that started the operation.
+    pub const fn call_id(&self) -> i32 {
+        self.call_id
+    }
+
+    /// Gets the [OperationId] of the operation.
+    pub const fn operation_id(&self) -> OperationId {
+        self.operation_id
+    }
+
+    /// Gets the result of the operation.
+    ///
+    /// `None` if the operation has not completed.
+    pub const fn result(&self) -> Option<i32> {
+        self.result
+    }
+
+    /// Gets the extended result of the operation.
+    ///
+    /// `None` if the operation has not completed.
+    pub const fn ext_result(&self) -> Option<i32> {
+        self.ext_result
+    }
+
+    /// Gets whether the operation completed.
+    pub const fn is_complete(&self) -> bool {
+        self.result.is_some()
+    }
+
+    /// Gets whether the operation completed successfully.
+    pub const fn is_success(&self) -> bool {
+        matches!(self.result, Some(0))
+    }
+
+    #[cfg_attr(not(feature = "usb"), allow(dead_code))]
+    fn complete(&mut self, result: i32, ext_result: i32) {
+        self.result = Some(result);
+        self.ext_result = Some(ext_result);
+    }
+}
+
+impl fmt::Display for StaleOperation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{{")?;
+        write!(f, r#""call_id": {}, "#, self.call_id)?;
+        write!(f, r#""operation_id": {}, "#, self.operation_id)?;
+        match self.result {
+            Some(res) => write!(f, r#""result": {res}, "#)?,
+            None => write!(f, r#""result": null, "#)?,
+        }
+        match self.ext_result {
+            Some(res) => write!(f, r#""ext_result": {res}"#)?,
+            None => write!(f, r#""ext_result": null"#)?,
+        }
+        write!(f, "}}")
+    }
+}
+
+/// Describes the leftover state found on the BNR device when opening a connection.
+///
+/// After a crash of the host process, the BNR can still hold queued responses and callback calls
+/// from the previous session. Those messages are drained and discarded before the first call of
+/// the new session, and summarised here.
+#[repr(C)]
+#[derive(Clone, Debug, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct ResyncReport {
+    discarded_responses: usize,
+    discarded_callbacks: usize,
+    completed_operations: Vec<StaleOperation>,
+    pending_operation: Option<StaleOperation>,
+    cash_presented: bool,
+    stacker_not_empty: bool,
+}
+
+impl ResyncReport {
+    /// Creates a new [ResyncReport].
+    pub const fn new() -> Self {
+        Self {
+            discarded_responses: 0,
+            discarded_callbacks: 0,
+            completed_operations: Vec::new(),
+            pending_operation: None,
+            cash_presented: false,
+            stacker_not_empty: false,
+        }
+    }
+
+    /// Gets the number of stale method responses discarded from the response endpoint.
+    pub const fn discarded_responses(&self) -> usize {
+        self.discarded_responses
+    }
+
+    /// Gets the number of stale callback calls discarded from the callback endpoint.
+    pub const fn discarded_callbacks(&self) -> usize {
+        self.discarded_callbacks
+    }
+
+    /// Gets the list of operations from a previous session that completed before the connection
+    /// was opened.
+    pub fn completed_operations(&self) -> &[StaleOperation] {
+        self.completed_operations.as_ref()
+    }
+
+    /// Gets the operation from a previous session that is still running on the device, if any.
+    ///
+    /// For example, a `Dispense` or `CashIn` interrupted by a crash of the host process.
+    pub const fn pending_operation(&self) -> Option<&StaleOperation> {
+        self.pending_operation.as_ref()
+    }
+
+    /// Gets whether the previous session left cash presented at the outlet.
+    ///
+    /// Set when a `CASH_AVAILABLE` status event was drained without a following `CASH_TAKEN`, or
+    /// when the device status reports cash at a position.
+    pub const fn cash_presented(&self) -> bool {
+        self.cash_presented
+    }
+
+    /// Gets whether the previous session left bills in the intermediate stacker.
+    ///
+    /// An interrupted `Dispense` or `CashIn` can leave bills in the stacker without sending any
+    /// intermediate event.
+    pub const fn stacker_not_empty(&self) -> bool {
+        self.stacker_not_empty
+    }
+
+    /// Gets whether any leftover state was found on the device.
+    pub fn is_clean(&self) -> bool {
+        self.discarded_responses == 0
+            && self.discarded_callbacks == 0
+            && self.completed_operations.is_empty()
+            && self.pending_operation.is_none()
+            && !self.cash_presented
+            && !self.stacker_not_empty
+    }
+
+    #[cfg_attr(not(feature = "usb"), allow(dead_code))]
+    pub(crate) fn discard_response(&mut self) {
+        self.discarded_responses = self.discarded_responses.saturating_add(1);
+    }
+
+    /// Records the intermediate stacker and position status queried after draining.
+    ///
+    /// The device status is authoritative over the drained status events.
+    #[cfg_attr(not(feature = "usb"), allow(dead_code))]
+    pub(crate) fn check_status(&mut self, status: &CdrStatus) {
+        self.stacker_not_empty =
+            status.intermediate_stacker_status == IntermediateStackerStatus::NotEmpty;
+        self.cash_presented = status
+            .position_status_list
+            .iter()
+            .any(|pos| pos.content_status == ContentStatus::NotEmpty);
+    }
+
+    /// Records a stale callback call drained from the device.
+    #[cfg_attr(not(feature = "usb"), allow(dead_code))]
+    pub(crate) fn discard_callback(&mut self, msg: &XfsMethodCall) {
+        self.discarded_callbacks = self.discarded_callbacks.saturating_add(1);
+
+        let name = match msg.name() {
+            Ok(name) => name,
+            Err(err) => {
+                log::warn!("Unknown stale callback call: {err}");
+                return;
+            }
+        };
+
+        let id = msg.call_id().unwrap_or(-1);
+
+        match name {
+            XfsMethodName::IntermediateOccurred => {
+                if let Ok(op_id) = msg.operation_id() {
+                    match self.pending_operation.as_ref() {
+                        Some(op) if op.call_id() == id => (),
+                        _ => self.pending_operation = Some(StaleOperation::create(id, op_id)),
+                    }
+                }
+            }
+            XfsMethodName::OperationCompleteOccurred => {
+                let mut op = match self.pending_operation.take() {
+                    Some(op) if op.call_id() == id => op,
+                    Some(op) => {
+                        self.pending_operation = Some(op);
+                        StaleOperation::create(id, msg.operation_id().unwrap_or_default())
+                    }
+                    None => StaleOperation::create(id, msg.operation_id().unwrap_or_default()),
+                };
+
+                op.complete(msg.result().unwrap_or(-1), msg.ext_result().unwrap_or(0));
+
+                self.completed_operations.push(op);
+            }
+            // status events carry the status code in place of the call ID
+            XfsMethodName::StatusOccurred => match id as u32 {
+                CASH_AVAILABLE => self.cash_presented = true,
+                CASH_TAKEN => self.cash_presented = false,
+                _ => (),
+            },
+            _ => (),
+        }
+    }
+}
+
+impl fmt::Display for ResyncReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{{")?;
+        write!(
+            f,
+            r#""discarded_responses": {}, "#,
+            self.discarded_responses
+        )?;
+        write!(
+            f,
+            r#""discarded_callbacks": {}, "#,
+            self.discarded_callbacks
+        )?;
+        write!(f, r#""completed_operations": ["#)?;
+        for (i, op) in self.completed_operations.iter().enumerate() {
+            if i != 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{op}")?;
+        }
+        write!(f, "], ")?;
+        match self.pending_operation.as_ref() {
+            Some(op) => write!(f, r#""pending_operation": {op}, "#)?,
+            None => write!(f, r#""pending_operation": null, "#)?,
+        }
+        write!(f, r#""cash_presented": {}, "#, self.cash_presented)?;
+        write!(f, r#""stacker_not_empty": {}"#, self.stacker_not_empty)?;
+        write!(f, "}}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::status::CdrPositionStatusList;
+    use crate::xfs::{params::XfsParam, value::XfsValue};
+
+    fn callback(name: XfsMethodName, params: &[i32]) -> XfsMethodCall {
+        XfsMethodCall::create(
+            name,
+            params
+                .iter()
+                .map(|&p| XfsParam::create(XfsValue::new().with_i4(p)))
+                .collect::<Vec<XfsParam>>(),
+        )
+    }
+
+    #[test]
+    fn test_resync_report() {
+        let dispense = OperationId::Dispense.inner() as i32;
+        let present = OperationId::Present.inner() as i32;
+
+        let mut report = ResyncReport::new();
+        assert!(report.is_clean());
+
+        report.discard_callback(&callback(
+            XfsMethodName::IntermediateOccurred,
+            &[8, dispense, 0],
+        ));
+
+        let pending = report.pending_operation().unwrap();
+        assert_eq!(pending.call_id(), 8);
+        assert_eq!(pending.operation_id(), OperationId::Dispense);
+        assert!(!pending.is_complete());
+
+        report.discard_callback(&callback(
+            XfsMethodName::OperationCompleteOccurred,
+            &[8, dispense, 0, 0],
+        ));
+
+        assert!(report.pending_operation().is_none());
+        assert_eq!(report.completed_operations().len(), 1);
+        assert!(report.completed_operations()[0].is_success());
+
+        report.discard_callback(&callback(
+            XfsMethodName::StatusOccurred,
+            &[CASH_AVAILABLE as i32, 0, 0],
+        ));
+        report.discard_callback(&callback(
+            XfsMethodName::IntermediateOccurred,
+            &[16, present, 0],
+        ));
+
+        assert!(report.cash_presented());
+        assert_eq!(
+            report.pending_operation().map(|op| op.operation_id()),
+            Some(OperationId::Present)
+        );
+        assert_eq!(report.discarded_callbacks(), 4);
+        assert!(!report.is_clean());
+    }
+
+    #[test]
+    fn test_resync_report_status() {
+        let mut status = CdrStatus::new();
+        status.intermediate_stacker_status = IntermediateStackerStatus::Empty;
+
+        let mut report = ResyncReport::new();
+        report.check_status(&status);
+        assert!(report.is_clean());
+
+        // an interrupted dispense that sent no intermediate event
+        status.intermediate_stacker_status = IntermediateStackerStatus::NotEmpty;
+        report.check_status(&status);
+
+        assert!(report.stacker_not_empty());
+        assert!(report.pending_operation().is_none());
+        assert!(!report.is_clean());
+
+        status.intermediate_stacker_status = IntermediateStackerStatus::Empty;
+        status.position_status_list = CdrPositionStatusList::new().with_size(1);
+        status.position_status_list.items_mut()[0].content_status = ContentStatus::NotEmpty;
+        report.check_status(&status);
+
+        assert!(!report.stacker_not_empty());
+        assert!(report.cash_presented());
+        assert!(!report.is_clean());
+    }
+}
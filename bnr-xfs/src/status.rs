@@ -3,7 +3,7 @@
 use std::fmt;
 
 use crate::xfs::{method_response::XfsMethodResponse, params::XfsParam};
-use crate::{impl_xfs_struct, Error, Result};
+use crate::{Error, Result, XfsStruct};
 
 mod content;
 mod device;
@@ -32,7 +32,8 @@ pub use transport::*;
 /// Represents the CDR status returned by the
 /// [`get_status`](crate::device_handle::DeviceHandle::get_status) call.
 #[repr(C)]
-#[derive(Clone, Copy, Debug, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+#[derive(Clone, Debug, Default, PartialEq, serde::Deserialize, serde::Serialize, XfsStruct)]
+#[xfs(name = "status", no_display)]
 pub struct CdrStatus {
     pub device_status: DeviceStatus,
     pub dispenser_status: DispenserStatus,
@@ -73,20 +74,6 @@ impl From<CdrStatus> for HardwareStatus {
     }
 }
 
-impl_xfs_struct!(
-    CdrStatus,
-    "status",
-    [
-        device_status: DeviceStatus,
-        dispenser_status: DispenserStatus,
-        intermediate_stacker_status: IntermediateStackerStatus,
-        safe_door_status: SafeDoorStatus,
-        shutter_status: ShutterStatus,
-        transport_status: TransportStatus,
-        position_status_list: CdrPositionStatusList
-    ]
-);
-
 impl From<&CdrStatus> for XfsParam {
     fn from(val: &CdrStatus) -> Self {
         Self::create(val.into())
@@ -148,9 +135,9 @@ impl fmt::Display for CdrStatus {
             r#" "intermediate_stacker_status": "{}","#,
             self.intermediate_stacker_status
         )?;
-        write!(f, r#" "safe_door_status": "{}","#, self.safe_door_status)?;
-        write!(f, r#" "shutter_status": "{}","#, self.shutter_status)?;
-        write!(f, r#" "transport_status": "{}","#, self.transport_status)?;
+        write!(f, r#" "safe_door_status": {},"#, self.safe_door_status)?;
+        write!(f, r#" "shutter_status": {},"#, self.shutter_status)?;
+        write!(f, r#" "transport_status": {},"#, self.transport_status)?;
         write!(
             f,
             r#" "position_status_list": {}}}"#,
@@ -1,6 +1,6 @@
-use std::{cmp, fmt};
+use std::fmt;
 
-use crate::{create_xfs_bool, create_xfs_i4, impl_xfs_array, impl_xfs_struct, ShutterCmd, Size};
+use crate::{BoundedList, ShutterCmd, XfsArray, XfsStruct};
 
 use super::CdrPosition;
 
@@ -36,14 +36,17 @@ create_xfs_bool!(Refusal, "refusal", "Specifies whether refused notes can be mov
 
 /// Characteristics of an input/output position.
 #[repr(C)]
-#[derive(Clone, Copy, Debug, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, serde::Deserialize, serde::Serialize, XfsStruct,
+)]
+#[xfs(name = "positionCapabilities", no_display)]
 pub struct CdrPositionCapabilities {
     /// Position fo the unit.
     pub position: CdrPosition,
-    pub content_status_supported: ContentStatusSupported,
     pub shutter_status_supported: ShutterStatusSupported,
     /// Defines if the shutter has to be explicitly controlled by the application.
     pub shutter_cmd: ShutterCmd,
+    pub content_status_supported: ContentStatusSupported,
     pub max_items: MaxItems,
     pub input: Input,
     pub output: Output,
@@ -92,95 +95,31 @@ impl fmt::Display for CdrPositionCapabilities {
     }
 }
 
-impl_xfs_struct!(
-    CdrPositionCapabilities,
-    "positionCapabilities",
-    [
-        position: CdrPosition,
-        shutter_status_supported: ShutterStatusSupported,
-        shutter_cmd: ShutterCmd,
-        content_status_supported: ContentStatusSupported,
-        max_items: MaxItems,
-        input: Input,
-        output: Output,
-        rollback: Rollback,
-        refusal: Refusal
-    ]
-);
-
 /// List of position capabilties.
-#[repr(C)]
-#[derive(Clone, Copy, Debug, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+#[derive(Clone, Debug, Default, PartialEq, serde::Deserialize, serde::Serialize, XfsArray)]
+#[xfs(name = "positionCapabilitiesList", no_display)]
 pub struct CdrPositionCapabilitiesList {
-    size: Size,
-    items: [CdrPositionCapabilities; CDR_POS_CAP_LIST_LEN],
+    items: BoundedList<CdrPositionCapabilities, CDR_POS_CAP_LIST_LEN>,
 }
 
 impl CdrPositionCapabilitiesList {
     /// Creates a new [CdrPositionCapabilitiesList].
     pub const fn new() -> Self {
         Self {
-            size: Size::new(),
-            items: [CdrPositionCapabilities::new(); CDR_POS_CAP_LIST_LEN],
-        }
-    }
-
-    /// Creates a new [CdrPositionCapabilitiesList] from the provided parameter.
-    pub const fn create(items: [CdrPositionCapabilities; CDR_POS_CAP_LIST_LEN]) -> Self {
-        Self {
-            size: Size::create(CDR_POS_CAP_LIST_LEN as u32),
-            items,
+            items: BoundedList::new(),
         }
     }
 
-    /// Gets the max size.
-    pub const fn max_size() -> usize {
-        CDR_POS_CAP_LIST_LEN
-    }
-
     /// Gets the size.
-    pub const fn size(&self) -> u32 {
-        self.size.inner()
+    pub fn size(&self) -> u32 {
+        self.items.len() as u32
     }
 
     /// Sets the size.
+    ///
+    /// New items are set to the default value, no-op if `size` is larger than the max size.
     pub fn set_size(&mut self, size: u32) {
-        if size as usize <= Self::max_size() {
-            self.size.set_inner(size);
-        }
-    }
-
-    /// Gets a reference to the [CdrPositionCapabilities] list.
-    pub fn items(&self) -> &[CdrPositionCapabilities] {
-        let len = self.size.inner() as usize;
-        if len <= Self::max_size() {
-            self.items[..len].as_ref()
-        } else {
-            self.items.as_ref()
-        }
-    }
-
-    /// Gets a reference to the [CdrPositionCapabilities] list.
-    pub fn items_mut(&mut self) -> &mut [CdrPositionCapabilities] {
-        let len = self.size.inner() as usize;
-        if len <= Self::max_size() {
-            self.items[..len].as_mut()
-        } else {
-            self.items.as_mut()
-        }
-    }
-
-    /// Sets the [CdrPositionCapabilities] list items.
-    pub fn set_items(&mut self, items: &[CdrPositionCapabilities]) {
-        let len = cmp::min(items.len(), Self::max_size());
-        self.items[..len].copy_from_slice(&items[..len]);
-        self.size.set_inner(len as u32);
-    }
-
-    /// Builder function that sets the [CdrPositionCapabilities] list items.
-    pub fn with_items(mut self, items: &[CdrPositionCapabilities]) -> Self {
-        self.set_items(items);
-        self
+        self.items.resize(size as usize);
     }
 }
 
@@ -200,8 +139,6 @@ impl fmt::Display for CdrPositionCapabilitiesList {
     }
 }
 
-impl_xfs_array!(CdrPositionCapabilitiesList, "positionCapabilitiesList");
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -230,23 +167,18 @@ mod tests {
                 ]),
             )])),
         );
-        let exp_cap_list = CdrPositionCapabilitiesList {
-            size: Size::create(1),
-            items: [
-                CdrPositionCapabilities {
-                    position: CdrPosition::Top,
-                    shutter_status_supported: false.into(),
-                    shutter_cmd: false.into(),
-                    content_status_supported: true.into(),
-                    max_items: 1u32.into(),
-                    input: true.into(),
-                    output: false.into(),
-                    rollback: false.into(),
-                    refusal: true.into(),
-                },
-                CdrPositionCapabilities::new(),
-            ],
-        };
+        let exp_cap_list =
+            CdrPositionCapabilitiesList::new().with_items(&[CdrPositionCapabilities {
+                position: CdrPosition::Top,
+                shutter_status_supported: false.into(),
+                shutter_cmd: false.into(),
+                content_status_supported: true.into(),
+                max_items: 1u32.into(),
+                input: true.into(),
+                output: false.into(),
+                rollback: false.into(),
+                refusal: true.into(),
+            }]);
 
         assert_eq!(
             CdrPositionCapabilitiesList::try_from(&exp_xfs)?,
@@ -342,33 +274,30 @@ mod tests {
             ])),
         );
 
-        let exp_cap_list = CdrPositionCapabilitiesList {
-            size: Size::create(2),
-            items: [
-                CdrPositionCapabilities {
-                    position: CdrPosition::Top,
-                    content_status_supported: true.into(),
-                    shutter_status_supported: false.into(),
-                    shutter_cmd: false.into(),
-                    max_items: 1u32.into(),
-                    input: true.into(),
-                    output: false.into(),
-                    rollback: false.into(),
-                    refusal: true.into(),
-                },
-                CdrPositionCapabilities {
-                    position: CdrPosition::Bottom,
-                    content_status_supported: true.into(),
-                    shutter_status_supported: true.into(),
-                    shutter_cmd: true.into(),
-                    max_items: 15u32.into(),
-                    input: false.into(),
-                    output: true.into(),
-                    rollback: true.into(),
-                    refusal: false.into(),
-                },
-            ],
-        };
+        let exp_cap_list = CdrPositionCapabilitiesList::new().with_items(&[
+            CdrPositionCapabilities {
+                position: CdrPosition::Top,
+                content_status_supported: true.into(),
+                shutter_status_supported: false.into(),
+                shutter_cmd: false.into(),
+                max_items: 1u32.into(),
+                input: true.into(),
+                output: false.into(),
+                rollback: false.into(),
+                refusal: true.into(),
+            },
+            CdrPositionCapabilities {
+                position: CdrPosition::Bottom,
+                content_status_supported: true.into(),
+                shutter_status_supported: true.into(),
+                shutter_cmd: true.into(),
+                max_items: 15u32.into(),
+                input: false.into(),
+                output: true.into(),
+                rollback: true.into(),
+                refusal: false.into(),
+            },
+        ]);
 
         assert_eq!(
             CdrPositionCapabilitiesList::try_from(&exp_xfs)?,
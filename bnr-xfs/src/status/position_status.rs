@@ -1,6 +1,6 @@
-use std::{cmp, fmt};
+use std::fmt;
 
-use crate::{MaxSize, Size};
+use crate::{BoundedList, XfsArray, XfsStruct};
 
 use super::{CdrPosition, ContentStatus, HardwareStatus, ShutterStatus};
 
@@ -8,7 +8,10 @@ pub const POS_STATUS_LIST_LEN: usize = 2;
 
 /// Status of a CDR stacker.
 #[repr(C)]
-#[derive(Clone, Copy, Debug, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, serde::Deserialize, serde::Serialize, XfsStruct,
+)]
+#[xfs(name = "positionStatus", no_display)]
 pub struct PositionStatus {
     pub position: CdrPosition,
     pub content_status: ContentStatus,
@@ -46,61 +49,31 @@ impl fmt::Display for PositionStatus {
     }
 }
 
-impl_xfs_struct!(
-    PositionStatus,
-    "positionStatus",
-    [
-        position: CdrPosition,
-        content_status: ContentStatus,
-        shutter_status: ShutterStatus
-    ]
-);
-
 /// List of CDR stacker status by position.
-#[repr(C)]
-#[derive(Clone, Copy, Debug, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+#[derive(Clone, Debug, Default, PartialEq, serde::Deserialize, serde::Serialize, XfsArray)]
+#[xfs(name = "positionStatusList", no_display)]
 pub struct CdrPositionStatusList {
-    max_size: MaxSize,
-    size: Size,
-    items: [PositionStatus; POS_STATUS_LIST_LEN],
+    items: BoundedList<PositionStatus, POS_STATUS_LIST_LEN>,
 }
 
 impl CdrPositionStatusList {
     /// Creates a new [CdrPositionStatusList].
     pub const fn new() -> Self {
         Self {
-            max_size: MaxSize::create(POS_STATUS_LIST_LEN as u32),
-            size: Size::new(),
-            items: [PositionStatus::new(); POS_STATUS_LIST_LEN],
-        }
-    }
-
-    /// Creates a new [XfsCdrPositionStatusList] from the provided parameter.
-    pub const fn create(items: [PositionStatus; POS_STATUS_LIST_LEN]) -> Self {
-        Self {
-            max_size: MaxSize::create(POS_STATUS_LIST_LEN as u32),
-            size: Size::create(POS_STATUS_LIST_LEN as u32),
-            items,
+            items: BoundedList::new(),
         }
     }
 
-    /// Gets the max size.
-    pub const fn max_size() -> usize {
-        POS_STATUS_LIST_LEN
-    }
-
     /// Gets the populated size of the [PositionStatus] list.
-    pub const fn size(&self) -> usize {
-        self.size.inner() as usize
+    pub fn size(&self) -> usize {
+        self.items.len()
     }
 
     /// Sets the populated size of the [PositionStatus] list.
     ///
     /// **NOTE** `size` must be less-than-or-equal to `POS_STATUS_LIST_LEN`.
     pub fn set_size(&mut self, size: u32) {
-        if size as usize <= POS_STATUS_LIST_LEN {
-            self.size.set_inner(size);
-        }
+        self.items.resize(size as usize);
     }
 
     /// Builder function that sets the populated size of the [PositionStatus] list.
@@ -110,59 +83,33 @@ impl CdrPositionStatusList {
         self.set_size(size);
         self
     }
+}
 
-    /// Gets a reference to the [PositionStatus] list.
-    pub fn items(&self) -> &[PositionStatus] {
-        let len = self.size();
-        if len <= Self::max_size() {
-            self.items[..len].as_ref()
-        } else {
-            self.items.as_ref()
-        }
-    }
-
-    /// Gets a mutable reference to the [PositionStatus] list.
-    pub fn items_mut(&mut self) -> &mut [PositionStatus] {
-        let len = self.size();
-        if len <= Self::max_size() {
-            self.items[..len].as_mut()
-        } else {
-            self.items.as_mut()
-        }
-    }
-
-    /// Sets the [PositionStatus] list.
-    ///
-    /// **NOTE** sets at most `POS_STATUS_LIST_LEN` items.
-    pub fn set_items(&mut self, items: &[PositionStatus]) {
-        let len = cmp::min(items.len(), Self::max_size());
-        self.items[..len].copy_from_slice(&items[..len]);
-        self.size.set_inner(len as u32);
-    }
-
-    /// Builder function that sets the [PositionStatus] list.
-    ///
-    /// **NOTE** sets at most `POS_STATUS_LIST_LEN` items.
-    pub fn with_items(mut self, items: &[PositionStatus]) -> Self {
-        self.set_items(items);
-        self
+impl From<&CdrPositionStatusList> for HardwareStatus {
+    fn from(val: &CdrPositionStatusList) -> Self {
+        val.iter()
+            .copied()
+            .map(Self::from)
+            .collect::<Vec<Self>>()
+            .as_slice()
+            .into()
     }
 }
 
 impl From<CdrPositionStatusList> for HardwareStatus {
     fn from(val: CdrPositionStatusList) -> Self {
-        val.items.map(Self::from).into()
+        (&val).into()
     }
 }
 
 impl fmt::Display for CdrPositionStatusList {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{{")?;
-        write!(f, r#""max_size": {}, "#, self.max_size)?;
-        write!(f, r#""size": {}, "#, self.size)?;
+        write!(f, r#""max_size": {}, "#, Self::max_size())?;
+        write!(f, r#""size": {}, "#, self.size())?;
         write!(f, r#""items": ["#)?;
 
-        for (i, item) in self.items().iter().enumerate() {
+        for (i, item) in self.iter().enumerate() {
             if i != 0 {
                 write!(f, ", ")?;
             }
@@ -172,5 +119,3 @@ impl fmt::Display for CdrPositionStatusList {
         write!(f, "]}}")
     }
 }
-
-impl_xfs_array!(CdrPositionStatusList, "positionStatusList");
@@ -1,8 +1,9 @@
 //! XFS message types for BNR communication.
 
-use crate::Result;
+use crate::{Error, Result};
 
 pub mod array;
+pub mod codec;
 pub mod fault;
 pub mod method_call;
 pub mod method_response;
@@ -29,6 +30,9 @@ pub fn to_string<S: serde::Serialize>(s: S) -> Result<String> {
 }
 
 /// Serializes to a XML string with `ISO-8859-1` encoding attribute.
+///
+/// **NOTE** the returned string is still UTF-8, use [to_iso_bytes] to get the encoded bytes for
+/// sending to the device.
 pub fn to_iso_string<S: serde::Serialize>(s: S) -> Result<String> {
     let mut sink = Vec::with_capacity(0xff);
 
@@ -43,6 +47,14 @@ pub fn to_iso_string<S: serde::Serialize>(s: S) -> Result<String> {
     Ok(String::from_utf8(sink)?)
 }
 
+/// Serializes to `ISO-8859-1` encoded XML bytes.
+pub fn to_iso_bytes<S: serde::Serialize>(s: S) -> Result<Vec<u8>> {
+    encode_latin1(to_iso_string(s)?.as_str())
+}
+
+/// Deserializes from a XML string.
+///
+/// The string is already decoded, so the encoding attribute of the XML declaration is ignored.
 pub fn from_str<'de, T: serde::Deserialize<'de>>(xml_str: &'de str) -> Result<T> {
     let mut de = serde_xml::Deserializer::new(xml::EventReader::new_with_config(
         xml_str.as_bytes(),
@@ -50,8 +62,122 @@ pub fn from_str<'de, T: serde::Deserialize<'de>>(xml_str: &'de str) -> Result<T>
             .trim_whitespace(true)
             .whitespace_to_characters(false)
             .cdata_to_characters(true)
-            .coalesce_characters(true),
+            .coalesce_characters(true)
+            .override_encoding(Some(xml::Encoding::Utf8))
+            .ignore_invalid_encoding_declarations(true),
     ));
 
     Ok(T::deserialize(&mut de)?)
 }
+
+/// Deserializes from raw XML bytes, decoding using the encoding attribute of the XML declaration.
+pub fn from_bytes<T: serde::de::DeserializeOwned>(xml_bytes: &[u8]) -> Result<T> {
+    from_str(decode(xml_bytes)?.as_str())
+}
+
+/// Decodes raw XML bytes into a string.
+///
+/// Uses the encoding attribute of the XML declaration, defaulting to `UTF-8` when no encoding is
+/// declared.
+pub fn decode(xml_bytes: &[u8]) -> Result<String> {
+    match declared_encoding(xml_bytes)
+        .unwrap_or("UTF-8")
+        .to_ascii_uppercase()
+        .as_str()
+    {
+        "UTF-8" | "UTF8" => Ok(std::str::from_utf8(xml_bytes)?.into()),
+        "ISO-8859-1" | "ISO8859-1" | "ISO_8859-1" | "LATIN1" | "LATIN-1" => {
+            Ok(decode_latin1(xml_bytes))
+        }
+        "US-ASCII" | "ASCII" if xml_bytes.is_ascii() => Ok(std::str::from_utf8(xml_bytes)?.into()),
+        enc => Err(Error::Xfs(format!("unsupported XML encoding: {enc}"))),
+    }
+}
+
+/// Decodes `ISO-8859-1` bytes into a string.
+///
+/// Every byte maps directly to the Unicode code point of the same value.
+pub fn decode_latin1(bytes: &[u8]) -> String {
+    bytes.iter().map(|&b| char::from(b)).collect()
+}
+
+/// Encodes a string into `ISO-8859-1` bytes.
+///
+/// Returns an error if the string contains characters outside the `ISO-8859-1` range.
+pub fn encode_latin1(s: &str) -> Result<Vec<u8>> {
+    s.chars()
+        .map(|c| {
+            u8::try_from(u32::from(c)).map_err(|_| {
+                Error::Xfs(format!(
+                    "character cannot be encoded as ISO-8859-1: {c:?} (U+{:04X})",
+                    u32::from(c)
+                ))
+            })
+        })
+        .collect()
+}
+
+/// Gets the value of the encoding attribute from the XML declaration, if present.
+fn declared_encoding(xml_bytes: &[u8]) -> Option<&str> {
+    let start = xml_bytes.iter().position(|&b| b == b'<')?;
+    let decl = xml_bytes.get(start..)?.strip_prefix(b"<?xml")?;
+    let end = decl.windows(2).position(|w| w == b"?>")?;
+    // the declaration is always ASCII, regardless of the document encoding
+    let decl = std::str::from_utf8(&decl[..end]).ok()?;
+
+    let (_, rest) = decl.split_once("encoding")?;
+    let rest = rest.trim_start().strip_prefix('=')?.trim_start();
+    let quote = rest.chars().next().filter(|&c| c == '"' || c == '\'')?;
+    let rest = &rest[1..];
+
+    rest.split_once(quote).map(|(enc, _)| enc.trim())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_latin1_round_trip() {
+        let exp_str = "Café Zürich ÀÉÎÕÜ ÿ";
+        let exp_bytes = [
+            b'C', b'a', b'f', 0xe9, b' ', b'Z', 0xfc, b'r', b'i', b'c', b'h', b' ', 0xc0, 0xc9,
+            0xce, 0xd5, 0xdc, b' ', 0xff,
+        ];
+
+        assert_eq!(encode_latin1(exp_str).unwrap(), exp_bytes);
+        assert_eq!(decode_latin1(exp_bytes.as_ref()), exp_str);
+
+        assert!(encode_latin1("€").is_err());
+    }
+
+    #[test]
+    fn test_declared_encoding() {
+        assert_eq!(
+            declared_encoding(br#"<?xml version="1.0" encoding="ISO-8859-1"?><a/>"#),
+            Some("ISO-8859-1")
+        );
+        assert_eq!(
+            declared_encoding(b"<?xml version='1.0' encoding='utf-8' ?><a/>"),
+            Some("utf-8")
+        );
+        assert_eq!(declared_encoding(br#"<?xml version="1.0"?><a/>"#), None);
+        assert_eq!(declared_encoding(b"<a/>"), None);
+    }
+
+    #[test]
+    fn test_decode() {
+        let iso = b"<?xml version=\"1.0\" encoding=\"ISO-8859-1\"?><a>\xe9</a>";
+        assert_eq!(
+            decode(iso.as_ref()).unwrap(),
+            "<?xml version=\"1.0\" encoding=\"ISO-8859-1\"?><a>é</a>"
+        );
+
+        let utf8 = "<?xml version=\"1.0\" encoding=\"UTF-8\"?><a>é</a>";
+        assert_eq!(decode(utf8.as_bytes()).unwrap(), utf8);
+
+        // Latin-1 bytes are not valid UTF-8
+        assert!(decode(b"<?xml version=\"1.0\"?><a>\xe9</a>".as_ref()).is_err());
+        assert!(decode(b"<?xml version=\"1.0\" encoding=\"EBCDIC\"?><a/>".as_ref()).is_err());
+    }
+}
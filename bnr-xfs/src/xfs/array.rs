@@ -3,6 +3,7 @@
 use std::fmt;
 
 use super::value::{ListValue, XfsValue};
+use crate::Error;
 
 #[repr(C)]
 #[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
@@ -91,6 +92,22 @@ impl fmt::Display for XfsArray {
     }
 }
 
+/// Handles an item that failed to convert for a [XfsArray](crate::XfsArray) derived type.
+///
+/// Logs a warning naming the type and item index, the item is set to its default value.
+pub fn item_error(ty: &str, name: &str, index: usize, err: Error) {
+    log::warn!(r#"{ty} ("{name}") item[{index}]: {err}, using the default value"#);
+}
+
+/// Handles items past the maximum size for a [XfsArray](crate::XfsArray) derived type.
+///
+/// Logs a warning with the number of `skipped` items, if any.
+pub fn truncated_items(ty: &str, name: &str, skipped: usize, max: usize) {
+    if skipped != 0 {
+        log::warn!(r#"{ty} ("{name}"): ignoring {skipped} items past the maximum size: {max}"#);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1,836 @@
+//! Streaming codec for XFS messages.
+//!
+//! The XFS message format is a small, fixed subset of XML-RPC. Instead of going through a generic
+//! XML deserializer into an [XfsValue] tree, the [XfsReader] pull-parser walks the borrowed
+//! message text, and [XfsDecode] types read their members straight off the event stream.
+//!
+//! The serializer writes the same output as [to_string](super::to_string) directly into a
+//! [String], without the intermediate XML event writer.
+
+use std::borrow::Cow;
+use std::fmt;
+
+use crate::{Error, Result};
+
+use super::array::XfsArray;
+use super::fault::XfsFault;
+use super::method_call::XfsMethodCall;
+use super::method_response::XfsMethodResponse;
+use super::params::{XfsParam, XfsParams};
+use super::value::XfsValue;
+use super::xfs_struct::{XfsMember, XfsStruct};
+
+/// Represents a parsing event emitted by the [XfsReader].
+#[derive(Clone, Debug, PartialEq)]
+pub enum XfsEvent<'a> {
+    /// Start of an element, e.g. `<value>`.
+    Start(&'a str),
+    /// End of an element, e.g. `</value>`.
+    ///
+    /// Self-closing elements, e.g. `<value/>`, emit a [Start](Self::Start) followed by an `End`.
+    End(&'a str),
+    /// Unescaped text content, borrowed when no entities are present.
+    Text(Cow<'a, str>),
+    /// End of the message.
+    Eof,
+}
+
+impl fmt::Display for XfsEvent<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Start(name) => write!(f, "<{name}>"),
+            Self::End(name) => write!(f, "</{name}>"),
+            Self::Text(text) => write!(f, r#""{text}""#),
+            Self::Eof => write!(f, "EOF"),
+        }
+    }
+}
+
+/// Pull-parser over a borrowed XFS message.
+///
+/// The reader is `Copy`, so callers can save the current position, and rewind to it on failure.
+#[derive(Clone, Copy, Debug)]
+pub struct XfsReader<'a> {
+    xml: &'a str,
+    pos: usize,
+    pending_end: Option<&'a str>,
+}
+
+impl<'a> XfsReader<'a> {
+    /// Creates a new [XfsReader] over the provided XML string.
+    pub fn new(xml: &'a str) -> Self {
+        Self {
+            xml: xml.strip_prefix('\u{feff}').unwrap_or(xml),
+            pos: 0,
+            pending_end: None,
+        }
+    }
+
+    /// Gets the current byte offset into the XML string.
+    pub const fn position(&self) -> usize {
+        self.pos
+    }
+
+    /// Reads the next [XfsEvent].
+    ///
+    /// The XML declaration, processing instructions, comments, and whitespace-only text are
+    /// skipped. Text content is trimmed of surrounding whitespace.
+    pub fn next_event(&mut self) -> Result<XfsEvent<'a>> {
+        if let Some(name) = self.pending_end.take() {
+            return Ok(XfsEvent::End(name));
+        }
+
+        loop {
+            let rest = &self.xml[self.pos..];
+
+            if rest.is_empty() {
+                return Ok(XfsEvent::Eof);
+            } else if rest.starts_with("<?") {
+                self.skip_past("?>")?;
+            } else if rest.starts_with("<!--") {
+                self.skip_past("-->")?;
+            } else if let Some(cdata) = rest.strip_prefix("<![CDATA[") {
+                let end = cdata
+                    .find("]]>")
+                    .ok_or_else(|| self.error("unterminated CDATA section"))?;
+                self.pos += "<![CDATA[".len() + end + "]]>".len();
+                return Ok(XfsEvent::Text(Cow::Borrowed(&cdata[..end])));
+            } else if rest.starts_with("<!") {
+                self.skip_past(">")?;
+            } else if let Some(tag) = rest.strip_prefix("</") {
+                let end = tag
+                    .find('>')
+                    .ok_or_else(|| self.error("unterminated end tag"))?;
+                self.pos += "</".len() + end + 1;
+                return Ok(XfsEvent::End(tag[..end].trim_end()));
+            } else if let Some(tag) = rest.strip_prefix('<') {
+                let end = tag
+                    .find('>')
+                    .ok_or_else(|| self.error("unterminated start tag"))?;
+                self.pos += "<".len() + end + 1;
+
+                let tag = &tag[..end];
+                let (tag, self_closing) = match tag.strip_suffix('/') {
+                    Some(t) => (t, true),
+                    None => (tag, false),
+                };
+                // XFS elements have no attributes, but skip any that are present
+                let name = tag
+                    .split(|c: char| c.is_ascii_whitespace())
+                    .next()
+                    .unwrap_or("");
+
+                if name.is_empty() {
+                    return Err(self.error("empty tag name"));
+                }
+
+                if self_closing {
+                    self.pending_end = Some(name);
+                }
+
+                return Ok(XfsEvent::Start(name));
+            } else {
+                let end = rest.find('<').unwrap_or(rest.len());
+                self.pos += end;
+
+                let text = rest[..end].trim();
+                if !text.is_empty() {
+                    return Ok(XfsEvent::Text(unescape(text)?));
+                }
+            }
+        }
+    }
+
+    /// Reads the next [XfsEvent] without advancing the reader.
+    pub fn peek(&self) -> Result<XfsEvent<'a>> {
+        let mut reader = *self;
+        reader.next_event()
+    }
+
+    /// Reads the start of the element `name`.
+    pub fn expect_start(&mut self, name: &str) -> Result<()> {
+        match self.next_event()? {
+            XfsEvent::Start(n) if n == name => Ok(()),
+            ev => Err(self.error(format!("expected <{name}>, have: {ev}"))),
+        }
+    }
+
+    /// Reads the end of the element `name`.
+    pub fn expect_end(&mut self, name: &str) -> Result<()> {
+        match self.next_event()? {
+            XfsEvent::End(n) if n == name => Ok(()),
+            ev => Err(self.error(format!("expected </{name}>, have: {ev}"))),
+        }
+    }
+
+    /// Reads the text content up to, and including, the end of the element `name`.
+    ///
+    /// **NOTE** the start of the element must already be read.
+    pub fn read_text(&mut self, name: &str) -> Result<Cow<'a, str>> {
+        let mut text: Option<Cow<'a, str>> = None;
+
+        loop {
+            match self.next_event()? {
+                XfsEvent::Text(t) => {
+                    text = match text {
+                        Some(prev) => Some(Cow::Owned(prev.into_owned() + t.as_ref())),
+                        None => Some(t),
+                    };
+                }
+                XfsEvent::End(n) if n == name => return Ok(text.unwrap_or_default()),
+                ev => return Err(self.error(format!("expected text in <{name}>, have: {ev}"))),
+            }
+        }
+    }
+
+    /// Skips over the remaining content, and the end of the element `name`.
+    ///
+    /// **NOTE** the start of the element must already be read.
+    pub fn skip_element(&mut self, name: &str) -> Result<()> {
+        let mut depth = 0usize;
+
+        loop {
+            match self.next_event()? {
+                XfsEvent::Start(_) => depth += 1,
+                XfsEvent::End(n) if depth == 0 && n == name => return Ok(()),
+                XfsEvent::End(_) if depth > 0 => depth -= 1,
+                XfsEvent::Text(_) => (),
+                ev => return Err(self.error(format!("expected </{name}>, have: {ev}"))),
+            }
+        }
+    }
+
+    /// Skips over a complete `value` element.
+    pub fn skip_value(&mut self) -> Result<()> {
+        self.expect_start("value")?;
+        self.skip_element("value")
+    }
+
+    /// Decodes a `value` element into `T`.
+    ///
+    /// Returns:
+    ///
+    /// - `Ok(Ok(T))` on success
+    /// - `Ok(Err(_))` if the value is well-formed, but does not convert to `T`. The reader is
+    ///   positioned after the `value` element.
+    /// - `Err(_)` if the message is malformed
+    pub fn try_decode<T: XfsDecode>(&mut self) -> Result<Result<T>> {
+        let start = *self;
+
+        match T::decode_value(self) {
+            Ok(val) => Ok(Ok(val)),
+            Err(err) => {
+                *self = start;
+                self.skip_value()?;
+                Ok(Err(err))
+            }
+        }
+    }
+
+    /// Reads a `value` element containing a `struct`, calling `member` for each member.
+    ///
+    /// The `member` function is passed the reader positioned at the member's `value` element,
+    /// and the member name. It returns `true` if it decoded the `value`, or `false` to skip it.
+    pub fn decode_struct<F>(&mut self, mut member: F) -> Result<()>
+    where
+        F: FnMut(&mut Self, &str) -> Result<bool>,
+    {
+        self.expect_start("value")?;
+        self.expect_start("struct")?;
+
+        loop {
+            match self.next_event()? {
+                XfsEvent::Start("member") => {
+                    self.expect_start("name")?;
+                    let name = self.read_text("name")?;
+
+                    if !member(self, name.as_ref())? {
+                        self.skip_value()?;
+                    }
+
+                    self.expect_end("member")?;
+                }
+                XfsEvent::End("struct") => break,
+                ev => return Err(self.error(format!("expected <member>, have: {ev}"))),
+            }
+        }
+
+        self.expect_end("value")
+    }
+
+    /// Reads a `value` element containing an `array`, calling `item` for each item.
+    ///
+    /// The `item` function is passed the reader positioned at the item's `value` element, and
+    /// the item index. It must read the complete `value` element.
+    pub fn decode_array<F>(&mut self, mut item: F) -> Result<()>
+    where
+        F: FnMut(&mut Self, usize) -> Result<()>,
+    {
+        self.expect_start("value")?;
+        self.expect_start("array")?;
+        self.expect_start("data")?;
+
+        let mut index = 0usize;
+        loop {
+            match self.peek()? {
+                XfsEvent::Start("value") => {
+                    item(self, index)?;
+                    index += 1;
+                }
+                XfsEvent::End("data") => {
+                    self.next_event()?;
+                    break;
+                }
+                ev => return Err(self.error(format!("expected <value>, have: {ev}"))),
+            }
+        }
+
+        self.expect_end("array")?;
+        self.expect_end("value")
+    }
+
+    fn skip_past(&mut self, pat: &str) -> Result<()> {
+        let rest = &self.xml[self.pos..];
+        let end = rest
+            .find(pat)
+            .ok_or_else(|| self.error(format!("missing closing {pat}")))?;
+        self.pos += end + pat.len();
+        Ok(())
+    }
+
+    fn error<S: fmt::Display>(&self, msg: S) -> Error {
+        Error::Xfs(format!("XFS parse error at byte {}: {msg}", self.pos))
+    }
+}
+
+/// Decodes a type directly from an XFS `value` element.
+pub trait XfsDecode: Sized {
+    /// Decodes the type from the `value` element at the current reader position.
+    ///
+    /// On success, the reader is positioned after the end of the `value` element.
+    fn decode_value(reader: &mut XfsReader<'_>) -> Result<Self>;
+}
+
+impl XfsDecode for XfsValue {
+    fn decode_value(reader: &mut XfsReader<'_>) -> Result<Self> {
+        reader.expect_start("value")?;
+
+        let mut val = Self::new();
+
+        loop {
+            match reader.next_event()? {
+                XfsEvent::Start(tag) => match tag {
+                    "int" => val.int = Some(parse_num(reader.read_text(tag)?.as_ref(), tag)?),
+                    "i4" => val.i4 = Some(parse_num(reader.read_text(tag)?.as_ref(), tag)?),
+                    "boolean" => {
+                        val.boolean = Some(parse_num(reader.read_text(tag)?.as_ref(), tag)?)
+                    }
+                    "base64" => val.base64 = Some(reader.read_text(tag)?.into_owned()),
+                    "dateTime.iso8601" => val.date_time = Some(reader.read_text(tag)?.into_owned()),
+                    "string" => val.string = Some(reader.read_text(tag)?.into_owned()),
+                    "struct" => val.xfs_struct = Some(decode_struct_body(reader)?),
+                    "array" => val.array = Some(decode_array_body(reader)?),
+                    _ => {
+                        log::warn!("Skipping unknown XFS value type: {tag}");
+                        reader.skip_element(tag)?;
+                    }
+                },
+                // untyped XML-RPC values are strings
+                XfsEvent::Text(text) => val.string = Some(text.into_owned()),
+                XfsEvent::End("value") => break,
+                ev => return Err(reader.error(format!("expected </value>, have: {ev}"))),
+            }
+        }
+
+        Ok(val)
+    }
+}
+
+impl XfsDecode for XfsStruct {
+    fn decode_value(reader: &mut XfsReader<'_>) -> Result<Self> {
+        XfsValue::decode_value(reader)?
+            .unset_xfs_struct()
+            .ok_or(Error::Xfs("Expected struct XfsValue".into()))
+    }
+}
+
+impl XfsDecode for XfsArray {
+    fn decode_value(reader: &mut XfsReader<'_>) -> Result<Self> {
+        XfsValue::decode_value(reader)?
+            .unset_array()
+            .ok_or(Error::Xfs("Expected array XfsValue".into()))
+    }
+}
+
+/// Reads the members of a `struct`, after the `struct` start tag.
+fn decode_struct_body(reader: &mut XfsReader<'_>) -> Result<XfsStruct> {
+    let mut members = Vec::new();
+
+    loop {
+        match reader.next_event()? {
+            XfsEvent::Start("member") => {
+                reader.expect_start("name")?;
+                let name = reader.read_text("name")?.into_owned();
+                let value = XfsValue::decode_value(reader)?;
+                reader.expect_end("member")?;
+
+                members.push(XfsMember::create(name, value));
+            }
+            XfsEvent::End("struct") => break,
+            ev => return Err(reader.error(format!("expected <member>, have: {ev}"))),
+        }
+    }
+
+    Ok(XfsStruct::create(members))
+}
+
+/// Reads the items of an `array`, after the `array` start tag.
+fn decode_array_body(reader: &mut XfsReader<'_>) -> Result<XfsArray> {
+    reader.expect_start("data")?;
+
+    let mut data = Vec::new();
+
+    loop {
+        match reader.peek()? {
+            XfsEvent::Start("value") => data.push(XfsValue::decode_value(reader)?),
+            XfsEvent::End("data") => {
+                reader.next_event()?;
+                break;
+            }
+            ev => return Err(reader.error(format!("expected <value>, have: {ev}"))),
+        }
+    }
+
+    reader.expect_end("array")?;
+
+    Ok(XfsArray::create(data))
+}
+
+/// Reads a `params` list, after the `params` start tag.
+fn decode_params_body(reader: &mut XfsReader<'_>) -> Result<XfsParams> {
+    let mut params = Vec::new();
+
+    loop {
+        match reader.next_event()? {
+            XfsEvent::Start("param") => {
+                params.push(XfsParam::create(XfsValue::decode_value(reader)?));
+                reader.expect_end("param")?;
+            }
+            XfsEvent::End("params") => break,
+            ev => return Err(reader.error(format!("expected <param>, have: {ev}"))),
+        }
+    }
+
+    Ok(XfsParams::create(params))
+}
+
+/// Reads a `fault`, after the `fault` start tag.
+fn decode_fault_body(reader: &mut XfsReader<'_>) -> Result<XfsFault> {
+    let fault = XfsFault::from(XfsValue::decode_value(reader)?);
+    reader.expect_end("fault")?;
+
+    Ok(fault)
+}
+
+/// Decodes an [XfsMethodCall] from an XML string.
+pub fn decode_method_call(xml: &str) -> Result<XfsMethodCall> {
+    let mut reader = XfsReader::new(xml);
+
+    reader.expect_start("methodCall")?;
+
+    let mut call = XfsMethodCall::new();
+
+    loop {
+        match reader.next_event()? {
+            XfsEvent::Start("methodName") => {
+                call.set_name_str(reader.read_text("methodName")?.as_ref());
+            }
+            XfsEvent::Start("params") => call.set_params(decode_params_body(&mut reader)?),
+            XfsEvent::End("methodCall") => break,
+            ev => {
+                return Err(reader.error(format!("expected <methodName> or <params>, have: {ev}")))
+            }
+        }
+    }
+
+    Ok(call)
+}
+
+/// Decodes an [XfsMethodResponse] from an XML string.
+pub fn decode_method_response(xml: &str) -> Result<XfsMethodResponse> {
+    let mut reader = XfsReader::new(xml);
+
+    reader.expect_start("methodResponse")?;
+
+    let res = match reader.next_event()? {
+        XfsEvent::Start("params") => XfsMethodResponse::Params(decode_params_body(&mut reader)?),
+        XfsEvent::Start("fault") => XfsMethodResponse::Fault(decode_fault_body(&mut reader)?),
+        ev => return Err(reader.error(format!("expected <params> or <fault>, have: {ev}"))),
+    };
+
+    reader.expect_end("methodResponse")?;
+
+    Ok(res)
+}
+
+/// Decodes the first `param` of an [XfsMethodResponse] that converts to `T`, without building
+/// the intermediate [XfsValue] tree.
+///
+/// Returns an error if the response is a `fault`, or no `param` converts to `T`.
+pub fn decode_response<T: XfsDecode>(xml: &str) -> Result<T> {
+    let mut reader = XfsReader::new(xml);
+    let name = std::any::type_name::<T>();
+
+    reader.expect_start("methodResponse")?;
+
+    match reader.next_event()? {
+        XfsEvent::Start("params") => {
+            let mut last_err = None;
+
+            loop {
+                match reader.next_event()? {
+                    XfsEvent::Start("param") => {
+                        match reader.try_decode::<T>()? {
+                            Ok(val) => return Ok(val),
+                            Err(err) => last_err = Some(err),
+                        }
+                        reader.expect_end("param")?;
+                    }
+                    XfsEvent::End("params") => break,
+                    ev => return Err(reader.error(format!("expected <param>, have: {ev}"))),
+                }
+            }
+
+            match last_err {
+                Some(err) => Err(Error::Xfs(format!(
+                    "Expected {name} XfsMethodResponse, have error: {err}"
+                ))),
+                None => Err(Error::Xfs(format!(
+                    "Expected {name} XfsMethodResponse, have empty params"
+                ))),
+            }
+        }
+        XfsEvent::Start("fault") => {
+            let fault = decode_fault_body(&mut reader)?;
+            Err(Error::Xfs(format!("BNR response fault: {fault}")))
+        }
+        ev => Err(reader.error(format!("expected <params> or <fault>, have: {ev}"))),
+    }
+}
+
+/// Encodes an [XfsMethodCall] into an XML string, with the provided `encoding` attribute.
+pub fn encode_method_call(call: &XfsMethodCall, encoding: &str) -> String {
+    let mut out = String::with_capacity(0xff);
+
+    write_declaration(&mut out, encoding);
+
+    out.push_str("<methodCall><methodName>");
+    out.push_str(escape(call.name_str()).as_ref());
+    out.push_str("</methodName>");
+    write_params(&mut out, call.params());
+    out.push_str("</methodCall>");
+
+    out
+}
+
+/// Encodes an [XfsMethodResponse] into an XML string, with the provided `encoding` attribute.
+pub fn encode_method_response(res: &XfsMethodResponse, encoding: &str) -> String {
+    let mut out = String::with_capacity(0xff);
+
+    write_declaration(&mut out, encoding);
+
+    out.push_str("<methodResponse>");
+    match res {
+        XfsMethodResponse::Params(params) => write_params(&mut out, params),
+        XfsMethodResponse::Fault(fault) => {
+            out.push_str("<fault>");
+            write_value(&mut out, fault.value());
+            out.push_str("</fault>");
+        }
+    }
+    out.push_str("</methodResponse>");
+
+    out
+}
+
+/// Encodes an [XfsValue] into the provided XML string.
+pub fn write_value(out: &mut String, val: &XfsValue) {
+    if val.is_empty() {
+        out.push_str("<value/>");
+        return;
+    }
+
+    out.push_str("<value>");
+
+    if let Some(v) = val.int() {
+        write_text(out, "int", v.to_string().as_str());
+    }
+    if let Some(v) = val.i4() {
+        write_text(out, "i4", v.to_string().as_str());
+    }
+    if let Some(v) = val.base64() {
+        write_text(out, "base64", v);
+    }
+    if let Some(v) = val.date_time() {
+        write_text(out, "dateTime.iso8601", v);
+    }
+    if let Some(v) = val.boolean() {
+        write_text(out, "boolean", v.to_string().as_str());
+    }
+    if let Some(v) = val.string() {
+        write_text(out, "string", v);
+    }
+    if let Some(v) = val.xfs_struct() {
+        if v.members().is_empty() {
+            out.push_str("<struct/>");
+        } else {
+            out.push_str("<struct>");
+            for m in v.members().iter().map(|m| m.inner()) {
+                out.push_str("<member>");
+                write_text(out, "name", m.name());
+                write_value(out, m.value());
+                out.push_str("</member>");
+            }
+            out.push_str("</struct>");
+        }
+    }
+    if let Some(v) = val.array() {
+        if v.data().is_empty() {
+            out.push_str("<array><data/></array>");
+        } else {
+            out.push_str("<array><data>");
+            for d in v.data().iter().map(|d| d.inner()) {
+                write_value(out, d);
+            }
+            out.push_str("</data></array>");
+        }
+    }
+
+    out.push_str("</value>");
+}
+
+fn write_declaration(out: &mut String, encoding: &str) {
+    out.push_str(r#"<?xml version="1.0" encoding=""#);
+    out.push_str(encoding);
+    out.push_str(r#""?>"#);
+}
+
+fn write_params(out: &mut String, params: &XfsParams) {
+    if params.params().is_empty() {
+        out.push_str("<params/>");
+    } else {
+        out.push_str("<params>");
+        for p in params.params().iter().map(|p| p.inner()) {
+            out.push_str("<param>");
+            write_value(out, p.value());
+            out.push_str("</param>");
+        }
+        out.push_str("</params>");
+    }
+}
+
+fn write_text(out: &mut String, tag: &str, text: &str) {
+    out.push('<');
+    out.push_str(tag);
+    out.push('>');
+    out.push_str(escape(text).as_ref());
+    out.push_str("</");
+    out.push_str(tag);
+    out.push('>');
+}
+
+fn escape(text: &str) -> Cow<'_, str> {
+    xml::escape::escape_str_pcdata(text)
+}
+
+fn unescape(text: &str) -> Result<Cow<'_, str>> {
+    if !text.contains('&') {
+        return Ok(Cow::Borrowed(text));
+    }
+
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(start) = rest.find('&') {
+        out.push_str(&rest[..start]);
+        rest = &rest[start + 1..];
+
+        let end = rest
+            .find(';')
+            .ok_or(Error::Xfs(format!("unterminated XML entity: {text}")))?;
+        let entity = &rest[..end];
+
+        let c = match entity {
+            "lt" => '<',
+            "gt" => '>',
+            "amp" => '&',
+            "quot" => '"',
+            "apos" => '\'',
+            _ => entity
+                .strip_prefix("#x")
+                .or_else(|| entity.strip_prefix("#X"))
+                .map(|hex| u32::from_str_radix(hex, 16))
+                .or_else(|| entity.strip_prefix('#').map(str::parse::<u32>))
+                .and_then(|c| c.ok())
+                .and_then(char::from_u32)
+                .ok_or(Error::Xfs(format!("invalid XML entity: &{entity};")))?,
+        };
+
+        out.push(c);
+        rest = &rest[end + 1..];
+    }
+
+    out.push_str(rest);
+
+    Ok(Cow::Owned(out))
+}
+
+fn parse_num<N: std::str::FromStr>(text: &str, tag: &str) -> Result<N>
+where
+    N::Err: fmt::Display,
+{
+    text.parse::<N>()
+        .map_err(|err| Error::Xfs(format!("invalid {tag} value {text:?}: {err}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::xfs::{self, method_call::XfsMethodName, method_response::XfsMethodResponseStruct};
+    use crate::{Capabilities, CashUnit, CdrStatus};
+
+    #[test]
+    fn test_reader_events() -> Result<()> {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+            <!-- comment -->
+            <value><string> a &amp; b &#xe9;</string><i4/></value>"#;
+        let mut reader = XfsReader::new(xml);
+
+        assert_eq!(reader.next_event()?, XfsEvent::Start("value"));
+        assert_eq!(reader.next_event()?, XfsEvent::Start("string"));
+        assert_eq!(
+            reader.next_event()?,
+            XfsEvent::Text(Cow::Owned("a & b é".into()))
+        );
+        assert_eq!(reader.next_event()?, XfsEvent::End("string"));
+        assert_eq!(reader.peek()?, XfsEvent::Start("i4"));
+        assert_eq!(reader.next_event()?, XfsEvent::Start("i4"));
+        assert_eq!(reader.next_event()?, XfsEvent::End("i4"));
+        assert_eq!(reader.next_event()?, XfsEvent::End("value"));
+        assert_eq!(reader.next_event()?, XfsEvent::Eof);
+
+        Ok(())
+    }
+
+    // wraps a bare `struct` fixture into a method response
+    fn response_xml(struct_xml: &str) -> String {
+        let struct_xml = struct_xml.trim_start_matches(r#"<?xml version="1.0"?>"#);
+        format!(
+            r#"<?xml version="1.0"?><methodResponse><params><param><value>{struct_xml}</value></param></params></methodResponse>"#
+        )
+    }
+
+    fn fixtures() -> [String; 4] {
+        [
+            include_str!("../../tests/xml/query-cash-unit.xml").into(),
+            response_xml(include_str!("../../tests/xml/status.xml")),
+            response_xml(include_str!("../../tests/xml/capabilities.xml")),
+            response_xml(
+                format!(
+                    "<struct>{}</struct>",
+                    include_str!("../../tests/xml/position-capabilities.xml")
+                        .trim_start_matches(r#"<?xml version="1.0"?>"#)
+                )
+                .as_str(),
+            ),
+        ]
+    }
+
+    #[test]
+    fn test_decode_matches_serde() -> Result<()> {
+        for xml in fixtures() {
+            let exp = xfs::from_str::<XfsMethodResponseStruct>(xml.as_str())?.into_inner();
+            assert_eq!(decode_method_response(xml.as_str())?, exp);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_decode_typed() -> Result<()> {
+        let [cash_unit_xml, status_xml, caps_xml, _] = fixtures();
+
+        let exp =
+            CashUnit::try_from(xfs::from_str::<XfsMethodResponseStruct>(&cash_unit_xml)?.inner())?;
+        assert_eq!(decode_response::<CashUnit>(&cash_unit_xml)?, exp);
+
+        let exp =
+            CdrStatus::try_from(xfs::from_str::<XfsMethodResponseStruct>(&status_xml)?.inner())?;
+        assert_eq!(decode_response::<CdrStatus>(&status_xml)?, exp);
+
+        let exp =
+            Capabilities::try_from(xfs::from_str::<XfsMethodResponseStruct>(&caps_xml)?.inner())?;
+        assert_eq!(decode_response::<Capabilities>(&caps_xml)?, exp);
+
+        let fault = XfsMethodResponseStruct::from(XfsMethodResponse::new_fault(6072, "fault"));
+        let fault_xml = xfs::to_string(&fault)?;
+        assert!(decode_response::<CdrStatus>(fault_xml.as_str()).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_encode_matches_serde() -> Result<()> {
+        let call = XfsMethodCall::create(
+            XfsMethodName::GetIdentification,
+            [XfsParam::create(XfsValue::new().with_xfs_struct(
+                XfsStruct::create([
+                    XfsMember::create("a", XfsValue::new().with_string("x < y & é")),
+                    XfsMember::create("b", XfsValue::new().with_array(XfsArray::new())),
+                    XfsMember::create("c", XfsValue::new()),
+                ]),
+            ))],
+        );
+        let xml = encode_method_call(&call, "UTF-8");
+
+        assert_eq!(xml, xfs::to_string(&call)?);
+        assert_eq!(decode_method_call(xml.as_str())?, call);
+        assert_eq!(
+            encode_method_call(&XfsMethodCall::new(), "UTF-8"),
+            xfs::to_string(XfsMethodCall::new())?
+        );
+
+        // the call path writes `ISO-8859-1` on the wire, see `UsbDeviceHandle::write_call`
+        let latin1 = xfs::encode_latin1(encode_method_call(&call, "ISO-8859-1").as_str())?;
+        assert!(latin1.contains(&0xe9));
+        assert_eq!(
+            decode_method_call(xfs::decode(latin1.as_ref())?.as_str())?,
+            call
+        );
+
+        for res in [
+            XfsMethodResponse::new_fault(6072, "Défaut"),
+            XfsMethodResponse::new_params([XfsParam::create(XfsValue::new().with_i4(32))]),
+            XfsMethodResponse::new_params([]),
+        ] {
+            let xml = encode_method_response(&res, "ISO-8859-1");
+
+            assert_eq!(
+                xml,
+                xfs::to_iso_string(XfsMethodResponseStruct::from(&res))?
+            );
+            assert_eq!(decode_method_response(xml.as_str())?, res);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_decode_errors() {
+        assert!(decode_method_response("<methodResponse><params>").is_err());
+        assert!(decode_method_response("<methodResponse><params></fault>").is_err());
+        assert!(decode_method_call("<methodCall><methodName>a</methodName>").is_err());
+        assert!(decode_method_call("<value><i4>x</i4></value>").is_err());
+        assert!(unescape("&bogus;").is_err());
+    }
+}
@@ -44,6 +44,11 @@ impl XfsFault {
         }
     }
 
+    /// Gets a reference to the fault [XfsValue].
+    pub const fn value(&self) -> &XfsValue {
+        &self.value
+    }
+
     /// Gets the fault code value.
     pub fn code(&self) -> i32 {
         match self.value.xfs_struct() {
@@ -106,6 +111,12 @@ impl XfsFault {
 
 impl_default!(XfsFault);
 
+impl From<XfsValue> for XfsFault {
+    fn from(val: XfsValue) -> Self {
+        Self { value: val }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -54,6 +54,11 @@ impl XfsMethodCall {
         self.name = <&str>::from(name).into();
     }
 
+    /// Sets the [name](XfsMethodName) from a string, allowing names unknown to [XfsMethodName].
+    pub(crate) fn set_name_str(&mut self, name: &str) {
+        self.name = name.into();
+    }
+
     /// Builder function that sets the [name](XfsMethodName).
     pub fn with_name(mut self, name: XfsMethodName) -> Self {
         self.set_name(name);
@@ -205,7 +210,7 @@ impl From<XfsMethodName> for XfsMethodCall {
 impl fmt::Display for XfsMethodCall {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{{")?;
-        write!(f, r#""name": {}", "#, self.name)?;
+        write!(f, r#""name": "{}", "#, self.name)?;
         write!(f, r#""params": ["#)?;
         for (i, param) in self.params.params().iter().enumerate() {
             if i != 0 {
@@ -151,6 +151,25 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_method_response_iso_serde() -> Result<()> {
+        let exp_xml = r#"<?xml version="1.0" encoding="ISO-8859-1"?><methodResponse><fault><value><struct><member><name>faultCode</name><value><i4>6072</i4></value></member><member><name>faultString</name><value><string>Défaut à l'entrée</string></value></member></struct></value></fault></methodResponse>"#;
+        let exp_bytes = xfs::encode_latin1(exp_xml)?;
+        let res =
+            XfsMethodResponseStruct::from(XfsMethodResponse::new_fault(6072, "Défaut à l'entrée"));
+
+        // accented characters are single bytes on the wire
+        assert_eq!(exp_bytes.len(), exp_xml.chars().count());
+        assert_eq!(xfs::to_iso_bytes(&res)?, exp_bytes);
+        assert_eq!(
+            xfs::from_bytes::<XfsMethodResponseStruct>(exp_bytes.as_ref())?,
+            res
+        );
+        assert_eq!(xfs::from_str::<XfsMethodResponseStruct>(exp_xml)?, res);
+
+        Ok(())
+    }
+
     #[test]
     fn test_method_response_accessors() -> Result<()> {
         let fault = XfsMethodResponse::new_fault(1010, "");
@@ -61,6 +61,31 @@ impl XfsStruct {
     }
 }
 
+/// Adds the struct member details to an error from converting an [XfsMember] value.
+///
+/// Used by [XfsStruct](crate::XfsStruct) derived types, nested errors read as a path to the
+/// failing member, e.g. `CashUnit.logical_cu_list ("logicalCashUnitList"): ...`.
+pub fn member_error(ty: &str, field: &str, name: &str, err: Error) -> Error {
+    match err {
+        Error::Xfs(msg) => Error::Xfs(format!(r#"{ty}.{field} ("{name}"): {msg}"#)),
+        err => Error::Xfs(format!(r#"{ty}.{field} ("{name}"): {err}"#)),
+    }
+}
+
+/// Handles a missing [XfsMember] for a [XfsStruct](crate::XfsStruct) derived type.
+///
+/// Returns an error for `required` members, otherwise logs a warning.
+pub fn missing_member(ty: &str, field: &str, name: &str, required: bool) -> Result<()> {
+    if required {
+        Err(Error::Xfs(format!(
+            r#"{ty}.{field}: missing required member "{name}""#
+        )))
+    } else {
+        log::warn!(r#"Missing member "{name}" for {ty}.{field}"#);
+        Ok(())
+    }
+}
+
 impl fmt::Display for XfsStruct {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, r#"{{"members": ["#)?;
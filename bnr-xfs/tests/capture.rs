@@ -0,0 +1,111 @@
+use bnr_xfs::{
+    parse_capture, parse_capture_bytes,
+    xfs::{
+        self, codec,
+        method_call::{XfsMethodCall, XfsMethodName},
+        method_response::XfsMethodResponse,
+        params::XfsParam,
+        value::XfsValue,
+        OperationId,
+    },
+    DecodedMessage, Result,
+};
+
+fn cash_unit_xml() -> &'static str {
+    std::str::from_utf8(include_bytes!("xml/query-cash-unit.xml")).unwrap_or("")
+}
+
+fn callback_xml() -> String {
+    let params = [6, OperationId::Dispense.inner() as i32, 0, 0]
+        .map(|p| XfsParam::create(XfsValue::new().with_i4(p)));
+
+    codec::encode_method_call(
+        &XfsMethodCall::create(XfsMethodName::OperationCompleteOccurred, params),
+        "UTF-8",
+    )
+}
+
+#[test]
+fn test_parse_capture_log() -> Result<()> {
+    let log = format!(
+        "[2024-01-01T00:00:00Z TRACE bnr_xfs::device_handle::usb] Raw \"bnr.querycashunit\" response: {}\n\
+         [2024-01-01T00:00:01Z TRACE bnr_xfs::device_handle::usb] Raw callback call: {}\n",
+        cash_unit_xml(),
+        callback_xml(),
+    );
+
+    let msgs = parse_capture(log.as_str());
+    assert_eq!(msgs.len(), 2);
+
+    assert!(msgs[0].is_response());
+    assert_eq!(msgs[0].method(), Some(XfsMethodName::QueryCashUnit));
+
+    let cash_unit = msgs[0].decode()?;
+    assert!(matches!(cash_unit, DecodedMessage::CashUnit(_)));
+    assert!(cash_unit.to_json().is_object());
+
+    assert!(msgs[1].is_call());
+    match msgs[1].decode()? {
+        DecodedMessage::Callback(cb) => {
+            assert_eq!(cb.name(), XfsMethodName::OperationCompleteOccurred);
+            assert_eq!(cb.call_id(), 6);
+            assert_eq!(cb.operation_id(), OperationId::Dispense);
+            assert!(cb.cash_order().is_none());
+        }
+        msg => panic!("unexpected message: {msg}"),
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_parse_capture_raw() -> Result<()> {
+    let call = codec::encode_method_call(
+        &XfsMethodCall::new().with_name(XfsMethodName::QueryCashUnit),
+        "UTF-8",
+    );
+    let capture = format!("{call}\n{}\n", cash_unit_xml());
+
+    let msgs = parse_capture(capture.as_str());
+    assert_eq!(msgs.len(), 2);
+
+    // the response takes the method from the preceding call
+    assert!(matches!(msgs[0].decode()?, DecodedMessage::Call(_)));
+    assert_eq!(msgs[1].method(), Some(XfsMethodName::QueryCashUnit));
+    assert!(matches!(msgs[1].decode()?, DecodedMessage::CashUnit(_)));
+
+    // without a method, the response decodes to the generic type
+    let msgs = parse_capture(cash_unit_xml());
+    assert_eq!(msgs.len(), 1);
+    assert_eq!(msgs[0].method(), None);
+    assert!(matches!(msgs[0].decode()?, DecodedMessage::Response(_)));
+
+    Ok(())
+}
+
+#[test]
+fn test_parse_capture_bytes() -> Result<()> {
+    let fault = XfsMethodResponse::new_fault(6072, "Défaut");
+    let xml = codec::encode_method_response(&fault, "ISO-8859-1");
+
+    // raw captures are decoded with the declared encoding
+    let mut capture = xfs::encode_latin1(xml.as_str())?;
+    capture.push(b'\n');
+    assert!(std::str::from_utf8(capture.as_ref()).is_err());
+
+    let msgs = parse_capture_bytes(capture.as_ref());
+    assert_eq!(msgs.len(), 1);
+    assert_eq!(msgs[0].decode()?, DecodedMessage::Response(fault.clone()));
+
+    // log lines hold the already decoded message, written as UTF-8
+    let log = format!(
+        "[2024-01-01T00:00:00Z TRACE bnr_xfs::device_handle::usb] Raw \"bnr.getstatus\" response: {xml}\n"
+    );
+
+    let msgs = parse_capture_bytes(log.as_bytes());
+    assert_eq!(msgs.len(), 1);
+    assert_eq!(msgs[0].method(), Some(XfsMethodName::GetStatus));
+    assert_eq!(msgs[0].decode()?, DecodedMessage::Response(fault));
+
+    Ok(())
+}
@@ -0,0 +1,272 @@
+use bnr_xfs::{
+    xfs::{
+        array::XfsArray as XfsArrayValue,
+        codec,
+        value::XfsValue,
+        xfs_struct::{XfsMember, XfsStruct},
+    },
+    BoundedList, Count, Error, Result, Unit, XfsArray, XfsEnum, XfsStruct,
+};
+
+const MODE_OFF: u32 = 1;
+const MODE_ON: u32 = 2;
+
+#[repr(u32)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, XfsEnum)]
+#[xfs(name = "mode")]
+enum Mode {
+    #[default]
+    Off = MODE_OFF,
+    #[xfs(display = "fully on")]
+    On = MODE_ON,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, XfsStruct)]
+#[xfs(name = "item")]
+struct Item {
+    unit: Unit,
+    #[xfs(name = "itemCount", required)]
+    count: Count,
+    mode: Mode,
+    #[xfs(name = "comment", string)]
+    comment: String,
+    #[xfs(name = "index", i4)]
+    index: u32,
+    #[xfs(name = "enabled", boolean)]
+    enabled: bool,
+    #[xfs(skip)]
+    local: u64,
+}
+
+#[derive(Clone, Debug, PartialEq, XfsStruct)]
+#[xfs(name = "named", no_display)]
+struct Named {
+    #[xfs(name = "name", string)]
+    name: String,
+    #[xfs(skip)]
+    capacity: u32,
+}
+
+impl Default for Named {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            capacity: 10,
+        }
+    }
+}
+
+const ITEMS_LEN: usize = 2;
+
+#[derive(Clone, Debug, Default, PartialEq, XfsArray)]
+#[xfs(name = "items")]
+struct Items {
+    items: BoundedList<Item, ITEMS_LEN>,
+}
+
+impl Items {
+    const fn new() -> Self {
+        Self {
+            items: BoundedList::new(),
+        }
+    }
+}
+
+fn item() -> Item {
+    Item {
+        unit: Unit::create(5),
+        count: Count::create(10),
+        mode: Mode::On,
+        comment: "note".into(),
+        index: 3,
+        enabled: true,
+        local: 0,
+    }
+}
+
+#[test]
+fn test_derive_enum() -> Result<()> {
+    assert_eq!(Mode::new(), Mode::Off);
+    assert_eq!(Mode::create(MODE_ON), Mode::On);
+    assert_eq!(Mode::create(0), Mode::Off);
+    assert_eq!(Mode::On.inner(), MODE_ON);
+    assert_eq!(Mode::xfs_name(), "mode");
+
+    assert_eq!(format!("{}", Mode::Off).as_str(), r#""off""#);
+    assert_eq!(format!("{}", Mode::On).as_str(), r#""fully on""#);
+
+    let member = XfsMember::from(Mode::On);
+    assert_eq!(member.name(), "mode");
+    assert_eq!(member.value().i4(), Some(&(MODE_ON as i32)));
+    assert_eq!(Mode::try_from(&member)?, Mode::On);
+
+    Ok(())
+}
+
+#[test]
+fn test_derive_struct() -> Result<()> {
+    let exp_item = item();
+    let xfs_struct = XfsStruct::from(&exp_item);
+
+    let names: Vec<&str> = xfs_struct
+        .members()
+        .iter()
+        .map(|m| m.inner().name())
+        .collect();
+    assert_eq!(
+        names,
+        ["unit", "itemCount", "mode", "comment", "index", "enabled"]
+    );
+    assert_eq!(
+        xfs_struct.find_member("comment")?.value().string(),
+        Some("note")
+    );
+    assert_eq!(
+        xfs_struct.find_member("enabled")?.value().boolean(),
+        Some(&1)
+    );
+
+    assert_eq!(Item::try_from(&xfs_struct)?, exp_item);
+    assert_eq!(Item::try_from(XfsMember::from(&exp_item))?, exp_item);
+    assert_eq!(Item::try_from(XfsValue::from(&exp_item))?, exp_item);
+
+    assert_eq!(
+        format!("{exp_item}").as_str(),
+        r#"{"unit": 5, "count": 10, "mode": "fully on", "comment": "note", "index": 3, "enabled": true}"#
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_derive_struct_decode() -> Result<()> {
+    let exp_item = item();
+    let xml = codec::encode_method_response(
+        &bnr_xfs::xfs::method_response::XfsMethodResponse::new_params([
+            bnr_xfs::xfs::params::XfsParam::create(XfsValue::from(&exp_item)),
+        ]),
+        "UTF-8",
+    );
+
+    assert_eq!(codec::decode_response::<Item>(xml.as_str())?, exp_item);
+
+    Ok(())
+}
+
+#[test]
+fn test_derive_struct_skip() -> Result<()> {
+    let named = Named {
+        name: "cashbox".into(),
+        capacity: 0,
+    };
+    let exp_named = Named {
+        name: "cashbox".into(),
+        ..Default::default()
+    };
+
+    // skipped fields are set from the struct default, and field names do not clash with the
+    // generated code
+    let xfs_struct = XfsStruct::from(&named);
+    assert_eq!(xfs_struct.members().len(), 1);
+    assert_eq!(Named::try_from(&xfs_struct)?, exp_named);
+
+    let xml = codec::encode_method_response(
+        &bnr_xfs::xfs::method_response::XfsMethodResponse::new_params([
+            bnr_xfs::xfs::params::XfsParam::create(XfsValue::from(&named)),
+        ]),
+        "UTF-8",
+    );
+    assert_eq!(codec::decode_response::<Named>(xml.as_str())?, exp_named);
+
+    Ok(())
+}
+
+#[test]
+fn test_derive_struct_errors() -> Result<()> {
+    let mut xfs_struct = XfsStruct::from(&item());
+
+    // wrong value type names the failing member
+    xfs_struct.members_mut()[4]
+        .inner_mut()
+        .set_value(XfsValue::new().with_string("three"));
+    match Item::try_from(&xfs_struct) {
+        Err(Error::Xfs(msg)) => assert!(msg.starts_with(r#"Item.index ("index"): "#), "{msg}"),
+        res => panic!("unexpected result: {res:?}"),
+    }
+
+    // missing optional members use the default value
+    let partial = XfsStruct::create([XfsMember::create("itemCount", XfsValue::new().with_i4(7))]);
+    let exp_item = Item {
+        count: Count::create(7),
+        ..Default::default()
+    };
+    assert_eq!(Item::try_from(&partial)?, exp_item);
+
+    // missing required members are an error
+    let partial = XfsStruct::create([XfsMember::create("unit", XfsValue::new().with_i4(5))]);
+    match Item::try_from(&partial) {
+        Err(Error::Xfs(msg)) => assert_eq!(
+            msg.as_str(),
+            r#"Item.count: missing required member "itemCount""#
+        ),
+        res => panic!("unexpected result: {res:?}"),
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_derive_array() -> Result<()> {
+    let mut items = Items::new();
+    items.push_item(item())?;
+    items.push_item(Item::default())?;
+
+    assert_eq!(Items::xfs_name(), "items");
+    assert_eq!(Items::max_size(), ITEMS_LEN);
+
+    let array = XfsArrayValue::from(&items);
+    assert_eq!(array.data().len(), 2);
+    assert_eq!(Items::try_from(&array)?, items);
+    assert_eq!(Items::try_from(XfsMember::from(&items))?, items);
+    assert_eq!(Items::try_from(XfsValue::from(&items))?, items);
+
+    assert!(format!("{items}").starts_with(r#"{"items":[{"unit": 5, "#));
+
+    // items past the maximum size are ignored
+    let long = XfsArrayValue::create(
+        [item(), item(), item()]
+            .iter()
+            .map(XfsValue::from)
+            .collect::<Vec<XfsValue>>(),
+    );
+    assert_eq!(Items::try_from(&long)?.len(), ITEMS_LEN);
+
+    // items failing to convert are set to the default value
+    let bad = XfsArrayValue::create([XfsValue::from(&item()), XfsValue::new().with_i4(1)]);
+    assert_eq!(Items::try_from(&bad)?.items(), [item(), Item::default()]);
+
+    // the member name is checked
+    match Items::try_from(XfsMember::create("other", XfsValue::from(&items))) {
+        Err(Error::Xfs(msg)) => assert!(msg.starts_with("Expected items XfsMember"), "{msg}"),
+        res => panic!("unexpected result: {res:?}"),
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_derive_array_decode() -> Result<()> {
+    let mut items = Items::new();
+    items.push_item(item())?;
+
+    let xml = codec::encode_method_response(
+        &bnr_xfs::xfs::method_response::XfsMethodResponse::new_params([
+            bnr_xfs::xfs::params::XfsParam::create(XfsValue::from(&items)),
+        ]),
+        "UTF-8",
+    );
+
+    assert_eq!(codec::decode_response::<Items>(xml.as_str())?, items);
+
+    Ok(())
+}
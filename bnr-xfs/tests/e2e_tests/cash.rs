@@ -1,4 +1,6 @@
-use bnr_xfs::{DeviceHandle, DispenseRequest, Result};
+use std::time::Duration;
+
+use bnr_xfs::{DepositSession, DeviceHandle, DispenseRequest, Result};
 
 use super::common;
 
@@ -155,6 +157,28 @@ fn test_present() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_present_supervised() -> Result<()> {
+    let _lock = common::init();
+
+    let handle = DeviceHandle::open(None, None, None)?;
+
+    handle.close()?;
+
+    let date = handle.get_date_time()?;
+    if date.year() == 2001 {
+        handle.set_current_date_time()?;
+    }
+
+    // Nothing was dispensed, this only exercises the cancel + retract sequence.
+    let report = handle.present_supervised(Duration::from_secs(1))?;
+    assert!(!report.taken());
+
+    handle.reset()?;
+
+    Ok(())
+}
+
 #[test]
 fn test_cancel_waiting_cash_taken() -> Result<()> {
     let _lock = common::init();
@@ -294,3 +318,26 @@ fn test_dispense() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn test_deposit_session_rollback() -> Result<()> {
+    let _lock = common::init();
+
+    let handle = DeviceHandle::open(None, None, None)?;
+
+    handle.close()?;
+
+    let date = handle.get_date_time()?;
+    if date.year() == 2001 {
+        handle.set_current_date_time()?;
+    }
+
+    let session = DepositSession::new(&handle).start()?.accept(None)?;
+    assert_eq!(session.state_name(), "accepting");
+
+    session.cancel()?.rollback()?;
+
+    handle.reset()?;
+
+    Ok(())
+}
@@ -11,7 +11,7 @@ fn test_update_denominations() -> Result<()> {
     handle.close()?;
 
     let mut denom_list = DenominationList::new();
-    denom_list.push(DenominationInfo::new());
+    denom_list.push(DenominationInfo::new())?;
 
     handle.update_denominations(&denom_list)
 }
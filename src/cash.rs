@@ -1,11 +1,19 @@
 //! Functions for cash-related operations.
 
+use std::sync::Mutex;
+use std::time::Duration;
+
 use bnr_xfs::{
-    CashUnit, CurrencyCode, DispenseRequest, LogicalCashUnit, LogicalCashUnitList,
-    PhysicalCashUnit, PhysicalCashUnitList, LCU_LIST_LEN, PCU_LIST_LEN,
+    CashInResult, CashInventory, CashUnit, CashUnitLayout, CurrencyCode, DenominateResult,
+    DispensePlan, DispenseRequest, LogicalCashUnit, LogicalCashUnitList, MixNumber, MixTable,
+    MixTableRegistry, Money, PaymentOptions, PaymentReceipt, PhysicalCashUnit,
+    PhysicalCashUnitList, PresentReport,
 };
 
-use crate::{with_handle, Result};
+use crate::{with_handle, Error, Result};
+
+// Global registry of user-defined mix tables, see [register_mix_table].
+static MIX_TABLES: Mutex<MixTableRegistry> = Mutex::new(MixTableRegistry::new());
 
 /// Sends the initial message to start a `CashIn` transaction, and begin accepting notes.
 pub fn cash_in_start() -> Result<()> {
@@ -42,6 +50,13 @@ pub fn cash_in(limit: Option<u32>, currency: Option<CurrencyCode>) -> Result<()>
     with_handle::<()>(|h| h.cash_in(limit, currency))
 }
 
+/// Sends the follow-up message to start a `CashIn` transaction for an amount of [Money].
+///
+/// With `None`, the device accepts one note of any currency.
+pub fn cash_in_amount(amount: Option<Money>) -> Result<()> {
+    with_handle::<()>(|h| h.cash_in_amount(amount))
+}
+
 /// Sends the message to rollback a `CashIn` transaction, returning any inserted notes to the
 /// customer.
 ///
@@ -54,8 +69,8 @@ pub fn cash_in_rollback() -> Result<()> {
 /// Sends the message to end a `CashIn` transaction.
 ///
 /// The caller will need to call [cash_in_start] and [cash_in] to begin accepting notes again.
-pub fn cash_in_end() -> Result<()> {
-    with_handle::<()>(|h| h.cash_in_end())
+pub fn cash_in_end() -> Result<CashInResult> {
+    with_handle::<CashInResult>(|h| h.cash_in_end())
 }
 
 /// This command allows the application to force cash that has been presented to be ejected from the bezel.
@@ -121,6 +136,25 @@ pub fn update_cash_unit(
     with_handle::<()>(|h| h.update_cash_unit(transport_count, lcu_list, pcu_list))
 }
 
+/// Gets the [CashInventory] of the BNR.
+///
+/// Aggregates the [LogicalCashUnit] counts by denomination, currency and location, and reports
+/// which stock is dispensable versus deposit-only.
+pub fn query_cash_inventory() -> Result<CashInventory> {
+    with_handle::<CashInventory>(|h| Ok(h.query_cash_unit()?.into()))
+}
+
+/// Applies a declarative [CashUnitLayout] profile to the BNR’s cash unit.
+///
+/// The profile is validated against the current [CashUnit] before anything is sent to the device.
+///
+/// Returns the resulting [LogicalCashUnitList] and [PhysicalCashUnitList].
+pub fn apply_cash_unit_layout(
+    layout: &CashUnitLayout,
+) -> Result<(LogicalCashUnitList, PhysicalCashUnitList)> {
+    with_handle::<(LogicalCashUnitList, PhysicalCashUnitList)>(|h| h.apply_cash_unit_layout(layout))
+}
+
 /// Resets the [LogicalCashUnit]s and [PhysicalCashUnit]s `count` to zero.
 ///
 /// Useful for resetting device counters after physically removing notes from a device.
@@ -129,49 +163,25 @@ pub fn reset_cash_unit_counts() -> Result<()> {
 
     let lcu_keep: Vec<LogicalCashUnit> = cu
         .logical_cash_unit_list()
-        .items()
         .iter()
         .filter(|l| l.count() != 0)
-        .map(|l| l.with_count(0))
+        .map(|l| l.clone().with_count(0))
         .collect();
 
-    let mut lcu_keep_list = [LogicalCashUnit::new(); LCU_LIST_LEN];
-
-    let lcu_keep_len = lcu_keep.len();
-
-    lcu_keep_list[..lcu_keep_len]
-        .iter_mut()
-        .zip(lcu_keep.iter())
-        .for_each(|(dst, src)| {
-            *dst = *src;
-        });
-
     let lcu = LogicalCashUnitList::new()
-        .with_size(lcu_keep_len as u32)
-        .with_items(&lcu_keep_list[..lcu_keep_len]);
+        .with_size(lcu_keep.len() as u32)
+        .with_items(&lcu_keep);
 
     let pcu_keep: Vec<PhysicalCashUnit> = cu
         .physical_cash_unit_list()
-        .items()
         .iter()
         .filter(|p| p.count() != 0)
         .map(|p| p.with_count(0))
         .collect();
 
-    let mut pcu_keep_list = [PhysicalCashUnit::new(); PCU_LIST_LEN];
-
-    let pcu_keep_len = pcu_keep.len();
-
-    pcu_keep_list[..pcu_keep_len]
-        .iter_mut()
-        .zip(pcu_keep.iter())
-        .for_each(|(dst, src)| {
-            *dst = *src;
-        });
-
     let pcu = PhysicalCashUnitList::new()
-        .with_size(pcu_keep_len as u32)
-        .with_items(&pcu_keep_list[..pcu_keep_len]);
+        .with_size(pcu_keep.len() as u32)
+        .with_items(&pcu_keep);
 
     update_cash_unit(0, &lcu, &pcu)
 }
@@ -193,8 +203,16 @@ pub fn reset_cash_unit_counts() -> Result<()> {
 ///   - for each item of denominateRequest->denomination.items from 0 to (denominateRequest->denomination.size - 1):
 ///     - denominateRequest->denomination.items[item].unit contains the number of a LCU from where banknotes must be distributed.
 ///     - denominateRequest->denomination.items[item].count gives the number of banknotes to distribute from the LCU.
-pub fn denominate(request: &DispenseRequest) -> Result<()> {
-    with_handle::<()>(|h| h.denominate(request))
+///
+/// Returns the [DenominateResult] with the bills the BNR would dispense.
+pub fn denominate(request: &DispenseRequest) -> Result<DenominateResult> {
+    with_handle::<DenominateResult>(|h| h.denominate(request))
+}
+
+/// Determines if an amount of [Money] is available for dispense, using the minimum number of
+/// bills.
+pub fn denominate_amount(amount: &Money) -> Result<DenominateResult> {
+    with_handle::<DenominateResult>(|h| h.denominate_amount(amount))
 }
 
 /// Dispenses the amount requested by value or by bill list.
@@ -228,10 +246,66 @@ pub fn denominate(request: &DispenseRequest) -> Result<()> {
 /// - `request`: Amount or bill list requested for dispense.
 ///
 /// Returns `Ok` If function call is successful. Otherwise, return is strictly negative and its absolute value contains the error code.
+#[allow(clippy::doc_overindented_list_items)]
 pub fn dispense(request: &DispenseRequest) -> Result<()> {
     with_handle::<()>(|h| h.dispense(request))
 }
 
+/// Dispenses an amount of [Money], using the minimum number of bills.
+pub fn dispense_amount(amount: &Money) -> Result<()> {
+    with_handle::<()>(|h| h.dispense_amount(amount))
+}
+
+/// Plans the dispense of an amount of [Money] over the current cash unit counts, without moving
+/// any bills.
+///
+/// Returns the [DispensePlan] with the request to pass to [dispense], or an error if the amount
+/// is not payable.
+pub fn plan_dispense(amount: &Money, mix_number: MixNumber) -> Result<DispensePlan> {
+    with_handle::<DispensePlan>(|h| h.plan_dispense(amount, mix_number))
+}
+
+/// Validates and registers a named [MixTable], replacing any table with the same name.
+pub fn register_mix_table(table: MixTable) -> Result<()> {
+    MIX_TABLES.lock()?.register(table)
+}
+
+/// Removes the [MixTable] registered under `name`, returning it if present.
+pub fn unregister_mix_table(name: &str) -> Result<Option<MixTable>> {
+    Ok(MIX_TABLES.lock()?.unregister(name))
+}
+
+/// Gets the registered [MixTable]s.
+pub fn mix_tables() -> Result<Vec<MixTable>> {
+    Ok(MIX_TABLES.lock()?.tables().into())
+}
+
+/// Dispenses an amount of [Money] with the [MixTable] registered under `name`.
+///
+/// Returns the [DispensePlan] sent to the device, see [register_mix_table].
+pub fn dispense_table(name: &str, amount: &Money) -> Result<DispensePlan> {
+    let table = MIX_TABLES
+        .lock()?
+        .get(name)
+        .cloned()
+        .ok_or(Error::Config(format!(
+            "no mix table registered as {name:?}"
+        )))?;
+
+    with_handle::<DispensePlan>(|h| h.dispense_table(amount, &table))
+}
+
+/// Collects a payment of `price`: accepts notes until the price is covered, then dispenses and
+/// presents the change.
+///
+/// Notes that would make the change impossible are refused. If the customer cancels, or stops
+/// inserting notes, the notes are returned.
+///
+/// Returns the [PaymentReceipt], or an error if the change cannot be made for the price.
+pub fn collect_payment(price: &Money, options: &PaymentOptions) -> Result<PaymentReceipt> {
+    with_handle::<PaymentReceipt>(|h| h.collect_payment(price, options))
+}
+
 /// Activates the presentation of the cash.
 ///
 /// It can only be used following the [dispense] method.
@@ -245,6 +319,17 @@ pub fn present() -> Result<()> {
     with_handle::<()>(|h| h.present())
 }
 
+/// Presents the cash, and supervises its removal.
+///
+/// If the bills are not taken within `timeout`, stops waiting with [cancel_waiting_cash_taken],
+/// then clears the outlet with [retract].
+///
+/// Returns the [PresentReport] with whether the bills were taken, retracted, or are in an unknown
+/// state, and the number of bills retracted to the bundler.
+pub fn present_supervised(timeout: Duration) -> Result<PresentReport> {
+    with_handle::<PresentReport>(|h| h.present_supervised(timeout))
+}
+
 /// Asks the BNR to stop waiting for cash removal at the Bezel if any.
 ///
 /// If it can do so, an OperationCompleteEvent is sent with the result field containing #XFS_E_CANCELLED to indicate that the operation was cancelled.
@@ -1,6 +1,8 @@
+use std::path::Path;
+
 use crate::{deinit_handle, init_handle, with_handle, DeviceHandle, Result};
 
-use bnr_xfs::{IntermediateOccurredFn, OperationCompletedFn, StatusOccurredFn};
+use bnr_xfs::{IntermediateOccurredFn, OperationCompletedFn, RecoveryReport, StatusOccurredFn};
 
 /// Sends the message to open the device.
 ///
@@ -24,6 +26,36 @@ pub fn open(
     init_handle(handle)
 }
 
+/// Sends the message to open the device, with the journal of money-moving calls stored in the
+/// file at `path`.
+///
+/// Every `CashIn*`, `Dispense`, `Present`, `Retract` and `Empty` call is recorded with its call ID
+/// and completion. Calls left unfinished by a previous session are reconciled with the device
+/// state, see [recovery_report].
+///
+/// See [open] for the callback parameters.
+pub fn open_with_journal<P: AsRef<Path>>(
+    path: P,
+    op_complete_callback: Option<OperationCompletedFn>,
+    intermediate_occurred_callback: Option<IntermediateOccurredFn>,
+    status_occurred_callback: Option<StatusOccurredFn>,
+) -> Result<()> {
+    let handle = DeviceHandle::open_with_journal(
+        path,
+        op_complete_callback,
+        intermediate_occurred_callback,
+        status_occurred_callback,
+    )?;
+
+    init_handle(handle)
+}
+
+/// Gets the [RecoveryReport] describing what happened to the calls left unfinished in the
+/// journal when the device was opened, or reconnected.
+pub fn recovery_report() -> Result<RecoveryReport> {
+    with_handle::<RecoveryReport>(|h| Ok(h.recovery_report().clone()))
+}
+
 /// Sends the message to reset the device.
 pub fn reset() -> Result<()> {
     with_handle::<()>(|h| h.reset())
@@ -1,8 +1,54 @@
 //! Types and functionality for performing maintenance on BNR devices.
 
-use crate::{with_handle, Result};
+use crate::{
+    with_handle, CollectionReport, CorrectionRecord, CountCorrection, LoadedNotes,
+    PendingCollection, RefillReconciliation, RefillRecord, Result,
+};
 
 /// Prepares all modules in the BNR device to be removed.
 pub fn park() -> Result<()> {
     with_handle::<()>(|h| h.park())
 }
+
+/// Starts an end-of-day cashbox collection.
+///
+/// Empties every recycler to its float, waiting for each operation to complete, then parks the
+/// modules, and records the cashbox count before removal. If a recycler cannot be emptied, the
+/// partial [PendingCollection] is returned with the error, and the modules are not parked.
+///
+/// Once the operator has emptied and reinserted the cashbox, call [finish_collection] with the
+/// returned [PendingCollection].
+pub fn start_collection() -> Result<PendingCollection> {
+    with_handle::<PendingCollection>(|h| h.start_collection())
+}
+
+/// Finishes an end-of-day cashbox collection after the emptied cashbox is reinserted.
+///
+/// Resets the cashbox counts, and returns the [CollectionReport] signed with the HMAC `key`.
+pub fn finish_collection(pending: PendingCollection, key: &[u8]) -> Result<CollectionReport> {
+    with_handle::<CollectionReport>(|h| h.finish_collection(pending.clone(), key))
+}
+
+/// Records a loader refill.
+///
+/// Adds the `loaded` bills, stacked in the loader by the operator, to the loader counts, and sets
+/// the loader initial counts to the new counts.
+///
+/// Returns the [RefillRecord] for [reconcile_refill].
+pub fn refill_loader(loaded: &[LoadedNotes]) -> Result<RefillRecord> {
+    with_handle::<RefillRecord>(|h| h.refill_loader(loaded))
+}
+
+/// Reconciles the loader count with the bills extracted from the loader since the refill.
+pub fn reconcile_refill(record: &RefillRecord) -> Result<RefillReconciliation> {
+    with_handle::<RefillReconciliation>(|h| h.reconcile_refill(record))
+}
+
+/// Corrects the cash unit counts with physically counted values, e.g. after a jam is cleared by
+/// hand.
+///
+/// Only the cashbox and loader counts are writable: differences on bundler and recycler units are
+/// logged, and left unchanged.
+pub fn correct_counts(correction: &CountCorrection) -> Result<CorrectionRecord> {
+    with_handle::<CorrectionRecord>(|h| h.correct_counts(correction))
+}
@@ -1,6 +1,6 @@
 //! Types and functions for handling system status events.
 
-use bnr_xfs::CdrStatus;
+use bnr_xfs::{CdrStatus, Error, HealthReport};
 
 use crate::{with_handle, Result};
 
@@ -8,3 +8,13 @@ use crate::{with_handle, Result};
 pub fn get_status() -> Result<CdrStatus> {
     with_handle::<CdrStatus>(|h| h.get_status())
 }
+
+/// Evaluates the overall health of the CDR device.
+///
+/// `last_error` is the most recent error reported by the caller, if any.
+///
+/// If the device handle is unavailable, the report is [`HealthStatus::OutOfService`](bnr_xfs::HealthStatus::OutOfService).
+pub fn evaluate_health(last_error: Option<&Error>) -> HealthReport {
+    with_handle::<HealthReport>(|h| Ok(h.evaluate_health(last_error)))
+        .unwrap_or_else(|err| HealthReport::unreachable(&err))
+}
@@ -2,7 +2,7 @@
 
 use time::OffsetDateTime;
 
-use bnr_xfs::Capabilities;
+use bnr_xfs::{Capabilities, ConfigChange, DeviceConfig};
 
 use crate::{with_handle, Result};
 
@@ -37,3 +37,19 @@ pub fn set_date_time(date_time: OffsetDateTime) -> Result<()> {
 pub fn set_current_date_time() -> Result<()> {
     with_handle::<()>(|h| h.set_current_date_time())
 }
+
+/// Takes a [DeviceConfig] snapshot of the persistent device configuration.
+///
+/// The snapshot can be written to a versioned JSON document with [DeviceConfig::to_json], and restored onto another device with [restore_config].
+pub fn backup_config() -> Result<DeviceConfig> {
+    with_handle::<DeviceConfig>(|h| h.backup_config())
+}
+
+/// Restores a [DeviceConfig] snapshot onto the device.
+///
+/// With `dry_run` set, only returns the list of changes without writing anything to the device.
+///
+/// Returns the list of [ConfigChange]s from the current device configuration.
+pub fn restore_config(config: &DeviceConfig, dry_run: bool) -> Result<Vec<ConfigChange>> {
+    with_handle::<Vec<ConfigChange>>(|h| h.restore_config(config, dry_run))
+}